@@ -4,7 +4,7 @@ use bevy::prelude::*;
 use derive_more::{Display, Error, From};
 use smallvec::SmallVec;
 
-use crate::parser::{SourcePosition, DisplayAddress, FormatterFn};
+use crate::parser::{SourcePosition, DisplayAddress, FormatterFn, LineColumn};
 use crate::string::{escape_string, parse_string, recognize_string};
 
 fn dot_register_name(index: usize) -> impl Display {
@@ -51,6 +51,12 @@ pub enum ParseError<P: SourcePosition> {
     #[error(ignore)]
     #[display("{}: expected integer (with radix {})", DisplayAddress(_0), _1)]
     ExpectedIntRadix(P, u32),
+    #[error(ignore)]
+    #[display("{}: expected file path", DisplayAddress(_0))]
+    ExpectedFilePath(P),
+    #[error(ignore)]
+    #[display("{}: document already extends another prefab", DisplayAddress(_0))]
+    DuplicateExtends(P),
 }
 
 impl<P: SourcePosition> ParseError<P> {
@@ -71,6 +77,8 @@ impl<P: SourcePosition> ParseError<P> {
             ParseError::ExpectedOpenBrace(p) => ParseError::ExpectedOpenBrace(f(p)),
             ParseError::ExpectedImportPath(p) => ParseError::ExpectedImportPath(f(p)),
             ParseError::ExpectedIntRadix(p, r) => ParseError::ExpectedIntRadix(f(p), r),
+            ParseError::ExpectedFilePath(p) => ParseError::ExpectedFilePath(f(p)),
+            ParseError::DuplicateExtends(p) => ParseError::DuplicateExtends(f(p)),
         }
     }
 }
@@ -358,16 +366,25 @@ impl<'a> From<Expression<'a>> for Register<'a> {
 pub struct Application {
     pub entity: usize,
     pub expression: usize,
+    /// Soft applications (written with a `?` suffix) log a warning on failure
+    /// instead of aborting fabrication.
+    pub soft: bool,
 }
 
 impl Debug for Application {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "%{} <- %{}", self.entity, self.expression)
+        let soft = if self.soft { "?" } else { "" };
+        write!(f, "%{} <- %{}{soft}", self.entity, self.expression)
     }
 }
 
 #[derive(Clone, Default)]
 pub struct Document<'a> {
+    /// A base prefab which is fabricated before this one.
+    ///
+    /// Named registers which match the base's parameters are passed to it, and
+    /// any other base parameters are inherited.
+    pub extends: Option<&'a str>,
     pub registers: Vec<Register<'a>>,
     pub applications: Vec<Application>,
 }
@@ -383,6 +400,12 @@ impl<'a> Document<'a> {
         parse_document(input)
     }
 
+    /// Parse a document, reporting errors with line and column numbers.
+    pub fn parse_with_positions(input: &'a str) -> Result<Document<'a>, ParseError<LineColumn>> {
+        parse_document(input)
+            .map_err(|e| e.map_position(|p| LineColumn::from_remaining(input, p)))
+    }
+
     fn fmt_dot(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "digraph Document {{")?;
 
@@ -391,7 +414,8 @@ impl<'a> Document<'a> {
         }
 
         for (index, a) in self.applications.iter().enumerate() {
-            writeln!(f, "  a{index} [label=apply]")?;
+            let label = if a.soft { "\"apply?\"" } else { "apply" };
+            writeln!(f, "  a{index} [label={label}]")?;
             writeln!(f, "  a{index} -> {}", dot_register_name(a.entity))?;
             writeln!(f, "  {} -> a{index}", dot_register_name(a.expression))?;
         }
@@ -405,6 +429,11 @@ impl<'a> Document<'a> {
 
     pub fn dependencies(&self) -> Vec<String> {
         let mut deps = Vec::new();
+        if let Some(file_path) = self.extends {
+            let (_, file_path) = parse_string(file_path).unwrap().unwrap();
+            deps.push(file_path);
+        }
+
         for expr in self.registers.iter().filter_map(|r| r.expression.as_ref()) {
             if let Expression::Import(import) = expr {
                 match import {
@@ -425,6 +454,10 @@ impl Debug for Document<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Document {{")?;
 
+        if let Some(extends) = self.extends {
+            writeln!(f, "  extends {extends};")?;
+        }
+
         for (index, expr) in self.registers.iter().enumerate() {
             write!(f, "  ")?;
             expr.fmt_with_index(index, f)?;
@@ -1049,10 +1082,30 @@ fn expect_expression_index<'a>(
     Ok((rest, index))
 }
 
+fn parse_extends<'a>(
+    document: &mut Document<'a>,
+    input: &'a str,
+) -> Result<Option<&'a str>, ParseError<&'a str>> {
+    let Some(rest) = parse_keyword(input, "extends") else { return Ok(None) };
+    if document.extends.is_some() {
+        return Err(ParseError::DuplicateExtends(input));
+    }
+
+    let rest = skip_whitespace(rest);
+    let (rest, file_path) = recognize_string(rest)?
+        .ok_or(ParseError::ExpectedFilePath(rest))?;
+    document.extends = Some(file_path);
+    Ok(Some(rest))
+}
+
 fn parse_statement<'a>(
     document: &mut Document<'a>,
     input: &'a str,
 ) -> Result<Option<&'a str>, ParseError<&'a str>> {
+    if let Some(rest) = parse_extends(document, input)? {
+        return Ok(Some(rest));
+    }
+
     if let Some(rest) = parse_import(document, input)? {
         return Ok(Some(rest));
     }
@@ -1068,9 +1121,12 @@ fn parse_statement<'a>(
             let rest = skip_whitespace(next);
             let expr = document.push_register(expr);
             let (rest, source_expr) = expect_expression_index(document, rest)?;
+            let rest = skip_whitespace(rest);
+            let (rest, soft) = take_next_if(rest, |c| c == '?');
             document.applications.push(Application {
                 entity: expr,
                 expression: source_expr,
+                soft,
             });
             return Ok(Some(rest));
         }
@@ -1123,6 +1179,7 @@ mod tests {
     #[test]
     fn test_build() {
         let doc = Document {
+            extends: Some("\"base.fab\""),
             registers: vec![
                 Register {
                     name: Some("var"),
@@ -1139,7 +1196,7 @@ mod tests {
                 Expression::Struct(Some(Path::single("MyStruct")), SmallVec::from_iter([("field1", 0), ("field2", 1)])).into(),
             ],
             applications: vec![
-                Application { entity: 0, expression: 0 },
+                Application { entity: 0, expression: 0, soft: false },
             ],
         };
 
@@ -1185,4 +1242,41 @@ mod tests {
         "}",
         ));
     }
+
+    #[test]
+    fn test_parse_extends() {
+        let doc = Document::parse("
+            extends \"base.fab\";
+            local hue: u16 = 5;
+        ").unwrap();
+        assert_eq!(doc.extends, Some("\"base.fab\""));
+        assert_eq!(doc.dependencies(), vec!["base.fab".to_string()]);
+
+        let err = Document::parse("extends \"a.fab\"; extends \"b.fab\";");
+        assert!(matches!(err, Err(ParseError::DuplicateExtends(_))));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let doc = Document::parse("
+            local size: f32 = 1.5;
+            $ <- Scale(size);
+        ").unwrap();
+        let dot = doc.to_dot();
+        assert!(dot.starts_with("digraph Document {\n"));
+        assert!(dot.contains("  v0 [shape=box,label=\"%0 local size: f32 = 1.5\"];\n"));
+        assert!(dot.contains("  v2 -> v3;\n"));
+        assert!(dot.contains("  a0 [label=apply]\n"));
+        assert!(dot.contains("  a0 -> v1\n"));
+    }
+
+    #[test]
+    fn test_parse_with_positions() {
+        let err = Document::parse_with_positions("
+local a = 1;
+local c = (1, 2;
+").err().unwrap();
+        assert!(matches!(err, ParseError::UnclosedTuple(LineColumn { line: 3, column: 16 })));
+        assert_eq!(err.to_string(), "3:16: unclosed tuple");
+    }
 }
@@ -21,6 +21,7 @@ pub mod loader;
 pub mod any;
 pub mod values;
 pub mod hot_reload;
+pub mod validate;
 pub mod glam;
 
 #[cfg(feature = "humantime")]
@@ -126,6 +127,22 @@ impl FabricateRequest {
     }
 }
 
+/// Triggered on an entity once it has been fabricated.
+#[derive(Clone, Event)]
+pub struct OnFabricated {
+    pub entity: Entity,
+    pub fabricator: WeakFactory,
+}
+
+impl OnFabricated {
+    fn trigger(world: &mut World, entity: Entity, request: &FabricateRequest) {
+        world.trigger_targets(OnFabricated {
+            entity,
+            fabricator: Arc::downgrade(&request.factory),
+        }, entity);
+    }
+}
+
 #[derive(Clone, Copy, Debug, Reflect, Component)]
 #[reflect(Component)]
 pub struct FabricatedChild(pub Entity);
@@ -177,6 +194,7 @@ pub fn fabricate(
             match request.fabricate(world, entity) {
                 Ok(result) => {
                     world.entity_mut(entity).insert(result);
+                    OnFabricated::trigger(world, entity, &request);
                 }
                 Err(err) => {
                     warn!("fabrication failed: {err}");
@@ -200,8 +218,9 @@ impl FabricateExt for EntityCommands<'_> {
     ) -> &mut Self {
         let request = request.into();
         self.queue::<()>(move |entity, world: &mut World| {
-            if let Err(err) = request.fabricate(world, entity) {
-                error!("failed to fabricate: {err}");
+            match request.fabricate(world, entity) {
+                Ok(_) => OnFabricated::trigger(world, entity, &request),
+                Err(err) => error!("failed to fabricate: {err}"),
             }
         });
         self
@@ -219,6 +238,7 @@ impl FabricateExt for EntityWorldMut<'_> {
             match request.fabricate(world, entity) {
                 Ok(fabricated) => {
                     world.entity_mut(entity).insert(fabricated);
+                    OnFabricated::trigger(world, entity, &request);
                 }
                 Err(err) => {
                     error!("failed to fabricate: {err}");
@@ -262,3 +282,32 @@ impl Plugin for FabricatorPlugin {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Resource)]
+    struct Seen(Vec<Entity>);
+
+    #[test]
+    fn test_on_fabricated() {
+        let mut world = World::new();
+        world.init_resource::<Seen>();
+        world.add_observer(|trigger: Trigger<OnFabricated>, mut seen: ResMut<Seen>| {
+            assert!(trigger.event().fabricator.upgrade().is_some());
+            seen.0.push(trigger.event().entity);
+        });
+
+        let factory: Factory = Arc::new(|_: Entity, _: &dyn PartialReflect, _: &mut World| {
+            Ok(Fabricated::default())
+        });
+        let request = FabricateRequest {
+            factory,
+            parameters: empty_reflect(),
+        };
+        let entity = world.spawn_empty().fabricate(request).id();
+        assert_eq!(world.resource::<Seen>().0, vec![entity]);
+        assert!(world.get::<Fabricated>(entity).is_some());
+    }
+}
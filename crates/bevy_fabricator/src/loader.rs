@@ -1,15 +1,54 @@
 use std::path::PathBuf;
 
+use anyhow::bail;
 use bevy::asset::io::Reader;
-use bevy::asset::{AssetLoader, LoadContext};
+use bevy::asset::{AssetLoader, LoadContext, LoadState};
 use bevy::prelude::*;
 use bevy::utils::{ConditionalSendFuture, HashMap};
+use derive_more::{Display, Error};
 
 use crate::document::Document;
 use crate::parser::FilePosition;
-use crate::prefab::{convert, FabricatorMap};
+use crate::prefab::{convert, FabricatorMap, FabricatorSource};
 use crate::Fabricator;
 
+/// Returned when an imported fabricator has not finished loading.
+///
+/// Conversion can be retried once the asset has loaded.
+#[derive(Clone, Debug, Display, Error)]
+#[display("fabricator '{path}' is not loaded yet")]
+pub struct FabricatorNotLoaded {
+    pub path: String,
+}
+
+/// Resolves imports against fabricators which have been loaded by the asset server.
+pub struct AssetFabricatorSource<'a> {
+    pub fabricators: &'a Assets<Fabricator>,
+    pub asset_server: &'a AssetServer,
+}
+
+impl FabricatorSource for AssetFabricatorSource<'_> {
+    fn get(&self, path: &str) -> Option<Fabricator> {
+        self.try_get(path).ok()
+    }
+
+    fn try_get(&self, path: &str) -> anyhow::Result<Fabricator> {
+        let not_loaded = || FabricatorNotLoaded { path: path.to_string() };
+        let handle = self.asset_server.get_handle::<Fabricator>(path)
+            .ok_or_else(not_loaded)?;
+
+        if let Some(fabricator) = self.fabricators.get(&handle) {
+            return Ok(fabricator.clone());
+        }
+
+        if let Some(LoadState::Failed(err)) = self.asset_server.get_load_state(&handle) {
+            bail!("failed to load fabricator '{path}': {err}");
+        }
+
+        Err(not_loaded().into())
+    }
+}
+
 
 pub struct FabricatorLoader {
     type_registry: AppTypeRegistry,
@@ -69,3 +108,52 @@ impl AssetLoader for FabricatorLoader {
         &["fab"]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::asset::io::memory::{Dir, MemoryAssetReader};
+    use bevy::asset::io::{AssetSource, AssetSourceId};
+
+    use super::*;
+
+    #[test]
+    fn test_asset_source() {
+        let dir = Dir::default();
+        dir.insert_asset_text(Path::new("base.fab"), "in hue: u16? = 2;");
+
+        let mut app = App::new();
+        app
+            .register_asset_source(AssetSourceId::Default, AssetSource::build()
+                .with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })))
+            .add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Fabricator>();
+        let type_registry = app.world().resource::<AppTypeRegistry>().clone();
+        app.register_asset_loader(FabricatorLoader::new(type_registry.clone()));
+
+        let doc = Document::parse("import \"base.fab\" as base;").unwrap();
+        let try_convert = |app: &App| {
+            let source = AssetFabricatorSource {
+                fabricators: app.world().resource::<Assets<Fabricator>>(),
+                asset_server: app.world().resource::<AssetServer>(),
+            };
+            convert(&type_registry.read(), &source, &doc)
+        };
+
+        let err = try_convert(&app).unwrap_err();
+        assert!(err.downcast_ref::<FabricatorNotLoaded>().is_some());
+
+        // The handle is registered as soon as the load starts, but the asset is
+        // only added once the app updates, so add it directly instead of waiting.
+        let handle = app.world().resource::<AssetServer>().load::<Fabricator>("base.fab");
+        let err = try_convert(&app).unwrap_err();
+        assert!(err.downcast_ref::<FabricatorNotLoaded>().is_some());
+
+        let base = Document::parse("in hue: u16? = 2;").unwrap();
+        let base = convert(&type_registry.read(), &FabricatorMap(HashMap::new()), &base).unwrap();
+        app.world_mut().resource_mut::<Assets<Fabricator>>().insert(&handle, base);
+
+        assert!(try_convert(&app).is_ok());
+    }
+}
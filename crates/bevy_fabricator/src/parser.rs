@@ -26,43 +26,63 @@ impl SourcePosition for String {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct FilePosition {
-    pub file: String,
+/// A 1-based line and column (in characters) within a source string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineColumn {
     pub line: usize,
-    pub offset: usize,
+    pub column: usize,
 }
 
-impl FilePosition {
-    pub fn from_file_and_str(
-        file: impl Into<String>,
-        contents: &str,
-        remaining: &str,
-    ) -> FilePosition {
-        let file = file.into();
+impl LineColumn {
+    /// Find the position of `remaining`, which must be a suffix of `contents`.
+    pub fn from_remaining(contents: &str, remaining: &str) -> LineColumn {
         let rlen = remaining.len();
         let clen = contents.len();
         if clen < rlen {
-            panic!("FilePosition created with invalid remaining string");
+            panic!("LineColumn created with invalid remaining string");
         }
 
         let used = &contents[..(clen - rlen)];
         let mut line = 1;
-        let mut offset = 0;
+        let mut column = 1;
 
         for c in used.chars() {
             if c == '\n' {
                 line += 1;
-                offset = 0;
+                column = 1;
             } else {
-                offset += 1;
+                column += 1;
             }
         }
 
+        LineColumn { line, column }
+    }
+}
+
+impl SourcePosition for LineColumn {
+    fn address(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FilePosition {
+    pub file: String,
+    pub line: usize,
+    pub offset: usize,
+}
+
+impl FilePosition {
+    pub fn from_file_and_str(
+        file: impl Into<String>,
+        contents: &str,
+        remaining: &str,
+    ) -> FilePosition {
+        let position = LineColumn::from_remaining(contents, remaining);
         FilePosition {
-            file,
-            line,
-            offset,
+            file: file.into(),
+            line: position.line,
+            offset: position.column - 1,
         }
     }
 }
@@ -94,3 +114,19 @@ impl<F: Fn(&mut Formatter<'_>) -> std::fmt::Result> Debug for FormatterFn<F> {
         self.0(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_column() {
+        let src = "one\ntwo\nthree";
+        assert_eq!(LineColumn::from_remaining(src, src), LineColumn { line: 1, column: 1 });
+        assert_eq!(LineColumn::from_remaining(src, "ee"), LineColumn { line: 3, column: 4 });
+        assert_eq!(LineColumn::from_remaining(src, ""), LineColumn { line: 3, column: 6 });
+
+        let position = FilePosition::from_file_and_str("test.fab", src, "wo\nthree");
+        assert_eq!((position.line, position.offset), (2, 1));
+    }
+}
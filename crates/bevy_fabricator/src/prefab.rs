@@ -11,7 +11,7 @@ use smallvec::SmallVec;
 use crate::document::{Document, Expression, Import, Number, Path, Visibility};
 use crate::string::parse_string;
 use crate::traits::{ReflectEvaluate, ReflectApply, Context, ReflectConvert};
-use crate::{Fabricated, FabricationParameter, Fabricator};
+use crate::{values, Fabricated, FabricationParameter, Fabricator};
 use crate::parser::FormatterFn;
 
 type RegisterValue = Option<Arc<dyn PartialReflect>>;
@@ -59,6 +59,24 @@ fn lookup_type_or_variant<'a>(
     }
 }
 
+/// Like [`lookup_type_or_variant`], but also accepts a bare variant name of the expected
+/// enum type, so that `North` can be written in place of `Direction::North`.
+fn lookup_type_or_inferred_variant<'a>(
+    type_registry: &'a TypeRegistry, path: &'a Path, expected: Option<TypeId>,
+) -> Option<TypeOrVariant<'a>> {
+    lookup_type_or_variant(type_registry, path).or_else(|| {
+        if path.len() != 1 {
+            return None;
+        }
+
+        let variant_name = path.0[0];
+        let reg = type_registry.get(expected?)?;
+        let enum_info = reg.type_info().as_enum().ok()?;
+        enum_info.contains_variant(variant_name)
+            .then_some(TypeOrVariant::Variant(reg.type_id(), variant_name))
+    })
+}
+
 fn resolve_alias<'a>(aliases: &HashMap<String, Path<'a>>, path: &Path<'a>) -> Path<'a> {
     if let Some(first) = path.0.first() {
         if let Some(existing) = aliases.get(*first) {
@@ -72,8 +90,21 @@ fn resolve_alias<'a>(aliases: &HashMap<String, Path<'a>>, path: &Path<'a>) -> Pa
     path.clone()
 }
 
+fn input_field<'a>(input: &'a dyn PartialReflect, name: &String) -> Option<&'a dyn PartialReflect> {
+    match input.reflect_ref() {
+        ReflectRef::Struct(struct_input) => struct_input.field(name),
+        ReflectRef::Map(map_input) => map_input.get(name),
+        _ => None,
+    }
+}
+
 pub trait FabricatorSource {
     fn get(&self, path: &str) -> Option<Fabricator>;
+
+    /// Like [`FabricatorSource::get`], but reports why a fabricator is unavailable.
+    fn try_get(&self, path: &str) -> anyhow::Result<Fabricator> {
+        self.get(path).ok_or_else(|| anyhow!("no such fabricator '{path}'"))
+    }
 }
 
 #[derive(Default)]
@@ -386,6 +417,42 @@ fn build_list(
     }
 }
 
+/// Find the types of fields which are set by child registers of an expression.
+fn child_field_types(type_info: &TypeInfo, expr: &Expression) -> Vec<(usize, TypeId)> {
+    match (type_info, expr) {
+        (TypeInfo::Struct(struct_info), Expression::Struct(_, body)) => body.iter()
+            .filter_map(|(name, index)| Some((*index, struct_info.field(name)?.type_id())))
+            .collect(),
+        (TypeInfo::TupleStruct(struct_info), Expression::Tuple(_, body)) => body.iter()
+            .enumerate()
+            .filter_map(|(field_index, index)| Some((*index, struct_info.field_at(field_index)?.type_id())))
+            .collect(),
+        (TypeInfo::Tuple(tuple_info), Expression::Tuple(_, body)) => body.iter()
+            .enumerate()
+            .filter_map(|(field_index, index)| Some((*index, tuple_info.field_at(field_index)?.type_id())))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// If `type_id` is an `Option<T>`, return the type ID of `T`.
+fn option_inner_type(type_registry: &TypeRegistry, type_id: TypeId) -> Option<TypeId> {
+    let type_info = type_registry.get(type_id)?.type_info();
+    if type_info.type_path_table().module_path() != Some("core::option") {
+        return None;
+    }
+
+    let enum_info = type_info.as_enum().ok()?;
+    let VariantInfo::Tuple(variant) = enum_info.variant("Some")? else { return None };
+    Some(variant.field_at(0)?.type_id())
+}
+
+fn option_converter(type_registry: &TypeRegistry, option_type: Option<TypeId>) -> Option<ValueConverter> {
+    option_type
+        .and_then(|id| type_registry.get(id))
+        .map(ValueConverter::from_registration)
+}
+
 pub fn convert(
     type_registry: &TypeRegistry,
     documents: &dyn FabricatorSource,
@@ -402,11 +469,16 @@ pub fn convert(
     let mut requires_an_input = false;
     let mut steps: Vec<Step> = Vec::new();
     let mut register_types = vec![None; doc.registers.len()];
+    let mut option_types = vec![None; doc.registers.len()];
 
     // Add constants
     constants.insert("true".to_string(), Arc::new(true));
     constants.insert("false".to_string(), Arc::new(false));
 
+    // Add option shorthand, which can be shadowed by imports
+    aliases.insert("Some".to_string(), Path::from_iter(["bevy_fabricator", "values", "Some"]));
+    aliases.insert("None".to_string(), Path::from_iter(["bevy_fabricator", "values", "None"]));
+
     // Add fixed root entity
     let root_index = register_types.len();
     register_types.push(Some(TypeId::of::<Entity>()));
@@ -424,9 +496,11 @@ pub fn convert(
             }
 
             if register.visibility == Visibility::In {
-                inputs.insert(name.to_string(), (index, !register.optional));
+                // Inputs with a default expression fall back to it when omitted.
+                let required = !register.optional && register.expression.is_none();
+                inputs.insert(name.to_string(), (index, required));
 
-                if !register.optional && register.expression.is_none() {
+                if required {
                     requires_an_input = true;
                 }
             }
@@ -441,8 +515,8 @@ pub fn convert(
                         let (_, unescaped_path) = parse_string(path)
                             .unwrap()
                             .unwrap();
-                        let imported = documents.get(&unescaped_path)
-                            .ok_or_else(|| anyhow!("missing imported prefab '{path}'"))?;
+                        let imported = documents.try_get(&unescaped_path)
+                            .map_err(|err| err.context(format!("missing imported prefab '{path}'")))?;
                         file_imports.insert(name.to_string(), Arc::new(imported) as Arc<dyn PartialReflect>);
                     }
                 }
@@ -450,6 +524,32 @@ pub fn convert(
         }
     }
 
+    // Resolve the base prefab, if any
+    let input_index = root_index + 1;
+    let mut base_overrides = Vec::new();
+    let mut base_inherited = Vec::new();
+    let base = match doc.extends {
+        Some(path) => {
+            let (_, unescaped_path) = parse_string(path)
+                .unwrap()
+                .unwrap();
+            let base = documents.try_get(&unescaped_path)
+                .map_err(|err| err.context(format!("missing base prefab '{path}'")))?;
+
+            for (name, parameter) in &base.parameters {
+                if let Some(index) = locals.get(name) {
+                    base_overrides.push((name.clone(), *index));
+                } else {
+                    parameters.insert(name.clone(), parameter.clone());
+                    base_inherited.push(name.clone());
+                }
+            }
+
+            Some(base)
+        }
+        None => None,
+    };
+
     // Second pass: lookup types
     for (index, register) in doc.registers.iter().enumerate() {
         let mut register_type = register.variable_type.as_ref()
@@ -461,15 +561,21 @@ pub fn convert(
         match &register.expression {
             Some(Expression::Struct(Some(path), _)) => {
                 let path = resolve_alias(&aliases, path);
-                let id = lookup_type_or_variant(type_registry, &path)
-                    .ok_or_else(|| anyhow!("unknown type {path:?}"))?;
-                register_type = Some(id.id());
+                match lookup_type_or_variant(type_registry, &path) {
+                    Some(id) => register_type = Some(id.id()),
+                    // Bare variant names are inferred from the field type later.
+                    None if path.len() == 1 => {}
+                    None => bail!("unknown type {path:?}"),
+                }
             }
             Some(Expression::Tuple(Some(path), _)) => {
                 let path = resolve_alias(&aliases, path);
-                let id = lookup_type_or_variant(type_registry, &path)
-                    .ok_or_else(|| anyhow!("unknown type {path:?}"))?;
-                register_type = Some(id.id());
+                match lookup_type_or_variant(type_registry, &path) {
+                    Some(id) => register_type = Some(id.id()),
+                    // Bare variant names are inferred from the field type later.
+                    None if path.len() == 1 => {}
+                    None => bail!("unknown type {path:?}"),
+                }
             }
             Some(Expression::List(Some(path), _)) => {
                 let path = resolve_alias(&aliases, path);
@@ -501,6 +607,26 @@ pub fn convert(
     for (index, register) in doc.registers.iter().enumerate().rev() {
         let Some(type_id) = register_types[index] else { continue };
         let register_type = type_registry.get(type_id).unwrap();
+
+        // Infer the option type for `Some(...)` and `None` from the field they're assigned to.
+        if let Some(expr) = &register.expression {
+            for (child, field_ty) in child_field_types(register_type.type_info(), expr) {
+                let is_shorthand = register_types[child]
+                    .is_some_and(|id| id == TypeId::of::<values::Some>() || id == TypeId::of::<values::None>());
+                if !is_shorthand {
+                    continue;
+                }
+
+                let Some(inner_ty) = option_inner_type(type_registry, field_ty) else { continue };
+                option_types[child] = Some(field_ty);
+                if let Some(Expression::Tuple(_, body)) = &doc.registers[child].expression {
+                    if let Some(first) = body.first() {
+                        register_types[*first].get_or_insert(inner_ty);
+                    }
+                }
+            }
+        }
+
         if let Some(expr) = &register.expression {
             match expr {
                 Expression::Tuple(type_path, body) => {
@@ -696,7 +822,7 @@ pub fn convert(
         if let (Visibility::In, Some(name), Some(ty)) = (register.visibility, register.name, register_type) {
             parameters.insert(name.to_string(), FabricationParameter {
                 parameter_type: *ty,
-                optional: register.optional,
+                optional: register.optional || register.expression.is_some(),
             });
         }
 
@@ -752,7 +878,7 @@ pub fn convert(
                 Expression::Tuple(type_path, body) => {
                     if let Some(type_path) = type_path.as_ref() {
                         let type_path = resolve_alias(&aliases, type_path);
-                        let type_or_variant = lookup_type_or_variant(type_registry, &type_path)
+                        let type_or_variant = lookup_type_or_inferred_variant(type_registry, &type_path, register_type_id)
                             .ok_or_else(|| anyhow!("unknown type: {type_path}"))?;
 
                         match type_or_variant {
@@ -762,11 +888,15 @@ pub fn convert(
                                     .ok_or_else(|| anyhow!("missing type registry for {type_path:?}"))?;
                                 let converter = ValueConverter::from_registration(type_reg);
                                 let evaluator = Evaluator::from_registration(type_reg);
+                                let option_converter = option_converter(type_registry, option_types[index]);
                                 steps.push(Box::new(move |ctx, registers, _| {
                                     if registers[index].is_none() {
                                         let value = Box::new(factory(ctx, registers)?);
                                         let value = converter.convert(ctx, value)?;
-                                        let value = evaluator.evaluate(ctx, value)?;
+                                        let mut value = evaluator.evaluate(ctx, value)?;
+                                        if let Some(option_converter) = &option_converter {
+                                            value = option_converter.convert(ctx, value)?;
+                                        }
                                         registers[index] = Some(value.into());
                                     }
                                     Ok(())
@@ -824,7 +954,7 @@ pub fn convert(
 
                     if let Some(type_path) = type_path.as_ref() {
                         let type_path = resolve_alias(&aliases, type_path);
-                        let type_or_variant = lookup_type_or_variant(type_registry, &type_path)
+                        let type_or_variant = lookup_type_or_inferred_variant(type_registry, &type_path, register_type_id)
                             .ok_or_else(|| anyhow!("unknown type: {type_path}"))?;
 
                         match type_or_variant {
@@ -951,17 +1081,21 @@ pub fn convert(
                         }
                     }
 
-                    if let Some(type_or_variant) = lookup_type_or_variant(type_registry, &path) {
+                    if let Some(type_or_variant) = lookup_type_or_inferred_variant(type_registry, &path, register_type_id) {
                         match type_or_variant {
                             TypeOrVariant::Type(id) => {
                                 let type_reg = type_registry.get(id)
                                     .ok_or_else(|| anyhow!("missing type registry for {path:?}"))?;
                                 let constructor = Constructor::from_registration(type_reg);
                                 let evaluator = Evaluator::from_registration(type_reg);
+                                let option_converter = option_converter(type_registry, option_types[index]);
                                 steps.push(Box::new(move |ctx, registers, _| {
                                     if registers[index].is_none() {
                                         let value = constructor.construct(ctx)?;
-                                        let value = evaluator.evaluate(ctx, value)?;
+                                        let mut value = evaluator.evaluate(ctx, value)?;
+                                        if let Some(option_converter) = &option_converter {
+                                            value = option_converter.convert(ctx, value)?;
+                                        }
                                         registers[index] = Some(value.into());
                                     }
                                     Ok(())
@@ -996,6 +1130,32 @@ pub fn convert(
         }
     }
 
+    // Fabricate the base before our own applications, so that they override it.
+    let base_inherited_empty = base_inherited.is_empty();
+    if let Some(base) = base {
+        steps.push(Box::new(move |ctx, registers, entity| {
+            let mut base_input = DynamicStruct::default();
+            for (name, index) in &base_overrides {
+                if let Some(value) = &registers[*index] {
+                    base_input.insert_boxed(name, value.clone_value());
+                }
+            }
+
+            let input = registers.get(input_index).cloned().flatten();
+            if let Some(input) = input.as_deref() {
+                for name in &base_inherited {
+                    if let Some(value) = input_field(input, name) {
+                        base_input.insert_boxed(name, value.clone_value());
+                    }
+                }
+            }
+
+            let fabricated = base.fabricate(&base_input, ctx.world, entity)?;
+            ctx.fabricated.children.extend(fabricated.children);
+            Ok(())
+        }));
+    }
+
     for (index, application) in doc.applications.iter().enumerate() {
         let source = application.expression;
         let target = application.entity;
@@ -1003,26 +1163,41 @@ pub fn convert(
         let type_info = type_id.and_then(|id| type_registry.get(id))
             .ok_or_else(|| anyhow!("missing apply operand type info for %{source} in application {index}, type id {type_id:?}"))?;
         let applicator = Applicator::from_registration(type_info);
+        let soft = application.soft;
 
         steps.push(Box::new(move |ctx, registers, _| {
-            let Some(source_value) = &registers[source] else {
-                bail!("apply source null");
-            };
-            let Some(target_value) = &registers[target] else {
-                bail!("apply target null");
-            };
-            let Some(entity) = Entity::from_reflect(target_value.as_ref()) else {
-                bail!("apply target was not entity: {target_value:?}");
-            };
-            applicator.apply(ctx, source_value.as_ref(), entity)
+            let result = (|| {
+                let Some(source_value) = &registers[source] else {
+                    bail!("apply source null");
+                };
+                let Some(target_value) = &registers[target] else {
+                    bail!("apply target null");
+                };
+                let Some(entity) = Entity::from_reflect(target_value.as_ref()) else {
+                    bail!("apply target was not entity: {target_value:?}");
+                };
+                applicator.apply(ctx, source_value.as_ref(), entity)
+            })();
+
+            match result {
+                Err(err) if soft => {
+                    warn!("soft application {index} failed: {err}");
+                    Ok(())
+                }
+                result => result,
+            }
         }));
     }
 
     let num_registers = doc.registers.len();
+    let pass_input = !base_inherited_empty;
     let fabricate = move |entity: Entity, input: &dyn PartialReflect, world: &mut World| {
         let mut registers: Vec<Option<Arc<dyn PartialReflect>>> = Vec::with_capacity(num_registers + 1);
         registers.extend(std::iter::repeat_with(|| None).take(num_registers));
         registers.push(Some(Arc::new(entity)));
+        if pass_input {
+            registers.push(Some(input.clone_value().into()));
+        }
 
         // Apply inputs
         match input.reflect_ref() {
@@ -1079,6 +1254,8 @@ pub fn convert(
 
 #[cfg(test)]
 mod tests {
+    use crate::traits::Apply;
+
     use super::*;
 
     #[test]
@@ -1136,4 +1313,222 @@ mod tests {
         }
         assert!(found_child);
     }
+
+    #[test]
+    fn test_input_defaults() {
+        let doc = Document::parse("
+            import bevy_transform::components::transform::Transform;
+            in param1: f32 = 5.0;
+            in param2: f32? = 0.4;
+            $ <- Transform { translation: (param1, param2, 0) };
+        ").unwrap();
+        let app_type_registry = AppTypeRegistry::default();
+        let mut type_registry = app_type_registry.write();
+        type_registry.register::<Transform>();
+        let fabricator = convert(&type_registry, &FabricatorMap::default(), &doc).unwrap();
+        assert!(fabricator.parameters["param1"].optional);
+        drop(type_registry);
+
+        let mut world = World::new();
+        world.insert_resource(app_type_registry);
+
+        #[derive(Reflect)]
+        struct Params {
+            pub param1: f32,
+        }
+
+        let target = world.spawn_empty().id();
+        fabricator.fabricate(&Params { param1: 42. }, &mut world, target).unwrap();
+        let translation = world.get::<Transform>(target).unwrap().translation;
+        assert_eq!(translation, Vec3::new(42., 0.4, 0.));
+
+        let target = world.spawn_empty().id();
+        fabricator.fabricate(&DynamicStruct::default(), &mut world, target).unwrap();
+        let translation = world.get::<Transform>(target).unwrap().translation;
+        assert_eq!(translation, Vec3::new(5., 0.4, 0.));
+    }
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component, Default)]
+    struct TestHue(u16);
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component, Default)]
+    struct TestName(String);
+
+    #[test]
+    fn test_extends() {
+        let app_type_registry = AppTypeRegistry::default();
+        let mut type_registry = app_type_registry.write();
+        type_registry.register::<TestHue>();
+        type_registry.register::<TestName>();
+
+        let base = Document::parse("
+            import bevy_fabricator::prefab::tests::{TestHue, TestName};
+            in hue: u16? = 2;
+            $ <- TestHue(hue);
+            $ <- TestName(\"base\");
+        ").unwrap();
+        let base = convert(&type_registry, &FabricatorMap::default(), &base).unwrap();
+        let mut documents = FabricatorMap::default();
+        documents.0.insert("base.fab".to_string(), base);
+
+        let override_application = Document::parse("
+            extends \"base.fab\";
+            import bevy_fabricator::prefab::tests::TestHue;
+            $ <- TestHue(7);
+        ").unwrap();
+        let override_application = convert(&type_registry, &documents, &override_application).unwrap();
+        assert!(override_application.parameters.contains_key("hue"));
+
+        let override_parameter = Document::parse("
+            extends \"base.fab\";
+            local hue: u16 = 9;
+        ").unwrap();
+        let override_parameter = convert(&type_registry, &documents, &override_parameter).unwrap();
+        assert!(!override_parameter.parameters.contains_key("hue"));
+        drop(type_registry);
+
+        let mut world = World::new();
+        world.insert_resource(app_type_registry);
+
+        let entity = world.spawn_empty().id();
+        override_application.fabricate(&(), &mut world, entity).unwrap();
+        assert_eq!(world.get::<TestHue>(entity).unwrap().0, 7);
+        assert_eq!(world.get::<TestName>(entity).unwrap().0, "base");
+
+        let entity = world.spawn_empty().id();
+        override_parameter.fabricate(&(), &mut world, entity).unwrap();
+        assert_eq!(world.get::<TestHue>(entity).unwrap().0, 9);
+        assert_eq!(world.get::<TestName>(entity).unwrap().0, "base");
+    }
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component, Default)]
+    struct TestOption {
+        value: Option<u32>,
+    }
+
+    #[test]
+    fn test_option_shorthand() {
+        let app_type_registry = AppTypeRegistry::default();
+        let mut type_registry = app_type_registry.write();
+        type_registry.register::<TestOption>();
+        type_registry.register::<values::Some>();
+        type_registry.register::<values::None>();
+
+        let some = Document::parse("
+            import bevy_fabricator::prefab::tests::TestOption;
+            $ <- TestOption { value: Some(5) };
+        ").unwrap();
+        let some = convert(&type_registry, &FabricatorMap::default(), &some).unwrap();
+        let none = Document::parse("
+            import bevy_fabricator::prefab::tests::TestOption;
+            $ <- TestOption { value: None };
+        ").unwrap();
+        let none = convert(&type_registry, &FabricatorMap::default(), &none).unwrap();
+        drop(type_registry);
+
+        let mut world = World::new();
+        world.insert_resource(app_type_registry);
+
+        let entity = world.spawn(TestOption { value: Some(1) }).id();
+        some.fabricate(&(), &mut world, entity).unwrap();
+        assert_eq!(world.get::<TestOption>(entity).unwrap().value, Some(5));
+
+        none.fabricate(&(), &mut world, entity).unwrap();
+        assert_eq!(world.get::<TestOption>(entity).unwrap().value, None);
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Default)]
+    enum Direction {
+        #[default]
+        North,
+        South,
+        Offset(i32),
+    }
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component, Default)]
+    struct TestFacing {
+        direction: Direction,
+    }
+
+    #[test]
+    fn test_inferred_variant() {
+        let app_type_registry = AppTypeRegistry::default();
+        let mut type_registry = app_type_registry.write();
+        type_registry.register::<TestFacing>();
+
+        let north = Document::parse("
+            import bevy_fabricator::prefab::tests::TestFacing;
+            $ <- TestFacing { direction: North };
+        ").unwrap();
+        let north = convert(&type_registry, &FabricatorMap::default(), &north).unwrap();
+        let offset = Document::parse("
+            import bevy_fabricator::prefab::tests::TestFacing;
+            $ <- TestFacing { direction: Offset(3) };
+        ").unwrap();
+        let offset = convert(&type_registry, &FabricatorMap::default(), &offset).unwrap();
+        let unknown = Document::parse("
+            import bevy_fabricator::prefab::tests::TestFacing;
+            $ <- TestFacing { direction: West };
+        ").unwrap();
+        assert!(convert(&type_registry, &FabricatorMap::default(), &unknown).is_err());
+        drop(type_registry);
+
+        let mut world = World::new();
+        world.insert_resource(app_type_registry);
+
+        let entity = world.spawn(TestFacing { direction: Direction::South }).id();
+        north.fabricate(&(), &mut world, entity).unwrap();
+        assert_eq!(world.get::<TestFacing>(entity).unwrap().direction, Direction::North);
+
+        offset.fabricate(&(), &mut world, entity).unwrap();
+        assert_eq!(world.get::<TestFacing>(entity).unwrap().direction, Direction::Offset(3));
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default, Apply)]
+    struct TestFail;
+
+    impl Apply for TestFail {
+        fn apply(&self, _ctx: &mut Context<'_>, _entity: Entity) -> anyhow::Result<()> {
+            bail!("always fails")
+        }
+    }
+
+    #[test]
+    fn test_soft_apply() {
+        let app_type_registry = AppTypeRegistry::default();
+        let mut type_registry = app_type_registry.write();
+        type_registry.register::<TestFail>();
+        type_registry.register::<TestHue>();
+
+        let soft = Document::parse("
+            import bevy_fabricator::prefab::tests::{TestFail, TestHue};
+            $ <- TestFail?;
+            $ <- TestHue(4);
+        ").unwrap();
+        assert!(soft.applications[0].soft);
+        let soft = convert(&type_registry, &FabricatorMap::default(), &soft).unwrap();
+        let hard = Document::parse("
+            import bevy_fabricator::prefab::tests::{TestFail, TestHue};
+            $ <- TestFail;
+            $ <- TestHue(4);
+        ").unwrap();
+        let hard = convert(&type_registry, &FabricatorMap::default(), &hard).unwrap();
+        drop(type_registry);
+
+        let mut world = World::new();
+        world.insert_resource(app_type_registry);
+
+        let entity = world.spawn_empty().id();
+        soft.fabricate(&(), &mut world, entity).unwrap();
+        assert_eq!(world.get::<TestHue>(entity).unwrap().0, 4);
+
+        let entity = world.spawn_empty().id();
+        assert!(hard.fabricate(&(), &mut world, entity).is_err());
+    }
 }
@@ -43,6 +43,10 @@ pub trait Evaluate {
     fn evaluate(&self, ctx: &mut Context<'_>) -> anyhow::Result<Box<dyn PartialReflect>>;
 }
 
+/// Applies a value to an entity.
+///
+/// An error aborts the whole fabrication, unless the application is marked soft
+/// with a `?` suffix (`$ <- Decoration?;`), in which case it is logged and skipped.
 #[reflect_trait]
 pub trait Apply {
     fn apply(&self, ctx: &mut Context<'_>, entity: Entity) -> anyhow::Result<()>;
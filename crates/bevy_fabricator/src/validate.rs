@@ -0,0 +1,141 @@
+use anyhow::anyhow;
+use bevy::reflect::TypeRegistry;
+use bevy::utils::HashMap;
+
+use crate::document::Document;
+use crate::parser::FilePosition;
+use crate::prefab::{convert, FabricatorMap};
+use crate::Fabricator;
+
+/// Resolve a dependency path relative to the document which imports it.
+pub fn resolve_path(from: &str, path: &str) -> String {
+    let mut segments = from.split('/').collect::<Vec<_>>();
+    segments.pop();
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+struct Validator<'a> {
+    type_registry: &'a TypeRegistry,
+    sources: &'a HashMap<String, String>,
+    results: HashMap<String, Option<Fabricator>>,
+    errors: Vec<String>,
+}
+
+impl Validator<'_> {
+    fn convert(&mut self, path: &str) -> Option<Fabricator> {
+        if let Some(result) = self.results.get(path) {
+            return result.clone();
+        }
+
+        // Mark as failed until finished, to break import cycles.
+        self.results.insert(path.to_string(), None);
+
+        let result = match self.try_convert(path) {
+            Ok(fabricator) => Some(fabricator),
+            Err(err) => {
+                self.errors.push(err.to_string());
+                None
+            }
+        };
+        self.results.insert(path.to_string(), result.clone());
+        result
+    }
+
+    fn try_convert(&mut self, path: &str) -> anyhow::Result<Fabricator> {
+        let sources = self.sources;
+        let src = sources.get(path)
+            .ok_or_else(|| anyhow!("{path}: no such file"))?;
+        let doc = Document::parse(src)
+            .map_err(|e| e.map_position(|p| FilePosition::from_file_and_str(path, src, p)))?;
+
+        let mut deps = HashMap::new();
+        for dep_path in doc.dependencies() {
+            let resolved = resolve_path(path, &dep_path);
+            let fabricator = self.convert(&resolved)
+                .ok_or_else(|| anyhow!("{path}: failed to load dependency '{dep_path}'"))?;
+            deps.insert(dep_path, fabricator);
+        }
+
+        convert(self.type_registry, &FabricatorMap(deps), &doc)
+            .map_err(|err| anyhow!("{path}: {err}"))
+    }
+}
+
+/// Convert every document in `sources` without fabricating anything.
+///
+/// `sources` maps `/`-separated paths to document contents. All documents are
+/// checked, and an error is returned for each one which fails to parse or convert.
+pub fn validate_documents(
+    type_registry: &TypeRegistry,
+    sources: &HashMap<String, String>,
+) -> Result<(), Vec<String>> {
+    let mut validator = Validator {
+        type_registry,
+        sources,
+        results: HashMap::new(),
+        errors: Vec::new(),
+    };
+
+    let mut paths = sources.keys().collect::<Vec<_>>();
+    paths.sort();
+    for path in paths {
+        validator.convert(path);
+    }
+
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(resolve_path("prefabs/items/a.fab", "b.fab"), "prefabs/items/b.fab");
+        assert_eq!(resolve_path("prefabs/items/a.fab", "../c/d.fab"), "prefabs/c/d.fab");
+        assert_eq!(resolve_path("a.fab", "./b.fab"), "b.fab");
+    }
+
+    #[test]
+    fn test_validate_documents() {
+        let mut type_registry = TypeRegistry::new();
+        type_registry.register::<Transform>();
+
+        let mut sources = HashMap::new();
+        sources.insert("items/base.fab".to_string(), "
+            import bevy_transform::components::transform::Transform;
+            $ <- Transform { translation: (1, 2, 3) };
+        ".to_string());
+        sources.insert("items/derived.fab".to_string(), "
+            extends \"base.fab\";
+        ".to_string());
+        assert!(validate_documents(&type_registry, &sources).is_ok());
+
+        sources.insert("items/broken.fab".to_string(), "$ <- ".to_string());
+        sources.insert("items/unknown.fab".to_string(), "$ <- Unknown;".to_string());
+        sources.insert("items/missing.fab".to_string(), "import \"nope.fab\" as nope;".to_string());
+        let errors = validate_documents(&type_registry, &sources).unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].starts_with("items/broken.fab:1:"));
+        assert_eq!(errors[1], "items/nope.fab: no such file");
+        assert_eq!(errors[2], "items/missing.fab: failed to load dependency 'nope.fab'");
+        assert!(errors[3].starts_with("items/unknown.fab: "));
+    }
+}
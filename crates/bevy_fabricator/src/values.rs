@@ -3,6 +3,7 @@ use bevy::reflect::{DynamicEnum, DynamicTuple, DynamicVariant};
 use crate::any::Any;
 use crate::traits::{Context, Evaluate, ReflectEvaluate};
 
+/// `None`, which becomes `Option::None` when assigned to an `Option<T>` field.
 #[derive(Default, Reflect)]
 #[reflect(Default, Evaluate)]
 pub struct None;
@@ -13,6 +14,10 @@ impl Evaluate for None {
     }
 }
 
+/// `Some(value)`, which becomes `Option::Some` when assigned to an `Option<T>` field.
+///
+/// The type of `value` is inferred from the field, so `amount: Some(5)` works for an
+/// `Option<u32>`.
 #[derive(Reflect)]
 #[reflect(Evaluate)]
 pub struct Some(Any);
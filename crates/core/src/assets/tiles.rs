@@ -7,7 +7,7 @@ use byteorder::{LittleEndian as Endian, ReadBytesExt};
 use crate::assets::mul::MulReader;
 
 bitflags! {
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
     pub struct TileFlags : u64 {
         const BACKGROUND = 1 << 0;
         const WEAPON = 1 << 1;
@@ -66,6 +66,61 @@ pub struct TileData {
     pub items: Vec<ItemInfo>,
 }
 
+impl TileData {
+    pub fn land_flags(&self, tile_id: u16) -> TileFlags {
+        self.land.get(tile_id as usize).map_or(TileFlags::empty(), |info| info.flags)
+    }
+
+    pub fn item_flags(&self, graphic: u16) -> TileFlags {
+        self.items.get(graphic as usize).map_or(TileFlags::empty(), |info| info.flags)
+    }
+
+    pub fn is_land_impassable(&self, tile_id: u16) -> bool {
+        self.land_flags(tile_id).contains(TileFlags::IMPASSABLE)
+    }
+
+    pub fn is_land_wet(&self, tile_id: u16) -> bool {
+        self.land_flags(tile_id).contains(TileFlags::WET)
+    }
+
+    pub fn is_impassable(&self, graphic: u16) -> bool {
+        self.item_flags(graphic).contains(TileFlags::IMPASSABLE)
+    }
+
+    pub fn is_surface(&self, graphic: u16) -> bool {
+        self.item_flags(graphic).contains(TileFlags::SURFACE)
+    }
+
+    pub fn is_wet(&self, graphic: u16) -> bool {
+        self.item_flags(graphic).contains(TileFlags::WET)
+    }
+
+    pub fn is_wall(&self, graphic: u16) -> bool {
+        self.item_flags(graphic).contains(TileFlags::WALL)
+    }
+
+    pub fn is_bridge(&self, graphic: u16) -> bool {
+        self.item_flags(graphic).contains(TileFlags::BRIDGE)
+    }
+
+    /// The height of an item tile, or 0 if it is unknown.
+    pub fn height(&self, graphic: u16) -> u8 {
+        self.items.get(graphic as usize).map_or(0, |info| info.height)
+    }
+
+    /// The height a character standing on top of this item is raised by.
+    ///
+    /// Bridges (which includes stairs) are only half-height to make them walkable.
+    pub fn stand_height(&self, graphic: u16) -> u8 {
+        let height = self.height(graphic);
+        if self.is_bridge(graphic) {
+            height / 2
+        } else {
+            height
+        }
+    }
+}
+
 const NUM_LAND_TILES: usize = 0x4000;
 const NUM_ITEMS: usize = 0x10000;
 
@@ -131,3 +186,75 @@ pub async fn load_tile_data(data_path: &Path) -> anyhow::Result<TileData> {
 
     Ok(TileData { land, items })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn land(name: &str, flags: TileFlags) -> LandInfo {
+        LandInfo {
+            name: name.to_string(),
+            flags,
+            texture_id: 0,
+        }
+    }
+
+    fn item(name: &str, flags: TileFlags, height: u8) -> ItemInfo {
+        ItemInfo {
+            name: name.to_string(),
+            flags,
+            weight: 0,
+            quality: 0,
+            animation: 0,
+            quantity: 0,
+            value: 0,
+            height,
+        }
+    }
+
+    fn test_tile_data() -> TileData {
+        TileData {
+            land: vec![
+                land("void", TileFlags::IMPASSABLE),
+                land("grass", TileFlags::empty()),
+                land("water", TileFlags::WET | TileFlags::IMPASSABLE),
+            ],
+            items: vec![
+                item("nothing", TileFlags::empty(), 0),
+                item("stone wall", TileFlags::WALL | TileFlags::IMPASSABLE, 20),
+                item("wooden floor", TileFlags::SURFACE, 0),
+                item("stairs", TileFlags::SURFACE | TileFlags::BRIDGE | TileFlags::IMPASSABLE, 5),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_land_flags() {
+        let tile_data = test_tile_data();
+        assert!(tile_data.is_land_impassable(0));
+        assert!(!tile_data.is_land_impassable(1));
+        assert!(!tile_data.is_land_wet(1));
+        assert!(tile_data.is_land_wet(2));
+        assert_eq!(tile_data.land_flags(1000), TileFlags::empty());
+    }
+
+    #[test]
+    fn test_item_flags() {
+        let tile_data = test_tile_data();
+        assert!(tile_data.is_wall(1));
+        assert!(tile_data.is_impassable(1));
+        assert!(!tile_data.is_surface(1));
+        assert_eq!(tile_data.height(1), 20);
+
+        assert!(tile_data.is_surface(2));
+        assert!(!tile_data.is_wall(2));
+        assert!(!tile_data.is_impassable(2));
+
+        assert_eq!(tile_data.height(3), 5);
+        assert_eq!(tile_data.stand_height(3), 2);
+        assert_eq!(tile_data.stand_height(1), 20);
+
+        assert!(!tile_data.is_wall(0xffff));
+        assert_eq!(tile_data.height(0xffff), 0);
+    }
+}
@@ -6,6 +6,7 @@ pub mod protocol;
 pub mod types;
 
 pub const MIN_ITEM_ID: u32 = 0x40000000;
+pub const MAX_ITEM_ID: u32 = 0x7fffffff;
 
 #[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub struct EntityId(u32);
@@ -41,7 +42,7 @@ pub enum Direction {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, Default, FromRepr)]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, FromRepr)]
 pub enum EntityKind {
     #[default]
     Item = 0,
@@ -3,7 +3,7 @@ use std::fmt::{Debug, Formatter};
 use std::io::Write;
 use std::sync::Arc;
 
-use crate::protocol::{AccountLogin, AsciiTextMessage, AsciiTextMessageRequest, AttackRequest, BeginEnterWorld, ChangeSeason, CharacterAnimation, CharacterList, CharacterPredefinedAnimation, ClientVersion, ClientVersionRequest, CreateCharacterClassic, CreateCharacterEnhanced, DamageDealt, DeleteCharacter, DeleteEntity, DoubleClick, DropEntity, EndEnterWorld, EntityLightLevel, EntityRequest, EntityTooltip, EntityTooltipVersion, EquipEntity, ExtendedCommand, ExtendedCommandAos, GameServerLogin, GlobalLightLevel, GumpResult, LocalisedTextMessage, LoginError, Logout, Move, MoveConfirm, PickUpReject, MoveReject, OpenChatWindow, OpenContainer, OpenGump, OpenGumpCompressed, OpenPaperDoll, OutgoingPacket, Packet, PickTarget, PickUpEntity, Ping, PlayMusic, PlaySoundEffect, RenameEntity, RequestHelp, RequestName, Seed, SelectCharacter, SelectGameServer, ServerList, SetAttackTarget, SetTime, ShowPublicHouses, SingleClick, SupportedFeatures, Swing, SwitchServer, UnicodeTextMessage, UnicodeTextMessageRequest, UpdateCharacter, UpsertContainerContents, UpsertContainerEquipment, UpsertEntityCharacter, UpsertEntityContained, UpsertEntityEquipped, UpsertEntityLegacy, UpsertEntityStats, UpsertEntityWorld, UpsertLocalPlayer, ViewRange, WarMode, DropAccept, TextCommand, ProfileRequest, ProfileResponse, SkillLockRequest, SkillsResponse, EntityTooltipRequest};
+use crate::protocol::{AccountLogin, AsciiTextMessage, AsciiTextMessageRequest, AttackRequest, BeginEnterWorld, ChangeSeason, CharacterAnimation, CharacterList, CharacterPredefinedAnimation, ClientVersion, ClientVersionRequest, CreateCharacterClassic, CreateCharacterEnhanced, DamageDealt, DeleteCharacter, DeleteEntity, DoubleClick, DropEntity, EndEnterWorld, EntityLightLevel, EntityRequest, EntityTooltip, EntityTooltipVersion, EquipEntity, ExtendedCommand, ExtendedCommandAos, GameServerLogin, GlobalLightLevel, GumpResult, LocalisedTextMessage, LoginError, Logout, Move, MoveConfirm, PickUpReject, MoveReject, OpenChatWindow, OpenContainer, OpenGump, OpenGumpCompressed, OpenPaperDoll, OutgoingPacket, Packet, PickTarget, PickUpEntity, Ping, PlayMusic, PlaySoundEffect, RenameEntity, RequestHelp, RequestName, Seed, SelectCharacter, SelectGameServer, ServerList, SetAttackTarget, SetTime, SetWeather, ShowPublicHouses, SingleClick, SupportedFeatures, Swing, SwitchServer, UnicodeTextMessage, UnicodeTextMessageRequest, UpdateCharacter, UpsertContainerContents, UpsertContainerEquipment, UpsertEntityCharacter, UpsertEntityContained, UpsertEntityEquipped, UpsertEntityLegacy, UpsertEntityStats, UpsertEntityWorld, UpsertLocalPlayer, ViewRange, WarMode, DropAccept, TextCommand, ProfileRequest, ProfileResponse, SkillLockRequest, SkillsResponse, EntityTooltipRequest};
 
 pub trait IntoAnyPacket where Self: Sized {
     fn into_any(self) -> AnyPacket;
@@ -237,6 +237,7 @@ impl_any!(
 
     // Map
     SetTime,
+    SetWeather,
     ChangeSeason,
     ViewRange,
     GlobalLightLevel,
@@ -34,7 +34,7 @@ impl Packet for ProfileRequest {
         let target_id = payload.read_entity_id()?;
         let new_profile = if is_edit {
             payload.skip(2)?;
-            Some(payload.read_utf16_pascal()?)
+            Some(payload.read_utf16_be_pascal()?)
         } else {
             None
         };
@@ -51,7 +51,7 @@ impl Packet for ProfileRequest {
 
         if let Some(new_profile) = self.new_profile.as_ref() {
             writer.write_u16::<Endian>(1)?;
-            writer.write_utf16_pascal(new_profile)?;
+            writer.write_utf16_be_pascal(new_profile)?;
         }
         Ok(())
     }
@@ -65,8 +65,8 @@ impl Packet for ProfileResponse {
     fn decode(_client_version: ClientVersion, mut payload: &[u8]) -> anyhow::Result<Self> {
         let target_id = payload.read_entity_id()?;
         let title = payload.read_str_nul()?;
-        let static_profile = payload.read_utf16_nul()?;
-        let profile = payload.read_utf16_nul()?;
+        let static_profile = payload.read_utf16_be_nul()?;
+        let profile = payload.read_utf16_be_nul()?;
         Ok(ProfileResponse {
             target_id,
             header: title,
@@ -78,8 +78,8 @@ impl Packet for ProfileResponse {
     fn encode(&self, _client_version: ClientVersion, writer: &mut impl Write) -> anyhow::Result<()> {
         writer.write_entity_id(self.target_id)?;
         writer.write_str_nul(&self.header)?;
-        writer.write_utf16_nul(&self.footer)?;
-        writer.write_utf16_nul(&self.profile)?;
+        writer.write_utf16_be_nul(&self.footer)?;
+        writer.write_utf16_be_nul(&self.profile)?;
         Ok(())
     }
 }
@@ -366,3 +366,52 @@ impl Packet for CharacterPredefinedAnimation {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION: ClientVersion = ClientVersion::new(7, 0, 9, 0);
+
+    #[test]
+    fn test_profile_request_roundtrip() {
+        let request = ProfileRequest {
+            target_id: EntityId::from_u32(0x1234),
+            new_profile: Some("Smith of Britain".into()),
+        };
+        let mut buffer = Vec::new();
+        request.encode(VERSION, &mut buffer).unwrap();
+
+        let decoded = ProfileRequest::decode(VERSION, &buffer).unwrap();
+        assert_eq!(decoded.target_id, request.target_id);
+        assert_eq!(decoded.new_profile, request.new_profile);
+
+        let request = ProfileRequest {
+            target_id: EntityId::from_u32(0x1234),
+            new_profile: None,
+        };
+        let mut buffer = Vec::new();
+        request.encode(VERSION, &mut buffer).unwrap();
+
+        let decoded = ProfileRequest::decode(VERSION, &buffer).unwrap();
+        assert_eq!(decoded.new_profile, None);
+    }
+
+    #[test]
+    fn test_profile_response_roundtrip() {
+        let response = ProfileResponse {
+            target_id: EntityId::from_u32(0x1234),
+            header: "Gerome, the Blacksmith".into(),
+            footer: String::new(),
+            profile: "Smith of Britain".into(),
+        };
+        let mut buffer = Vec::new();
+        response.encode(VERSION, &mut buffer).unwrap();
+
+        let decoded = ProfileResponse::decode(VERSION, &buffer).unwrap();
+        assert_eq!(decoded.target_id, response.target_id);
+        assert_eq!(decoded.header, response.header);
+        assert_eq!(decoded.footer, response.footer);
+        assert_eq!(decoded.profile, response.profile);
+    }
+}
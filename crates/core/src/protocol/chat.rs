@@ -157,7 +157,7 @@ impl Packet for UnicodeTextMessage {
         let font = payload.read_u16::<Endian>()?;
         let language = payload.read_str_fixed()?;
         let name = payload.read_str_fixed()?;
-        let text = payload.read_utf16_nul()?;
+        let text = payload.read_utf16_be_nul()?;
         Ok(Self {
             entity_id,
             kind,
@@ -182,7 +182,7 @@ impl Packet for UnicodeTextMessage {
         writer.write_u16::<Endian>(self.font)?;
         writer.write_str_fixed(&self.language)?;
         writer.write_str_fixed(&self.name)?;
-        writer.write_utf16_nul(&self.text)?;
+        writer.write_utf16_be_nul(&self.text)?;
         Ok(())
     }
 }
@@ -217,7 +217,7 @@ impl Packet for LocalisedTextMessage {
         let font = payload.read_u16::<Endian>()?;
         let text_id = payload.read_u32::<Endian>()?;
         let name = payload.read_str_fixed()?;
-        let params = payload.read_utf16le_nul()?;
+        let params = payload.read_utf16_le_nul()?;
         Ok(Self {
             entity_id,
             graphic_id,
@@ -242,7 +242,7 @@ impl Packet for LocalisedTextMessage {
         writer.write_u16::<Endian>(self.font)?;
         writer.write_u32::<Endian>(self.text_id)?;
         writer.write_str_fixed(&self.name)?;
-        writer.write_utf16le_nul(&self.params)?;
+        writer.write_utf16_le_nul(&self.params)?;
         Ok(())
     }
 }
@@ -325,7 +325,7 @@ impl Packet for UnicodeTextMessageRequest {
 
             payload.read_str_nul()?
         } else {
-            payload.read_utf16_nul()?
+            payload.read_utf16_be_nul()?
         };
 
         Ok(Self { kind, hue, font, language, text, keywords })
@@ -361,7 +361,7 @@ impl Packet for UnicodeTextMessageRequest {
             writer.write_u16::<Endian>(to_write << if have_bits { 4 } else { 8 })?;
             writer.write_str_nul(&self.text)?;
         } else {
-            writer.write_utf16_nul(&self.text)?;
+            writer.write_utf16_be_nul(&self.text)?;
         }
 
         Ok(())
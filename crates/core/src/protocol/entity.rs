@@ -174,7 +174,7 @@ impl Packet for UpsertEntityLegacy {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
 pub struct UpsertEntityWorld {
     pub id: EntityId,
     pub kind: EntityKind,
@@ -202,6 +202,7 @@ impl Packet for UpsertEntityWorld {
         let graphic_id = payload.read_u16::<Endian>()?;
         let graphic_inc = payload.read_u8()?;
         let quantity = payload.read_u16::<Endian>()?;
+        // The quantity is repeated.
         payload.skip(2)?;
         let x = payload.read_u16::<Endian>()? as i32;
         let y = payload.read_u16::<Endian>()? as i32;
@@ -233,6 +234,7 @@ impl Packet for UpsertEntityWorld {
         writer.write_entity_id(self.id)?;
         writer.write_u16::<Endian>(self.graphic_id)?;
         writer.write_u8(self.graphic_inc)?;
+        // The protocol sends the quantity twice; decode skips the second copy.
         writer.write_u16::<Endian>(self.quantity)?;
         writer.write_u16::<Endian>(self.quantity)?;
         writer.write_u16::<Endian>(self.position.x as u16)?;
@@ -408,6 +410,7 @@ impl Packet for UpsertEntityCharacter {
             let hue = if client_version >= Self::MIN_VERSION_HUE {
                 payload.read_u16::<Endian>()?
             } else if graphic_id & 0x8000 != 0 {
+                // Older clients flag the presence of a hue in the top bit of the graphic.
                 graphic_id &= 0x7fff;
                 payload.read_u16::<Endian>()?
             } else {
@@ -416,7 +419,7 @@ impl Packet for UpsertEntityCharacter {
 
             equipment.push(CharacterEquipment {
                 id: child_id,
-                graphic_id: graphic_id & 0x7fff,
+                graphic_id,
                 slot,
                 hue,
             });
@@ -447,15 +450,21 @@ impl Packet for UpsertEntityCharacter {
 
         for item in self.equipment.iter() {
             writer.write_entity_id(item.id)?;
-            if client_version >= Self::MIN_VERSION_HUE || item.hue == 0 {
+            if client_version >= Self::MIN_VERSION_HUE {
                 writer.write_u16::<Endian>(item.graphic_id)?;
-            } else if item.hue != 0 {
-                writer.write_u16::<Endian>(item.graphic_id | 0x8000)?;
-            }
-            writer.write_u8(item.slot as u8)?;
-
-            if client_version >= Self::MIN_VERSION_HUE || item.hue != 0 {
+                writer.write_u8(item.slot as u8)?;
                 writer.write_u16::<Endian>(item.hue)?;
+            } else {
+                // The top bit signals a trailing hue, so it must never leak from the graphic.
+                let graphic_id = item.graphic_id & 0x7fff;
+                if item.hue != 0 {
+                    writer.write_u16::<Endian>(graphic_id | 0x8000)?;
+                    writer.write_u8(item.slot as u8)?;
+                    writer.write_u16::<Endian>(item.hue)?;
+                } else {
+                    writer.write_u16::<Endian>(graphic_id)?;
+                    writer.write_u8(item.slot as u8)?;
+                }
             }
         }
 
@@ -630,7 +639,7 @@ impl Packet for EntityTooltip {
                 break;
             }
 
-            let params = Cow::Owned(payload.read_utf16le_pascal()?);
+            let params = Cow::Owned(payload.read_utf16_le_pascal()?);
             entries.push(EntityTooltipLine { text_id, params });
         }
 
@@ -645,7 +654,7 @@ impl Packet for EntityTooltip {
 
         for line in &self.entries {
             writer.write_u32::<Endian>(line.text_id)?;
-            writer.write_utf16le_pascal(&line.params)?;
+            writer.write_utf16_le_pascal(&line.params)?;
         }
 
         writer.write_u32::<Endian>(0)?;
@@ -1149,3 +1158,89 @@ impl Packet for EntityLightLevel {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_VERSION: ClientVersion = ClientVersion::new(7, 0, 9, 0);
+    const NEW_VERSION: ClientVersion = ClientVersion::new(7, 0, 33, 1);
+
+    fn character(equipment: &[(u16, u16)]) -> UpsertEntityCharacter {
+        UpsertEntityCharacter {
+            id: EntityId::from_u32(1),
+            body_type: 0x190,
+            position: IVec3::new(100, 200, 5),
+            hue: 0x83ea,
+            equipment: equipment.iter()
+                .enumerate()
+                .map(|(index, (graphic_id, hue))| CharacterEquipment {
+                    id: EntityId::from_u32(0x40000001 + index as u32),
+                    graphic_id: *graphic_id,
+                    slot: EquipmentSlot::Head,
+                    hue: *hue,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn roundtrip(version: ClientVersion, packet: &UpsertEntityCharacter) -> UpsertEntityCharacter {
+        let mut buffer = Vec::new();
+        packet.encode(version, &mut buffer).unwrap();
+        UpsertEntityCharacter::decode(version, &buffer).unwrap()
+    }
+
+    #[test]
+    fn test_world_item_roundtrip() {
+        let packet = UpsertEntityWorld {
+            id: EntityId::from_u32(0x40000010),
+            kind: EntityKind::Item,
+            graphic_id: 0xeed,
+            graphic_inc: 0,
+            direction: Direction::North,
+            quantity: 1500,
+            position: IVec3::new(1400, 1600, 10),
+            hue: 0x35,
+            flags: EntityFlags::empty(),
+        };
+
+        for version in [ClientVersion::new(6, 0, 14, 2), VERSION_HIGH_SEAS] {
+            let mut buffer = Vec::new();
+            packet.encode(version, &mut buffer).unwrap();
+            let expected_length = UpsertEntityWorld::fixed_length(version).unwrap();
+            assert_eq!(buffer.len() + 1, expected_length, "version {version}");
+            assert_eq!(&buffer[10..14], &[0x05, 0xdc, 0x05, 0xdc]);
+            assert_eq!(UpsertEntityWorld::decode(version, &buffer).unwrap(), packet);
+        }
+    }
+
+    #[test]
+    fn test_equipment_hue_roundtrip() {
+        let packet = character(&[(0x1415, 0), (0x1408, 0x44e), (0x1f03, 0)]);
+        for version in [OLD_VERSION, NEW_VERSION] {
+            let decoded = roundtrip(version, &packet);
+            assert_eq!(decoded.hue, packet.hue);
+            assert_eq!(decoded.equipment.len(), 3, "version {version}");
+            for (decoded, expected) in decoded.equipment.iter().zip(packet.equipment.iter()) {
+                assert_eq!(decoded.id, expected.id);
+                assert_eq!(decoded.graphic_id, expected.graphic_id);
+                assert_eq!(decoded.slot, expected.slot);
+                assert_eq!(decoded.hue, expected.hue);
+            }
+        }
+    }
+
+    #[test]
+    fn test_old_client_masks_graphic() {
+        let packet = character(&[(0x9415, 0), (0x1408, 0)]);
+        let decoded = roundtrip(OLD_VERSION, &packet);
+        assert_eq!(decoded.equipment.len(), 2);
+        assert_eq!(decoded.equipment[0].graphic_id, 0x1415);
+        assert_eq!(decoded.equipment[0].hue, 0);
+        assert_eq!(decoded.equipment[1].graphic_id, 0x1408);
+
+        let decoded = roundtrip(NEW_VERSION, &packet);
+        assert_eq!(decoded.equipment[0].graphic_id, 0x9415);
+    }
+}
@@ -6,7 +6,7 @@ use byteorder::{ReadBytesExt, WriteBytesExt};
 use smallvec::SmallVec;
 use tracing::warn;
 
-use crate::protocol::{ClientFlags, PacketReadExt, PacketWriteExt};
+use crate::protocol::{ClientFlags, PacketReadExt, PacketWriteExt, SkillLock};
 use crate::EntityId;
 
 use super::{ClientVersion, Endian, Packet};
@@ -53,6 +53,13 @@ pub struct ContextMenuResponse {
     pub id: u16,
 }
 
+#[derive(Debug, Clone)]
+pub struct StatLock {
+    /// 0 for strength, 1 for dexterity and 2 for intelligence.
+    pub stat: u8,
+    pub lock: SkillLock,
+}
+
 #[derive(Debug, Clone)]
 pub enum ExtendedCommand {
     Unknown(u16),
@@ -66,6 +73,7 @@ pub enum ExtendedCommand {
     ContextMenu(ContextMenu),
     ContextMenuEnhanced(ContextMenu),
     ContextMenuResponse(ContextMenuResponse),
+    StatLock(StatLock),
 }
 
 impl ExtendedCommand {
@@ -78,6 +86,7 @@ impl ExtendedCommand {
     const CONTEXT_MENU_REQUEST: u16 = 0x13;
     const CONTEXT_MENU: u16 = 0x14;
     const CONTEXT_MENU_RESPONSE: u16 = 0x15;
+    const STAT_LOCK: u16 = 0x1a;
 
     const CLASSIC_CONTEXT_MIN_TEXT_ID: u32 = 3000000;
 
@@ -94,6 +103,7 @@ impl ExtendedCommand {
             ExtendedCommand::ContextMenu(_) => Self::CONTEXT_MENU,
             ExtendedCommand::ContextMenuEnhanced(_) => Self::CONTEXT_MENU,
             ExtendedCommand::ContextMenuResponse(_) => Self::CONTEXT_MENU_RESPONSE,
+            ExtendedCommand::StatLock(_) => Self::STAT_LOCK,
         }
     }
 }
@@ -161,6 +171,12 @@ impl Packet for ExtendedCommand {
                 let id = payload.read_u16::<Endian>()?;
                 Ok(ExtendedCommand::ContextMenuResponse(ContextMenuResponse { id, target_id }))
             }
+            Self::STAT_LOCK => {
+                let stat = payload.read_u8()?;
+                let lock = SkillLock::from_repr(payload.read_u8()?)
+                    .ok_or_else(|| anyhow!("invalid stat lock"))?;
+                Ok(ExtendedCommand::StatLock(StatLock { stat, lock }))
+            }
             c => {
                 warn!("Unknown extended packet {kind}");
                 Ok(ExtendedCommand::Unknown(c))
@@ -231,6 +247,10 @@ impl Packet for ExtendedCommand {
                 writer.write_entity_id(response.target_id)?;
                 writer.write_u16::<Endian>(response.id)?;
             }
+            ExtendedCommand::StatLock(stat_lock) => {
+                writer.write_u8(stat_lock.stat)?;
+                writer.write_u8(stat_lock.lock as u8)?;
+            }
         }
         Ok(())
     }
@@ -253,3 +273,29 @@ impl Packet for ExtendedCommandAos {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION: ClientVersion = ClientVersion::new(7, 0, 9, 0);
+
+    #[test]
+    fn test_stat_lock_roundtrip() {
+        let packet = ExtendedCommand::StatLock(StatLock {
+            stat: 1,
+            lock: SkillLock::Locked,
+        });
+        let mut buffer = Vec::new();
+        packet.encode(VERSION, &mut buffer).unwrap();
+        assert_eq!(buffer, [0, 0x1a, 1, 2]);
+
+        let ExtendedCommand::StatLock(decoded) = ExtendedCommand::decode(VERSION, &buffer).unwrap() else {
+            panic!("expected a stat lock");
+        };
+        assert_eq!(decoded.stat, 1);
+        assert_eq!(decoded.lock, SkillLock::Locked);
+
+        assert!(ExtendedCommand::decode(VERSION, &[0, 0x1a, 1, 7]).is_err());
+    }
+}
@@ -15,12 +15,13 @@ pub trait PacketWriteExt {
     fn write_zeros(&mut self, count: usize) -> anyhow::Result<()>;
     fn write_str_fixed<const I: usize>(&mut self, src: &FixedString<I>) -> anyhow::Result<()>;
     fn write_str_block(&mut self, src: &str, block_size: usize) -> anyhow::Result<()>;
+    fn try_write_str_block(&mut self, src: &str, block_size: usize) -> anyhow::Result<()>;
     fn write_str_pascal(&mut self, src: &str) -> anyhow::Result<()>;
     fn write_str_nul(&mut self, src: &str) -> anyhow::Result<()>;
-    fn write_utf16_nul(&mut self, src: &str) -> anyhow::Result<()>;
-    fn write_utf16_pascal(&mut self, src: &str) -> anyhow::Result<()>;
-    fn write_utf16le_nul(&mut self, src: &str) -> anyhow::Result<()>;
-    fn write_utf16le_pascal(&mut self, src: &str) -> anyhow::Result<()>;
+    fn write_utf16_be_nul(&mut self, src: &str) -> anyhow::Result<()>;
+    fn write_utf16_be_pascal(&mut self, src: &str) -> anyhow::Result<()>;
+    fn write_utf16_le_nul(&mut self, src: &str) -> anyhow::Result<()>;
+    fn write_utf16_le_pascal(&mut self, src: &str) -> anyhow::Result<()>;
     fn write_entity_id(&mut self, src: EntityId) -> anyhow::Result<()>;
     fn write_direction(&mut self, src: Direction) -> anyhow::Result<()>;
 }
@@ -49,6 +50,22 @@ impl<T: Write> PacketWriteExt for T {
         }
     }
 
+    /// Write a fixed-length UTF-8 block, failing rather than truncating.
+    ///
+    /// The string must fit in the block with room for a NUL terminator and
+    /// must not contain any NULs itself, since readers stop at the first one.
+    fn try_write_str_block(&mut self, src: &str, block_size: usize) -> anyhow::Result<()> {
+        if src.len() >= block_size {
+            return Err(anyhow!("string too long for {block_size} byte block"));
+        }
+
+        if src.contains('\0') {
+            return Err(anyhow!("string contains embedded NUL"));
+        }
+
+        self.write_str_block(src, block_size)
+    }
+
     fn write_str_pascal(&mut self, src: &str) -> anyhow::Result<()> {
         self.write_u16::<Endian>(src.len() as u16)?;
         self.write_all(src.as_bytes())?;
@@ -61,7 +78,7 @@ impl<T: Write> PacketWriteExt for T {
         Ok(())
     }
 
-    fn write_utf16_nul(&mut self, src: &str) -> anyhow::Result<()> {
+    fn write_utf16_be_nul(&mut self, src: &str) -> anyhow::Result<()> {
         for c in src.encode_utf16() {
             self.write_u16::<Endian>(c)?;
         }
@@ -69,7 +86,8 @@ impl<T: Write> PacketWriteExt for T {
         Ok(())
     }
 
-    fn write_utf16_pascal(&mut self, src: &str) -> anyhow::Result<()> {
+    /// Write a big-endian UTF-16 string, prefixed by its length in code units.
+    fn write_utf16_be_pascal(&mut self, src: &str) -> anyhow::Result<()> {
         let utf16 = src.encode_utf16();
         let len = utf16.clone().count();
         self.write_u16::<Endian>(len as u16)?;
@@ -79,7 +97,7 @@ impl<T: Write> PacketWriteExt for T {
         Ok(())
     }
 
-    fn write_utf16le_nul(&mut self, src: &str) -> anyhow::Result<()> {
+    fn write_utf16_le_nul(&mut self, src: &str) -> anyhow::Result<()> {
         for c in src.encode_utf16() {
             self.write_u16::<LE>(c)?;
         }
@@ -87,7 +105,8 @@ impl<T: Write> PacketWriteExt for T {
         Ok(())
     }
 
-    fn write_utf16le_pascal(&mut self, src: &str) -> anyhow::Result<()> {
+    /// Write a little-endian UTF-16 string, prefixed by its length in bytes.
+    fn write_utf16_le_pascal(&mut self, src: &str) -> anyhow::Result<()> {
         let utf16 = src.encode_utf16();
         let len = utf16.clone().count();
         self.write_u16::<Endian>((len * size_of::<u16>()) as u16)?;
@@ -110,12 +129,13 @@ pub trait PacketReadExt {
     fn skip(&mut self, count: usize) -> anyhow::Result<()>;
     fn read_str_fixed<const I: usize>(&mut self) -> anyhow::Result<FixedString<I>>;
     fn read_str_block(&mut self, block_size: usize) -> anyhow::Result<String>;
+    fn read_str_block_lossy(&mut self, block_size: usize) -> anyhow::Result<String>;
     fn read_str_nul(&mut self) -> anyhow::Result<String>;
     fn read_str_pascal(&mut self) -> anyhow::Result<String>;
-    fn read_utf16_nul(&mut self) -> anyhow::Result<String>;
-    fn read_utf16_pascal(&mut self) -> anyhow::Result<String>;
-    fn read_utf16le_nul(&mut self) -> anyhow::Result<String>;
-    fn read_utf16le_pascal(&mut self) -> anyhow::Result<String>;
+    fn read_utf16_be_nul(&mut self) -> anyhow::Result<String>;
+    fn read_utf16_be_pascal(&mut self) -> anyhow::Result<String>;
+    fn read_utf16_le_nul(&mut self) -> anyhow::Result<String>;
+    fn read_utf16_le_pascal(&mut self) -> anyhow::Result<String>;
     fn read_entity_id(&mut self) -> anyhow::Result<EntityId>;
     fn read_direction(&mut self) -> anyhow::Result<Direction>;
 }
@@ -145,16 +165,31 @@ impl PacketReadExt for &[u8] {
         }
     }
 
+    /// Read a fixed-length UTF-8 block, failing on invalid UTF-8.
+    ///
+    /// The string ends at the first NUL and any bytes after it are not
+    /// validated. The reader is only advanced on success.
     fn read_str_block(&mut self, block_size: usize) -> anyhow::Result<String> {
         if self.len() < block_size {
             Err(anyhow!("unexpected EOF"))
         } else {
-            let mut str_ref = std::str::from_utf8(&self[..block_size])?;
-            if let Some(idx) = str_ref.find('\0') {
-                str_ref = &str_ref[..idx];
-            }
+            let bytes = truncate_at_nul(&self[..block_size]);
+            let result = std::str::from_utf8(bytes)?.to_string();
+            *self = &self[block_size..];
+            Ok(result)
+        }
+    }
 
-            let result = str_ref.to_string();
+    /// Read a fixed-length UTF-8 block, replacing invalid UTF-8 with U+FFFD.
+    ///
+    /// Only use this for display text, where a garbled string is better
+    /// than dropping the packet.
+    fn read_str_block_lossy(&mut self, block_size: usize) -> anyhow::Result<String> {
+        if self.len() < block_size {
+            Err(anyhow!("unexpected EOF"))
+        } else {
+            let bytes = truncate_at_nul(&self[..block_size]);
+            let result = String::from_utf8_lossy(bytes).into_owned();
             *self = &self[block_size..];
             Ok(result)
         }
@@ -177,7 +212,7 @@ impl PacketReadExt for &[u8] {
         Ok(result.to_string())
     }
 
-    fn read_utf16_nul(&mut self) -> anyhow::Result<String> {
+    fn read_utf16_be_nul(&mut self) -> anyhow::Result<String> {
         if let Some(idx) = self.windows(2).position(|window| window == [0, 0]) {
             let result = self[..idx]
                 .chunks_exact(2)
@@ -192,15 +227,19 @@ impl PacketReadExt for &[u8] {
         }
     }
 
-    fn read_utf16_pascal(&mut self) -> anyhow::Result<String> {
-        let len = self.read_u16::<Endian>()? as usize;
-        let bytes = &self[..len * 2];
-        let result = utf16_slice_to_string(bytes);
-        *self = &self[len * 2..];
+    /// Read a big-endian UTF-16 string, prefixed by its length in code units.
+    fn read_utf16_be_pascal(&mut self) -> anyhow::Result<String> {
+        let len = self.read_u16::<Endian>()? as usize * 2;
+        if self.len() < len {
+            return Err(anyhow!("unexpected EOF"));
+        }
+
+        let result = utf16_be_slice_to_string(&self[..len]);
+        *self = &self[len..];
         Ok(result)
     }
 
-    fn read_utf16le_nul(&mut self) -> anyhow::Result<String> {
+    fn read_utf16_le_nul(&mut self) -> anyhow::Result<String> {
         if let Some(idx) = self.windows(2).position(|window| window == [0, 0]) {
             let result = self[..idx]
                 .chunks_exact(2)
@@ -215,10 +254,14 @@ impl PacketReadExt for &[u8] {
         }
     }
 
-    fn read_utf16le_pascal(&mut self) -> anyhow::Result<String> {
+    /// Read a little-endian UTF-16 string, prefixed by its length in bytes.
+    fn read_utf16_le_pascal(&mut self) -> anyhow::Result<String> {
         let len = self.read_u16::<Endian>()? as usize;
-        let bytes = &self[..len];
-        let result = utf16le_slice_to_string(bytes);
+        if self.len() < len {
+            return Err(anyhow!("unexpected EOF"));
+        }
+
+        let result = utf16_le_slice_to_string(&self[..len]);
         *self = &self[len..];
         Ok(result)
     }
@@ -232,7 +275,14 @@ impl PacketReadExt for &[u8] {
     }
 }
 
-pub fn utf16_slice_to_string(bytes: &[u8]) -> String {
+fn truncate_at_nul(bytes: &[u8]) -> &[u8] {
+    match bytes.iter().position(|b| *b == 0) {
+        Some(idx) => &bytes[..idx],
+        None => bytes,
+    }
+}
+
+pub fn utf16_be_slice_to_string(bytes: &[u8]) -> String {
     bytes.chunks_exact(2)
         .map(Endian::read_u16)
         .to_utf16chars()
@@ -240,7 +290,7 @@ pub fn utf16_slice_to_string(bytes: &[u8]) -> String {
         .collect()
 }
 
-pub fn utf16le_slice_to_string(bytes: &[u8]) -> String {
+pub fn utf16_le_slice_to_string(bytes: &[u8]) -> String {
     bytes.chunks_exact(2)
         .map(LE::read_u16)
         .to_utf16chars()
@@ -248,4 +298,78 @@ pub fn utf16le_slice_to_string(bytes: &[u8]) -> String {
         .collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_str_block_clean() {
+        let mut buffer = Vec::new();
+        buffer.try_write_str_block("Britain", 16).unwrap();
+        assert_eq!(buffer.len(), 16);
+
+        let mut reader = &buffer[..];
+        assert_eq!(reader.read_str_block(16).unwrap(), "Britain");
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_str_block_embedded_nul() {
+        let mut block = [0u8; 8];
+        block[..3].copy_from_slice(b"Yew");
+        block[4..7].copy_from_slice(b"\xff\xfeX");
+
+        let mut reader = &block[..];
+        assert_eq!(reader.read_str_block(8).unwrap(), "Yew");
+        assert!(reader.is_empty());
+
+        let mut buffer = Vec::new();
+        assert!(buffer.try_write_str_block("Y\0ew", 8).is_err());
+        assert!(buffer.try_write_str_block("Trinsic", 7).is_err());
+    }
+
+    #[test]
+    fn test_str_block_invalid_utf8() {
+        let block = [b'M', 0xc3, 0x28, b'n', 0, 0];
+
+        let mut reader = &block[..];
+        assert!(reader.read_str_block(6).is_err());
+        assert_eq!(reader.len(), 6);
 
+        assert_eq!(reader.read_str_block_lossy(6).unwrap(), "M\u{fffd}(n");
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_utf16_be_pascal_roundtrip() {
+        let text = "Vendor \u{1f4b0} 42";
+        let mut buffer = Vec::new();
+        buffer.write_utf16_be_pascal(text).unwrap();
+        assert_eq!(&buffer[..2], &[0, 12]);
+        assert_eq!(&buffer[2..4], &[0, b'V']);
+
+        let mut reader = &buffer[..];
+        assert_eq!(reader.read_utf16_be_pascal().unwrap(), text);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_utf16_le_pascal_roundtrip() {
+        let text = "Vendor \u{1f4b0} 42";
+        let mut buffer = Vec::new();
+        buffer.write_utf16_le_pascal(text).unwrap();
+        assert_eq!(&buffer[..2], &[0, 24]);
+        assert_eq!(&buffer[2..4], &[b'V', 0]);
+
+        let mut reader = &buffer[..];
+        assert_eq!(reader.read_utf16_le_pascal().unwrap(), text);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_utf16_pascal_truncated() {
+        let buffer = [0u8, 4, 0, b'a'];
+        assert!((&buffer[..]).read_utf16_be_pascal().is_err());
+        assert!((&buffer[..]).read_utf16_le_pascal().is_err());
+    }
+}
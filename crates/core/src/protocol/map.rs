@@ -1,6 +1,8 @@
 use std::io::Write;
 
+use anyhow::anyhow;
 use byteorder::{ReadBytesExt, WriteBytesExt};
+use strum_macros::FromRepr;
 
 use super::{ClientVersion, Packet};
 
@@ -53,6 +55,45 @@ impl Packet for ChangeSeason {
     }
 }
 
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromRepr)]
+pub enum WeatherKind {
+    Rain = 0,
+    Storm = 1,
+    Snow = 2,
+    StormBrewing = 3,
+    #[default]
+    None = 0xff,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SetWeather {
+    pub kind: WeatherKind,
+    /// The number of particles to show, up to 70.
+    pub intensity: u8,
+    pub temperature: u8,
+}
+
+impl Packet for SetWeather {
+    const PACKET_KIND: u8 = 0x65;
+    fn fixed_length(_client_version: ClientVersion) -> Option<usize> { Some(4) }
+
+    fn decode(_client_version: ClientVersion, mut payload: &[u8]) -> anyhow::Result<Self> {
+        let kind = WeatherKind::from_repr(payload.read_u8()?)
+            .ok_or_else(|| anyhow!("invalid weather kind"))?;
+        let intensity = payload.read_u8()?;
+        let temperature = payload.read_u8()?;
+        Ok(SetWeather { kind, intensity, temperature })
+    }
+
+    fn encode(&self, _client_version: ClientVersion, writer: &mut impl Write) -> anyhow::Result<()> {
+        writer.write_u8(self.kind as u8)?;
+        writer.write_u8(self.intensity)?;
+        writer.write_u8(self.temperature)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ViewRange(pub u8);
 
@@ -86,3 +127,41 @@ impl Packet for GlobalLightLevel {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION: ClientVersion = ClientVersion::new(7, 0, 9, 0);
+
+    #[test]
+    fn test_season_roundtrip() {
+        let season = ChangeSeason { season: 3, play_sound: true };
+        let mut buffer = Vec::new();
+        season.encode(VERSION, &mut buffer).unwrap();
+        assert_eq!(buffer, [3, 1]);
+
+        let decoded = ChangeSeason::decode(VERSION, &buffer).unwrap();
+        assert_eq!(decoded.season, season.season);
+        assert_eq!(decoded.play_sound, season.play_sound);
+    }
+
+    #[test]
+    fn test_weather_roundtrip() {
+        let weather = SetWeather {
+            kind: WeatherKind::Snow,
+            intensity: 40,
+            temperature: 5,
+        };
+        let mut buffer = Vec::new();
+        weather.encode(VERSION, &mut buffer).unwrap();
+        assert_eq!(buffer, [2, 40, 5]);
+
+        let decoded = SetWeather::decode(VERSION, &buffer).unwrap();
+        assert_eq!(decoded.kind, weather.kind);
+        assert_eq!(decoded.intensity, weather.intensity);
+        assert_eq!(decoded.temperature, weather.temperature);
+
+        assert!(SetWeather::decode(VERSION, &[0x10, 0, 0]).is_err());
+    }
+}
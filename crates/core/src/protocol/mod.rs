@@ -23,7 +23,7 @@ pub use login::*;
 pub use map::*;
 pub use sound::*;
 pub use ui::*;
-pub use any::{AnyPacket, IntoAnyPacket};
+pub use any::{AnyDowncast, AnyPacket, IntoAnyPacket};
 
 mod format;
 
@@ -1,6 +1,7 @@
 use std::io::{Read, Write};
 use anyhow::anyhow;
 
+use bitflags::bitflags;
 use byteorder::{ReadBytesExt, WriteBytesExt};
 use flate2::Compression;
 use flate2::read::ZlibDecoder;
@@ -8,9 +9,9 @@ use flate2::write::{ZlibEncoder};
 use glam::IVec2;
 use smallvec::SmallVec;
 use crate::EntityId;
-use crate::protocol::{EntityFlags, PacketReadExt, PacketWriteExt};
+use crate::protocol::{PacketReadExt, PacketWriteExt};
 use crate::protocol::client_version::VERSION_HIGH_SEAS;
-use crate::protocol::format::utf16_slice_to_string;
+use crate::protocol::format::utf16_be_slice_to_string;
 use crate::types::FixedString;
 
 use super::{ClientVersion, Packet, Endian};
@@ -32,11 +33,19 @@ impl Packet for OpenChatWindow {
     }
 }
 
+bitflags! {
+    #[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+    pub struct PaperDollFlags : u8 {
+        const WAR_MODE = 0x1;
+        const CAN_LIFT = 0x2;
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OpenPaperDoll {
     pub id: EntityId,
     pub text: FixedString<60>,
-    pub flags: EntityFlags,
+    pub flags: PaperDollFlags,
 }
 
 impl Packet for OpenPaperDoll {
@@ -46,7 +55,7 @@ impl Packet for OpenPaperDoll {
     fn decode(_client_version: ClientVersion, mut payload: &[u8]) -> anyhow::Result<Self> {
         let id = payload.read_entity_id()?;
         let text = payload.read_str_fixed()?;
-        let flags = EntityFlags::from_bits_truncate(payload.read_u8()?);
+        let flags = PaperDollFlags::from_bits_truncate(payload.read_u8()?);
         Ok(Self { id, text, flags })
     }
 
@@ -113,7 +122,7 @@ impl TryFrom<CompressedGumpLayout> for GumpLayout {
             let len = z.read_u16::<Endian>()? as usize;
             tmp.resize(len * 2, 0u8);
             z.read_exact(&mut tmp)?;
-            let line = utf16_slice_to_string(&tmp);
+            let line = utf16_be_slice_to_string(&tmp);
             text.push(line);
         }
 
@@ -144,7 +153,7 @@ impl Packet for OpenGump {
         let text_count = payload.read_u16::<Endian>()? as usize;
         let mut text = Vec::with_capacity(text_count);
         for _ in 0..text_count {
-            let line = payload.read_utf16_pascal()?;
+            let line = payload.read_utf16_be_pascal()?;
             text.push(line);
         }
 
@@ -165,7 +174,7 @@ impl Packet for OpenGump {
 
         writer.write_u16::<Endian>(self.layout.text.len() as u16)?;
         for line in self.layout.text.iter() {
-            writer.write_utf16_pascal(line)?;
+            writer.write_utf16_be_pascal(line)?;
         }
 
         Ok(())
@@ -193,7 +202,7 @@ impl TryFrom<GumpLayout> for CompressedGumpLayout {
         let mut text = Vec::new();
         let mut z = ZlibEncoder::new(&mut text, Compression::fast());
         for line in source.text.iter() {
-            z.write_utf16_pascal(line)?;
+            z.write_utf16_be_pascal(line)?;
         }
         let text_length = z.total_in() as usize;
         z.finish()?;
@@ -276,13 +285,19 @@ impl Packet for OpenGumpCompressed {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GumpTextEntry {
+    pub id: u16,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct GumpResult {
     pub gump_id: u32,
     pub type_id: u32,
     pub button_id: u32,
     pub on_switches: SmallVec<[u32; 16]>,
-    pub text_fields: Vec<String>,
+    pub text_fields: Vec<GumpTextEntry>,
 }
 
 impl Packet for GumpResult {
@@ -303,7 +318,9 @@ impl Packet for GumpResult {
         let text_field_count = payload.read_u32::<Endian>()? as usize;
         let mut text_fields = Vec::with_capacity(text_field_count);
         for _ in 0..text_field_count {
-            text_fields.push(payload.read_utf16_pascal()?);
+            let id = payload.read_u16::<Endian>()?;
+            let text = payload.read_utf16_be_pascal()?;
+            text_fields.push(GumpTextEntry { id, text });
         }
 
         Ok(Self { gump_id: id, type_id, button_id, on_switches, text_fields })
@@ -318,9 +335,33 @@ impl Packet for GumpResult {
             writer.write_u32::<Endian>(*id)?;
         }
         writer.write_u32::<Endian>(self.text_fields.len() as u32)?;
-        for content in self.text_fields.iter() {
-            writer.write_utf16_pascal(content)?;
+        for entry in self.text_fields.iter() {
+            writer.write_u16::<Endian>(entry.id)?;
+            writer.write_utf16_be_pascal(&entry.text)?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::*;
+
+    #[test]
+    fn test_gump_result_roundtrip() {
+        let version = ClientVersion::new(7, 0, 9, 0);
+        let result = GumpResult {
+            gump_id: 0x1234,
+            type_id: 0x42,
+            button_id: 3,
+            on_switches: smallvec![5, 9],
+            text_fields: vec![GumpTextEntry { id: 7, text: "Gerome \u{e9}".into() }],
+        };
+
+        let mut buffer = Vec::new();
+        result.encode(version, &mut buffer).unwrap();
+        assert_eq!(GumpResult::decode(version, &buffer).unwrap(), result);
+    }
+}
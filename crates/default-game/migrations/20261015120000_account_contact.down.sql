@@ -0,0 +1,4 @@
+ALTER TABLE accounts
+    DROP COLUMN last_login_ip,
+    DROP COLUMN last_login_at,
+    DROP COLUMN email;
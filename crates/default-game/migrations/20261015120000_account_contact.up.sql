@@ -0,0 +1,4 @@
+ALTER TABLE accounts
+    ADD COLUMN email TEXT,
+    ADD COLUMN last_login_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN last_login_ip INET;
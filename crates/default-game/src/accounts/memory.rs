@@ -1,20 +1,23 @@
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use bevy::ecs::system::Resource;
+use chrono::Utc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use yewoh::protocol::{CreateCharacter, DeleteCharacter};
 
 use crate::accounts::DEFAULT_CHARACTER_SLOTS;
-use crate::accounts::repository::{AccountCharacter, AccountCharacters, AccountRepository, NewCharacterInfo, CharacterToSpawn};
+use crate::accounts::repository::{AccountCharacter, AccountCharacters, AccountDetails, AccountRepository, NewCharacterInfo, CharacterToSpawn};
 use crate::entities::new_uuid;
 
 #[derive(Debug, Clone, Default)]
 struct MemoryUser {
     characters: Vec<Uuid>,
+    details: AccountDetails,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -68,4 +71,68 @@ impl AccountRepository for MemoryAccountRepository {
         }
         Ok(CharacterToSpawn::ExistingCharacter(user.characters[slot as usize]))
     }
+
+    async fn account_details(&self, username: &str) -> anyhow::Result<AccountDetails> {
+        let locked = self.locked.lock().await;
+        Ok(locked.users.get(username)
+            .map(|user| user.details.clone())
+            .unwrap_or_default())
+    }
+
+    async fn set_email(&self, username: &str, email: Option<&str>) -> anyhow::Result<()> {
+        let mut locked = self.locked.lock().await;
+        let user = locked.users.get_mut(username)
+            .ok_or_else(|| anyhow::anyhow!("no such account {username}"))?;
+        user.details.email = email.map(str::to_string);
+        Ok(())
+    }
+
+    async fn record_login(&self, username: &str, address: IpAddr) -> anyhow::Result<()> {
+        let mut locked = self.locked.lock().await;
+        let user = locked.users.get_mut(username)
+            .ok_or_else(|| anyhow::anyhow!("no such account {username}"))?;
+        user.details.last_login_at = Some(Utc::now());
+        user.details.last_login_ip = Some(address);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use bevy::tasks::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_record_login() {
+        let repo = MemoryAccountRepository::default();
+        let address = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        assert!(block_on(repo.record_login("alice", address)).is_err());
+
+        block_on(repo.list_characters("alice")).unwrap();
+        let before = Utc::now();
+        block_on(repo.record_login("alice", address)).unwrap();
+
+        let details = block_on(repo.account_details("alice")).unwrap();
+        assert_eq!(details.last_login_ip, Some(address));
+        assert!(details.last_login_at.unwrap() >= before);
+    }
+
+    #[test]
+    fn test_set_email() {
+        let repo = MemoryAccountRepository::default();
+        assert!(block_on(repo.set_email("alice", Some("alice@example.com"))).is_err());
+
+        block_on(repo.list_characters("alice")).unwrap();
+        assert_eq!(block_on(repo.account_details("alice")).unwrap().email, None);
+
+        block_on(repo.set_email("alice", Some("alice@example.com"))).unwrap();
+        let details = block_on(repo.account_details("alice")).unwrap();
+        assert_eq!(details.email.as_deref(), Some("alice@example.com"));
+
+        block_on(repo.set_email("alice", None)).unwrap();
+        assert_eq!(block_on(repo.account_details("alice")).unwrap().email, None);
+    }
 }
@@ -9,16 +9,17 @@ use uuid::Uuid;
 use yewoh::protocol::{CharacterFromList, CharacterList, CharacterListFlags};
 use yewoh::types::FixedString;
 use yewoh_server::async_runtime::AsyncRuntime;
-use yewoh_server::world::account::{OnClientDeleteCharacter, OnClientCharacterListRequest, OnClientCreateCharacter, OnClientSelectCharacter, User};
+use yewoh_server::world::account::{OnClientDeleteCharacter, OnClientCharacterListRequest, OnClientCreateCharacter, OnClientSelectCharacter, ServerFeatures, User};
 use yewoh_server::world::characters::{CharacterBodyType, CharacterName, CharacterRace};
 use yewoh_server::world::connection::{NetClient, OwningClient, Possessing};
-use yewoh_server::world::entity::{EquipmentSlot, Hue, MapPosition};
+use yewoh_server::world::entity::{ContainedPosition, EquipmentSlot, Hue};
 use yewoh_server::world::items::ItemGraphic;
 use yewoh_server::world::ServerSet;
 
 use crate::accounts::repository::{AccountCharacters, AccountRepository, CharacterToSpawn, NewCharacterInfo};
 use crate::characters::persistence::{PersistName, PersistStats};
-use crate::characters::player::NewPlayerCharacter;
+use crate::characters::player::{NewPlayerCharacter, StartingBackpack};
+use crate::characters::skills::CharacterSkills;
 use crate::data::prefabs::PrefabLibraryWorldExt;
 use crate::data::static_data::StaticData;
 use crate::entities::persistence::PersistHue;
@@ -84,6 +85,7 @@ pub fn on_list_characters<T: AccountRepository>(
 pub fn handle_list_characters_callback(
     clients: Query<&NetClient>,
     static_data: Res<StaticData>,
+    features: Res<ServerFeatures>,
     mut pending: ResMut<PendingCharacterLists>,
     players_query: Query<(Entity, &UniqueId, &CharacterName), With<CharacterBodyType>>,
     mut all_players: Local<HashMap<Uuid, (Entity, String)>>,
@@ -104,12 +106,7 @@ pub fn handle_list_characters_callback(
 
         match result {
             Ok(characters) => {
-                let mut flags = CharacterListFlags::CONTEXT_MENU
-                    | CharacterListFlags::PALADIN_NECROMANCER_TOOLTIPS
-                    | CharacterListFlags::SAMURAI_NINJA
-                    | CharacterListFlags::ELVES
-                    | CharacterListFlags::NEW_MOVEMENT_SYSTEM
-                    | CharacterListFlags::ALLOW_FELUCCA;
+                let mut flags = features.character_list_flags;
 
                 if characters.len() > 6 {
                     flags |= CharacterListFlags::SEVENTH_CHARACTER_SLOT;
@@ -224,6 +221,7 @@ pub fn on_delete_character<T: AccountRepository>(
 pub fn create_new_character(
     commands: &mut Commands,
     static_data: &StaticData,
+    starting_backpack: &StartingBackpack,
     info: NewCharacterInfo,
 ) -> anyhow::Result<Entity> {
     let race_name = match info.race {
@@ -242,7 +240,7 @@ pub fn create_new_character(
         pants_hue: info.pants_hue,
     };
 
-    let Some(city) = static_data.cities.cities.get(info.city_index as usize) else {
+    let Some(position) = static_data.cities.starting_position(info.city_index as usize) else {
         bail!("Unknown city index {}", info.city_index);
     };
 
@@ -257,11 +255,9 @@ pub fn create_new_character(
             CharacterName(info.name.clone()),
             Hue(info.hue),
             info.stats,
+            CharacterSkills::from_new_character(&info.skills),
             new_character,
-            MapPosition {
-                map_id: city.map_id as u8,
-                position: city.position,
-            },
+            position,
         ))
         .id();
 
@@ -287,6 +283,22 @@ pub fn create_new_character(
             .move_to_equipped_position(entity, EquipmentSlot::FacialHair);
     }
 
+    let backpack = commands
+        .fabricate_prefab(&starting_backpack.prefab)
+        .insert(Persistent)
+        .move_to_equipped_position(entity, EquipmentSlot::Backpack)
+        .id();
+
+    for prefab in &starting_backpack.items {
+        commands
+            .fabricate_prefab(prefab)
+            .insert(Persistent)
+            .move_to_container_position(backpack, ContainedPosition {
+                position: IVec2::ZERO,
+                grid_index: 0,
+            });
+    }
+
     Ok(entity)
 }
 
@@ -294,6 +306,7 @@ pub fn create_new_character(
 pub fn handle_spawn_character<T: AccountRepository>(
     runtime: Res<AsyncRuntime>,
     static_data: Res<StaticData>,
+    starting_backpack: Res<StartingBackpack>,
     mut pending: ResMut<PendingCharacterInfo>,
     pending_list: ResMut<PendingCharacterLists>,
     mut commands: Commands,
@@ -344,7 +357,7 @@ pub fn handle_spawn_character<T: AccountRepository>(
             }
             CharacterToSpawn::NewCharacter(id, info) => {
                 info!("Creating new character: {}", &id);
-                let primary_entity = match create_new_character(&mut commands, &static_data, info) {
+                let primary_entity = match create_new_character(&mut commands, &static_data, &starting_backpack, info) {
                     Ok(x) => x,
                     Err(err) => {
                         warn!("failed to create character: {err}");
@@ -394,3 +407,165 @@ impl<T: AccountRepository> Plugin for AccountsPlugin<T> {
             ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::ecs::world::CommandQueue;
+    use bevy::utils::HashMap;
+    use bevy_fabricator::{Fabricated, Fabricator};
+    use yewoh_server::world::entity::{EquippedPosition, MapPosition};
+    use yewoh_server::world::items::Container;
+
+    use crate::accounts::repository::NewCharacterSkill;
+    use crate::data::cities::{Cities, City};
+    use crate::data::prefabs::PrefabLibrary;
+    use crate::networking::{sent_packets, test_client};
+
+    use super::*;
+
+    fn prefab_library() -> PrefabLibrary {
+        let mut library = PrefabLibrary::default();
+        library.insert("backpack".to_string(), Fabricator {
+            parameters: HashMap::new(),
+            factory: Arc::new(|entity, _, world| {
+                world.entity_mut(entity).insert(Container { gump_id: 0x3c });
+                Ok(Fabricated::default())
+            }),
+        });
+        library.insert("bandage".to_string(), Fabricator {
+            parameters: HashMap::new(),
+            factory: Arc::new(|_, _, _| Ok(Fabricated::default())),
+        });
+        library
+    }
+
+    #[test]
+    fn test_new_character_backpack() {
+        let mut world = World::new();
+        world.insert_resource(prefab_library());
+
+        let static_data = StaticData {
+            cities: Cities { cities: vec![City::default()] },
+            maps: default(),
+            skills: default(),
+            locations: default(),
+        };
+        let starting_backpack = StartingBackpack {
+            items: vec!["bandage".into()],
+            ..default()
+        };
+
+        let mut queue = CommandQueue::default();
+        let character = create_new_character(
+            &mut Commands::new(&mut queue, &world), &static_data, &starting_backpack, default(),
+        ).unwrap();
+        queue.apply(&mut world);
+
+        let backpack = world.get::<Children>(character).unwrap().iter()
+            .copied()
+            .find(|child| world.get::<EquippedPosition>(*child)
+                .is_some_and(|position| position.slot == EquipmentSlot::Backpack))
+            .unwrap();
+        assert!(world.get::<Container>(backpack).is_some());
+
+        let contents = world.get::<Children>(backpack).unwrap();
+        assert_eq!(contents.len(), 1);
+        assert!(world.get::<ContainedPosition>(contents[0]).is_some());
+    }
+
+    #[test]
+    fn test_new_character_city() {
+        let mut world = World::new();
+        world.insert_resource(prefab_library());
+
+        let city = |name: &str, position: IVec3| City {
+            name: name.into(),
+            map_id: 1,
+            position,
+            ..default()
+        };
+        let static_data = StaticData {
+            cities: Cities {
+                cities: vec![
+                    city("New Haven", IVec3::new(3503, 2574, 14)),
+                    city("Yew", IVec3::new(633, 858, 0)),
+                ],
+            },
+            maps: default(),
+            skills: default(),
+            locations: default(),
+        };
+        let starting_backpack = StartingBackpack::default();
+
+        let mut queue = CommandQueue::default();
+        let info = NewCharacterInfo { city_index: 1, ..default() };
+        let character = create_new_character(
+            &mut Commands::new(&mut queue, &world), &static_data, &starting_backpack, info,
+        ).unwrap();
+        queue.apply(&mut world);
+
+        assert_eq!(*world.get::<MapPosition>(character).unwrap(), MapPosition {
+            position: IVec3::new(633, 858, 0),
+            map_id: 1,
+        });
+
+        let info = NewCharacterInfo { city_index: 2, ..default() };
+        assert!(create_new_character(
+            &mut Commands::new(&mut queue, &world), &static_data, &starting_backpack, info,
+        ).is_err());
+    }
+
+    #[test]
+    fn test_new_character_skills() {
+        let mut world = World::new();
+        world.insert_resource(prefab_library());
+
+        let static_data = StaticData {
+            cities: Cities { cities: vec![City::default()] },
+            maps: default(),
+            skills: default(),
+            locations: default(),
+        };
+        let mut info = NewCharacterInfo::default();
+        info.skills[0] = NewCharacterSkill { skill_id: 40, points: 50 };
+
+        let mut queue = CommandQueue::default();
+        let character = create_new_character(
+            &mut Commands::new(&mut queue, &world), &static_data, &StartingBackpack::default(), info,
+        ).unwrap();
+        queue.apply(&mut world);
+
+        let skills = world.get::<CharacterSkills>(character).unwrap();
+        assert_eq!(skills.get(40).value, 500);
+        assert_eq!(skills.total(), 500);
+    }
+
+    #[test]
+    fn test_character_list_features() {
+        let features: ServerFeatures = serde_yaml::from_str("character_list_flags: CONTEXT_MENU | ELVES").unwrap();
+        assert_eq!(features.feature_flags.bits(), ServerFeatures::default().feature_flags.bits());
+
+        let mut world = World::new();
+        world.insert_resource(StaticData {
+            cities: Cities { cities: vec![City::default()] },
+            maps: default(),
+            skills: default(),
+            locations: default(),
+        });
+        world.insert_resource(features);
+        world.init_resource::<PendingCharacterLists>();
+
+        let (client, mut rx) = test_client();
+        let client_entity = world.spawn(client).id();
+        world.resource::<PendingCharacterLists>().tx.send((client_entity, Ok(vec![None, None]))).unwrap();
+        world.run_system_once(handle_list_characters_callback).unwrap();
+
+        let [packet] = &sent_packets::<CharacterList>(&mut rx)[..] else {
+            panic!("expected character list");
+        };
+        assert_eq!(packet.flags.bits(), (CharacterListFlags::CONTEXT_MENU | CharacterListFlags::ELVES).bits());
+    }
+}
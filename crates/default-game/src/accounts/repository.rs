@@ -1,5 +1,8 @@
+use std::net::IpAddr;
+
 use async_trait::async_trait;
 use bevy::prelude::*;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use yewoh::protocol;
 use yewoh::protocol::{CreateCharacter, DeleteCharacter};
@@ -99,10 +102,21 @@ pub struct AccountCharacter {
 
 pub type AccountCharacters = Vec<Option<AccountCharacter>>;
 
+/// Contact and login details stored against an account.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountDetails {
+    pub email: Option<String>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub last_login_ip: Option<IpAddr>,
+}
+
 #[async_trait]
 pub trait AccountRepository: Clone + Resource {
     async fn list_characters(&self, username: &str) -> anyhow::Result<AccountCharacters>;
     async fn create_character(&self, username: &str, request: CreateCharacter) -> anyhow::Result<CharacterToSpawn>;
     async fn delete_character(&self, username: &str, request: DeleteCharacter) -> anyhow::Result<()>;
     async fn load_character(&self, username: &str, slot: i32) -> anyhow::Result<CharacterToSpawn>;
+    async fn account_details(&self, username: &str) -> anyhow::Result<AccountDetails>;
+    async fn set_email(&self, username: &str, email: Option<&str>) -> anyhow::Result<()>;
+    async fn record_login(&self, username: &str, address: IpAddr) -> anyhow::Result<()>;
 }
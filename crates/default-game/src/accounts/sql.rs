@@ -1,10 +1,12 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use async_trait::async_trait;
 use bevy::ecs::system::Resource;
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use rand::thread_rng;
 use sqlx::{FromRow, PgPool};
@@ -14,13 +16,88 @@ use uuid::Uuid;
 use yewoh::protocol::{CreateCharacter, DeleteCharacter};
 use yewoh_server::lobby;
 
-use crate::accounts::repository::{AccountCharacter, AccountCharacters, AccountRepository, NewCharacterInfo, CharacterToSpawn};
+use crate::accounts::repository::{AccountCharacter, AccountCharacters, AccountDetails, AccountRepository, NewCharacterInfo, CharacterToSpawn};
 use crate::accounts::DEFAULT_CHARACTER_SLOTS;
 use crate::entities::new_uuid;
 
+/// Restrictions on which accounts may be created automatically on first login.
+#[derive(Debug, Clone, Default)]
+pub struct AutoCreatePolicy {
+    /// A pattern new usernames must match, where `*` matches any run of characters and `?`
+    /// matches any single character.
+    pub username_pattern: Option<String>,
+    pub min_password_length: usize,
+    /// If set, the first login must use `<invite code>:<password>` as the password.
+    pub invite_code: Option<String>,
+}
+
+impl AutoCreatePolicy {
+    /// Check whether an account may be created, returning the password to store.
+    pub fn check<'a>(&self, username: &str, password: &'a str) -> anyhow::Result<&'a str> {
+        if let Some(pattern) = &self.username_pattern {
+            if !glob_match(pattern, username) {
+                bail!("username {username} does not match the allowed pattern");
+            }
+        }
+
+        let password = match &self.invite_code {
+            Some(code) => password.strip_prefix(code.as_str())
+                .and_then(|rest| rest.strip_prefix(':'))
+                .ok_or_else(|| anyhow!("missing or invalid invite code"))?,
+            None => password,
+        };
+
+        if password.chars().count() < self.min_password_length {
+            bail!("password must be at least {} characters", self.min_password_length);
+        }
+
+        Ok(password)
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let mut p = 0;
+    let mut t = 0;
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => {
+                let Some((star, star_t)) = backtrack else {
+                    return false;
+                };
+                p = star + 1;
+                t = star_t + 1;
+                backtrack = Some((star, t));
+            }
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn expect_account_updated(username: &str, num_rows: u64) -> anyhow::Result<()> {
+    if num_rows != 1 {
+        return Err(anyhow!("no such account {username}"));
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct SqlAccountRepositoryConfig {
     pub auto_create_accounts: bool,
+    pub auto_create_policy: AutoCreatePolicy,
 }
 
 #[derive(Clone, FromRow)]
@@ -173,29 +250,154 @@ impl AccountRepository for SqlAccountRepository {
             Err(anyhow!("unable to log {username} in as slot {}", slot))
         }
     }
+
+    async fn account_details(&self, username: &str) -> anyhow::Result<AccountDetails> {
+        #[derive(FromRow)]
+        struct Details {
+            pub email: Option<String>,
+            pub last_login_at: Option<DateTime<Utc>>,
+            pub last_login_ip: Option<IpAddr>,
+        }
+
+        let details: Details = sqlx::query_as(
+            "SELECT email, last_login_at, last_login_ip FROM accounts WHERE username = $1")
+            .bind(username)
+            .fetch_optional(self.inner.pool.as_ref())
+            .await?
+            .ok_or_else(|| anyhow!("no such account"))?;
+        Ok(AccountDetails {
+            email: details.email,
+            last_login_at: details.last_login_at,
+            last_login_ip: details.last_login_ip,
+        })
+    }
+
+    async fn set_email(&self, username: &str, email: Option<&str>) -> anyhow::Result<()> {
+        let num_rows = sqlx::query("UPDATE accounts SET email = $1, updated_at = NOW() WHERE username = $2")
+            .bind(email)
+            .bind(username)
+            .execute(self.inner.pool.as_ref())
+            .await?
+            .rows_affected();
+        expect_account_updated(username, num_rows)
+    }
+
+    async fn record_login(&self, username: &str, address: IpAddr) -> anyhow::Result<()> {
+        let num_rows = sqlx::query("UPDATE accounts SET last_login_at = NOW(), last_login_ip = $1 WHERE username = $2")
+            .bind(address)
+            .bind(username)
+            .execute(self.inner.pool.as_ref())
+            .await?
+            .rows_affected();
+        expect_account_updated(username, num_rows)
+    }
 }
 
 #[async_trait]
 impl lobby::AccountRepository for SqlAccountRepository {
-    async fn login(&mut self, username: &str, password: &str) -> anyhow::Result<()> {
+    async fn login(&mut self, username: &str, password: &str, address: IpAddr) -> anyhow::Result<()> {
         let account = self.get_account_optional(username).await?;
-        let account: AccountDto = match account {
-            Some(x) => x,
+        match account {
+            Some(account) => {
+                let hash = PasswordHash::new(&account.password_hash)?;
+                if let Err(err) = self.inner.password_hasher.verify_password(password.as_bytes(), &hash) {
+                    warn!("bad login attempt: {err}");
+                    return Err(anyhow!("invalid username or password"));
+                }
+            }
             None => {
-                return if self.inner.config.auto_create_accounts {
-                    self.create_account(username, password).await
-                } else {
-                    Err(anyhow!("invalid username or password"))
-                };
+                if !self.inner.config.auto_create_accounts {
+                    return Err(anyhow!("invalid username or password"));
+                }
+
+                let password = self.inner.config.auto_create_policy.check(username, password)?;
+                self.create_account(username, password).await?;
             }
-        };
+        }
 
-        let hash = PasswordHash::new(&account.password_hash)?;
-        if let Err(err) = self.inner.password_hasher.verify_password(password.as_bytes(), &hash) {
-            warn!("bad login attempt: {err}");
-            Err(anyhow!("invalid username or password"))
-        } else {
-            Ok(())
+        // The login itself succeeded, so don't turn a bookkeeping failure into a refusal.
+        if let Err(err) = self.record_login(username, address).await {
+            warn!("failed to record login for {username}: {err}");
         }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("guest*", "guest42"));
+        assert!(glob_match("guest*", "guest"));
+        assert!(glob_match("a?c*z", "abcxyz"));
+        assert!(glob_match("*x*y", "axbxy"));
+        assert!(!glob_match("guest*", "admin"));
+        assert!(!glob_match("a?c", "ac"));
+    }
+
+    #[test]
+    fn test_auto_create_policy() {
+        let policy = AutoCreatePolicy {
+            username_pattern: Some("test*".into()),
+            min_password_length: 8,
+            invite_code: None,
+        };
+        assert_eq!(policy.check("tester", "password1").unwrap(), "password1");
+        assert!(policy.check("admin", "password1").is_err());
+        assert!(policy.check("tester", "short").is_err());
+
+        let policy = AutoCreatePolicy {
+            invite_code: Some("letmein".into()),
+            ..policy
+        };
+        assert_eq!(policy.check("tester", "letmein:password1").unwrap(), "password1");
+        assert!(policy.check("tester", "password1").is_err());
+        assert!(policy.check("tester", "letmein:short").is_err());
+    }
+    #[test]
+    fn test_expect_account_updated() {
+        assert!(expect_account_updated("tester", 1).is_ok());
+        let err = expect_account_updated("tester", 0).unwrap_err();
+        assert_eq!(err.to_string(), "no such account tester");
+    }
+
+    fn auto_create_accounts(pool: PgPool) -> SqlAccountRepository {
+        let config = SqlAccountRepositoryConfig {
+            auto_create_accounts: true,
+            auto_create_policy: AutoCreatePolicy::default(),
+        };
+        SqlAccountRepository::new(config, Arc::new(pool))
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres database in DATABASE_URL"]
+    async fn test_login_records_address(pool: PgPool) -> anyhow::Result<()> {
+        let mut accounts = auto_create_accounts(pool);
+        let address = "2001:db8::1".parse::<IpAddr>()?;
+        lobby::AccountRepository::login(&mut accounts, "tester", "password1", address).await?;
+
+        let details = accounts.account_details("tester").await?;
+        assert_eq!(details.last_login_ip, Some(address));
+        assert!(details.last_login_at.is_some());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres database in DATABASE_URL"]
+    async fn test_login_survives_record_failure(pool: PgPool) -> anyhow::Result<()> {
+        let mut accounts = auto_create_accounts(pool.clone());
+        let address = "192.0.2.1".parse::<IpAddr>()?;
+        lobby::AccountRepository::login(&mut accounts, "tester", "password1", address).await?;
+
+        sqlx::query("ALTER TABLE accounts DROP COLUMN last_login_ip")
+            .execute(&pool)
+            .await?;
+        assert!(accounts.record_login("tester", address).await.is_err());
+        lobby::AccountRepository::login(&mut accounts, "tester", "password1", address).await?;
+        assert!(lobby::AccountRepository::login(&mut accounts, "tester", "wrong", address).await.is_err());
+        Ok(())
     }
 }
@@ -1,16 +1,14 @@
 use bevy::prelude::*;
-use smallvec::smallvec;
 use yewoh::protocol;
-use yewoh::protocol::{MoveConfirm, PickUpReject, MoveReject, ProfileResponse, SkillEntry, SkillLock, SkillsResponse, SkillsResponseKind, EntityFlags};
-use yewoh_server::world::characters::{CharacterBodyType, NotorietyQuery, OnClientProfileRequest, OnClientSkillsRequest, WarMode};
+use yewoh::protocol::{MoveConfirm, PickUpReject, MoveReject, EntityFlags};
+use yewoh_server::world::characters::{CharacterBodyType, NotorietyQuery, WarMode};
 use yewoh_server::world::combat::{AttackTarget, OnClientWarModeChanged};
 use yewoh_server::world::connection::{NetClient, Possessing};
-use yewoh_server::world::entity::{ContainedPosition, Direction, EquippedPosition, MapPosition, RootPosition};
-use yewoh_server::world::input::{OnClientDrop, OnClientEquip, OnClientMove, OnClientPickUp};
+use yewoh_server::world::entity::{ContainedPosition, Direction, EquipmentSlot, EquippedPosition, Frozen, MapPosition, RootPosition};
+use yewoh_server::world::input::{MoveThrottle, OnClientDrop, OnClientEquip, OnClientMove, OnClientPickUp};
 use yewoh_server::world::items::{Container, ItemPosition, ItemQuantity, PositionQuery};
 use yewoh_server::world::map::{Chunk, TileDataResource};
 use yewoh_server::world::navigation::try_move_in_direction;
-use yewoh_server::world::net_id::NetId;
 use yewoh_server::world::spatial::SpatialQuery;
 use yewoh_server::world::ServerSet;
 use yewoh_server::world::sound::{OnClientSound, SoundKind};
@@ -20,7 +18,8 @@ use crate::data::prefabs::PrefabLibraryWorldExt;
 use crate::entities::position::PositionExt;
 use crate::entities::{Persistent, PrefabInstance};
 use crate::entities::tooltips::MarkTooltipChanged;
-use crate::items::common::{CanLift, DropSound, Stackable};
+use crate::housing::HousePermissions;
+use crate::items::common::{CanLift, DropSound, MaxStack, Stackable};
 use crate::items::MAX_STACK;
 
 #[derive(Debug, Clone, Component, Reflect)]
@@ -38,22 +37,45 @@ pub fn on_client_move(
     spatial_query: SpatialQuery,
     chunk_query: Query<(&MapPosition, &Chunk)>,
     tile_data: Res<TileDataResource>,
-    mut connection_query: Query<(&NetClient, &Possessing, &mut ExpectedCharacterState)>,
-    mut characters: Query<(&mut MapPosition, &mut Direction, NotorietyQuery), Without<Chunk>>,
+    mut connection_query: Query<(&NetClient, &Possessing, &mut ExpectedCharacterState, &mut MoveThrottle)>,
+    mut characters: Query<(&mut MapPosition, &mut Direction, &Frozen, Option<&Children>, NotorietyQuery), Without<Chunk>>,
+    equipment: Query<&EquippedPosition>,
     mut events: EventReader<OnClientMove>,
 ) {
     for request in events.read() {
-        let Ok((client, owned, mut expected)) = connection_query.get_mut(request.client_entity) else {
+        let Ok((client, owned, mut expected, mut throttle)) = connection_query.get_mut(request.client_entity) else {
             continue;
         };
 
         let primary_entity = owned.entity;
-        let Ok((mut map_position, mut direction, notoriety)) = characters.get_mut(primary_entity) else {
+        let Ok((mut map_position, mut direction, frozen, children, notoriety)) = characters.get_mut(primary_entity) else {
             continue;
         };
 
-        if *direction != request.direction {
+        if **frozen {
+            client.send_packet(MoveReject {
+                sequence: request.sequence,
+                position: map_position.position,
+                direction: (*direction).into(),
+            });
+            continue;
+        }
+
+        throttle.mounted = children.iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|child| equipment.get(*child).ok())
+            .any(|position| position.slot == EquipmentSlot::Mount);
+
+        if request.is_turn(*direction) {
+            // Turning in place doesn't move the character or count as a step.
             *direction = request.direction;
+        } else if !throttle.try_step(request.received_at, request.run) {
+            client.send_packet(MoveReject {
+                sequence: request.sequence,
+                position: map_position.position,
+                direction: (*direction).into(),
+            });
+            continue;
         } else {
             match try_move_in_direction(&spatial_query, &chunk_query, &tile_data, *map_position, request.direction, Some(primary_entity)) {
                 Ok(new_position) => {
@@ -79,13 +101,29 @@ pub fn on_client_move(
     }
 }
 
+fn is_frozen(frozen: Option<&Frozen>) -> bool {
+    frozen.is_some_and(|frozen| **frozen)
+}
+
+/// Put a held item back where it was picked up from, i.e. when a drop is refused.
+fn return_held_item(commands: &mut Commands, character: Entity, character_position: &MapPosition, held: &Held) {
+    let position = held.previous_position.clone()
+        .unwrap_or(ItemPosition::Map(*character_position));
+    commands.entity(held.held_entity)
+        .remove::<Holder>()
+        .move_to_item_position(position);
+    commands.entity(character)
+        .remove::<Held>();
+}
+
 pub fn on_client_pick_up(
     clients: Query<(&NetClient, &Possessing)>,
-    characters: Query<Option<&Held>>,
+    characters: Query<(Option<&Held>, Option<&Frozen>)>,
     targets: Query<
         (Entity, &PrefabInstance, &ItemQuantity, &RootPosition, PositionQuery),
         With<CanLift>,
     >,
+    permissions: HousePermissions,
     mut commands: Commands,
     mut events: EventReader<OnClientPickUp>,
     mut sounds: EventWriter<OnClientSound>,
@@ -96,10 +134,15 @@ pub fn on_client_pick_up(
         };
 
         let character = owner.entity;
-        let Ok(held) = characters.get(character) else {
+        let Ok((held, frozen)) = characters.get(character) else {
             continue;
         };
 
+        if is_frozen(frozen) {
+            client.send_packet(PickUpReject::CannotLift);
+            continue;
+        }
+
         if held.is_some() {
             client.send_packet(PickUpReject::AlreadyHolding);
             continue;
@@ -110,6 +153,11 @@ pub fn on_client_pick_up(
             continue;
         };
 
+        if !permissions.can_move(character, entity) {
+            client.send_packet(PickUpReject::BelongsToAnother);
+            continue;
+        }
+
         let item_position = position.item_position().unwrap();
         let quantity_left = (**quantity).saturating_sub(request.quantity.max(1));
         let quantity_taken = **quantity - quantity_left;
@@ -149,9 +197,9 @@ pub fn on_client_pick_up(
 
 pub fn on_client_drop(
     clients: Query<&Possessing>,
-    holders: Query<(&MapPosition, &Held)>,
+    holders: Query<(&MapPosition, &Held, Option<&Frozen>)>,
     containers: Query<&Container>,
-    stackable: Query<(&PrefabInstance, &ItemQuantity), With<Stackable>>,
+    stackable: Query<(&PrefabInstance, &ItemQuantity, Option<&MaxStack>), With<Stackable>>,
     targets: Query<&DropSound>,
     mut commands: Commands,
     mut events: EventReader<OnClientDrop>,
@@ -163,10 +211,15 @@ pub fn on_client_drop(
         };
 
         let character = owner.entity;
-        let Ok((character_position, held)) = holders.get(character) else {
+        let Ok((character_position, held, frozen)) = holders.get(character) else {
             continue;
         };
 
+        if is_frozen(frozen) {
+            return_held_item(&mut commands, character, character_position, held);
+            continue;
+        }
+
         let target = held.held_entity;
         if let Ok(sound) = targets.get(target) {
             sounds.send(OnClientSound {
@@ -186,9 +239,10 @@ pub fn on_client_drop(
                         position: request.position.truncate(),
                         grid_index: request.grid_index,
                     });
-            } else if let Ok([(a_prefab, a_quantity), (b_prefab, b_quantity)]) = stackable.get_many([target, container_entity]) {
+            } else if let Ok([(a_prefab, a_quantity, _), (b_prefab, b_quantity, max_stack)]) = stackable.get_many([target, container_entity]) {
                 let new_quantity = (**a_quantity as u32) + (**b_quantity as u32);
-                if a_prefab.prefab_name != b_prefab.prefab_name || new_quantity > MAX_STACK as u32 {
+                let max_stack = max_stack.map_or(MAX_STACK, |m| **m);
+                if a_prefab.prefab_name != b_prefab.prefab_name || new_quantity > max_stack as u32 {
                     commands.entity(target)
                         .remove::<Holder>()
                         .insert(MapPosition {
@@ -228,7 +282,7 @@ pub fn on_client_drop(
 
 pub fn on_client_equip(
     clients: Query<(&NetClient, &Possessing)>,
-    characters: Query<(&MapPosition, &Held)>,
+    characters: Query<(&MapPosition, &Held, Option<&Frozen>)>,
     mut loadouts: Query<&mut CharacterBodyType>,
     mut commands: Commands,
     mut events: EventReader<OnClientEquip>,
@@ -239,7 +293,7 @@ pub fn on_client_equip(
         };
 
         let character = owner.entity;
-        let Ok((character_position, held)) = characters.get(character) else {
+        let Ok((character_position, held, frozen)) = characters.get(character) else {
             continue;
         };
 
@@ -248,6 +302,11 @@ pub fn on_client_equip(
             continue;
         }
 
+        if is_frozen(frozen) {
+            return_held_item(&mut commands, character, character_position, held);
+            continue;
+        }
+
         let target = request.target;
         if loadouts.get_mut(request.character).is_ok() {
             commands.entity(target)
@@ -271,53 +330,6 @@ pub fn on_client_equip(
     }
 }
 
-pub fn on_client_profile_request(
-    net_ids: Query<&NetId>,
-    clients: Query<&NetClient>,
-    mut events: EventReader<OnClientProfileRequest>,
-) {
-    for request in events.read() {
-        let Ok(client) = clients.get(request.client_entity) else {
-            continue;
-        };
-
-        let Ok(target_id) = net_ids.get(request.target) else {
-            continue;
-        };
-
-        client.send_packet(ProfileResponse {
-            target_id: target_id.id,
-            header: "Supreme Commander".to_string(),
-            footer: "Static Profile".to_string(),
-            profile: "Bio".to_string(),
-        });
-    }
-}
-
-pub fn on_client_skills_request(
-    clients: Query<&NetClient>,
-    mut events: EventReader<OnClientSkillsRequest>,
-) {
-    for request in events.read() {
-        let Ok(client) = clients.get(request.client_entity) else {
-            continue;
-        };
-
-        client.send_packet(SkillsResponse {
-            kind: SkillsResponseKind::FullWithCaps,
-            skills: smallvec![
-                SkillEntry {
-                    id: 1,
-                    value: 724,
-                    raw_value: 701,
-                    lock: SkillLock::Up,
-                    cap: 1200,
-                }
-            ],
-        });
-    }
-}
-
 pub fn on_client_war_mode_changed(
     mut commands: Commands,
     mut clients: Query<(&NetClient, &Possessing, &mut ExpectedCharacterState)>,
@@ -355,8 +367,199 @@ pub fn plugin(app: &mut App) {
                 on_client_drop,
                 on_client_equip,
                 on_client_move,
-                on_client_profile_request,
-                on_client_skills_request,
             ).in_set(ServerSet::HandlePackets),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh_server::world::connection::WriterAction;
+    use yewoh_server::world::input::MOUNTED_RUN_INTERVAL;
+    use yewoh_server::world::items::ItemGraphic;
+    use yewoh_server::world::spatial::{ChunkLookup, SpatialCharacterLookup, SpatialDynamicItemLookup, SpatialStaticItemLookup};
+
+    use crate::housing::HouseOwnership;
+    use crate::housing::lockdown::LockedDown;
+    use crate::networking::{sent_packets, test_client};
+
+    use super::*;
+
+    const START: MapPosition = MapPosition { position: IVec3::new(100, 100, 0), map_id: 1 };
+
+    fn move_app() -> App {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .init_resource::<TileDataResource>()
+            .init_resource::<SpatialCharacterLookup>()
+            .init_resource::<SpatialDynamicItemLookup>()
+            .init_resource::<SpatialStaticItemLookup>()
+            .init_resource::<ChunkLookup>()
+            .add_event::<OnClientMove>()
+            .add_systems(Update, on_client_move);
+        app
+    }
+
+    /// Spawn a character at [`START`] facing north, and the client possessing it.
+    fn spawn_mover(app: &mut App, bundle: impl Bundle) -> (Entity, Entity, UnboundedReceiver<WriterAction>) {
+        let character = app.world_mut()
+            .spawn((CharacterBodyType(0x190), START, Direction::North, bundle))
+            .id();
+        let (client, rx) = test_client();
+        let client_entity = app.world_mut().spawn((
+            client,
+            Possessing { entity: character },
+            ExpectedCharacterState { body_type: 0x190, hue: 0, flags: 0, position: START },
+        )).id();
+        (character, client_entity, rx)
+    }
+
+    fn send_move(app: &mut App, client_entity: Entity, run: bool, sequence: u8) {
+        app.world_mut().send_event(OnClientMove {
+            client_entity,
+            direction: Direction::East,
+            run,
+            sequence,
+            fast_walk: 0,
+            received_at: Instant::now(),
+        });
+        app.update();
+    }
+
+    #[test]
+    fn test_turn_in_place() {
+        let mut app = move_app();
+        let (character, client_entity, mut rx) = spawn_mover(&mut app, ());
+
+        send_move(&mut app, client_entity, false, 1);
+        assert_eq!(*app.world().get::<Direction>(character).unwrap(), Direction::East);
+        assert_eq!(*app.world().get::<MapPosition>(character).unwrap(), START);
+        assert!(app.world().get::<MoveThrottle>(client_entity).unwrap().next_step.is_none());
+        assert_eq!(sent_packets::<MoveConfirm>(&mut rx).len(), 1);
+    }
+
+    #[test]
+    fn test_mounted_step_interval() {
+        let mut app = move_app();
+        let (character, client_entity, _rx) = spawn_mover(&mut app, ());
+        let mount = app.world_mut().spawn(EquippedPosition { slot: EquipmentSlot::Mount }).id();
+        app.world_mut().entity_mut(character).add_child(mount);
+
+        send_move(&mut app, client_entity, true, 1);
+        let throttle = app.world().get::<MoveThrottle>(client_entity).unwrap();
+        assert!(throttle.mounted);
+        assert_eq!(throttle.step_interval(true), MOUNTED_RUN_INTERVAL);
+    }
+
+    #[test]
+    fn test_frozen_move_rejected() {
+        let mut app = move_app();
+        let (character, client_entity, mut rx) = spawn_mover(&mut app, Frozen(true));
+
+        send_move(&mut app, client_entity, false, 1);
+        assert_eq!(*app.world().get::<Direction>(character).unwrap(), Direction::North);
+        assert_eq!(sent_packets::<MoveReject>(&mut rx).len(), 1);
+
+        **app.world_mut().get_mut::<Frozen>(character).unwrap() = false;
+        send_move(&mut app, client_entity, false, 2);
+        assert_eq!(*app.world().get::<Direction>(character).unwrap(), Direction::East);
+        assert_eq!(sent_packets::<MoveConfirm>(&mut rx).len(), 1);
+    }
+
+    #[test]
+    fn test_frozen_item_actions() {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientPickUp>()
+            .add_event::<OnClientDrop>()
+            .add_event::<OnClientEquip>()
+            .add_event::<OnClientSound>()
+            .add_systems(Update, (on_client_pick_up, on_client_drop, on_client_equip));
+
+        let (character, client_entity, mut rx) = spawn_mover(&mut app, Frozen(true));
+        let item_position = MapPosition { position: IVec3::new(101, 100, 0), map_id: 1 };
+        let item = app.world_mut().spawn((
+            ItemGraphic(0xf51),
+            PrefabInstance { prefab_name: "dagger".into() },
+            CanLift,
+            item_position,
+        )).id();
+
+        app.world_mut().send_event(OnClientPickUp { client_entity, target: item, quantity: 1 });
+        app.update();
+        assert!(app.world().get::<Held>(character).is_none());
+        assert!(matches!(sent_packets::<PickUpReject>(&mut rx)[..], [PickUpReject::CannotLift]));
+
+        // Anything held when the character was frozen goes back where it came from.
+        let hold = |app: &mut App| {
+            app.world_mut().entity_mut(item).remove::<MapPosition>().insert(Holder { held_by: character });
+            app.world_mut().entity_mut(character).insert(Held {
+                held_entity: item,
+                previous_position: Some(ItemPosition::Map(item_position)),
+            });
+        };
+
+        hold(&mut app);
+        app.world_mut().send_event(OnClientDrop {
+            client_entity,
+            target: item,
+            position: IVec3::new(105, 105, 0),
+            grid_index: 0,
+            dropped_on: None,
+        });
+        app.update();
+        assert!(app.world().get::<Held>(character).is_none());
+        assert_eq!(*app.world().get::<MapPosition>(item).unwrap(), item_position);
+
+        hold(&mut app);
+        app.world_mut().send_event(OnClientEquip {
+            client_entity,
+            target: item,
+            character,
+            slot: EquipmentSlot::MainHand,
+        });
+        app.update();
+        assert!(app.world().get::<Held>(character).is_none());
+        assert!(app.world().get::<EquippedPosition>(item).is_none());
+        assert_eq!(*app.world().get::<MapPosition>(item).unwrap(), item_position);
+    }
+
+    #[test]
+    fn test_pick_up_locked_down() {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientPickUp>()
+            .add_event::<OnClientSound>()
+            .add_systems(Update, on_client_pick_up);
+
+        let spawn_player = |app: &mut App| {
+            let character = app.world_mut().spawn_empty().id();
+            let (client, rx) = test_client();
+            let client_entity = app.world_mut().spawn((client, Possessing { entity: character })).id();
+            (character, client_entity, rx)
+        };
+        let (owner, owner_client, _owner_rx) = spawn_player(&mut app);
+        let (stranger, stranger_client, mut stranger_rx) = spawn_player(&mut app);
+
+        let house = app.world_mut().spawn(HouseOwnership::new(owner)).id();
+        let chair = app.world_mut().spawn((
+            ItemGraphic(0xb4f),
+            PrefabInstance { prefab_name: "chair".into() },
+            CanLift,
+            MapPosition { position: IVec3::new(5, 5, 0), map_id: 1 },
+            LockedDown { house },
+        )).id();
+
+        app.world_mut().send_event(OnClientPickUp { client_entity: stranger_client, target: chair, quantity: 1 });
+        app.update();
+        assert!(app.world().get::<Held>(stranger).is_none());
+        assert!(matches!(sent_packets::<PickUpReject>(&mut stranger_rx)[..], [PickUpReject::BelongsToAnother]));
+
+        app.world_mut().send_event(OnClientPickUp { client_entity: owner_client, target: chair, quantity: 1 });
+        app.update();
+        assert_eq!(app.world().get::<Held>(owner).unwrap().held_entity, chair);
+    }
+}
@@ -4,11 +4,17 @@ use yewoh_server::world::connection::NetClient;
 use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
 
 use crate::{hues, DefaultGameSet};
-use crate::data::prefabs::PrefabLibraryEntityExt;
-use crate::entities::interactions::OnEntityDoubleClick;
-use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
+use crate::characters::skills::SkillGain;
+use crate::data::prefabs::{PrefabLibraryEntityExt, PrefabReferences, PrefabReferencesAppExt};
+use crate::entities::interactions::{DoubleClickAppExt, OnEntityDoubleClick};
 use crate::networking::NetClientExt;
 
+/// Anatomy, which carving up corpses trains.
+pub const BUTCHERING_SKILL_ID: u8 = 1;
+
+/// The difficulty used when rolling for gains from butchering.
+pub const BUTCHERING_DIFFICULTY: u16 = 300;
+
 #[derive(Clone, Debug, Default, Reflect, Component)]
 #[reflect(Default, Component)]
 pub struct ButcheringKnife;
@@ -17,6 +23,12 @@ pub struct ButcheringKnife;
 #[reflect(Default, Component)]
 pub struct ButcheringPrefab(pub String);
 
+impl PrefabReferences for ButcheringPrefab {
+    fn prefab_references(&self) -> Vec<&str> {
+        vec![self.0.as_str()]
+    }
+}
+
 #[derive(Clone, Debug, Reflect, Component)]
 #[reflect(Component)]
 pub struct ButcheringRequest {
@@ -40,28 +52,27 @@ impl FromWorld for ButcheringRequest {
 pub struct Butchered;
 
 pub fn start_butchering(
+    In(event): In<OnEntityDoubleClick>,
     mut commands: Commands,
-    mut events: EntityEventReader<OnEntityDoubleClick, ButcheringKnife>,
 ) {
-    for event in events.read() {
-        commands
-            .spawn((
-                ButcheringRequest {
-                    client_entity: event.client_entity,
-                    character: event.character,
-                    butchering_knife: event.target,
-                },
-                EntityTargetRequest {
-                    client_entity: event.client_entity,
-                    target_type: TargetType::Neutral,
-                },
-            ));
-    }
+    commands
+        .spawn((
+            ButcheringRequest {
+                client_entity: event.client_entity,
+                character: event.character,
+                butchering_knife: event.target,
+            },
+            EntityTargetRequest {
+                client_entity: event.client_entity,
+                target_type: TargetType::Neutral,
+            },
+        ));
 }
 
 pub fn finish_butchering(
     mut commands: Commands,
     clients: Query<&NetClient>,
+    mut gain: SkillGain,
     completed_requests: Query<(Entity, &ButcheringRequest, &EntityTargetResponse)>,
     targets: Query<(Entity, &ButcheringPrefab), Without<Butchered>>,
 ) {
@@ -82,22 +93,19 @@ pub fn finish_butchering(
         commands.entity(target_entity)
             .insert(Butchered)
             .fabricate_insert(&prefab.0);
+        gain.gain_skill(request.character, BUTCHERING_SKILL_ID, BUTCHERING_DIFFICULTY);
     }
 }
 
 pub fn plugin(app: &mut App) {
     app
-        .add_plugins((
-            EntityEventRoutePlugin::<OnEntityDoubleClick, ButcheringKnife>::default(),
-        ))
+        .add_double_click_handler::<ButcheringKnife, _>(start_butchering)
         .register_type::<ButcheringKnife>()
         .register_type::<ButcheringPrefab>()
         .register_type::<ButcheringRequest>()
         .register_type::<Butchered>()
+        .register_prefab_references::<ButcheringPrefab>()
         .add_systems(First, (
-            (
-                start_butchering,
-                finish_butchering,
-            ).in_set(DefaultGameSet::HandleEvents),
+            finish_butchering.in_set(DefaultGameSet::HandleEvents),
         ));
 }
@@ -1,36 +1,107 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use serde::Deserialize;
+use std::ops::Add;
 use std::time::Duration;
-use yewoh_server::world::characters::{Animation, CharacterBodyType, Health, OnCharacterAnimationStart};
+use yewoh_server::world::characters::{
+    Allies,
+    Animation,
+    CharacterBodyType,
+    CharacterSummary,
+    DamageResists,
+    Health,
+    OnCharacterAnimationStart,
+};
 use yewoh_server::world::combat::{AttackTarget, OnCharacterDamage, OnCharacterSwing, OnClientAttackRequest};
 use yewoh_server::world::connection::Possessing;
-use yewoh_server::world::entity::{EquipmentSlot, EquippedPosition, MapPosition};
+use yewoh_server::world::entity::{Direction, EquipmentSlot, EquippedPosition, Frozen, MapPosition};
+use yewoh_server::world::map::TileDataResource;
+use yewoh_server::world::navigation::has_line_of_sight;
 use yewoh_server::world::net_id::NetId;
+use yewoh_server::world::spatial::SpatialQuery;
 use yewoh_server::world::ServerSet;
 
 use crate::activities::{progress_current_activity, CurrentActivity};
 use crate::characters::corpses::{spawn_corpses, OnCharacterDeath};
+use crate::characters::skills::SkillGain;
+
+/// The highest resistance which can be reached, as a percentage.
+pub const MAX_RESIST: u16 = 70;
+
+pub const TACTICS_SKILL_ID: u8 = 27;
 
 #[derive(Clone, Debug, Default, Reflect, Component)]
 #[reflect(Component)]
 pub struct Invulnerable;
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Deserialize)]
+#[reflect(Default, Deserialize)]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Fire,
+    Cold,
+    Poison,
+    Energy,
+}
+
+/// How damage was dealt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DamageKind {
+    /// A melee swing from `source` at `target`.
+    #[default]
+    Melee,
+    /// Damage to everything in an area, i.e. from a spell or special attack.
+    Area,
+}
+
 #[derive(Debug, Clone, Event)]
-pub struct OnDealMeleeDamage {
+pub struct OnDealDamage {
     pub target: Entity,
     pub source: Entity,
     pub damage: u16,
+    pub damage_type: DamageType,
+    pub kind: DamageKind,
     pub location: MapPosition,
 }
 
+#[derive(Debug, Clone, Event)]
+pub struct OnCharacterHealed {
+    pub target: Entity,
+    pub healer: Entity,
+    pub amount: u16,
+}
+
 #[derive(Debug, Clone, Default, Reflect, Component)]
 #[reflect(Component)]
 pub struct HitAnimation {
     pub hit_animation: Animation,
 }
 
+/// The skill which a weapon trains and is rolled against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Deserialize)]
+#[reflect(Default, Deserialize)]
+pub enum WeaponSkill {
+    Swordsmanship,
+    MaceFighting,
+    Fencing,
+    #[default]
+    Wrestling,
+}
+
+impl WeaponSkill {
+    pub fn skill_id(self) -> u8 {
+        match self {
+            WeaponSkill::Swordsmanship => 40,
+            WeaponSkill::MaceFighting => 41,
+            WeaponSkill::Fencing => 42,
+            WeaponSkill::Wrestling => 43,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Reflect, Component, Deserialize)]
-#[reflect(Component, Deserialize)]
+#[reflect(Component, Default, Deserialize)]
 pub struct MeleeWeapon {
     pub min_damage: u16,
     pub max_damage: u16,
@@ -38,6 +109,8 @@ pub struct MeleeWeapon {
     pub delay: Duration,
     pub range: i32,
     pub swing_animation: Animation,
+    #[serde(default)]
+    pub skill: WeaponSkill,
 }
 
 #[derive(Debug, Clone, Reflect, Component)]
@@ -46,10 +119,52 @@ pub struct Unarmed {
     pub weapon: MeleeWeapon,
 }
 
+/// Resistances granted by a piece of armor when equipped, or by a character's own hide.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Component, Deserialize)]
+#[reflect(Component, Default, Deserialize)]
+#[serde(default)]
+pub struct ArmorRating {
+    pub physical: u16,
+    pub fire: u16,
+    pub cold: u16,
+    pub poison: u16,
+    pub energy: u16,
+}
+
+impl Add for ArmorRating {
+    type Output = ArmorRating;
+
+    fn add(self, rhs: ArmorRating) -> ArmorRating {
+        ArmorRating {
+            physical: self.physical.saturating_add(rhs.physical),
+            fire: self.fire.saturating_add(rhs.fire),
+            cold: self.cold.saturating_add(rhs.cold),
+            poison: self.poison.saturating_add(rhs.poison),
+            energy: self.energy.saturating_add(rhs.energy),
+        }
+    }
+}
+
+/// Reduce damage by a resistance percentage, which is capped at [`MAX_RESIST`].
+pub fn mitigate_damage(damage: u16, resist: u16) -> u16 {
+    let resist = resist.min(MAX_RESIST) as u32;
+    (damage as u32 * (100 - resist) / 100) as u16
+}
+
+fn resist_for(summary: Option<&CharacterSummary>, resists: Option<&DamageResists>, damage_type: DamageType) -> u16 {
+    match damage_type {
+        DamageType::Physical => summary.map_or(0, |s| s.armor),
+        DamageType::Fire => resists.map_or(0, |r| r.fire_resist),
+        DamageType::Cold => resists.map_or(0, |r| r.cold_resist),
+        DamageType::Poison => resists.map_or(0, |r| r.poison_resist),
+        DamageType::Energy => resists.map_or(0, |r| r.energy_resist),
+    }
+}
 
 pub fn on_client_attack_request(
     mut commands: Commands,
     clients: Query<&Possessing>,
+    frozen: Query<&Frozen>,
     mut events: EventReader<OnClientAttackRequest>,
 ) {
     for request in events.read() {
@@ -57,6 +172,10 @@ pub fn on_client_attack_request(
             continue;
         };
 
+        if frozen.get(possessing.entity).is_ok_and(|f| **f) {
+            continue;
+        }
+
         commands.entity(possessing.entity).insert(AttackTarget {
             target: request.target,
         });
@@ -114,29 +233,87 @@ pub fn update_weapon_stats_on_equip(
     }
 }
 
+/// Sum a character's own [`ArmorRating`] and those of its equipped items into its resistances.
+pub fn update_armor_stats(
+    mut characters: Query<
+        (Option<&ArmorRating>, Option<&Children>, &mut CharacterSummary, &mut DamageResists),
+        With<CharacterBodyType>,
+    >,
+    changed_characters: Query<
+        Entity,
+        (With<CharacterBodyType>, Or<(Changed<Children>, Changed<ArmorRating>)>),
+    >,
+    mut removed_children: RemovedComponents<Children>,
+    mut removed_equipped: RemovedComponents<EquippedPosition>,
+    parents: Query<&Parent>,
+    changed_armor: Query<
+        &Parent,
+        (With<ArmorRating>, Without<CharacterBodyType>, Or<(Changed<ArmorRating>, Changed<EquippedPosition>)>),
+    >,
+    armor: Query<&ArmorRating, (With<EquippedPosition>, Without<CharacterBodyType>)>,
+) {
+    let mut dirty = changed_characters.iter()
+        .chain(removed_children.read())
+        .chain(removed_equipped.read().filter_map(|item| parents.get(item).ok().map(Parent::get)))
+        .chain(changed_armor.iter().map(|parent| parent.get()))
+        .collect::<Vec<_>>();
+    dirty.sort();
+    dirty.dedup();
+
+    for entity in dirty {
+        let Ok((own_rating, children, mut summary, mut resists)) = characters.get_mut(entity) else {
+            continue;
+        };
+
+        let total = children.map_or(&[][..], |children| &children[..]).iter()
+            .filter_map(|child| armor.get(*child).ok())
+            .fold(own_rating.copied().unwrap_or_default(), |total, rating| total + *rating);
+
+        if summary.armor != total.physical {
+            summary.armor = total.physical;
+        }
+
+        if resists.fire_resist != total.fire ||
+            resists.cold_resist != total.cold ||
+            resists.poison_resist != total.poison ||
+            resists.energy_resist != total.energy {
+            resists.fire_resist = total.fire;
+            resists.cold_resist = total.cold;
+            resists.poison_resist = total.poison;
+            resists.energy_resist = total.energy;
+        }
+    }
+}
+
 pub fn attack_current_target(
-    mut damage_events: EventWriter<OnDealMeleeDamage>,
+    mut damage_events: EventWriter<OnDealDamage>,
     mut animation_events: EventWriter<OnCharacterAnimationStart>,
+    mut gain: SkillGain,
     mut actors: Query<
-        (Entity, &mut CurrentActivity, &mut AttackTarget, &MapPosition, &MeleeWeapon),
+        (Entity, &mut CurrentActivity, &mut AttackTarget, &MapPosition, &mut Direction, &MeleeWeapon, Option<&Frozen>),
         Without<Invulnerable>,
     >,
-    mut targets: Query<(&MapPosition, Option<&HitAnimation>), Without<Invulnerable>>,
+    mut targets: Query<(&MapPosition, Option<&HitAnimation>, Option<&MeleeWeapon>), Without<Invulnerable>>,
 ) {
-    for (entity, mut current_activity, current_target, location, weapon) in &mut actors {
-        if !current_activity.is_idle() {
+    for (entity, mut current_activity, current_target, location, mut direction, weapon, frozen) in &mut actors {
+        if !current_activity.is_idle() || frozen.is_some_and(|f| **f) {
             continue;
         }
 
-        let (target_location, hit_animation) = match targets.get_mut(current_target.target) {
+        let (target_location, hit_animation, target_weapon) = match targets.get_mut(current_target.target) {
             Ok(x) => x,
             _ => continue,
         };
 
-        if !target_location.in_range(location, weapon.range) {
+        if !target_location.in_range_2d(location, weapon.range) {
             continue;
         }
 
+        // Targets on the same tile have no direction, so keep the current facing.
+        if let Some(new_direction) = location.direction_to(target_location) {
+            direction.set_if_neq(new_direction);
+        }
+
         animation_events.send(OnCharacterAnimationStart {
             animation: weapon.swing_animation.clone(),
             entity,
@@ -151,54 +328,151 @@ pub fn attack_current_target(
             });
         }
 
-        damage_events.send(OnDealMeleeDamage {
+        damage_events.send(OnDealDamage {
             target: current_target.target,
             source: entity,
             damage: weapon.min_damage,
+            damage_type: DamageType::Physical,
+            kind: DamageKind::Melee,
             location: *target_location,
         });
 
+        // Swings are harder to learn from against targets who are better at defending themselves.
+        let defense_skill = target_weapon.map_or(WeaponSkill::Wrestling, |weapon| weapon.skill);
+        let difficulty = gain.skill_value(current_target.target, defense_skill.skill_id());
+        gain.gain_skill(entity, weapon.skill.skill_id(), difficulty);
+        gain.gain_skill(entity, TACTICS_SKILL_ID, difficulty);
+
         *current_activity = CurrentActivity::Melee(Timer::new(weapon.delay, TimerMode::Once));
     }
 }
 
+/// Deals damage to every character in an area, i.e. for spells and special attacks.
+#[derive(SystemParam)]
+pub struct AreaDamage<'w, 's> {
+    spatial_query: SpatialQuery<'w>,
+    tile_data: Res<'w, TileDataResource>,
+    targets: Query<'w, 's, &'static MapPosition, (With<Health>, Without<Invulnerable>)>,
+    allies: Query<'w, 's, &'static Allies>,
+    damage_events: EventWriter<'w, OnDealDamage>,
+}
+
+impl AreaDamage<'_, '_> {
+    /// Damage everything within `radius` of `center` which can be seen from
+    /// `center`, sparing `source`, its allies and anything in `exclude`.
+    pub fn apply_area_damage(
+        &mut self,
+        center: MapPosition,
+        radius: i32,
+        damage: u16,
+        damage_type: DamageType,
+        source: Entity,
+        exclude: &[Entity],
+    ) {
+        let allies = self.allies.get(source).ok();
+        let center_2d = center.position.truncate();
+        for y in (center_2d.y - radius)..=(center_2d.y + radius) {
+            for x in (center_2d.x - radius)..=(center_2d.x + radius) {
+                let entries = self.spatial_query.characters.lookup.entries_at(center.map_id, IVec2::new(x, y));
+                for entry in entries {
+                    let target = entry.entity;
+                    if target == source ||
+                        exclude.contains(&target) ||
+                        allies.is_some_and(|allies| allies.0.contains(&target)) {
+                        continue;
+                    }
+
+                    let Ok(location) = self.targets.get(target) else {
+                        continue;
+                    };
+
+                    if !center.in_range_2d(location, radius) ||
+                        !has_line_of_sight(&self.spatial_query, &self.tile_data, center.map_id, center.position, location.position) {
+                        continue;
+                    }
+
+                    self.damage_events.send(OnDealDamage {
+                        target,
+                        source,
+                        damage,
+                        damage_type,
+                        kind: DamageKind::Area,
+                        location: *location,
+                    });
+                }
+            }
+        }
+    }
+}
+
 pub fn apply_damage(
-    mut damage_events: EventReader<OnDealMeleeDamage>,
+    mut damage_events: EventReader<OnDealDamage>,
     mut died_events: EventWriter<OnCharacterDeath>,
-    mut characters: Query<&mut Health, Without<Invulnerable>>,
+    mut characters: Query<(&mut Health, Option<&CharacterSummary>, Option<&DamageResists>), Without<Invulnerable>>,
 ) {
     for event in damage_events.read() {
-        let mut health = match characters.get_mut(event.target) {
+        let (mut health, summary, resists) = match characters.get_mut(event.target) {
             Ok(x) => x,
             _ => continue,
         };
 
-        health.hp = health.hp.saturating_sub(event.damage);
+        let damage = mitigate_damage(event.damage, resist_for(summary, resists, event.damage_type));
+        health.hp = health.hp.saturating_sub(damage);
         if health.hp > 0 {
             continue;
         }
 
         died_events.send(OnCharacterDeath {
             character: event.target,
+            killer: Some(event.source),
         });
     }
 }
 
+/// Restores health to characters, notifying anything which tracks healing.
+#[derive(SystemParam)]
+pub struct Healing<'w, 's> {
+    characters: Query<'w, 's, &'static mut Health>,
+    healed_events: EventWriter<'w, OnCharacterHealed>,
+}
+
+impl Healing<'_, '_> {
+    /// Heal `target` by up to `amount`, returning how much was actually healed.
+    pub fn heal(&mut self, target: Entity, healer: Entity, amount: u16) -> u16 {
+        let Ok(mut health) = self.characters.get_mut(target) else {
+            return 0;
+        };
+
+        let amount = amount.min(health.max_hp.saturating_sub(health.hp));
+        if amount == 0 || health.hp == 0 {
+            return 0;
+        }
+
+        health.hp += amount;
+        self.healed_events.send(OnCharacterHealed { target, healer, amount });
+        amount
+    }
+}
+
 pub fn send_damage_notices(
-    mut in_damage_events: EventReader<OnDealMeleeDamage>,
+    characters: Query<(Option<&CharacterSummary>, Option<&DamageResists>)>,
+    mut in_damage_events: EventReader<OnDealDamage>,
     mut out_damage_events: EventWriter<OnCharacterDamage>,
     mut out_swing_events: EventWriter<OnCharacterSwing>,
 ) {
     for event in in_damage_events.read() {
+        let (summary, resists) = characters.get(event.target).unwrap_or_default();
         out_damage_events.send(OnCharacterDamage {
             target: event.target,
-            damage: event.damage,
+            damage: mitigate_damage(event.damage, resist_for(summary, resists, event.damage_type)),
         });
 
-        out_swing_events.send(OnCharacterSwing {
-            target: event.target,
-            attacker: event.source,
-        });
+        if event.kind == DamageKind::Melee {
+            out_swing_events.send(OnCharacterSwing {
+                target: event.target,
+                attacker: event.source,
+            });
+        }
     }
 }
 
@@ -209,9 +483,13 @@ impl Plugin for CombatPlugin {
         app
             .register_type::<Invulnerable>()
             .register_type::<HitAnimation>()
+            .register_type::<WeaponSkill>()
             .register_type::<MeleeWeapon>()
             .register_type::<Unarmed>()
-            .add_event::<OnDealMeleeDamage>()
+            .register_type::<ArmorRating>()
+            .register_type::<DamageType>()
+            .add_event::<OnDealDamage>()
+            .add_event::<OnCharacterHealed>()
             .add_systems(First, (
                 (
                     on_client_attack_request,
@@ -220,6 +498,7 @@ impl Plugin for CombatPlugin {
             .add_systems(Update, (
                 update_weapon_stats,
                 update_weapon_stats_on_equip,
+                update_armor_stats.before(apply_damage),
                 attack_current_target
                     .after(progress_current_activity)
                     .after(update_weapon_stats),
@@ -230,3 +509,314 @@ impl Plugin for CombatPlugin {
             ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashSet;
+    use yewoh::assets::tiles::{ItemInfo, TileData, TileFlags};
+    use yewoh_server::world::spatial::{
+        update_character_lookup,
+        ChunkLookup,
+        ItemEntry,
+        SpatialCharacterLookup,
+        SpatialDynamicItemLookup,
+        SpatialStaticItemLookup,
+    };
+
+    use crate::characters::skills::{CharacterSkills, SkillLockState, SkillValue};
+    use crate::data::static_data::StaticData;
+    use crate::rates::ServerRates;
+    use crate::rng::GameRng;
+
+    use super::*;
+
+    fn attack_app() -> App {
+        let mut app = App::new();
+        app
+            .insert_resource(GameRng::from_seed(1))
+            .insert_resource(StaticData {
+                cities: default(),
+                maps: default(),
+                skills: default(),
+                locations: default(),
+            })
+            .init_resource::<ServerRates>()
+            .add_event::<OnDealDamage>()
+            .add_event::<OnCharacterAnimationStart>()
+            .add_systems(Update, attack_current_target);
+        app
+    }
+
+    fn spawn_attacker(app: &mut App, target: Entity, position: IVec3) -> Entity {
+        app.world_mut().spawn((
+            CurrentActivity::Idle,
+            AttackTarget { target },
+            MapPosition { position, map_id: 1 },
+            Direction::North,
+            MeleeWeapon { range: 1, ..default() },
+        )).id()
+    }
+
+    #[test]
+    fn test_attacker_faces_target() {
+        let mut app = attack_app();
+        let target = app.world_mut()
+            .spawn(MapPosition { position: IVec3::new(11, 10, 0), map_id: 1 })
+            .id();
+        let attacker = spawn_attacker(&mut app, target, IVec3::new(10, 10, 0));
+        app.update();
+
+        assert_eq!(*app.world().get::<Direction>(attacker).unwrap(), Direction::East);
+        assert!(!app.world().get::<CurrentActivity>(attacker).unwrap().is_idle());
+    }
+
+    #[test]
+    fn test_attacker_on_same_tile_keeps_facing() {
+        let mut app = attack_app();
+        let target = app.world_mut()
+            .spawn(MapPosition { position: IVec3::new(10, 10, 0), map_id: 1 })
+            .id();
+        let attacker = spawn_attacker(&mut app, target, IVec3::new(10, 10, 0));
+        app.update();
+
+        assert_eq!(*app.world().get::<Direction>(attacker).unwrap(), Direction::North);
+        assert!(!app.world().get::<CurrentActivity>(attacker).unwrap().is_idle());
+    }
+
+    #[test]
+    fn test_frozen_attacker_does_not_swing() {
+        let mut app = attack_app();
+        let target = app.world_mut()
+            .spawn(MapPosition { position: IVec3::new(11, 10, 0), map_id: 1 })
+            .id();
+        let attacker = spawn_attacker(&mut app, target, IVec3::new(10, 10, 0));
+        app.world_mut().entity_mut(attacker).insert(Frozen(true));
+        app.update();
+
+        assert_eq!(*app.world().get::<Direction>(attacker).unwrap(), Direction::North);
+        assert!(app.world().get::<CurrentActivity>(attacker).unwrap().is_idle());
+        assert!(app.world().resource::<Events<OnDealDamage>>().is_empty());
+    }
+
+    #[test]
+    fn test_hits_raise_weapon_skill() {
+        let mut app = attack_app();
+        let target = app.world_mut()
+            .spawn(MapPosition { position: IVec3::new(11, 10, 0), map_id: 1 })
+            .id();
+        let attacker = spawn_attacker(&mut app, target, IVec3::new(10, 10, 0));
+        let mut skills = CharacterSkills::default();
+        skills.skills.insert(WeaponSkill::Fencing.skill_id(), SkillValue::default());
+        skills.skills.insert(TACTICS_SKILL_ID, SkillValue { lock: SkillLockState::Locked, ..default() });
+        app.world_mut().entity_mut(attacker).insert((
+            skills,
+            MeleeWeapon { range: 1, skill: WeaponSkill::Fencing, ..default() },
+        ));
+
+        let fencing = |app: &App| app.world().get::<CharacterSkills>(attacker).unwrap()
+            .get(WeaponSkill::Fencing.skill_id()).value;
+        for _ in 0..20 {
+            app.world_mut().entity_mut(attacker).insert(CurrentActivity::Idle);
+            app.update();
+            if fencing(&app) > 0 {
+                break;
+            }
+        }
+
+        let skills = app.world().get::<CharacterSkills>(attacker).unwrap();
+        assert!(fencing(&app) > 0);
+        assert_eq!(skills.get(WeaponSkill::Wrestling.skill_id()).value, 0);
+        assert_eq!(skills.get(TACTICS_SKILL_ID).value, 0);
+    }
+
+    #[test]
+    fn test_armor_aggregation() {
+        let mut app = App::new();
+        app.add_systems(Update, update_armor_stats);
+
+        let character = app.world_mut()
+            .spawn((CharacterBodyType(0x190), ArmorRating { fire: 2, ..default() }))
+            .id();
+        let helmet = app.world_mut()
+            .spawn((
+                ArmorRating { physical: 5, fire: 3, ..default() },
+                EquippedPosition { slot: EquipmentSlot::Head },
+            ))
+            .set_parent(character)
+            .id();
+        app.update();
+
+        let armor = |app: &App| app.world().get::<CharacterSummary>(character).unwrap().armor;
+        let fire = |app: &App| app.world().get::<DamageResists>(character).unwrap().fire_resist;
+        assert_eq!(armor(&app), 5);
+        assert_eq!(fire(&app), 5);
+
+        app.world_mut().entity_mut(helmet)
+            .remove_parent()
+            .remove::<EquippedPosition>();
+        app.update();
+        assert_eq!(armor(&app), 0);
+        assert_eq!(fire(&app), 2);
+    }
+
+    #[test]
+    fn test_armor_unequip_with_other_items() {
+        let mut app = App::new();
+        app.add_systems(Update, update_armor_stats);
+
+        let character = app.world_mut().spawn(CharacterBodyType(0x190)).id();
+        let spawn_armor = |app: &mut App, physical, slot| app.world_mut()
+            .spawn((ArmorRating { physical, ..default() }, EquippedPosition { slot }))
+            .set_parent(character)
+            .id();
+        let helmet = spawn_armor(&mut app, 5, EquipmentSlot::Head);
+        let boots = spawn_armor(&mut app, 2, EquipmentSlot::Shoes);
+        app.update();
+
+        let armor = |app: &App| app.world().get::<CharacterSummary>(character).unwrap().armor;
+        assert_eq!(armor(&app), 7);
+
+        app.world_mut().entity_mut(helmet).remove_parent();
+        app.update();
+        assert_eq!(armor(&app), 2);
+
+        app.world_mut().entity_mut(boots).remove::<EquippedPosition>();
+        app.update();
+        assert_eq!(armor(&app), 0);
+    }
+
+    #[test]
+    fn test_mitigate_damage() {
+        assert_eq!(mitigate_damage(20, 0), 20);
+        assert_eq!(mitigate_damage(20, 50), 10);
+        assert_eq!(mitigate_damage(20, 100), 6);
+    }
+
+    #[test]
+    fn test_area_damage_notices() {
+        let mut app = App::new();
+        app
+            .add_event::<OnDealDamage>()
+            .add_event::<OnCharacterDamage>()
+            .add_event::<OnCharacterSwing>()
+            .add_systems(Update, send_damage_notices);
+
+        let target = app.world_mut().spawn_empty().id();
+        let source = app.world_mut().spawn_empty().id();
+        for kind in [DamageKind::Melee, DamageKind::Area] {
+            app.world_mut().send_event(OnDealDamage {
+                target,
+                source,
+                damage: 5,
+                damage_type: DamageType::Fire,
+                kind,
+                location: default(),
+            });
+        }
+        app.update();
+
+        let damage = app.world().resource::<Events<OnCharacterDamage>>();
+        assert_eq!(damage.iter_current_update_events().count(), 2);
+        let swings = app.world().resource::<Events<OnCharacterSwing>>();
+        assert_eq!(swings.iter_current_update_events().count(), 1);
+    }
+
+    #[test]
+    fn test_healing() {
+        let mut app = App::new();
+        app.add_event::<OnCharacterHealed>();
+
+        let healer = app.world_mut().spawn_empty().id();
+        let target = app.world_mut().spawn(Health { hp: 40, max_hp: 50 }).id();
+        let dead = app.world_mut().spawn(Health { hp: 0, max_hp: 50 }).id();
+        app.add_systems(Update, move |mut healing: Healing| {
+            assert_eq!(healing.heal(target, healer, 20), 10);
+            assert_eq!(healing.heal(target, healer, 20), 0);
+            assert_eq!(healing.heal(dead, healer, 20), 0);
+        });
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(target).unwrap().hp, 50);
+        let events = app.world().resource::<Events<OnCharacterHealed>>();
+        let healed = events.iter_current_update_events()
+            .map(|event| (event.target, event.healer, event.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(healed, vec![(target, healer, 10)]);
+    }
+
+    fn spawn_character(app: &mut App, x: i32, y: i32) -> Entity {
+        app.world_mut()
+            .spawn((CharacterBodyType(0x190), MapPosition { position: IVec3::new(x, y, 0), map_id: 1 }))
+            .id()
+    }
+
+    #[test]
+    fn test_area_damage() {
+        let mut characters = SpatialCharacterLookup::default();
+        characters.lookup.insert_map(1, IVec2::splat(32));
+        let mut static_items = SpatialStaticItemLookup::default();
+        static_items.lookup.insert_map(1, IVec2::splat(32));
+        static_items.lookup.insert(1, IVec2::new(10, 8), ItemEntry {
+            entity: Entity::PLACEHOLDER,
+            z_min: 0,
+            z_max: 20,
+            graphic: 0,
+        });
+        let tile_data = TileData {
+            land: Vec::new(),
+            items: vec![ItemInfo {
+                name: "wall".into(),
+                flags: TileFlags::WALL | TileFlags::IMPASSABLE,
+                weight: 0,
+                quality: 0,
+                animation: 0,
+                quantity: 0,
+                value: 0,
+                height: 20,
+            }],
+        };
+
+        let mut app = App::new();
+        app
+            .insert_resource(characters)
+            .insert_resource(static_items)
+            .init_resource::<SpatialDynamicItemLookup>()
+            .init_resource::<ChunkLookup>()
+            .insert_resource(TileDataResource { tile_data })
+            .add_event::<OnDealDamage>();
+
+        let source = spawn_character(&mut app, 10, 10);
+        let ally = spawn_character(&mut app, 11, 10);
+        let east = spawn_character(&mut app, 12, 10);
+        let south = spawn_character(&mut app, 10, 13);
+        let excluded = spawn_character(&mut app, 9, 10);
+        let far = spawn_character(&mut app, 14, 10);
+        let walled = spawn_character(&mut app, 10, 7);
+        app.world_mut().entity_mut(source).insert(Allies(HashSet::from_iter([ally])));
+
+        let center = MapPosition { position: IVec3::new(10, 10, 0), map_id: 1 };
+        app.add_systems(Update, (
+            update_character_lookup,
+            move |mut area_damage: AreaDamage| area_damage
+                .apply_area_damage(center, 3, 10, DamageType::Fire, source, &[excluded]),
+        ).chain());
+        app.update();
+
+        let events = app.world().resource::<Events<OnDealDamage>>();
+        let mut damaged = events.iter_current_update_events()
+            .inspect(|event| {
+                assert_eq!(event.source, source);
+                assert_eq!(event.damage, 10);
+                assert_eq!(event.damage_type, DamageType::Fire);
+                assert_eq!(event.kind, DamageKind::Area);
+            })
+            .map(|event| event.target)
+            .collect::<Vec<_>>();
+        damaged.sort();
+        let mut expected = vec![east, south];
+        expected.sort();
+        assert_eq!(damaged, expected);
+        assert!(!damaged.contains(&far));
+        assert!(!damaged.contains(&walled));
+    }
+}
@@ -3,20 +3,29 @@ use bevy::ecs::entity::{VisitEntities, VisitEntitiesMut};
 use bevy::ecs::reflect::ReflectMapEntities;
 use bevy::prelude::*;
 use glam::ivec2;
-use rand::{thread_rng, Rng, RngCore};
+use rand::{Rng, RngCore};
 use bevy_fabricator::traits::{Convert, ReflectConvert};
 use yewoh_server::world::entity::ContainedPosition;
 use yewoh_server::world::items::ItemQuantity;
 
-use crate::data::prefabs::PrefabLibraryWorldExt;
-use crate::entities::Persistent;
+use crate::data::prefabs::{PrefabLibraryWorldExt, PrefabReferences, PrefabReferencesAppExt};
+use crate::entities::{Persistent, PrefabInstance};
 use crate::entities::position::PositionExt;
+use crate::items::common::Stackable;
+use crate::rates::ServerRates;
+use crate::rng::GameRng;
 use crate::reflect::{assert_struct_fields, reflect_field, reflect_optional_field};
 
 #[derive(Clone, Debug, Default, Reflect, Component)]
 #[reflect(Default, Component)]
 pub struct LootPrefab(pub String);
 
+impl PrefabReferences for LootPrefab {
+    fn prefab_references(&self) -> Vec<&str> {
+        vec![self.0.as_str()]
+    }
+}
+
 #[derive(Clone, Debug, Reflect, Component, VisitEntities, VisitEntitiesMut)]
 #[reflect(Component, MapEntities, Convert)]
 pub struct LootRoll {
@@ -56,47 +65,261 @@ impl Convert for LootRoll {
     }
 }
 
+impl PrefabReferences for LootRoll {
+    fn prefab_references(&self) -> Vec<&str> {
+        vec![self.prefab_name.as_str()]
+    }
+}
+
+/// The most copies of an item which doesn't stack that a single roll can drop.
+pub const MAX_UNSTACKABLE_LOOT: u16 = 20;
+
+/// Give freshly spawned loot its quantity, spawning copies of items which don't stack.
+pub struct SetLootQuantity(pub u16);
+
+impl EntityCommand for SetLootQuantity {
+    fn apply(self, entity: Entity, world: &mut World) {
+        if self.0 <= 1 {
+            return;
+        }
+
+        if world.get::<Stackable>(entity).is_some() {
+            world.entity_mut(entity).insert(ItemQuantity(self.0));
+            return;
+        }
+
+        let Some(prefab_name) = world.get::<PrefabInstance>(entity).map(|p| p.prefab_name.clone()) else {
+            return;
+        };
+        let parent = world.get::<Parent>(entity).map(Parent::get);
+        let position = world.get::<ContainedPosition>(entity).copied();
+        for _ in 1..self.0.min(MAX_UNSTACKABLE_LOOT) {
+            let mut copy = world.fabricate_prefab(&prefab_name);
+            copy.insert(Persistent);
+            if let (Some(parent), Some(position)) = (parent, position) {
+                copy.move_to_container_position(parent, position);
+            }
+        }
+    }
+}
+
 impl LootRoll {
-    pub fn roll(&self, commands: &mut Commands, rng: &mut impl RngCore) {
+    /// Roll the quantity of loot to spawn, which is 0 if the chance roll fails.
+    pub fn roll_quantity(&self, rng: &mut impl RngCore) -> u16 {
         if rng.gen::<f32>() > self.chance {
-            return;
+            return 0;
         }
 
+        rng.gen_range(self.min_quantity..=self.max_quantity)
+    }
+
+    pub fn roll(&self, commands: &mut Commands, rng: &mut impl RngCore, rates: &ServerRates) {
         let position = ContainedPosition {
             position: ivec2(0, 0),
             grid_index: 0,
         };
 
-        let quantity = rng.gen_range(self.min_quantity..=self.max_quantity);
-        if quantity == 1 {
-            commands
-                .fabricate_prefab(&self.prefab_name)
-                .insert((
-                    Persistent,
-                ))
-                .move_to_container_position(self.target, position);
-        } else if quantity > 0 {
+        let quantity = rates.scale_loot(&self.prefab_name, self.roll_quantity(rng));
+        if quantity > 0 {
             commands
                 .fabricate_prefab(&self.prefab_name)
                 .insert((
                     Persistent,
-                    ItemQuantity(quantity),
                 ))
-                .move_to_container_position(self.target, position);
+                .move_to_container_position(self.target, position)
+                .queue(SetLootQuantity(quantity));
+        }
+    }
+}
+
+/// The maximum depth of nested loot tables.
+pub const MAX_LOOT_TABLE_DEPTH: usize = 8;
+
+/// The level of a creature, which gates the loot it can drop.
+#[derive(Clone, Copy, Debug, Default, Reflect, Component, Deref)]
+#[reflect(Default, Component)]
+pub struct CreatureLevel(pub u16);
+
+/// Details of the creature being looted, used to evaluate loot conditions.
+///
+/// Corpses carry this as a component, which takes precedence over the context
+/// a loot table was written with.
+#[derive(Clone, Copy, Debug, Default, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct LootContext {
+    pub level: u16,
+    pub luck: u16,
+}
+
+#[derive(Clone, Debug, Default, Reflect)]
+#[reflect(Default)]
+pub enum LootCondition {
+    #[default]
+    Always,
+    MinLevel(u16),
+    MinLuck(u16),
+}
+
+impl LootCondition {
+    pub fn matches(&self, context: &LootContext) -> bool {
+        match self {
+            LootCondition::Always => true,
+            LootCondition::MinLevel(level) => context.level >= *level,
+            LootCondition::MinLuck(luck) => context.luck >= *luck,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Reflect)]
+#[reflect(Default)]
+pub struct LootEntry {
+    pub prefab: String,
+    pub weight: u32,
+    pub min_quantity: u16,
+    pub max_quantity: u16,
+    #[reflect(default)]
+    pub condition: LootCondition,
+    /// A sub-table which is rolled instead of dropping `prefab`, if not empty.
+    #[reflect(default)]
+    pub table: Vec<LootEntry>,
+    #[reflect(default)]
+    pub table_rolls: u8,
+}
+
+fn collect_prefab_references<'a>(entries: &'a [LootEntry], references: &mut Vec<&'a str>) {
+    for entry in entries {
+        if entry.table.is_empty() {
+            references.push(entry.prefab.as_str());
+        } else {
+            collect_prefab_references(&entry.table, references);
+        }
+    }
+}
+
+/// Pick up to `rolls` entries by weight, without replacement.
+fn select_entries<'a>(
+    entries: &'a [LootEntry], rolls: u8, context: &LootContext, rng: &mut impl RngCore,
+) -> Vec<&'a LootEntry> {
+    let mut remaining = entries.iter()
+        .filter(|entry| entry.weight > 0 && entry.condition.matches(context))
+        .collect::<Vec<_>>();
+    let mut selected = Vec::with_capacity(rolls as usize);
+
+    while selected.len() < rolls as usize && !remaining.is_empty() {
+        let total = remaining.iter().map(|entry| entry.weight).sum::<u32>();
+        let mut pick = rng.gen_range(0..total);
+        let index = remaining.iter()
+            .position(|entry| {
+                if pick < entry.weight {
+                    true
+                } else {
+                    pick -= entry.weight;
+                    false
+                }
+            })
+            .unwrap();
+        selected.push(remaining.swap_remove(index));
+    }
+
+    selected
+}
+
+fn collect_drops<'a>(
+    entries: &'a [LootEntry], rolls: u8, context: &LootContext, rng: &mut impl RngCore,
+    depth: usize, drops: &mut Vec<&'a LootEntry>,
+) {
+    if depth >= MAX_LOOT_TABLE_DEPTH {
+        warn!("loot tables nested more than {MAX_LOOT_TABLE_DEPTH} deep");
+        return;
+    }
+
+    for entry in select_entries(entries, rolls, context, rng) {
+        if entry.table.is_empty() {
+            drops.push(entry);
+        } else {
+            collect_drops(&entry.table, entry.table_rolls.max(1), context, rng, depth + 1, drops);
+        }
+    }
+}
+
+/// A weighted loot table, which drops `rolls` distinct entries.
+#[derive(Clone, Debug, Reflect, Component, VisitEntities, VisitEntitiesMut)]
+#[reflect(Component, MapEntities)]
+pub struct LootTable {
+    pub target: Entity,
+    #[visit_entities(ignore)]
+    pub entries: Vec<LootEntry>,
+    #[visit_entities(ignore)]
+    pub rolls: u8,
+    #[visit_entities(ignore)]
+    #[reflect(default)]
+    pub context: LootContext,
+}
+
+impl PrefabReferences for LootTable {
+    fn prefab_references(&self) -> Vec<&str> {
+        let mut references = Vec::new();
+        collect_prefab_references(&self.entries, &mut references);
+        references
+    }
+}
+
+impl LootTable {
+    /// Pick up to `rolls` top-level entries by weight, without replacement.
+    pub fn select(&self, rng: &mut impl RngCore) -> Vec<&LootEntry> {
+        select_entries(&self.entries, self.rolls, &self.context, rng)
+    }
+
+    /// Pick the entries to drop, expanding any nested tables.
+    pub fn drops(&self, rng: &mut impl RngCore) -> Vec<&LootEntry> {
+        self.drops_in_context(&self.context, rng)
+    }
+
+    /// Pick the entries to drop, evaluating conditions against `context`.
+    pub fn drops_in_context(&self, context: &LootContext, rng: &mut impl RngCore) -> Vec<&LootEntry> {
+        let mut drops = Vec::new();
+        collect_drops(&self.entries, self.rolls, context, rng, 0, &mut drops);
+        drops
+    }
+
+    pub fn roll(&self, context: &LootContext, commands: &mut Commands, rng: &mut impl RngCore, rates: &ServerRates) {
+        for entry in self.drops_in_context(context, rng) {
+            LootRoll {
+                target: self.target,
+                chance: 1.,
+                min_quantity: entry.min_quantity,
+                max_quantity: entry.max_quantity,
+                prefab_name: entry.prefab.clone(),
+            }.roll(commands, rng, rates);
         }
     }
 }
 
 pub fn spawn_loot(
     mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    rates: Res<ServerRates>,
     rolls: Query<(Entity, &LootRoll)>,
 ) {
-    let mut rng = thread_rng();
-
     for (entity, roll) in &rolls {
         commands.entity(entity).despawn_recursive();
 
-        roll.roll(&mut commands, &mut rng);
+        roll.roll(&mut commands, &mut *rng, &rates);
+    }
+}
+
+pub fn spawn_loot_table(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    rates: Res<ServerRates>,
+    tables: Query<(Entity, &LootTable)>,
+    contexts: Query<&LootContext>,
+) {
+    for (entity, table) in &tables {
+        commands.entity(entity).despawn_recursive();
+
+        let context = contexts.get(table.target).unwrap_or(&table.context);
+        table.roll(context, &mut commands, &mut *rng, &rates);
     }
 }
 
@@ -104,7 +327,243 @@ pub fn plugin(app: &mut App) {
     app
         .register_type::<LootPrefab>()
         .register_type::<LootRoll>()
+        .register_type::<LootTable>()
+        .register_type::<LootContext>()
+        .register_type::<CreatureLevel>()
+        .register_prefab_references::<LootPrefab>()
+        .register_prefab_references::<LootRoll>()
+        .register_prefab_references::<LootTable>()
         .add_systems(Update, (
             spawn_loot,
+            spawn_loot_table,
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::ecs::world::CommandQueue;
+    use bevy::utils::HashMap;
+    use bevy_fabricator::{Fabricated, Fabricator};
+
+    use crate::data::prefabs::PrefabLibrary;
+
+    use super::*;
+
+    #[test]
+    fn test_seeded_rolls() {
+        let roll = LootRoll {
+            target: Entity::PLACEHOLDER,
+            chance: 0.5,
+            min_quantity: 1,
+            max_quantity: 20,
+            prefab_name: "gold".to_string(),
+        };
+
+        let roll_all = |seed: u64| {
+            let mut world = World::new();
+            world.insert_resource(GameRng::from_seed(seed));
+            let mut rng = world.resource_mut::<GameRng>();
+            (0..32).map(|_| roll.roll_quantity(&mut *rng)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(roll_all(1234), roll_all(1234));
+        assert_ne!(roll_all(1234), roll_all(4321));
+    }
+
+    fn entry(prefab: &str, weight: u32) -> LootEntry {
+        LootEntry {
+            prefab: prefab.to_string(),
+            weight,
+            min_quantity: 1,
+            max_quantity: 1,
+            ..default()
+        }
+    }
+
+    fn loot_library() -> PrefabLibrary {
+        let mut library = PrefabLibrary::default();
+        library.insert("bandage".to_string(), Fabricator {
+            parameters: HashMap::new(),
+            factory: Arc::new(|entity, _, world| {
+                world.entity_mut(entity).insert(Stackable);
+                Ok(Fabricated::default())
+            }),
+        });
+        library.insert("sword".to_string(), Fabricator {
+            parameters: HashMap::new(),
+            factory: Arc::new(|_, _, _| Ok(Fabricated::default())),
+        });
+        library
+    }
+
+    fn roll_doubled(prefab_name: &str, quantity: u16) -> (World, Entity) {
+        let mut world = World::new();
+        world.insert_resource(loot_library());
+        let target = world.spawn_empty().id();
+        let roll = LootRoll {
+            target,
+            chance: 1.,
+            min_quantity: quantity,
+            max_quantity: quantity,
+            prefab_name: prefab_name.to_string(),
+        };
+        let rates = ServerRates { loot_multiplier: 2., ..default() };
+
+        let mut queue = CommandQueue::default();
+        roll.roll(&mut Commands::new(&mut queue, &world), &mut GameRng::from_seed(1), &rates);
+        queue.apply(&mut world);
+        (world, target)
+    }
+
+    #[test]
+    fn test_loot_multiplier() {
+        let (mut world, _) = roll_doubled("bandage", 4);
+        let quantities = world.query::<&ItemQuantity>().iter(&world)
+            .map(|quantity| quantity.0)
+            .collect::<Vec<_>>();
+        assert_eq!(quantities, vec![8]);
+    }
+
+    #[test]
+    fn test_unstackable_loot_multiplier() {
+        let (mut world, target) = roll_doubled("sword", 1);
+        assert_eq!(world.query::<&ItemQuantity>().iter(&world).count(), 0);
+        let swords = world.query::<(&PrefabInstance, &Parent)>().iter(&world)
+            .map(|(prefab, parent)| (prefab.prefab_name.as_str(), parent.get()))
+            .collect::<Vec<_>>();
+        assert_eq!(swords, vec![("sword", target), ("sword", target)]);
+    }
+
+    #[test]
+    fn test_table_weighting() {
+        let table = LootTable {
+            target: Entity::PLACEHOLDER,
+            entries: vec![entry("common", 9), entry("rare", 1)],
+            rolls: 1,
+            context: LootContext::default(),
+        };
+
+        let mut rng = GameRng::from_seed(1);
+        let mut common = 0;
+        for _ in 0..1000 {
+            let selected = table.select(&mut rng);
+            assert_eq!(selected.len(), 1);
+            if selected[0].prefab == "common" {
+                common += 1;
+            }
+        }
+        assert!(common > 800, "common was picked {common} times");
+    }
+
+    #[test]
+    fn test_table_rolls() {
+        let mut table = LootTable {
+            target: Entity::PLACEHOLDER,
+            entries: vec![entry("a", 1), entry("b", 1), entry("c", 1), entry("never", 0)],
+            rolls: 2,
+            context: LootContext::default(),
+        };
+
+        let mut rng = GameRng::from_seed(1);
+        let selected = table.select(&mut rng);
+        assert_eq!(selected.len(), 2);
+        assert_ne!(selected[0].prefab, selected[1].prefab);
+
+        table.rolls = 5;
+        let mut selected = table.select(&mut rng).into_iter()
+            .map(|entry| entry.prefab.as_str())
+            .collect::<Vec<_>>();
+        selected.sort();
+        assert_eq!(selected, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_nested_table() {
+        let mut gems = entry("gems", 1);
+        gems.table = vec![entry("ruby", 1), entry("sapphire", 1)];
+        gems.table_rolls = 2;
+        let table = LootTable {
+            target: Entity::PLACEHOLDER,
+            entries: vec![gems],
+            rolls: 1,
+            context: LootContext::default(),
+        };
+        assert_eq!(table.prefab_references(), vec!["ruby", "sapphire"]);
+
+        let mut rng = GameRng::from_seed(1);
+        let mut drops = table.drops(&mut rng).into_iter()
+            .map(|entry| entry.prefab.as_str())
+            .collect::<Vec<_>>();
+        drops.sort();
+        assert_eq!(drops, vec!["ruby", "sapphire"]);
+
+        let mut deep = entry("bottom", 1);
+        for _ in 0..MAX_LOOT_TABLE_DEPTH {
+            let mut outer = entry("", 1);
+            outer.table = vec![deep];
+            deep = outer;
+        }
+        let table = LootTable {
+            entries: vec![deep],
+            ..table
+        };
+        assert!(table.drops(&mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_luck_gated_drop() {
+        let mut rare = entry("rare", 1);
+        rare.condition = LootCondition::MinLuck(100);
+        let mut table = LootTable {
+            target: Entity::PLACEHOLDER,
+            entries: vec![entry("common", 1), rare],
+            rolls: 1,
+            context: LootContext::default(),
+        };
+
+        let count_rare = |table: &LootTable| {
+            let mut rng = GameRng::from_seed(1);
+            (0..100)
+                .filter(|_| table.drops(&mut rng)[0].prefab == "rare")
+                .count()
+        };
+
+        assert_eq!(count_rare(&table), 0);
+        table.context.luck = 200;
+        assert!(count_rare(&table) > 0);
+    }
+
+    #[test]
+    fn test_corpse_context() {
+        let mut rare = entry("rare", 1);
+        rare.condition = LootCondition::MinLuck(100);
+        rare.min_quantity = 2;
+        rare.max_quantity = 2;
+
+        let drop_count = |context: Option<LootContext>| {
+            let mut world = World::new();
+            world.init_resource::<PrefabLibrary>();
+            world.init_resource::<ServerRates>();
+            world.insert_resource(GameRng::from_seed(1));
+            let mut corpse = world.spawn_empty();
+            if let Some(context) = context {
+                corpse.insert(context);
+            }
+            let target = corpse.id();
+            world.spawn(LootTable {
+                target,
+                entries: vec![rare.clone()],
+                rolls: 1,
+                context: LootContext::default(),
+            });
+            world.run_system_once(spawn_loot_table).unwrap();
+            world.query::<&PrefabInstance>().iter(&world).count()
+        };
+
+        assert_eq!(drop_count(None), 0);
+        assert_eq!(drop_count(Some(LootContext { luck: 200, ..default() })), 2);
+    }
+}
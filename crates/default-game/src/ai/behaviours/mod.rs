@@ -1,2 +1,3 @@
 
+pub mod threat;
 pub mod wander;
@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use bevy_fabricator::traits::{Apply, Context, ReflectApply};
+use yewoh_server::world::characters::Health;
+use yewoh_server::world::combat::AttackTarget;
+use yewoh_server::world::entity::MapPosition;
+
+use crate::activities::combat::{OnCharacterHealed, OnDealDamage};
+
+/// Entries with less threat than this are forgotten.
+pub const MIN_THREAT: f32 = 1.0;
+
+/// How much threat a healer gains per point healed on an entity a creature is fighting.
+pub const HEALING_THREAT_SCALE: f32 = 0.5;
+
+/// The threat a creature holds against each entity which has attacked it.
+#[derive(Debug, Clone, Default, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct ThreatTable {
+    /// The fraction of threat which is lost each second.
+    pub decay_rate: f32,
+    /// Entities further away than this are dropped from the table.
+    pub range: i32,
+    pub entries: HashMap<Entity, f32>,
+    /// The entry which was last selected as the attack target.
+    pub current: Option<Entity>,
+}
+
+impl ThreatTable {
+    pub fn add(&mut self, entity: Entity, amount: f32) {
+        *self.entries.entry(entity).or_default() += amount;
+    }
+
+    pub fn threat(&self, entity: Entity) -> f32 {
+        self.entries.get(&entity).copied().unwrap_or_default()
+    }
+
+    pub fn highest(&self) -> Option<Entity> {
+        self.entries.iter()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(entity, _)| *entity)
+    }
+
+    pub fn decay(&mut self, elapsed: Duration) {
+        let factor = (1.0 - self.decay_rate).clamp(0.0, 1.0).powf(elapsed.as_secs_f32());
+        for threat in self.entries.values_mut() {
+            *threat *= factor;
+        }
+        self.entries.retain(|_, threat| *threat >= MIN_THREAT);
+    }
+}
+
+pub fn accumulate_damage_threat(
+    mut tables: Query<&mut ThreatTable>,
+    mut events: EventReader<OnDealDamage>,
+) {
+    for event in events.read() {
+        if let Ok(mut table) = tables.get_mut(event.target) {
+            table.add(event.source, event.damage as f32);
+        }
+    }
+}
+
+pub fn accumulate_healing_threat(
+    mut tables: Query<&mut ThreatTable>,
+    mut events: EventReader<OnCharacterHealed>,
+) {
+    for event in events.read() {
+        for mut table in &mut tables {
+            if table.entries.contains_key(&event.target) {
+                table.add(event.healer, event.amount as f32 * HEALING_THREAT_SCALE);
+            }
+        }
+    }
+}
+
+pub fn decay_threat(
+    time: Res<Time>,
+    mut tables: Query<(&mut ThreatTable, &MapPosition)>,
+    targets: Query<(&MapPosition, &Health)>,
+) {
+    for (mut table, position) in &mut tables {
+        table.decay(time.delta());
+
+        let range = table.range;
+        table.entries.retain(|entity, _| targets.get(*entity)
+            .is_ok_and(|(target_position, health)| health.hp > 0 && position.in_range_2d(target_position, range)));
+    }
+}
+
+pub fn select_threat_target(
+    mut commands: Commands,
+    mut creatures: Query<(Entity, &mut ThreatTable, Option<&AttackTarget>), Changed<ThreatTable>>,
+) {
+    for (entity, mut table, attack_target) in &mut creatures {
+        // Decay changes the table every frame, so only retarget when the top entry changes.
+        let highest = table.highest();
+        if highest == table.current {
+            continue;
+        }
+        table.bypass_change_detection().current = highest;
+
+        match highest {
+            Some(target) => {
+                if !attack_target.is_some_and(|current| current.target == target) {
+                    commands.entity(entity).insert(AttackTarget { target });
+                }
+            }
+            None => {
+                if attack_target.is_some() {
+                    commands.entity(entity).remove::<AttackTarget>();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default, Reflect, Deserialize)]
+#[reflect(Default, Apply, Deserialize)]
+pub struct ThreatPrefab {
+    pub decay_rate: f32,
+    pub range: i32,
+}
+
+impl Apply for ThreatPrefab {
+    fn apply(&self, ctx: &mut Context, entity: Entity) -> anyhow::Result<()> {
+        ctx.world.entity_mut(entity)
+            .insert(ThreatTable {
+                decay_rate: self.decay_rate,
+                range: self.range,
+                entries: HashMap::new(),
+                current: None,
+            });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_at(app: &mut App, x: i32) -> Entity {
+        app.world_mut()
+            .spawn((MapPosition { position: IVec3::new(x, 10, 0), map_id: 1 }, Health::default()))
+            .id()
+    }
+
+    #[test]
+    fn test_highest_threat_targeted() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .add_event::<OnDealDamage>()
+            .add_event::<OnCharacterHealed>()
+            .add_systems(Update, (
+                accumulate_damage_threat,
+                accumulate_healing_threat,
+                decay_threat,
+                select_threat_target,
+            ).chain());
+
+        let creature = spawn_at(&mut app, 10);
+        app.world_mut().entity_mut(creature).insert(ThreatTable { decay_rate: 0.1, range: 12, ..default() });
+        let weak = spawn_at(&mut app, 11);
+        let strong = spawn_at(&mut app, 9);
+        let healer = spawn_at(&mut app, 14);
+        let distant = spawn_at(&mut app, 30);
+
+        let location = MapPosition { position: IVec3::new(10, 10, 0), map_id: 1 };
+        for (source, damage) in [(weak, 5), (strong, 20), (distant, 50)] {
+            app.world_mut().send_event(OnDealDamage {
+                target: creature,
+                source,
+                damage,
+                damage_type: default(),
+                kind: default(),
+                location,
+            });
+        }
+        app.update();
+
+        let table = app.world().get::<ThreatTable>(creature).unwrap();
+        assert_eq!(table.threat(distant), 0.0);
+        assert_eq!(app.world().get::<AttackTarget>(creature).unwrap().target, strong);
+
+        app.world_mut().send_event(OnCharacterHealed { target: weak, healer, amount: 60 });
+        app.update();
+        assert_eq!(app.world().get::<AttackTarget>(creature).unwrap().target, healer);
+
+        app.world_mut().get_mut::<Health>(healer).unwrap().hp = 0;
+        app.update();
+        assert_eq!(app.world().get::<AttackTarget>(creature).unwrap().target, strong);
+    }
+
+    #[test]
+    fn test_target_kept_while_top_unchanged() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .add_event::<OnDealDamage>()
+            .add_event::<OnCharacterHealed>()
+            .add_systems(Update, (
+                accumulate_damage_threat,
+                decay_threat,
+                select_threat_target,
+            ).chain());
+
+        let creature = spawn_at(&mut app, 10);
+        app.world_mut().entity_mut(creature).insert(ThreatTable { decay_rate: 0.1, range: 12, ..default() });
+        let attacker = spawn_at(&mut app, 11);
+        let other = spawn_at(&mut app, 12);
+
+        let location = MapPosition { position: IVec3::new(10, 10, 0), map_id: 1 };
+        let damage = |source| OnDealDamage {
+            target: creature,
+            source,
+            damage: 20,
+            damage_type: default(),
+            kind: default(),
+            location,
+        };
+        app.world_mut().send_event(damage(attacker));
+        app.update();
+        assert_eq!(app.world().get::<AttackTarget>(creature).unwrap().target, attacker);
+
+        app.world_mut().entity_mut(creature).insert(AttackTarget { target: other });
+        app.world_mut().get_mut::<ThreatTable>(creature).unwrap().add(attacker, 5.0);
+        app.update();
+        assert_eq!(app.world().get::<AttackTarget>(creature).unwrap().target, other);
+    }
+
+    #[test]
+    fn test_threat_decays() {
+        let attacker = Entity::from_raw(1);
+        let mut table = ThreatTable { decay_rate: 0.5, range: 12, ..default() };
+        table.add(attacker, 100.0);
+
+        table.decay(Duration::from_secs(1));
+        assert!((table.threat(attacker) - 50.0).abs() < 0.001);
+        table.decay(Duration::from_secs(2));
+        assert!((table.threat(attacker) - 12.5).abs() < 0.001);
+        table.decay(Duration::from_secs(4));
+        assert_eq!(table.highest(), None);
+    }
+}
@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::Deserialize;
 use bevy_fabricator::traits::{Apply, Context, ReflectApply};
 use yewoh_server::world::entity::{Direction, MapPosition};
@@ -9,6 +9,8 @@ use yewoh_server::world::map::{Chunk, TileDataResource};
 use yewoh_server::world::navigation::try_move_in_direction;
 use yewoh_server::world::spatial::SpatialQuery;
 
+use crate::rng::GameRng;
+
 #[derive(Debug, Clone, Component, Reflect)]
 pub struct Wander;
 
@@ -20,12 +22,11 @@ pub struct MoveTimer {
 pub fn wander(
     time: Res<Time>,
     tile_data: Res<TileDataResource>,
+    mut rng: ResMut<GameRng>,
     spatial_query: SpatialQuery,
     chunk_query: Query<(&MapPosition, &Chunk)>,
     mut npcs: Query<(Entity, &mut MapPosition, &mut Direction, &mut MoveTimer), (Without<Chunk>, With<Wander>)>,
 ) {
-    let mut rng = thread_rng();
-
     for (entity, mut position, mut direction, mut move_timer) in npcs.iter_mut() {
         if !move_timer.next_move.tick(time.delta()).just_finished() {
             continue;
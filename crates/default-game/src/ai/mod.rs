@@ -1,5 +1,14 @@
-use bevy::app::{App, Plugin, Update};
+use bevy::prelude::*;
 
+use crate::activities::combat::attack_current_target;
+use crate::ai::behaviours::threat::{
+    accumulate_damage_threat,
+    accumulate_healing_threat,
+    decay_threat,
+    select_threat_target,
+    ThreatPrefab,
+    ThreatTable,
+};
 use crate::ai::behaviours::wander::{wander, WanderPrefab};
 
 pub mod behaviours;
@@ -11,8 +20,16 @@ impl Plugin for AiPlugin {
     fn build(&self, app: &mut App) {
         app
             .register_type::<WanderPrefab>()
+            .register_type::<ThreatTable>()
+            .register_type::<ThreatPrefab>()
             .add_systems(Update, (
                 wander,
+                (
+                    accumulate_damage_threat,
+                    accumulate_healing_threat,
+                    decay_threat,
+                    select_threat_target,
+                ).chain().after(attack_current_target),
             ));
     }
 }
@@ -1,11 +1,11 @@
 use bevy::prelude::*;
-use yewoh_server::world::characters::CharacterBodyType;
+use yewoh_server::world::characters::{CharacterBodyType, CharacterSummary};
 use yewoh_server::world::entity::{ContainedPosition, EquipmentSlot, EquippedPosition, Hue, MapPosition};
 use yewoh_server::world::items::ItemQuantity;
 use yewoh_server::world::ServerSet;
 
 use crate::activities::butchering::ButcheringPrefab;
-use crate::activities::loot::LootPrefab;
+use crate::activities::loot::{CreatureLevel, LootContext, LootPrefab};
 use crate::data::prefabs::{PrefabLibraryEntityExt, PrefabLibraryWorldExt};
 use crate::entities::persistence::PersistHue;
 use crate::entities::Persistent;
@@ -19,6 +19,7 @@ pub struct CorpsePrefab(pub String);
 #[derive(Debug, Clone, Event)]
 pub struct OnCharacterDeath {
     pub character: Entity,
+    pub killer: Option<Entity>,
 }
 
 #[derive(Debug, Default, Clone, Component, Reflect)]
@@ -55,12 +56,14 @@ pub fn spawn_corpses(
         &CorpsePrefab,
         Option<&LootPrefab>,
         Option<&ButcheringPrefab>,
+        Option<&CreatureLevel>,
         Has<Persistent>,
     )>,
+    killers: Query<&CharacterSummary>,
     equipment: Query<&EquippedPosition>,
 ) {
     for event in died_events.read() {
-        let Ok((body_type, hue, map_position, children, prefab, loot, butchering, is_persistent)) = characters.get(event.character) else {
+        let Ok((body_type, hue, map_position, children, prefab, loot, butchering, level, is_persistent)) = characters.get(event.character) else {
             continue;
         };
 
@@ -81,6 +84,13 @@ pub fn spawn_corpses(
         }
 
         if let Some(loot) = loot {
+            let luck = event.killer
+                .and_then(|killer| killers.get(killer).ok())
+                .map_or(0, |summary| summary.luck);
+            corpse.insert(LootContext {
+                level: level.map_or(0, |level| **level),
+                luck,
+            });
             corpse.fabricate_insert(&loot.0);
         }
 
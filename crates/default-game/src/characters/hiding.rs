@@ -0,0 +1,294 @@
+use bevy::prelude::*;
+use yewoh_server::world::combat::AttackTarget;
+use yewoh_server::world::connection::NetClient;
+use yewoh_server::world::entity::{Hidden, MapPosition};
+use yewoh_server::world::spatial::SpatialCharacterLookup;
+
+use crate::activities::combat::OnDealDamage;
+use crate::chat::OnCharacterSpeech;
+use crate::characters::skill_use::{OnUseSkill, SkillUseAppExt};
+use crate::characters::skills::SkillGain;
+use crate::networking::NetClientExt;
+
+pub const HIDING_SKILL_ID: u8 = 21;
+
+pub const HIDING_DIFFICULTY: u16 = 400;
+
+pub const DETECT_HIDDEN_SKILL_ID: u8 = 14;
+
+/// The detection radius at 0 skill; each further 10 points adds a tile.
+pub const DETECT_HIDDEN_BASE_RANGE: i32 = 1;
+
+/// Reveal a hidden entity, i.e. when it takes a visible action such as speaking or attacking.
+pub struct Reveal;
+
+impl EntityCommand for Reveal {
+    fn apply(self, entity: Entity, world: &mut World) {
+        if let Some(mut hidden) = world.get_mut::<Hidden>(entity) {
+            if **hidden {
+                **hidden = false;
+            }
+        }
+    }
+}
+
+pub fn use_hiding(
+    In(event): In<OnUseSkill>,
+    mut gain: SkillGain,
+    clients: Query<&NetClient>,
+    mut characters: Query<&mut Hidden>,
+) {
+    let Ok(mut hidden) = characters.get_mut(event.character) else {
+        return;
+    };
+
+    let success = gain.check_skill(event.character, event.skill_id, HIDING_DIFFICULTY);
+    if success {
+        **hidden = true;
+    }
+
+    if let Ok(client) = clients.get(event.client_entity) {
+        client.send_system_message(if success {
+            "You have hidden yourself well."
+        } else {
+            "You can't seem to hide right now."
+        });
+    }
+}
+
+pub fn use_detect_hidden(
+    In(event): In<OnUseSkill>,
+    mut gain: SkillGain,
+    lookup: Res<SpatialCharacterLookup>,
+    clients: Query<&NetClient>,
+    positions: Query<&MapPosition>,
+    mut hidden: Query<(&MapPosition, &mut Hidden)>,
+) {
+    let Ok(position) = positions.get(event.character) else {
+        return;
+    };
+
+    let value = gain.skill_value(event.character, event.skill_id);
+    let range = DETECT_HIDDEN_BASE_RANGE + value as i32 / 100;
+    let center = position.position.truncate();
+    let mut found = 0;
+    for y in (center.y - range)..=(center.y + range) {
+        for x in (center.x - range)..=(center.x + range) {
+            for entry in lookup.lookup.entries_at(position.map_id, IVec2::new(x, y)) {
+                let target = entry.entity;
+                if target == event.character {
+                    continue;
+                }
+
+                let Ok((target_position, mut target_hidden)) = hidden.get_mut(target) else {
+                    continue;
+                };
+
+                if !**target_hidden || !position.in_range_2d(target_position, range) {
+                    continue;
+                }
+
+                if gain.contest_skill(event.character, event.skill_id, target, HIDING_SKILL_ID) {
+                    **target_hidden = false;
+                    found += 1;
+                }
+            }
+        }
+    }
+
+    if let Ok(client) = clients.get(event.client_entity) {
+        client.send_system_message(if found > 0 {
+            "You find someone hiding nearby."
+        } else {
+            "You can see nothing hidden here."
+        });
+    }
+
+    gain.gain_skill(event.character, event.skill_id, value);
+}
+
+pub fn reveal_attackers(
+    mut commands: Commands,
+    characters: Query<Entity, (With<Hidden>, Changed<AttackTarget>)>,
+) {
+    for entity in &characters {
+        commands.entity(entity).queue(Reveal);
+    }
+}
+
+pub fn reveal_on_damage(
+    mut commands: Commands,
+    mut events: EventReader<OnDealDamage>,
+) {
+    for event in events.read() {
+        for entity in [event.source, event.target] {
+            if let Some(mut entity) = commands.get_entity(entity) {
+                entity.queue(Reveal);
+            }
+        }
+    }
+}
+
+pub fn reveal_speakers(
+    mut commands: Commands,
+    mut events: EventReader<OnCharacterSpeech>,
+) {
+    for event in events.read() {
+        if let Some(mut speaker) = commands.get_entity(event.speaker) {
+            speaker.queue(Reveal);
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_skill_handler(HIDING_SKILL_ID, use_hiding)
+        .add_skill_handler(DETECT_HIDDEN_SKILL_ID, use_detect_hidden)
+        .add_systems(Update, (
+            reveal_attackers,
+            reveal_on_damage,
+            reveal_speakers,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use yewoh_server::world::characters::{CharacterBodyType, OnCharacterAnimationStart, OnClientUseSkill};
+    use yewoh_server::world::connection::Possessing;
+    use yewoh_server::world::entity::Direction;
+    use yewoh_server::world::spatial::update_character_lookup;
+
+    use crate::activities::CurrentActivity;
+    use crate::activities::combat::{attack_current_target, MeleeWeapon};
+    use crate::characters::skills::{CharacterSkills, SkillLockState, SkillValue};
+    use crate::data::static_data::StaticData;
+    use crate::rates::ServerRates;
+    use crate::rng::GameRng;
+
+    use super::*;
+
+    fn hiding_app() -> App {
+        let mut characters = SpatialCharacterLookup::default();
+        characters.lookup.insert_map(1, IVec2::splat(64));
+
+        let mut app = App::new();
+        app
+            .insert_resource(GameRng::from_seed(1))
+            .insert_resource(StaticData {
+                cities: default(),
+                maps: default(),
+                skills: default(),
+                locations: default(),
+            })
+            .init_resource::<ServerRates>()
+            .insert_resource(characters)
+            .add_event::<OnClientUseSkill>()
+            .add_event::<OnDealDamage>()
+            .add_event::<OnCharacterSpeech>()
+            .add_plugins((
+                crate::characters::skill_use::plugin,
+                plugin,
+            ))
+            .add_systems(First, update_character_lookup);
+        app
+    }
+
+    fn skills(skill_id: u8, value: u16) -> CharacterSkills {
+        let mut skills = CharacterSkills::default();
+        skills.skills.insert(skill_id, SkillValue {
+            value,
+            lock: SkillLockState::Locked,
+            ..default()
+        });
+        skills
+    }
+
+    fn spawn_character(app: &mut App, x: i32, skills: CharacterSkills, hidden: bool) -> Entity {
+        app.world_mut().spawn((
+            CharacterBodyType(0x190),
+            MapPosition { position: IVec3::new(x, 10, 0), map_id: 1 },
+            skills,
+            Hidden(hidden),
+        )).id()
+    }
+
+    fn use_skill(app: &mut App, character: Entity, skill_id: u8) {
+        let client_entity = app.world_mut().spawn(Possessing { entity: character }).id();
+        app.world_mut().send_event(OnClientUseSkill {
+            client_entity,
+            skill_id: skill_id as u16,
+        });
+        app.update();
+    }
+
+    fn is_hidden(app: &App, entity: Entity) -> bool {
+        **app.world().get::<Hidden>(entity).unwrap()
+    }
+
+    #[test]
+    fn test_hide_and_reveal() {
+        let mut app = hiding_app();
+        let character = spawn_character(&mut app, 10, skills(HIDING_SKILL_ID, 1000), false);
+        let target = app.world_mut().spawn_empty().id();
+
+        use_skill(&mut app, character, HIDING_SKILL_ID);
+        assert!(is_hidden(&app, character));
+
+        app.world_mut().entity_mut(character).insert(AttackTarget { target });
+        app.update();
+        assert!(!is_hidden(&app, character));
+    }
+
+    #[test]
+    fn test_speech_reveals() {
+        let mut app = hiding_app();
+        let speaker = spawn_character(&mut app, 10, default(), true);
+        let listener = spawn_character(&mut app, 11, default(), true);
+        let client_entity = app.world_mut().spawn(Possessing { entity: speaker }).id();
+
+        app.world_mut().send_event(OnCharacterSpeech {
+            client_entity,
+            speaker,
+            text: "Hail".into(),
+        });
+        app.update();
+        assert!(!is_hidden(&app, speaker));
+        assert!(is_hidden(&app, listener));
+    }
+
+    #[test]
+    fn test_swing_reveals() {
+        let mut app = hiding_app();
+        app
+            .add_event::<OnCharacterAnimationStart>()
+            .add_systems(Update, attack_current_target.before(reveal_on_damage));
+
+        let target = spawn_character(&mut app, 11, default(), false);
+        let attacker = spawn_character(&mut app, 10, default(), false);
+        app.world_mut().entity_mut(attacker).insert((
+            CurrentActivity::Idle,
+            AttackTarget { target },
+            Direction::East,
+            MeleeWeapon { range: 1, ..default() },
+        ));
+        app.update();
+
+        // Hide mid-fight, so only the next swing can reveal the attacker.
+        app.world_mut().entity_mut(attacker).insert((Hidden(true), CurrentActivity::Idle));
+        app.update();
+        assert!(!is_hidden(&app, attacker));
+    }
+
+    #[test]
+    fn test_detect_hidden() {
+        let mut app = hiding_app();
+        let detector = spawn_character(&mut app, 10, skills(DETECT_HIDDEN_SKILL_ID, 500), false);
+        let near = spawn_character(&mut app, 12, default(), true);
+        let far = spawn_character(&mut app, 30, default(), true);
+        app.update();
+
+        use_skill(&mut app, detector, DETECT_HIDDEN_SKILL_ID);
+        assert!(!is_hidden(&app, near));
+        assert!(is_hidden(&app, far));
+    }
+}
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use yewoh::Direction;
 use yewoh_server::world::characters::CharacterName;
 use crate::DefaultGameSet;
+use crate::entities::names::DisplayNames;
 use crate::entities::tooltips::{OnRequestEntityTooltip, TooltipLine, TOOLTIP_NAME_PRIORITY};
 use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
 
@@ -11,8 +12,20 @@ pub mod persistence;
 
 pub mod paperdoll;
 
+pub mod profile;
+
 pub mod corpses;
 
+pub mod rewards;
+
+pub mod skills;
+
+pub mod skill_use;
+
+pub mod hiding;
+
+pub mod reputation;
+
 #[derive(Clone, Debug, Default, Event)]
 pub struct OnCharacterMove {
     pub blocked: bool,
@@ -21,15 +34,12 @@ pub struct OnCharacterMove {
 }
 
 pub fn add_character_name_tooltip(
-    names: Query<&CharacterName>,
+    names: DisplayNames,
     mut events: EntityEventReader<OnRequestEntityTooltip, CharacterName>,
 ) {
     for event in events.read() {
-        let Ok(name) = names.get(event.target) else {
-            continue;
-        };
-
-        event.lines.push(TooltipLine::from_str(name.0.to_string(), TOOLTIP_NAME_PRIORITY));
+        let name = names.display_name(event.target);
+        event.lines.push(TooltipLine::from_str(name, TOOLTIP_NAME_PRIORITY));
     }
 }
 
@@ -40,7 +50,13 @@ pub fn plugin(app: &mut App) {
             player::plugin,
             persistence::plugin,
             paperdoll::plugin,
+            profile::plugin,
             corpses::plugin,
+            rewards::plugin,
+            skills::plugin,
+            skill_use::plugin,
+            hiding::plugin,
+            reputation::plugin,
         ))
         .add_event::<OnCharacterMove>()
         .add_systems(First, (
@@ -1,16 +1,21 @@
 use bevy::app::App;
 use bevy::prelude::*;
-use yewoh::protocol::OpenPaperDoll;
+use yewoh::protocol::{OpenPaperDoll, PaperDollFlags};
 use yewoh::types::FixedString;
-use yewoh_server::world::connection::NetClient;
+use yewoh_server::world::characters::WarMode;
+use yewoh_server::world::connection::{NetClient, Possessing};
+use yewoh_server::world::entity::{EquipmentSlot, EquippedPosition};
+use yewoh_server::world::items::OnContainerOpen;
 use yewoh_server::world::net_id::NetId;
 
 use crate::DefaultGameSet;
-use crate::entities::context_menu::{ContextMenuEntry, OnEntityContextMenuAction, OnEntityContextMenuRequest};
-use crate::entities::interactions::OnEntityDoubleClick;
+use crate::entities::context_menu::{ContextMenuEntry, OnEntityContextMenuRequest};
+use crate::entities::interactions::{DoubleClickAppExt, OnEntityDoubleClick};
+use crate::entities::names::DisplayNames;
 use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
 
 const PAPERDOLL_ID: u16 = 1;
+const BACKPACK_ID: u16 = 3;
 
 #[derive(Clone, Debug, Default, Component, Reflect)]
 #[reflect(Component)]
@@ -20,62 +25,132 @@ pub struct Paperdoll;
 #[reflect(Component)]
 pub struct DoubleClickPaperdoll;
 
+#[derive(Clone, Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct CharacterTitle(pub String);
+
+#[derive(Clone, Debug, Event)]
+pub struct OnPaperdollRequest {
+    pub client_entity: Entity,
+    pub target: Entity,
+}
+
 pub fn paperdoll_context_menu(
+    clients: Query<&Possessing>,
+    children: Query<&Children>,
+    equipment: Query<&EquippedPosition>,
     mut events: EntityEventReader<OnEntityContextMenuRequest, Paperdoll>,
 ) {
     for event in events.read() {
+        let request = OnPaperdollRequest {
+            client_entity: event.client_entity,
+            target: event.target,
+        };
         event.entries.push(ContextMenuEntry {
             id: PAPERDOLL_ID,
             text_id: 3006123,
             ..default()
-        });
-    }
-}
+        }.with_event(request));
 
-pub fn paperdoll_context_menu_action(
-    clients: Query<&NetClient>,
-    net_objects: Query<&NetId>,
-    mut events: EntityEventReader<OnEntityContextMenuAction, Paperdoll>,
-) {
-    for event in events.read() {
-        if event.id != PAPERDOLL_ID {
+        // Only offer to open your own backpack.
+        if !clients.get(event.client_entity).is_ok_and(|p| p.entity == event.target) {
             continue;
         }
 
-        let Ok(client) = clients.get(event.client_entity) else {
+        let backpack = children.get(event.target).ok()
+            .and_then(|children| children.iter()
+                .find(|child| equipment.get(**child).is_ok_and(|e| e.slot == EquipmentSlot::Backpack)))
+            .copied();
+        let Some(backpack) = backpack else {
             continue;
         };
 
-        let Ok(net_id) = net_objects.get(event.target) else {
-            continue;
+        let request = OnContainerOpen {
+            client_entity: event.client_entity,
+            container: backpack,
         };
-
-        client.send_packet(OpenPaperDoll {
-            id: net_id.id,
-            text: FixedString::from_str("Me, Myself and I"),
-            flags: Default::default(),
-        });
+        event.entries.push(ContextMenuEntry {
+            id: BACKPACK_ID,
+            text_id: 3006145,
+            ..default()
+        }.with_event(request));
     }
 }
 
 pub fn paperdoll_double_click(
-    clients: Query<&NetClient>,
-    net_objects: Query<&NetId>,
-    mut events: EntityEventReader<OnEntityDoubleClick, DoubleClickPaperdoll>,
+    In(event): In<OnEntityDoubleClick>,
+    mut out_events: EventWriter<OnPaperdollRequest>,
+) {
+    out_events.send(OnPaperdollRequest {
+        client_entity: event.client_entity,
+        target: event.target,
+    });
+}
+
+pub fn paperdoll_button(
+    In(event): In<OnEntityDoubleClick>,
+    double_click: Query<(), With<DoubleClickPaperdoll>>,
+    mut out_events: EventWriter<OnPaperdollRequest>,
+) {
+    // Entities which open their paperdoll on every double-click are handled above.
+    if !event.paperdoll || double_click.contains(event.target) {
+        return;
+    }
+
+    out_events.send(OnPaperdollRequest {
+        client_entity: event.client_entity,
+        target: event.target,
+    });
+}
+
+pub fn paperdoll_text(name: &str, title: &str) -> FixedString<60> {
+    let mut text = if title.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name}, {title}")
+    };
+
+    let mut len = text.len().min(60);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    text.truncate(len);
+
+    FixedString::from_str(&text)
+}
+
+pub fn send_paperdoll(
+    clients: Query<(&NetClient, Option<&Possessing>)>,
+    characters: Query<(&NetId, Option<&CharacterTitle>, Option<&WarMode>)>,
+    names: DisplayNames,
+    mut events: EventReader<OnPaperdollRequest>,
 ) {
     for event in events.read() {
-        let Ok(client) = clients.get(event.client_entity) else {
+        let Ok((client, possessing)) = clients.get(event.client_entity) else {
             continue;
         };
 
-        let Ok(net_id) = net_objects.get(event.target) else {
+        let Ok((net_id, title, war_mode)) = characters.get(event.target) else {
             continue;
         };
 
+        let name = names.display_name(event.target);
+        let title = title.map_or("", |t| t.0.as_str());
+
+        let mut flags = PaperDollFlags::empty();
+        if war_mode.is_some_and(|w| **w) {
+            flags |= PaperDollFlags::WAR_MODE;
+        }
+
+        // Only the owner may take items on and off.
+        if possessing.is_some_and(|p| p.entity == event.target) {
+            flags |= PaperDollFlags::CAN_LIFT;
+        }
+
         client.send_packet(OpenPaperDoll {
             id: net_id.id,
-            text: FixedString::from_str("Me, Myself and I"),
-            flags: Default::default(),
+            text: paperdoll_text(&name, title),
+            flags,
         });
     }
 }
@@ -84,16 +159,136 @@ pub fn plugin(app: &mut App) {
     app
         .register_type::<Paperdoll>()
         .register_type::<DoubleClickPaperdoll>()
+        .register_type::<CharacterTitle>()
+        .add_event::<OnPaperdollRequest>()
+        .add_double_click_handler::<DoubleClickPaperdoll, _>(paperdoll_double_click)
+        .add_double_click_handler::<Paperdoll, _>(paperdoll_button)
         .add_plugins((
-            EntityEventRoutePlugin::<OnEntityDoubleClick, DoubleClickPaperdoll>::default(),
             EntityEventRoutePlugin::<OnEntityContextMenuRequest, Paperdoll>::default(),
-            EntityEventRoutePlugin::<OnEntityContextMenuAction, Paperdoll>::default(),
         ))
         .add_systems(First, (
-            (
-                paperdoll_context_menu,
-                paperdoll_context_menu_action,
-                paperdoll_double_click,
-            ).in_set(DefaultGameSet::HandleEvents),
+            paperdoll_context_menu.in_set(DefaultGameSet::HandleEvents),
+            send_paperdoll.in_set(DefaultGameSet::FinishEvents),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use yewoh::EntityId;
+    use yewoh_server::world::characters::CharacterName;
+    use yewoh_server::world::input::{OnClientContextMenuAction, OnClientContextMenuRequest};
+    use yewoh_server::world::ServerSet;
+
+    use crate::entities::context_menu::OpenContextMenu;
+    use crate::entities::interactions::OnEntitySingleClick;
+    use crate::entity_events::EntityEventPlugin;
+    use crate::networking::{sent_packets, test_client};
+
+    use super::*;
+
+    fn open_paperdoll(viewer_is_owner: bool) -> OpenPaperDoll {
+        let mut app = App::new();
+        app
+            .add_event::<OnPaperdollRequest>()
+            .add_systems(Update, send_paperdoll);
+
+        let character = app.world_mut()
+            .spawn((
+                NetId { id: EntityId::from_u32(1) },
+                CharacterName("Gerome".into()),
+                CharacterTitle("the Blacksmith".into()),
+            ))
+            .id();
+
+        let (client, mut rx) = test_client();
+        let mut client_entity = app.world_mut().spawn(client);
+        if viewer_is_owner {
+            client_entity.insert(Possessing { entity: character });
+        }
+        let client_entity = client_entity.id();
+
+        app.world_mut().send_event(OnPaperdollRequest { client_entity, target: character });
+        app.update();
+
+        sent_packets::<OpenPaperDoll>(&mut rx).into_iter()
+            .next()
+            .expect("expected paperdoll packet")
+    }
+
+    #[test]
+    fn test_own_paperdoll() {
+        let packet = open_paperdoll(true);
+        assert_eq!(packet.id, EntityId::from_u32(1));
+        assert_eq!(&*packet.text, "Gerome, the Blacksmith");
+        assert_eq!(packet.flags, PaperDollFlags::CAN_LIFT);
+    }
+
+    #[test]
+    fn test_other_paperdoll() {
+        let packet = open_paperdoll(false);
+        assert_eq!(&*packet.text, "Gerome, the Blacksmith");
+        assert_eq!(packet.flags, PaperDollFlags::empty());
+    }
+
+    #[test]
+    fn test_paperdoll_text_truncated() {
+        let text = paperdoll_text(&"\u{e9}".repeat(40), "");
+        assert_eq!(text.len(), 60);
+    }
+
+    #[test]
+    fn test_context_menu() {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientContextMenuRequest>()
+            .add_event::<OnClientContextMenuAction>()
+            .add_event::<OnContainerOpen>()
+            .add_plugins((
+                EntityEventPlugin::<OnEntitySingleClick>::default(),
+                EntityEventPlugin::<OnEntityDoubleClick>::default(),
+                crate::entities::context_menu::plugin,
+                plugin,
+            ))
+            .configure_sets(First, (
+                DefaultGameSet::DispatchEvents.after(ServerSet::HandlePackets),
+                DefaultGameSet::HandleEvents,
+                DefaultGameSet::FinishEvents,
+            ).chain());
+
+        let character = app.world_mut()
+            .spawn((Paperdoll, NetId { id: EntityId::from_u32(1) }, CharacterName("Gerome".into())))
+            .id();
+        let backpack = app.world_mut()
+            .spawn(EquippedPosition { slot: EquipmentSlot::Backpack })
+            .set_parent(character)
+            .id();
+
+        let (client, mut rx) = test_client();
+        let client_entity = app.world_mut().spawn((client, Possessing { entity: character })).id();
+
+        app.world_mut().send_event(OnClientContextMenuRequest { client_entity, target: character });
+        app.update();
+
+        let menu = app.world().get::<OpenContextMenu>(client_entity).unwrap();
+        let ids = menu.entries.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![PAPERDOLL_ID, BACKPACK_ID]);
+
+        app.world_mut().send_event(OnClientContextMenuAction { client_entity, target: character, action_id: PAPERDOLL_ID });
+        app.update();
+
+        let opened = sent_packets::<OpenPaperDoll>(&mut rx);
+        assert!(!opened.is_empty());
+        assert!(opened.iter().all(|packet| packet.id == EntityId::from_u32(1)));
+
+        app.world_mut().send_event(OnClientContextMenuRequest { client_entity, target: character });
+        app.update();
+        app.world_mut().send_event(OnClientContextMenuAction { client_entity, target: character, action_id: BACKPACK_ID });
+        app.update();
+
+        let events = app.world().resource::<Events<OnContainerOpen>>();
+        let containers = events.iter_current_update_events()
+            .map(|e| e.container)
+            .collect::<Vec<_>>();
+        assert_eq!(containers, vec![backpack]);
+    }
+}
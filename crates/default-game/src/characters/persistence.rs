@@ -2,6 +2,7 @@ use bevy::ecs::query::WorldQuery;
 use bevy::prelude::*;
 use yewoh_server::world::characters::{CharacterName, CharacterStats};
 
+use crate::characters::profile::Profile;
 use crate::entities::Persistent;
 use crate::persistence::{BundleSerializer, SerializationSetupExt};
 
@@ -63,9 +64,31 @@ impl BundleSerializer for StatsSerializer {
     }
 }
 
+#[derive(Default)]
+pub struct ProfileSerializer;
+
+impl BundleSerializer for ProfileSerializer {
+    type Query = &'static Profile;
+    type Filter = With<Persistent>;
+    type Bundle = Profile;
+
+    fn id() -> &'static str {
+        "Profile"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        item.clone()
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(bundle);
+    }
+}
+
 pub fn plugin(app: &mut App) {
     app
         .register_type::<PersistStats>()
         .register_serializer::<NameSerializer>()
-        .register_serializer::<StatsSerializer>();
+        .register_serializer::<StatsSerializer>()
+        .register_serializer::<ProfileSerializer>();
 }
@@ -1,4 +1,6 @@
+
 use bevy::prelude::*;
+use serde::Deserialize;
 use yewoh_server::world::characters::CharacterSex;
 use yewoh_server::world::entity::{EquipmentSlot, Hue};
 
@@ -14,6 +16,24 @@ pub struct NewPlayerCharacter {
     pub pants_hue: u16,
 }
 
+/// The backpack equipped on new characters, and the items placed inside it.
+#[derive(Clone, Debug, PartialEq, Eq, Reflect, Resource, Deserialize)]
+#[reflect(Default, Resource)]
+#[serde(default)]
+pub struct StartingBackpack {
+    pub prefab: String,
+    pub items: Vec<String>,
+}
+
+impl Default for StartingBackpack {
+    fn default() -> Self {
+        Self {
+            prefab: "backpack".into(),
+            items: Vec::new(),
+        }
+    }
+}
+
 pub fn spawn_starting_items(
     mut commands: Commands,
     players: Query<(Entity, &NewPlayerCharacter, &CharacterSex)>,
@@ -21,12 +41,6 @@ pub fn spawn_starting_items(
     for (entity, request, sex) in &players {
         commands.entity(entity).remove::<NewPlayerCharacter>();
 
-        commands.fabricate_prefab("backpack")
-            .insert((
-                Persistent,
-            ))
-            .move_to_equipped_position(entity, EquipmentSlot::Backpack);
-
         commands.fabricate_prefab("test_top")
             .insert((
                 Persistent,
@@ -55,6 +69,8 @@ pub fn spawn_starting_items(
 pub fn plugin(app: &mut App) {
     app
         .register_type::<NewPlayerCharacter>()
+        .register_type::<StartingBackpack>()
+        .init_resource::<StartingBackpack>()
         .add_systems(Update, (
             spawn_starting_items,
         ));
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use yewoh::protocol::ProfileResponse;
+use yewoh_server::world::characters::{CharacterName, OnClientProfileRequest, OnClientProfileUpdateRequest};
+use yewoh_server::world::connection::{NetClient, Possessing};
+use yewoh_server::world::net_id::NetId;
+use yewoh_server::world::ServerSet;
+
+use crate::characters::paperdoll::CharacterTitle;
+
+pub const MAX_PROFILE_LENGTH: usize = 511;
+
+#[derive(Clone, Debug, Default, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct Profile {
+    pub text: String,
+    pub locked: bool,
+}
+
+pub fn on_client_profile_request(
+    clients: Query<&NetClient>,
+    characters: Query<(&NetId, Option<&CharacterName>, Option<&CharacterTitle>, Option<&Profile>)>,
+    mut events: EventReader<OnClientProfileRequest>,
+) {
+    for request in events.read() {
+        let Ok(client) = clients.get(request.client_entity) else {
+            continue;
+        };
+
+        let Ok((net_id, name, title, profile)) = characters.get(request.target) else {
+            continue;
+        };
+
+        let name = name.map_or("", |n| n.0.as_str());
+        let header = match title {
+            Some(title) if !title.0.is_empty() => format!("{name}, {}", title.0),
+            _ => name.to_string(),
+        };
+
+        client.send_packet(ProfileResponse {
+            target_id: net_id.id,
+            header,
+            footer: String::new(),
+            profile: profile.map_or_else(String::new, |p| p.text.clone()),
+        });
+    }
+}
+
+pub fn on_client_profile_update(
+    mut commands: Commands,
+    clients: Query<&Possessing>,
+    mut profiles: Query<Option<&mut Profile>>,
+    mut events: EventReader<OnClientProfileUpdateRequest>,
+) {
+    for request in events.read() {
+        // Only the character's own player may change their profile.
+        let Ok(possessing) = clients.get(request.client_entity) else {
+            continue;
+        };
+
+        if possessing.entity != request.target {
+            continue;
+        }
+
+        let Ok(profile) = profiles.get_mut(request.target) else {
+            continue;
+        };
+
+        let text = request.new_profile.chars().take(MAX_PROFILE_LENGTH).collect();
+        match profile {
+            Some(mut profile) => {
+                if !profile.locked {
+                    profile.text = text;
+                }
+            }
+            None => {
+                commands.entity(request.target).insert(Profile { text, locked: false });
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<Profile>()
+        .add_systems(First, (
+            (
+                on_client_profile_request,
+                on_client_profile_update,
+            ).in_set(ServerSet::HandlePackets),
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update_profile(locked: bool, new_profile: &str) -> String {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientProfileUpdateRequest>()
+            .add_systems(Update, on_client_profile_update);
+
+        let character = app.world_mut()
+            .spawn(Profile { text: "Old".into(), locked })
+            .id();
+        let client_entity = app.world_mut()
+            .spawn(Possessing { entity: character })
+            .id();
+
+        app.world_mut().send_event(OnClientProfileUpdateRequest {
+            client_entity,
+            target: character,
+            new_profile: new_profile.to_string(),
+        });
+        app.update();
+
+        app.world().get::<Profile>(character).unwrap().text.clone()
+    }
+
+    #[test]
+    fn test_update_profile() {
+        assert_eq!(update_profile(false, "Smith of Britain"), "Smith of Britain");
+    }
+
+    #[test]
+    fn test_update_locked_profile() {
+        assert_eq!(update_profile(true, "Smith of Britain"), "Old");
+    }
+
+    #[test]
+    fn test_update_profile_truncated() {
+        let text = update_profile(false, &"a".repeat(MAX_PROFILE_LENGTH + 10));
+        assert_eq!(text.len(), MAX_PROFILE_LENGTH);
+    }
+}
@@ -0,0 +1,144 @@
+use bevy::ecs::query::WorldQuery;
+use bevy::prelude::*;
+use yewoh_server::world::characters::Murderer;
+
+use crate::entities::Persistent;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+
+/// The number of murders at which a character becomes a murderer.
+pub const MURDERER_THRESHOLD: u16 = 5;
+
+/// The most fame a character can hold.
+pub const MAX_FAME: i32 = 32000;
+
+/// The most karma a character can hold, either positive or negative.
+pub const MAX_KARMA: i32 = 32000;
+
+/// How many innocents a character has murdered.
+#[derive(Clone, Copy, Debug, Default, Deref, DerefMut, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct MurderCount(pub u16);
+
+/// How renowned a character is, and how good or evil their deeds have been.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct Reputation {
+    pub fame: i32,
+    pub karma: i32,
+}
+
+impl Reputation {
+    pub fn award(&mut self, fame: i32, karma: i32) {
+        self.fame = self.fame.saturating_add(fame).clamp(0, MAX_FAME);
+        self.karma = self.karma.saturating_add(karma).clamp(-MAX_KARMA, MAX_KARMA);
+    }
+}
+
+/// Add fame and karma to a character, giving them a [`Reputation`] if needed.
+pub struct AwardReputation {
+    pub fame: i32,
+    pub karma: i32,
+}
+
+impl EntityCommand for AwardReputation {
+    fn apply(self, entity: Entity, world: &mut World) {
+        let mut entity = world.entity_mut(entity);
+        let mut reputation = entity.get::<Reputation>().copied().unwrap_or_default();
+        reputation.award(self.fame, self.karma);
+        entity.insert(reputation);
+    }
+}
+
+pub fn update_murderers(
+    mut characters: Query<(&MurderCount, &mut Murderer), Changed<MurderCount>>,
+) {
+    for (count, mut murderer) in &mut characters {
+        let is_murderer = **count >= MURDERER_THRESHOLD;
+        if **murderer != is_murderer {
+            **murderer = is_murderer;
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MurderCountSerializer;
+
+impl BundleSerializer for MurderCountSerializer {
+    type Query = &'static MurderCount;
+    type Filter = With<Persistent>;
+    type Bundle = u16;
+
+    fn id() -> &'static str {
+        "MurderCount"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        **item
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(MurderCount(bundle));
+    }
+}
+
+#[derive(Default)]
+pub struct ReputationSerializer;
+
+impl BundleSerializer for ReputationSerializer {
+    type Query = &'static Reputation;
+    type Filter = With<Persistent>;
+    type Bundle = Reputation;
+
+    fn id() -> &'static str {
+        "Reputation"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        *item
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(bundle);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<MurderCount>()
+        .register_type::<Reputation>()
+        .register_serializer::<MurderCountSerializer>()
+        .register_serializer::<ReputationSerializer>()
+        .add_systems(Update, update_murderers);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::persistence::{save_and_load, PersistencePlugin};
+
+    use super::*;
+
+    fn persistence_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            PersistencePlugin,
+            plugin,
+        ));
+        app
+    }
+
+    #[test]
+    fn test_persist_reputation() {
+        let mut app = persistence_app();
+        let world = app.world_mut();
+        world.spawn((Persistent, MurderCount(5), Reputation { fame: 100, karma: -50 }));
+
+        let mut loaded = persistence_app();
+        save_and_load(world, loaded.world_mut());
+
+        let world = loaded.world_mut();
+        let (count, reputation) = world.query::<(&MurderCount, &Reputation)>().single(world);
+        assert_eq!(**count, 5);
+        assert_eq!(*reputation, Reputation { fame: 100, karma: -50 });
+    }
+}
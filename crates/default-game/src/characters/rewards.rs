@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use yewoh_server::world::characters::{Allies, Health};
+use yewoh_server::world::entity::MapPosition;
+
+use crate::activities::combat::{apply_damage, OnDealDamage};
+use crate::characters::corpses::OnCharacterDeath;
+
+/// How close allies of a killer must be to the creature to share in its rewards.
+pub const REWARD_SHARE_RANGE: i32 = 18;
+
+/// The rewards for killing a creature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Component, Reflect, Deserialize)]
+#[reflect(Component, Default, Deserialize)]
+pub struct KillRewards {
+    pub fame: i32,
+    pub karma: i32,
+    pub gold: u32,
+}
+
+/// The damage a creature has taken from each attacker.
+#[derive(Debug, Clone, Default, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct DamageTaken(pub HashMap<Entity, u32>);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KillRewardShare {
+    pub entity: Entity,
+    /// The fraction of the rewards owed to this entity.
+    pub share: f32,
+}
+
+/// Sent when a creature with [`KillRewards`] dies, for progression systems to award.
+#[derive(Debug, Clone, Event)]
+pub struct OnCreatureKilled {
+    pub creature: Entity,
+    pub rewards: KillRewards,
+    pub recipients: Vec<KillRewardShare>,
+}
+
+pub fn record_damage_taken(
+    mut commands: Commands,
+    mut creatures: Query<Option<&mut DamageTaken>, With<KillRewards>>,
+    mut events: EventReader<OnDealDamage>,
+) {
+    for event in events.read() {
+        let Ok(damage_taken) = creatures.get_mut(event.target) else {
+            continue;
+        };
+
+        match damage_taken {
+            Some(mut damage_taken) => {
+                *damage_taken.0.entry(event.source).or_default() += event.damage as u32;
+            }
+            None => {
+                let mut damage_taken = DamageTaken::default();
+                damage_taken.0.insert(event.source, event.damage as u32);
+                commands.entity(event.target).insert(damage_taken);
+            }
+        }
+    }
+}
+
+pub fn distribute_kill_rewards(
+    creatures: Query<(&KillRewards, &MapPosition, Option<&DamageTaken>)>,
+    characters: Query<(&MapPosition, &Health)>,
+    allies: Query<&Allies>,
+    mut died_events: EventReader<OnCharacterDeath>,
+    mut killed_events: EventWriter<OnCreatureKilled>,
+) {
+    for event in died_events.read() {
+        let Ok((rewards, position, damage_taken)) = creatures.get(event.character) else {
+            continue;
+        };
+
+        let damagers = damage_taken.map(|d| &d.0);
+        let total = damagers.map_or(0, |d| d.values().sum::<u32>());
+        let mut shares = HashMap::<Entity, f32>::new();
+        for (damager, damage) in damagers.into_iter().flatten() {
+            if total == 0 {
+                break;
+            }
+
+            // The damager's portion is split evenly with any of their allies nearby.
+            let mut party = vec![*damager];
+            if let Ok(damager_allies) = allies.get(*damager) {
+                party.extend(damager_allies.0.iter().copied().filter(|ally| characters.get(*ally)
+                    .is_ok_and(|(ally_position, health)| health.hp > 0 &&
+                        ally_position.in_range_2d(position, REWARD_SHARE_RANGE))));
+            }
+
+            let portion = *damage as f32 / total as f32 / party.len() as f32;
+            for member in party {
+                *shares.entry(member).or_default() += portion;
+            }
+        }
+
+        killed_events.send(OnCreatureKilled {
+            creature: event.character,
+            rewards: *rewards,
+            recipients: shares.into_iter()
+                .map(|(entity, share)| KillRewardShare { entity, share })
+                .collect(),
+        });
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<KillRewards>()
+        .register_type::<DamageTaken>()
+        .add_event::<OnCreatureKilled>()
+        .add_systems(Update, (
+            record_damage_taken.before(apply_damage),
+            distribute_kill_rewards.after(apply_damage),
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashSet;
+
+    use super::*;
+
+    fn at(x: i32) -> MapPosition {
+        MapPosition { position: IVec3::new(x, 10, 0), map_id: 1 }
+    }
+
+    fn deal_damage(app: &mut App, target: Entity, source: Entity, damage: u16) {
+        app.world_mut().send_event(OnDealDamage {
+            target,
+            source,
+            damage,
+            damage_type: default(),
+            kind: default(),
+            location: at(10),
+        });
+        app.update();
+    }
+
+    #[test]
+    fn test_kill_rewards() {
+        let mut app = App::new();
+        app
+            .add_event::<OnDealDamage>()
+            .add_event::<OnCharacterDeath>()
+            .add_plugins(plugin)
+            .add_systems(Update, apply_damage);
+
+        let rewards = KillRewards { fame: 100, karma: -50, gold: 20 };
+        let creature = app.world_mut()
+            .spawn((rewards, at(10), Health { hp: 30, max_hp: 30 }))
+            .id();
+        let killer = app.world_mut().spawn((at(11), Health::default())).id();
+        let helper = app.world_mut().spawn((at(9), Health::default())).id();
+        let ally = app.world_mut().spawn((at(12), Health::default())).id();
+        let distant_ally = app.world_mut().spawn((at(60), Health::default())).id();
+        app.world_mut().entity_mut(killer).insert(Allies(HashSet::from_iter([ally, distant_ally])));
+
+        deal_damage(&mut app, creature, helper, 10);
+        assert!(app.world().resource::<Events<OnCreatureKilled>>().is_empty());
+        deal_damage(&mut app, creature, killer, 20);
+
+        let events = app.world().resource::<Events<OnCreatureKilled>>();
+        let killed = events.iter_current_update_events().collect::<Vec<_>>();
+        assert_eq!(killed.len(), 1);
+        assert_eq!(killed[0].creature, creature);
+        assert_eq!(killed[0].rewards, rewards);
+
+        let share = |entity| killed[0].recipients.iter()
+            .find(|r| r.entity == entity)
+            .map_or(0.0, |r| r.share);
+        assert!((share(killer) - 1.0 / 3.0).abs() < 0.001);
+        assert!((share(ally) - 1.0 / 3.0).abs() < 0.001);
+        assert!((share(helper) - 1.0 / 3.0).abs() < 0.001);
+        assert_eq!(share(distant_ally), 0.0);
+    }
+}
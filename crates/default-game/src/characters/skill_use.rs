@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use yewoh_server::world::characters::OnClientUseSkill;
+use yewoh_server::world::connection::Possessing;
+use yewoh_server::world::entity::Frozen;
+
+use crate::DefaultGameSet;
+
+/// A request from a client to actively use a skill.
+#[derive(Clone, Debug, Event)]
+pub struct OnUseSkill {
+    pub client_entity: Entity,
+    pub character: Entity,
+    pub skill_id: u8,
+}
+
+#[derive(Default, Resource)]
+pub struct SkillHandlers {
+    handlers: HashMap<u8, SystemId<In<OnUseSkill>>>,
+}
+
+pub fn dispatch_use_skill(
+    mut commands: Commands,
+    handlers: Res<SkillHandlers>,
+    clients: Query<&Possessing>,
+    frozen: Query<&Frozen>,
+    mut events: EventReader<OnClientUseSkill>,
+) {
+    for request in events.read() {
+        let Ok(possessing) = clients.get(request.client_entity) else {
+            continue;
+        };
+
+        if frozen.get(possessing.entity).is_ok_and(|f| **f) {
+            continue;
+        }
+
+        let Ok(skill_id) = u8::try_from(request.skill_id) else {
+            continue;
+        };
+
+        let Some(handler) = handlers.handlers.get(&skill_id) else {
+            debug!("no handler for skill {skill_id}");
+            continue;
+        };
+
+        commands.run_system_with_input(*handler, OnUseSkill {
+            client_entity: request.client_entity,
+            character: possessing.entity,
+            skill_id,
+        });
+    }
+}
+
+pub trait SkillUseAppExt {
+    /// Run `handler` whenever a client uses the skill `skill_id`.
+    fn add_skill_handler<M>(
+        &mut self, skill_id: u8, handler: impl IntoSystem<In<OnUseSkill>, (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl SkillUseAppExt for App {
+    fn add_skill_handler<M>(
+        &mut self, skill_id: u8, handler: impl IntoSystem<In<OnUseSkill>, (), M> + 'static,
+    ) -> &mut Self {
+        let handler = self.world_mut().register_system(handler);
+        self.world_mut().get_resource_or_insert_with(SkillHandlers::default).handlers.insert(skill_id, handler);
+        self
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .init_resource::<SkillHandlers>()
+        .add_systems(First, (
+            dispatch_use_skill.in_set(DefaultGameSet::HandleEvents),
+        ));
+}
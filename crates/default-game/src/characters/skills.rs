@@ -0,0 +1,736 @@
+use std::collections::HashMap;
+
+use bevy::ecs::query::WorldQuery;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rand::Rng;
+use smallvec::{smallvec, SmallVec};
+use yewoh::protocol::{SkillEntry, SkillLock, SkillsResponse, SkillsResponseKind};
+use yewoh_server::world::characters::{CharacterStats, OnClientSkillLockChange, OnClientSkillsRequest, OnClientStatLockChange};
+use yewoh_server::world::connection::{NetClient, OwningClient, Possessing};
+
+use crate::DefaultGameSet;
+use crate::accounts::repository::NewCharacterSkill;
+use crate::data::skills::Skills;
+use crate::data::static_data::StaticData;
+use crate::entities::Persistent;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+use crate::rates::ServerRates;
+use crate::rng::GameRng;
+
+/// The default cap for a single skill, in tenths of a point.
+pub const DEFAULT_SKILL_CAP: u16 = 1000;
+
+/// The default cap on the sum of all of a character's skills, in tenths of a point.
+pub const DEFAULT_TOTAL_SKILL_CAP: u16 = 7000;
+
+/// The default cap on the sum of a character's strength, dexterity and intelligence.
+pub const DEFAULT_STAT_CAP: u16 = 225;
+
+/// The chance of a stat fully aligned with a skill going up each time the skill is used.
+pub const STAT_GAIN_CHANCE: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub enum SkillLockState {
+    #[default]
+    Up,
+    Down,
+    Locked,
+}
+
+impl SkillLockState {
+    pub fn from_protocol(lock: SkillLock) -> SkillLockState {
+        match lock {
+            SkillLock::Up => SkillLockState::Up,
+            SkillLock::Down => SkillLockState::Down,
+            SkillLock::Locked => SkillLockState::Locked,
+        }
+    }
+
+    pub fn to_protocol(self) -> SkillLock {
+        match self {
+            SkillLockState::Up => SkillLock::Up,
+            SkillLockState::Down => SkillLock::Down,
+            SkillLockState::Locked => SkillLock::Locked,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct SkillValue {
+    pub value: u16,
+    pub cap: u16,
+    pub lock: SkillLockState,
+}
+
+impl Default for SkillValue {
+    fn default() -> Self {
+        Self {
+            value: 0,
+            cap: DEFAULT_SKILL_CAP,
+            lock: SkillLockState::Up,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct CharacterSkills {
+    pub skills: HashMap<u8, SkillValue>,
+    pub total_cap: u16,
+}
+
+impl Default for CharacterSkills {
+    fn default() -> Self {
+        Self {
+            skills: HashMap::new(),
+            total_cap: DEFAULT_TOTAL_SKILL_CAP,
+        }
+    }
+}
+
+impl CharacterSkills {
+    /// Skills for a new character, from the whole points chosen at character creation.
+    pub fn from_new_character(skills: &[NewCharacterSkill]) -> CharacterSkills {
+        let mut character_skills = CharacterSkills::default();
+        for skill in skills.iter().filter(|skill| skill.points > 0) {
+            character_skills.skills.insert(skill.skill_id, SkillValue {
+                value: (skill.points as u16 * 10).min(DEFAULT_SKILL_CAP),
+                ..default()
+            });
+        }
+        character_skills
+    }
+
+    pub fn get(&self, skill_id: u8) -> SkillValue {
+        self.skills.get(&skill_id).copied().unwrap_or_default()
+    }
+
+    pub fn total(&self) -> u32 {
+        self.skills.values().map(|skill| skill.value as u32).sum()
+    }
+
+    /// Raise a skill by a tenth of a point, returning the IDs of every skill which changed.
+    ///
+    /// At the total cap, a skill set to go down is lowered to make room. If there
+    /// is no such skill, nothing changes.
+    pub fn raise(&mut self, skill_id: u8) -> SmallVec<[u8; 2]> {
+        let skill = self.get(skill_id);
+        if skill.lock != SkillLockState::Up || skill.value >= skill.cap {
+            return SmallVec::new();
+        }
+
+        let mut changed = SmallVec::new();
+        if self.total() >= self.total_cap as u32 {
+            let mut candidates = self.skills.iter()
+                .filter(|(id, skill)| **id != skill_id && skill.lock == SkillLockState::Down && skill.value > 0)
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            candidates.sort();
+            let Some(lowered) = candidates.first().copied() else {
+                return SmallVec::new();
+            };
+
+            self.skills.get_mut(&lowered).unwrap().value -= 1;
+            changed.push(lowered);
+        }
+
+        self.skills.entry(skill_id).or_insert(skill).value += 1;
+        changed.push(skill_id);
+        changed
+    }
+
+    /// The entry for a single skill update, which uses zero-based skill IDs.
+    pub fn entry(&self, skill_id: u8) -> SkillEntry {
+        let skill = self.get(skill_id);
+        SkillEntry {
+            id: skill_id as u16,
+            value: skill.value,
+            raw_value: skill.value,
+            lock: skill.lock.to_protocol(),
+            cap: skill.cap,
+        }
+    }
+
+    /// The full skill list, which unlike single updates uses one-based skill IDs.
+    pub fn full_list(&self, skills: &Skills) -> SkillsResponse {
+        let mut skill_ids = skills.skills.keys()
+            .chain(self.skills.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        skill_ids.sort();
+        skill_ids.dedup();
+
+        SkillsResponse {
+            kind: SkillsResponseKind::FullWithCaps,
+            skills: skill_ids.into_iter()
+                .map(|skill_id| SkillEntry {
+                    id: skill_id as u16 + 1,
+                    ..self.entry(skill_id)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum Stat {
+    Str,
+    Dex,
+    Int,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 3] = [Stat::Str, Stat::Dex, Stat::Int];
+
+    pub fn from_index(index: u8) -> Option<Stat> {
+        Stat::ALL.get(index as usize).copied()
+    }
+
+    pub fn get(self, stats: &CharacterStats) -> u16 {
+        match self {
+            Stat::Str => stats.str,
+            Stat::Dex => stats.dex,
+            Stat::Int => stats.int,
+        }
+    }
+
+    pub fn get_mut(self, stats: &mut CharacterStats) -> &mut u16 {
+        match self {
+            Stat::Str => &mut stats.str,
+            Stat::Dex => &mut stats.dex,
+            Stat::Int => &mut stats.int,
+        }
+    }
+}
+
+/// Whether each of a character's stats may go up or down from use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct StatLocks {
+    pub str: SkillLockState,
+    pub dex: SkillLockState,
+    pub int: SkillLockState,
+}
+
+impl StatLocks {
+    pub fn get(&self, stat: Stat) -> SkillLockState {
+        match stat {
+            Stat::Str => self.str,
+            Stat::Dex => self.dex,
+            Stat::Int => self.int,
+        }
+    }
+
+    pub fn set(&mut self, stat: Stat, lock: SkillLockState) {
+        match stat {
+            Stat::Str => self.str = lock,
+            Stat::Dex => self.dex = lock,
+            Stat::Int => self.int = lock,
+        }
+    }
+}
+
+/// The cap on the sum of a character's stats, [`DEFAULT_STAT_CAP`] if missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deref, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct StatCap(pub u16);
+
+impl Default for StatCap {
+    fn default() -> Self {
+        StatCap(DEFAULT_STAT_CAP)
+    }
+}
+
+/// Raise a stat by one point, lowering another stat set to go down if at the cap.
+///
+/// Returns whether the stat went up.
+pub fn raise_stat(stats: &mut CharacterStats, locks: &StatLocks, cap: StatCap, stat: Stat) -> bool {
+    if locks.get(stat) != SkillLockState::Up {
+        return false;
+    }
+
+    let total = stats.str as u32 + stats.dex as u32 + stats.int as u32;
+    if total >= *cap as u32 {
+        let Some(lowered) = Stat::ALL.into_iter()
+            .find(|other| *other != stat && locks.get(*other) == SkillLockState::Down && other.get(stats) > 0) else {
+            return false;
+        };
+        *lowered.get_mut(stats) -= 1;
+    }
+
+    *stat.get_mut(stats) += 1;
+    true
+}
+
+/// The chance of gaining a skill when using it on a task of the given difficulty.
+///
+/// Gains are most likely on tasks close to the current skill level and become
+/// rarer as the skill approaches its cap.
+pub fn gain_chance(skill: SkillValue, difficulty: u16, gain_scale: f32) -> f32 {
+    if skill.value >= skill.cap || skill.cap == 0 {
+        return 0.;
+    }
+
+    let headroom = (skill.cap - skill.value) as f32 / skill.cap as f32;
+    let challenge = 1. - (difficulty.abs_diff(skill.value) as f32 / skill.cap as f32).min(1.);
+    (0.5 * headroom * challenge * gain_scale).clamp(0., 1.)
+}
+
+/// Whether a skill check against `difficulty` succeeds.
+///
+/// Checks always succeed 250 above the difficulty and always fail 250 below it.
+pub fn check_skill(rng: &mut impl Rng, value: u16, difficulty: u16) -> bool {
+    let chance = (value as f64 - difficulty as f64 + 250.) / 500.;
+    rng.gen_bool(chance.clamp(0., 1.))
+}
+
+#[derive(SystemParam)]
+pub struct SkillGain<'w, 's> {
+    rng: ResMut<'w, GameRng>,
+    rates: Res<'w, ServerRates>,
+    static_data: Res<'w, StaticData>,
+    characters: Query<'w, 's, (&'static mut CharacterSkills, Option<&'static OwningClient>)>,
+    stats: Query<'w, 's, (&'static mut CharacterStats, Option<&'static StatLocks>, Option<&'static StatCap>)>,
+    clients: Query<'w, 's, &'static NetClient>,
+}
+
+impl SkillGain<'_, '_> {
+    /// Roll a skill check against `difficulty`, then roll for gains from having used the skill.
+    pub fn check_skill(&mut self, character: Entity, skill_id: u8, difficulty: u16) -> bool {
+        let value = self.skill_value(character, skill_id);
+        let success = check_skill(&mut *self.rng, value, difficulty);
+        self.gain_skill(character, skill_id, difficulty);
+        success
+    }
+
+    /// The current value of `character`'s `skill_id`, or 0 if it has no skills.
+    pub fn skill_value(&self, character: Entity, skill_id: u8) -> u16 {
+        self.characters.get(character).map_or(0, |(skills, _)| skills.get(skill_id).value)
+    }
+
+    /// Roll a contest between `character`'s `skill_id` and `opponent`'s `opposing_skill_id`,
+    /// returning whether `character` won.
+    pub fn contest_skill(&mut self, character: Entity, skill_id: u8, opponent: Entity, opposing_skill_id: u8) -> bool {
+        let value = self.skill_value(character, skill_id);
+        let opposing_value = self.skill_value(opponent, opposing_skill_id);
+        check_skill(&mut *self.rng, value, opposing_value)
+    }
+
+    /// Roll for stat gains from using a skill, weighted by the stats the skill trains.
+    pub fn gain_stats(&mut self, character: Entity, skill_id: u8) {
+        let Some(skill) = self.static_data.skills.skills.get(&skill_id) else {
+            return;
+        };
+
+        let Ok((mut stats, locks, cap)) = self.stats.get_mut(character) else {
+            return;
+        };
+
+        let locks = locks.copied().unwrap_or_default();
+        let cap = cap.copied().unwrap_or_default();
+        for stat in Stat::ALL {
+            let chance = (STAT_GAIN_CHANCE * skill.stat_gain(stat)).clamp(0., 1.);
+            if chance > 0. && self.rng.gen_bool(chance as f64) {
+                raise_stat(&mut stats, &locks, cap, stat);
+            }
+        }
+    }
+
+    /// Roll for a gain in a skill which the character has just used, returning whether it went up.
+    ///
+    /// This also rolls for gains in the stats aligned with the skill.
+    pub fn gain_skill(&mut self, character: Entity, skill_id: u8, difficulty: u16) -> bool {
+        self.gain_stats(character, skill_id);
+
+        let Ok((mut skills, owning_client)) = self.characters.get_mut(character) else {
+            return false;
+        };
+
+        let gain_scale = self.static_data.skills.skills.get(&skill_id)
+            .map_or(self.rates.scale_skill_gain(1.), |skill| skill.gain_scale(&self.rates));
+        let chance = gain_chance(skills.get(skill_id), difficulty, gain_scale);
+        if chance <= 0. || !self.rng.gen_bool(chance as f64) {
+            return false;
+        }
+
+        let changed = skills.raise(skill_id);
+        if changed.is_empty() {
+            return false;
+        }
+
+        if let Some(client) = owning_client.and_then(|c| self.clients.get(c.client_entity).ok()) {
+            for id in changed {
+                client.send_packet(SkillsResponse {
+                    kind: SkillsResponseKind::SingleUpdateWithCap,
+                    skills: smallvec![skills.entry(id)],
+                });
+            }
+        }
+
+        true
+    }
+}
+
+pub fn on_client_skills_request(
+    static_data: Res<StaticData>,
+    clients: Query<(&NetClient, &Possessing)>,
+    characters: Query<&CharacterSkills>,
+    mut events: EventReader<OnClientSkillsRequest>,
+) {
+    for request in events.read() {
+        let Ok((client, possessing)) = clients.get(request.client_entity) else {
+            continue;
+        };
+
+        // Clients may only see their own skills.
+        if request.target != possessing.entity {
+            continue;
+        }
+
+        let skills = characters.get(possessing.entity).cloned().unwrap_or_default();
+        client.send_packet(skills.full_list(&static_data.skills));
+    }
+}
+
+/// Give characters from before skills were tracked an empty set of skills.
+pub fn add_missing_skills(
+    mut commands: Commands,
+    characters: Query<Entity, (With<OwningClient>, With<CharacterStats>, Without<CharacterSkills>)>,
+) {
+    for entity in &characters {
+        commands.entity(entity).insert(CharacterSkills::default());
+    }
+}
+
+pub fn on_skill_lock_change(
+    clients: Query<&Possessing>,
+    mut characters: Query<&mut CharacterSkills>,
+    mut events: EventReader<OnClientSkillLockChange>,
+) {
+    for event in events.read() {
+        let Ok(skill_id) = u8::try_from(event.skill_id) else {
+            continue;
+        };
+
+        let Ok(mut skills) = clients.get(event.client_entity)
+            .and_then(|possessing| characters.get_mut(possessing.entity)) else {
+            continue;
+        };
+
+        skills.skills.entry(skill_id).or_default().lock = SkillLockState::from_protocol(event.lock);
+    }
+}
+
+pub fn on_stat_lock_change(
+    mut commands: Commands,
+    clients: Query<&Possessing>,
+    characters: Query<Option<&StatLocks>, With<CharacterStats>>,
+    mut events: EventReader<OnClientStatLockChange>,
+) {
+    for event in events.read() {
+        let Some(stat) = Stat::from_index(event.stat) else {
+            continue;
+        };
+
+        let Ok(possessing) = clients.get(event.client_entity) else {
+            continue;
+        };
+
+        let Ok(locks) = characters.get(possessing.entity) else {
+            continue;
+        };
+
+        let mut locks = locks.copied().unwrap_or_default();
+        locks.set(stat, SkillLockState::from_protocol(event.lock));
+        commands.entity(possessing.entity).insert(locks);
+    }
+}
+
+#[derive(Default)]
+pub struct CharacterSkillsSerializer;
+
+impl BundleSerializer for CharacterSkillsSerializer {
+    type Query = &'static CharacterSkills;
+    type Filter = With<Persistent>;
+    type Bundle = CharacterSkills;
+
+    fn id() -> &'static str {
+        "CharacterSkills"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        item.clone()
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(bundle);
+    }
+}
+
+#[derive(Default)]
+pub struct StatLocksSerializer;
+
+impl BundleSerializer for StatLocksSerializer {
+    type Query = &'static StatLocks;
+    type Filter = With<Persistent>;
+    type Bundle = StatLocks;
+
+    fn id() -> &'static str {
+        "StatLocks"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        *item
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(bundle);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<SkillLockState>()
+        .register_type::<SkillValue>()
+        .register_type::<CharacterSkills>()
+        .register_type::<Stat>()
+        .register_type::<StatLocks>()
+        .register_type::<StatCap>()
+        .register_serializer::<CharacterSkillsSerializer>()
+        .register_serializer::<StatLocksSerializer>()
+        .add_systems(First, (
+            (
+                on_client_skills_request,
+                on_skill_lock_change,
+                on_stat_lock_change,
+            ).in_set(DefaultGameSet::HandleEvents),
+        ))
+        .add_systems(Update, (
+            add_missing_skills,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::data::skills::Skill;
+    use crate::networking::{sent_packets, test_client};
+
+    use super::*;
+
+    fn skills(values: &[(u8, u16, SkillLockState)], total_cap: u16) -> CharacterSkills {
+        CharacterSkills {
+            skills: values.iter()
+                .map(|(id, value, lock)| (*id, SkillValue { value: *value, lock: *lock, ..default() }))
+                .collect(),
+            total_cap,
+        }
+    }
+
+    fn insert_gain_resources(world: &mut World) {
+        world.insert_resource(GameRng::from_seed(1));
+        world.insert_resource(StaticData {
+            cities: default(),
+            maps: default(),
+            skills: default(),
+            locations: default(),
+        });
+        // Make every roll succeed.
+        world.insert_resource(ServerRates {
+            skill_gain_multiplier: 1000.,
+            ..default()
+        });
+    }
+
+    fn gain_world() -> World {
+        let mut world = World::new();
+        insert_gain_resources(&mut world);
+        world
+    }
+
+    fn gain(world: &mut World, character: Entity, skill_id: u8) -> bool {
+        world
+            .run_system_once(move |mut gain: SkillGain| gain.gain_skill(character, skill_id, 500))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_gain_within_cap() {
+        let mut world = gain_world();
+        let (client, mut rx) = test_client();
+        let client_entity = world.spawn(client).id();
+        let character = world
+            .spawn((skills(&[(1, 500, SkillLockState::Up)], 7000), OwningClient { client_entity }))
+            .id();
+
+        assert!(gain(&mut world, character, 1));
+        assert_eq!(world.get::<CharacterSkills>(character).unwrap().get(1).value, 501);
+
+        let [packet] = &sent_packets::<SkillsResponse>(&mut rx)[..] else {
+            panic!("expected a skill update");
+        };
+        assert_eq!(packet.kind, SkillsResponseKind::SingleUpdateWithCap);
+        assert_eq!(packet.skills[0].id, 1);
+        assert_eq!(packet.skills[0].value, 501);
+    }
+
+    #[test]
+    fn test_no_gain_at_cap() {
+        let mut world = gain_world();
+        let character = world.spawn(skills(&[(1, DEFAULT_SKILL_CAP, SkillLockState::Up)], 7000)).id();
+
+        assert!(!gain(&mut world, character, 1));
+        assert_eq!(world.get::<CharacterSkills>(character).unwrap().get(1).value, DEFAULT_SKILL_CAP);
+        assert_eq!(gain_chance(SkillValue { value: DEFAULT_SKILL_CAP, ..default() }, 500, 1.), 0.);
+    }
+
+    #[test]
+    fn test_total_cap_lowers_other_skill() {
+        let mut world = gain_world();
+        let character = world
+            .spawn(skills(&[
+                (1, 500, SkillLockState::Up),
+                (2, 300, SkillLockState::Locked),
+                (3, 200, SkillLockState::Down),
+            ], 1000))
+            .id();
+
+        assert!(gain(&mut world, character, 1));
+        let values = world.get::<CharacterSkills>(character).unwrap();
+        assert_eq!(values.get(1).value, 501);
+        assert_eq!(values.get(2).value, 300);
+        assert_eq!(values.get(3).value, 199);
+        assert_eq!(values.total(), 1000);
+
+        // Without a skill to lower, nothing is gained.
+        let mut capped = skills(&[(1, 500, SkillLockState::Up), (2, 500, SkillLockState::Locked)], 1000);
+        assert!(capped.raise(1).is_empty());
+    }
+
+    #[test]
+    fn test_strength_skill_raises_str() {
+        let mut world = gain_world();
+        let skill: Skill = serde_yaml::from_str("name: Swordsmanship\nstr_gain: 1").unwrap();
+        world.resource_mut::<StaticData>().skills.skills.insert(40, skill);
+
+        let stats = CharacterStats { str: 50, dex: 50, int: 50 };
+        let use_skill = |world: &mut World, locks: StatLocks| {
+            let character = world.spawn((stats.clone(), locks, StatCap(155))).id();
+            for _ in 0..3000 {
+                world
+                    .run_system_once(move |mut gain: SkillGain| gain.gain_stats(character, 40))
+                    .unwrap();
+            }
+            world.get::<CharacterStats>(character).unwrap().clone()
+        };
+
+        let raised = use_skill(&mut world, StatLocks::default());
+        assert_eq!((raised.str, raised.dex, raised.int), (55, 50, 50));
+
+        let lowered = use_skill(&mut world, StatLocks { dex: SkillLockState::Down, ..default() });
+        assert_eq!((lowered.str, lowered.dex, lowered.int), (105, 0, 50));
+
+        let locked = use_skill(&mut world, StatLocks { str: SkillLockState::Locked, ..default() });
+        assert_eq!((locked.str, locked.dex, locked.int), (50, 50, 50));
+    }
+
+    fn skills_app() -> App {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientSkillsRequest>()
+            .add_event::<OnClientSkillLockChange>()
+            .add_event::<OnClientStatLockChange>()
+            .add_plugins(plugin);
+        insert_gain_resources(app.world_mut());
+        app
+    }
+
+    #[test]
+    fn test_new_character_skills() {
+        let skills = CharacterSkills::from_new_character(&[
+            NewCharacterSkill { skill_id: 40, points: 50 },
+            NewCharacterSkill { skill_id: 17, points: 30 },
+            NewCharacterSkill { skill_id: 0, points: 0 },
+            NewCharacterSkill { skill_id: 0, points: 0 },
+        ]);
+        assert_eq!(skills.skills.len(), 2);
+        assert_eq!(skills.get(40).value, 500);
+        assert_eq!(skills.get(17).value, 300);
+        assert_eq!(skills.total_cap, DEFAULT_TOTAL_SKILL_CAP);
+    }
+
+    #[test]
+    fn test_skills_request() {
+        let mut app = skills_app();
+        app.world_mut().resource_mut::<StaticData>().skills.skills.insert(0, default());
+
+        let (client, mut rx) = test_client();
+        let client_entity = app.world_mut().spawn(client).id();
+        let character = app.world_mut()
+            .spawn((skills(&[(40, 500, SkillLockState::Down)], 7000), CharacterStats::default()))
+            .id();
+        app.world_mut().entity_mut(client_entity).insert(Possessing { entity: character });
+
+        app.world_mut().send_event(OnClientSkillsRequest { client_entity, target: character });
+        app.update();
+
+        let [packet] = &sent_packets::<SkillsResponse>(&mut rx)[..] else {
+            panic!("expected a skill list");
+        };
+        assert_eq!(packet.kind, SkillsResponseKind::FullWithCaps);
+        assert_eq!(packet.skills.len(), 2);
+        assert_eq!((packet.skills[0].id, packet.skills[0].value), (1, 0));
+        assert_eq!((packet.skills[1].id, packet.skills[1].value), (41, 500));
+        assert_eq!(packet.skills[1].lock, SkillLock::Down);
+
+        // Other characters' skills are not sent.
+        let other = app.world_mut().spawn(skills(&[], 7000)).id();
+        app.world_mut().send_event(OnClientSkillsRequest { client_entity, target: other });
+        app.update();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_existing_character_gets_skills() {
+        let mut app = skills_app();
+        let client_entity = app.world_mut().spawn_empty().id();
+        let character = app.world_mut()
+            .spawn((CharacterStats::default(), OwningClient { client_entity }))
+            .id();
+        app.update();
+
+        assert_eq!(*app.world().get::<CharacterSkills>(character).unwrap(), CharacterSkills::default());
+    }
+
+    #[test]
+    fn test_lock_changes() {
+        let mut app = skills_app();
+
+        let character = app.world_mut()
+            .spawn((skills(&[(1, 500, SkillLockState::Up)], 7000), CharacterStats::default()))
+            .id();
+        let client_entity = app.world_mut().spawn(Possessing { entity: character }).id();
+
+        app.world_mut().send_event(OnClientSkillLockChange {
+            client_entity,
+            skill_id: 1,
+            lock: SkillLock::Locked,
+        });
+        app.world_mut().send_event(OnClientStatLockChange {
+            client_entity,
+            stat: 2,
+            lock: SkillLock::Down,
+        });
+        app.update();
+
+        let world = app.world_mut();
+        assert_eq!(world.get::<CharacterSkills>(character).unwrap().get(1).lock, SkillLockState::Locked);
+        assert_eq!(*world.get::<StatLocks>(character).unwrap(), StatLocks { int: SkillLockState::Down, ..default() });
+
+        assert!(!gain(world, character, 1));
+        assert_eq!(world.get::<CharacterSkills>(character).unwrap().get(1).value, 500);
+    }
+}
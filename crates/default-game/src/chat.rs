@@ -1,5 +1,10 @@
-use bevy::prelude::*;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use clap::ValueEnum;
 use yewoh::protocol::{MessageKind, UnicodeTextMessage};
 use yewoh::types::FixedString;
 use yewoh_server::world::characters::CharacterName;
@@ -7,19 +12,216 @@ use yewoh_server::world::chat::OnClientChatMessage;
 use yewoh_server::world::connection::{broadcast, NetClient, Possessing};
 use yewoh_server::world::net_id::{NetId};
 
-use crate::commands::TextCommandExecutor;
+use crate::commands::{SpeechTriggers, TextCommandExecutor};
+use crate::networking::NetClientExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum ChatChannel {
+    Global,
+    Trade,
+    Help,
+}
+
+impl ChatChannel {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChatChannel::Global => "Global",
+            ChatChannel::Trade => "Trade",
+            ChatChannel::Help => "Help",
+        }
+    }
+
+    pub fn hue(self) -> u16 {
+        match self {
+            ChatChannel::Global => 0x5a,
+            ChatChannel::Trade => 0x35,
+            ChatChannel::Help => 0x44,
+        }
+    }
+}
+
+/// The chat channels a client has joined.
+#[derive(Debug, Clone, Default, Component)]
+pub struct ChatChannels(pub HashSet<ChatChannel>);
+
+impl ChatChannels {
+    pub fn is_member(&self, channel: ChatChannel) -> bool {
+        self.0.contains(&channel)
+    }
+}
+
+/// Send a message from `name` to every client which has joined `channel`.
+pub fn send_channel_message<'a>(
+    clients: impl IntoIterator<Item = (&'a NetClient, &'a ChatChannels)>,
+    channel: ChatChannel,
+    name: &str,
+    text: &str,
+) {
+    let message = format!("[{}] {name}: {text}", channel.label());
+    for (client, channels) in clients {
+        if channels.is_member(channel) {
+            client.send_system_message_hue(message.clone(), channel.hue());
+        }
+    }
+}
+
+/// Build a message which is spoken aloud by `speaker`.
+pub fn speech_message(speaker: &NetId, name: &str, text: String, hue: u16) -> UnicodeTextMessage {
+    UnicodeTextMessage {
+        entity_id: Some(speaker.id),
+        kind: MessageKind::Regular,
+        language: FixedString::from_str("ENG"),
+        text,
+        name: FixedString::from_str(name),
+        hue,
+        font: 1,
+        graphic_id: 0,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatFilterResult<'a> {
+    Accept(Cow<'a, str>),
+    Reject,
+}
+
+/// Consulted for every chat message, including commands, before it is handled.
+pub trait ChatFilter: Send + Sync + 'static {
+    fn filter<'a>(&mut self, client_entity: Entity, text: &'a str) -> ChatFilterResult<'a>;
+
+    /// Drop any state kept for a client which has disconnected.
+    fn forget(&mut self, _client_entity: Entity) {}
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoChatFilter;
+
+impl ChatFilter for NoChatFilter {
+    fn filter<'a>(&mut self, _client_entity: Entity, text: &'a str) -> ChatFilterResult<'a> {
+        ChatFilterResult::Accept(text.into())
+    }
+}
+
+/// Drops messages from clients which send more than `max_messages` within `window`.
+#[derive(Debug, Clone)]
+pub struct RateLimitChatFilter {
+    pub max_messages: usize,
+    pub window: Duration,
+    history: HashMap<Entity, VecDeque<Instant>>,
+}
+
+impl RateLimitChatFilter {
+    pub fn new(max_messages: usize, window: Duration) -> RateLimitChatFilter {
+        RateLimitChatFilter {
+            max_messages,
+            window,
+            history: HashMap::new(),
+        }
+    }
+
+    pub fn check_at(&mut self, client_entity: Entity, now: Instant) -> bool {
+        let history = self.history.entry(client_entity).or_default();
+        while history.front().is_some_and(|sent| now.duration_since(*sent) >= self.window) {
+            history.pop_front();
+        }
+
+        if history.len() >= self.max_messages {
+            false
+        } else {
+            history.push_back(now);
+            true
+        }
+    }
+}
+
+impl ChatFilter for RateLimitChatFilter {
+    fn filter<'a>(&mut self, client_entity: Entity, text: &'a str) -> ChatFilterResult<'a> {
+        if self.check_at(client_entity, Instant::now()) {
+            ChatFilterResult::Accept(text.into())
+        } else {
+            ChatFilterResult::Reject
+        }
+    }
+
+    fn forget(&mut self, client_entity: Entity) {
+        self.history.remove(&client_entity);
+    }
+}
+
+/// Replaces flagged words with asterisks, ignoring case.
+#[derive(Debug, Clone, Default)]
+pub struct MaskingChatFilter {
+    words: HashSet<String>,
+}
+
+impl MaskingChatFilter {
+    pub fn new(words: impl IntoIterator<Item = impl AsRef<str>>) -> MaskingChatFilter {
+        MaskingChatFilter {
+            words: words.into_iter().map(|w| w.as_ref().to_lowercase()).collect(),
+        }
+    }
+}
+
+impl ChatFilter for MaskingChatFilter {
+    fn filter<'a>(&mut self, _client_entity: Entity, text: &'a str) -> ChatFilterResult<'a> {
+        let flagged = text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty() && self.words.contains(&word.to_lowercase()))
+            .map(|word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+            .collect::<Vec<_>>();
+
+        // Replace from the end, so that earlier offsets remain valid.
+        let mut result = Cow::Borrowed(text);
+        for (start, word) in flagged.into_iter().rev() {
+            let mask = "*".repeat(word.chars().count());
+            result.to_mut().replace_range(start..(start + word.len()), &mask);
+        }
+        ChatFilterResult::Accept(result)
+    }
+}
+
+/// Sent for speech which has passed the chat filter and was not a command.
+#[derive(Debug, Clone, Event)]
+pub struct OnCharacterSpeech {
+    pub client_entity: Entity,
+    pub speaker: Entity,
+    pub text: String,
+}
+
+#[derive(Resource)]
+pub struct ActiveChatFilter(pub Box<dyn ChatFilter>);
+
+impl Default for ActiveChatFilter {
+    fn default() -> Self {
+        ActiveChatFilter(Box::new(NoChatFilter))
+    }
+}
 
 pub fn on_client_chat_message(
     mut command_executor: TextCommandExecutor,
+    speech_triggers: Res<SpeechTriggers>,
+    mut chat_filter: Option<ResMut<ActiveChatFilter>>,
     clients: Query<(&NetClient, &Possessing)>,
     character_query: Query<(&NetId, &CharacterName)>,
     mut events: EventReader<OnClientChatMessage>,
+    mut speech_events: EventWriter<OnCharacterSpeech>,
 ) {
     for request in events.read() {
-        if command_executor.try_split_exec(request.client_entity, &request.request.text) {
+        let text = match chat_filter.as_mut() {
+            Some(filter) => match filter.0.filter(request.client_entity, &request.request.text) {
+                ChatFilterResult::Accept(text) => text,
+                ChatFilterResult::Reject => continue,
+            },
+            None => Cow::Borrowed(request.request.text.as_str()),
+        };
+
+        if command_executor.try_split_exec(request.client_entity, &text) {
             continue;
         }
 
+        for command in speech_triggers.matching(&text) {
+            command_executor.try_exec(request.client_entity, command);
+        }
+
         let Ok((_, owned)) = clients.get(request.client_entity) else {
             continue;
         };
@@ -28,15 +230,178 @@ pub fn on_client_chat_message(
             continue;
         };
 
-        broadcast(clients.iter().map(|(c, _)| c), UnicodeTextMessage {
-            entity_id: Some(net.id),
-            kind: MessageKind::Regular,
-            language: FixedString::from_str("ENG"),
-            text: request.request.text.clone(),
-            name: FixedString::from_str(name.as_str()),
-            hue: 1234,
-            font: 1,
-            graphic_id: 0,
+        let text = text.into_owned();
+        speech_events.send(OnCharacterSpeech {
+            client_entity: request.client_entity,
+            speaker: owned.entity,
+            text: text.clone(),
         });
+        broadcast(clients.iter().map(|(c, _)| c), speech_message(net, name.as_str(), text, 1234));
+    }
+}
+
+pub fn forget_disconnected_chat_clients(
+    mut chat_filter: Option<ResMut<ActiveChatFilter>>,
+    mut removed: RemovedComponents<NetClient>,
+) {
+    for client_entity in removed.read() {
+        if let Some(filter) = chat_filter.as_mut() {
+            filter.0.forget(client_entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh::EntityId;
+    use yewoh::protocol::UnicodeTextMessageRequest;
+    use yewoh_server::world::connection::WriterAction;
+
+    use crate::commands::{
+        SpeechTriggerRegistrationExt,
+        TextCommand,
+        TextCommandQueue,
+        TextCommandRegistrationExt,
+        TextCommands,
+    };
+    use crate::networking::{sent_messages, test_client};
+
+    use super::*;
+
+    #[derive(Parser, Resource)]
+    struct Bank {}
+
+    impl TextCommand for Bank {
+        fn aliases() -> &'static [&'static str] {
+            &["bank"]
+        }
+    }
+
+    #[derive(Default, Resource)]
+    struct BankOpened(usize);
+
+    fn open_bank(mut exec: TextCommandQueue<Bank>, mut opened: ResMut<BankOpened>) {
+        for _ in exec.iter() {
+            opened.0 += 1;
+        }
+    }
+
+    fn setup() -> (App, Entity, UnboundedReceiver<WriterAction>) {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientChatMessage>()
+            .add_event::<OnCharacterSpeech>()
+            .insert_resource(TextCommands::new('['))
+            .init_resource::<SpeechTriggers>()
+            .init_resource::<BankOpened>()
+            .add_text_command::<Bank>()
+            .add_speech_trigger("bank", ["bank"])
+            .add_systems(Update, (on_client_chat_message, open_bank).chain());
+
+        let character = app.world_mut()
+            .spawn((NetId { id: EntityId::from_u32(1) }, CharacterName("Gerome".into())))
+            .id();
+        let (client, rx) = test_client();
+        let client = app.world_mut().spawn((client, Possessing { entity: character })).id();
+        (app, client, rx)
+    }
+
+    fn say(app: &mut App, client_entity: Entity, text: &str) {
+        app.world_mut().send_event(OnClientChatMessage {
+            client_entity,
+            request: UnicodeTextMessageRequest {
+                text: text.into(),
+                ..default()
+            },
+        });
+        app.update();
+    }
+
+    #[test]
+    fn test_rate_limit_filter() {
+        let client = Entity::from_raw(1);
+        let other = Entity::from_raw(2);
+        let start = Instant::now();
+        let mut filter = RateLimitChatFilter::new(2, Duration::from_secs(5));
+        assert!(filter.check_at(client, start));
+        assert!(filter.check_at(client, start + Duration::from_secs(1)));
+        assert!(!filter.check_at(client, start + Duration::from_secs(2)));
+        assert!(filter.check_at(other, start + Duration::from_secs(2)));
+        assert!(filter.check_at(client, start + Duration::from_secs(5)));
+
+        filter.forget(client);
+        assert!(!filter.history.contains_key(&client));
+        assert!(filter.history.contains_key(&other));
+    }
+
+    #[test]
+    fn test_rate_limited_commands() {
+        let (mut app, client, _rx) = setup();
+        app.insert_resource(ActiveChatFilter(Box::new(
+            RateLimitChatFilter::new(1, Duration::from_secs(60)))));
+        say(&mut app, client, "[bank");
+        say(&mut app, client, "[bank");
+        assert_eq!(app.world().resource::<BankOpened>().0, 1);
+    }
+
+    #[test]
+    fn test_rate_limited_chat() {
+        let (mut app, client, mut rx) = setup();
+        app.insert_resource(ActiveChatFilter(Box::new(
+            RateLimitChatFilter::new(1, Duration::from_secs(60)))));
+        say(&mut app, client, "Buy my wares");
+        say(&mut app, client, "Buy my wares");
+
+        assert_eq!(sent_messages(&mut rx).len(), 1);
+    }
+
+    #[test]
+    fn test_masking_filter() {
+        let mut filter = MaskingChatFilter::new(["darn"]);
+        let client = Entity::from_raw(1);
+        assert_eq!(filter.filter(client, "Darn it, darnation! darn."),
+            ChatFilterResult::Accept("**** it, darnation! ****.".into()));
+        assert!(matches!(filter.filter(client, "hello"), ChatFilterResult::Accept(Cow::Borrowed("hello"))));
+
+        let (mut app, client, mut rx) = setup();
+        app.insert_resource(ActiveChatFilter(Box::new(filter)));
+        say(&mut app, client, "darn");
+        assert_eq!(sent_messages(&mut rx).first().map(String::as_str), Some("****"));
+    }
+
+    fn speech(app: &App) -> Vec<String> {
+        app.world().resource::<Events<OnCharacterSpeech>>()
+            .iter_current_update_events()
+            .map(|event| event.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_speech_events() {
+        let (mut app, client, _rx) = setup();
+        app.insert_resource(ActiveChatFilter(Box::new(MaskingChatFilter::new(["darn"]))));
+        say(&mut app, client, "[bank");
+        assert!(speech(&app).is_empty());
+
+        say(&mut app, client, "darn this");
+        assert_eq!(speech(&app), vec!["**** this".to_string()]);
+    }
+
+    #[test]
+    fn test_speech_trigger() {
+        let (mut app, client, _rx) = setup();
+        say(&mut app, client, "Bank");
+        assert_eq!(app.world().resource::<BankOpened>().0, 1);
+    }
+
+    #[test]
+    fn test_unrelated_speech() {
+        let (mut app, client, mut rx) = setup();
+        say(&mut app, client, "Hail, friend");
+        assert_eq!(app.world().resource::<BankOpened>().0, 0);
+
+        assert_eq!(sent_messages(&mut rx).first().map(String::as_str), Some("Hail, friend"));
     }
 }
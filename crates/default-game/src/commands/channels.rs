@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh_server::world::characters::CharacterName;
+use yewoh_server::world::connection::{NetClient, Possessing};
+
+use crate::chat::{send_channel_message, ChatChannel, ChatChannels};
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+use crate::hues;
+use crate::networking::NetClientExt;
+
+/// Join a chat channel.
+#[derive(Parser, Resource)]
+pub struct Join {
+    #[clap(value_enum)]
+    channel: ChatChannel,
+}
+
+impl TextCommand for Join {
+    fn aliases() -> &'static [&'static str] {
+        &["join"]
+    }
+}
+
+/// Leave a chat channel.
+#[derive(Parser, Resource)]
+pub struct Leave {
+    #[clap(value_enum)]
+    channel: ChatChannel,
+}
+
+impl TextCommand for Leave {
+    fn aliases() -> &'static [&'static str] {
+        &["leave"]
+    }
+}
+
+/// Send a message to a chat channel.
+#[derive(Parser, Resource)]
+pub struct ChannelSay {
+    #[clap(value_enum)]
+    channel: ChatChannel,
+    #[clap(required = true)]
+    message: Vec<String>,
+}
+
+impl TextCommand for ChannelSay {
+    fn aliases() -> &'static [&'static str] {
+        &["c", "channel"]
+    }
+}
+
+pub fn join_channel(
+    mut commands: Commands,
+    mut clients: Query<(&NetClient, Option<&mut ChatChannels>)>,
+    mut exec: TextCommandQueue<Join>,
+) {
+    for (from, args) in exec.iter() {
+        let Ok((client, channels)) = clients.get_mut(from) else {
+            continue;
+        };
+
+        match channels {
+            Some(mut channels) => {
+                channels.0.insert(args.channel);
+            }
+            None => {
+                let mut channels = ChatChannels::default();
+                channels.0.insert(args.channel);
+                commands.entity(from).insert(channels);
+            }
+        }
+
+        client.send_system_message_hue(
+            format!("You have joined {}.", args.channel.label()), args.channel.hue());
+    }
+}
+
+pub fn leave_channel(
+    mut clients: Query<(&NetClient, Option<&mut ChatChannels>)>,
+    mut exec: TextCommandQueue<Leave>,
+) {
+    for (from, args) in exec.iter() {
+        let Ok((client, channels)) = clients.get_mut(from) else {
+            continue;
+        };
+
+        if channels.is_some_and(|mut channels| channels.0.remove(&args.channel)) {
+            client.send_system_message_hue(
+                format!("You have left {}.", args.channel.label()), args.channel.hue());
+        } else {
+            client.send_system_message_hue(
+                format!("You are not in {}.", args.channel.label()), hues::RED);
+        }
+    }
+}
+
+pub fn say_in_channel(
+    clients: Query<(&NetClient, &Possessing, Option<&ChatChannels>)>,
+    members: Query<(&NetClient, &ChatChannels)>,
+    names: Query<&CharacterName>,
+    mut exec: TextCommandQueue<ChannelSay>,
+) {
+    for (from, args) in exec.iter() {
+        let Ok((client, owned, channels)) = clients.get(from) else {
+            continue;
+        };
+
+        if !channels.is_some_and(|c| c.is_member(args.channel)) {
+            client.send_system_message_hue(
+                format!("You must join {} first.", args.channel.label()), hues::RED);
+            continue;
+        }
+
+        let Ok(name) = names.get(owned.entity) else {
+            continue;
+        };
+
+        send_channel_message(&members, args.channel, name.as_str(), &args.message.join(" "));
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<Join>()
+        .add_text_command::<Leave>()
+        .add_text_command::<ChannelSay>()
+        .add_systems(Update, (
+            join_channel,
+            leave_channel,
+            say_in_channel,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh_server::world::connection::WriterAction;
+
+    use crate::commands::{TextCommandExecutor, TextCommands};
+    use crate::networking::{sent_messages, test_client};
+
+    use super::*;
+
+    fn spawn_player(app: &mut App, name: &str) -> (Entity, UnboundedReceiver<WriterAction>) {
+        let character = app.world_mut().spawn(CharacterName(name.into())).id();
+        let (client, rx) = test_client();
+        let client = app.world_mut().spawn((client, Possessing { entity: character })).id();
+        (client, rx)
+    }
+
+    fn run_command(app: &mut App, from: Entity, line: &str) {
+        let line = line.to_string();
+        app.world_mut()
+            .run_system_once(move |mut exec: TextCommandExecutor| {
+                assert!(exec.try_split_exec(from, &line));
+            })
+            .unwrap();
+        app.update();
+    }
+
+    #[test]
+    fn test_channel_members() {
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .add_plugins(plugin);
+
+        let (trader, mut trader_rx) = spawn_player(&mut app, "Trader");
+        let (buyer, mut buyer_rx) = spawn_player(&mut app, "Buyer");
+        let (_, mut other_rx) = spawn_player(&mut app, "Other");
+
+        run_command(&mut app, trader, "[join trade");
+        run_command(&mut app, buyer, "[join trade");
+        sent_messages(&mut trader_rx);
+        sent_messages(&mut buyer_rx);
+
+        run_command(&mut app, trader, "[c trade selling swords");
+        assert_eq!(sent_messages(&mut trader_rx), vec!["[Trade] Trader: selling swords"]);
+        assert_eq!(sent_messages(&mut buyer_rx), vec!["[Trade] Trader: selling swords"]);
+        assert!(sent_messages(&mut other_rx).is_empty());
+
+        run_command(&mut app, buyer, "[leave trade");
+        sent_messages(&mut buyer_rx);
+        run_command(&mut app, trader, "[c trade still selling");
+        assert_eq!(sent_messages(&mut trader_rx), vec!["[Trade] Trader: still selling"]);
+        assert!(sent_messages(&mut buyer_rx).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh::protocol::TargetType;
+use yewoh_server::world::combat::AttackTarget;
+use yewoh_server::world::entity::Frozen;
+use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
+
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+
+/// Stop the targeted character from moving or acting.
+#[derive(Parser, Resource)]
+pub struct Freeze;
+
+impl TextCommand for Freeze {
+    fn aliases() -> &'static [&'static str] {
+        &["freeze"]
+    }
+}
+
+/// Allow the targeted character to move and act again.
+#[derive(Parser, Resource)]
+pub struct Unfreeze;
+
+impl TextCommand for Unfreeze {
+    fn aliases() -> &'static [&'static str] {
+        &["unfreeze", "thaw"]
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct FreezeRequest(pub bool);
+
+fn request_target(commands: &mut Commands, client_entity: Entity, frozen: bool) {
+    commands.spawn((
+        FreezeRequest(frozen),
+        EntityTargetRequest {
+            client_entity,
+            target_type: TargetType::Neutral,
+        },
+    ));
+}
+
+pub fn start_freeze(
+    mut exec: TextCommandQueue<Freeze>,
+    mut commands: Commands,
+) {
+    for (from, _) in exec.iter() {
+        request_target(&mut commands, from, true);
+    }
+}
+
+pub fn start_unfreeze(
+    mut exec: TextCommandQueue<Unfreeze>,
+    mut commands: Commands,
+) {
+    for (from, _) in exec.iter() {
+        request_target(&mut commands, from, false);
+    }
+}
+
+pub fn finish_freeze(
+    completed: Query<(Entity, &FreezeRequest, &EntityTargetResponse)>,
+    mut targets: Query<&mut Frozen>,
+    mut commands: Commands,
+) {
+    for (entity, request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some(target) = response.target else {
+            continue;
+        };
+
+        let Ok(mut frozen) = targets.get_mut(target) else {
+            continue;
+        };
+
+        frozen.set_if_neq(Frozen(request.0));
+        if request.0 {
+            commands.entity(target).remove::<AttackTarget>();
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<Freeze>()
+        .add_text_command::<Unfreeze>()
+        .add_systems(Update, (
+            start_freeze,
+            start_unfreeze,
+            finish_freeze,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use yewoh_server::world::characters::CharacterBodyType;
+    use yewoh_server::world::combat::AttackTarget;
+
+    use crate::commands::{run_on_target, TextCommands};
+
+    use super::*;
+
+    #[test]
+    fn test_freeze_and_unfreeze() {
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .add_plugins(plugin);
+
+        let victim = app.world_mut().spawn(CharacterBodyType(0x190)).id();
+        let character = app.world_mut()
+            .spawn((CharacterBodyType(0x190), AttackTarget { target: victim }))
+            .id();
+
+        run_on_target::<FreezeRequest>(&mut app, "[freeze", character);
+        assert!(**app.world().get::<Frozen>(character).unwrap());
+        assert!(app.world().get::<AttackTarget>(character).is_none());
+
+        run_on_target::<FreezeRequest>(&mut app, "[unfreeze", character);
+        assert!(!**app.world().get::<Frozen>(character).unwrap());
+    }
+}
@@ -4,8 +4,9 @@ use glam::{IVec2, IVec3};
 
 use yewoh::protocol::GumpLayout;
 use yewoh_server::gump_builder::{GumpBuilder, GumpRect, GumpRectLayout, GumpText};
+use yewoh_server::world::characters::CharacterName;
 use yewoh_server::world::entity::MapPosition;
-use yewoh_server::world::connection::Possessing;
+use yewoh_server::world::connection::{NetClient, Possessing};
 use yewoh_server::world::gump::{Gump, GumpClient};
 
 use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
@@ -16,6 +17,8 @@ use crate::entities::position::PositionExt;
 use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
 use crate::gumps::{OnCloseGump, RESIZABLE_PAPER_3};
 use crate::gumps::page_allocator::GumpPageBoxAllocator;
+use crate::hues;
+use crate::networking::NetClientExt;
 
 #[derive(Clone, Debug)]
 pub enum ButtonAction {
@@ -209,16 +212,247 @@ pub fn go(
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayerLookup {
+    Found(Entity),
+    NotFound,
+    Offline,
+    Ambiguous(Vec<String>),
+}
+
+/// Find an online character by name, ignoring case.
+pub fn find_player<'a>(
+    name: &str,
+    characters: impl IntoIterator<Item=(Entity, &'a str, bool)>,
+) -> PlayerLookup {
+    let mut online = Vec::new();
+    let mut any_offline = false;
+
+    for (entity, character_name, is_online) in characters {
+        if !character_name.eq_ignore_ascii_case(name) {
+            continue;
+        }
+
+        if is_online {
+            online.push((entity, character_name));
+        } else {
+            any_offline = true;
+        }
+    }
+
+    match online.len() {
+        0 if any_offline => PlayerLookup::Offline,
+        0 => PlayerLookup::NotFound,
+        1 => PlayerLookup::Found(online[0].0),
+        _ => PlayerLookup::Ambiguous(online.into_iter()
+            .map(|(entity, name)| format!("{name} ({entity})"))
+            .collect()),
+    }
+}
+
+pub fn resolve_player(
+    client: &NetClient,
+    name: &str,
+    clients: &Query<(&NetClient, &Possessing)>,
+    names: &Query<(Entity, &CharacterName)>,
+) -> Option<Entity> {
+    let lookup = find_player(name, names.iter()
+        .map(|(entity, character_name)| {
+            let is_online = clients.iter().any(|(_, possessing)| possessing.entity == entity);
+            (entity, character_name.0.as_str(), is_online)
+        }));
+
+    match lookup {
+        PlayerLookup::Found(entity) => Some(entity),
+        PlayerLookup::NotFound => {
+            client.send_system_message_hue(format!("No player named '{name}'"), hues::RED);
+            None
+        }
+        PlayerLookup::Offline => {
+            client.send_system_message_hue(format!("'{name}' is not online"), hues::RED);
+            None
+        }
+        PlayerLookup::Ambiguous(candidates) => {
+            client.send_system_message_hue(
+                format!("'{name}' is ambiguous: {}", candidates.join(", ")), hues::RED);
+            None
+        }
+    }
+}
+
+#[derive(Parser, Resource)]
+pub struct GoTo {
+    #[clap(required = true)]
+    name: Vec<String>,
+}
+
+impl TextCommand for GoTo {
+    fn aliases() -> &'static [&'static str] {
+        &["goto", "teleport-to-player"]
+    }
+}
+
+pub fn go_to_player(
+    mut commands: Commands,
+    clients: Query<(&NetClient, &Possessing)>,
+    names: Query<(Entity, &CharacterName)>,
+    positions: Query<&MapPosition>,
+    mut exec: TextCommandQueue<GoTo>,
+) {
+    for (from, args) in exec.iter() {
+        let Ok((client, owned)) = clients.get(from) else {
+            continue;
+        };
+
+        let name = args.name.join(" ");
+        let Some(target) = resolve_player(client, &name, &clients, &names) else {
+            continue;
+        };
+
+        let Ok(position) = positions.get(target) else {
+            client.send_system_message_hue(format!("'{name}' is not in the world"), hues::RED);
+            continue;
+        };
+
+        commands.entity(owned.entity).move_to_map_position(*position);
+    }
+}
+
+#[derive(Parser, Resource)]
+pub struct Bring {
+    #[clap(required = true)]
+    name: Vec<String>,
+}
+
+impl TextCommand for Bring {
+    fn aliases() -> &'static [&'static str] {
+        &["bring"]
+    }
+}
+
+pub fn bring_player(
+    mut commands: Commands,
+    clients: Query<(&NetClient, &Possessing)>,
+    names: Query<(Entity, &CharacterName)>,
+    positions: Query<&MapPosition>,
+    mut exec: TextCommandQueue<Bring>,
+) {
+    for (from, args) in exec.iter() {
+        let Ok((client, owned)) = clients.get(from) else {
+            continue;
+        };
+
+        let Ok(position) = positions.get(owned.entity) else {
+            continue;
+        };
+
+        let name = args.name.join(" ");
+        let Some(target) = resolve_player(client, &name, &clients, &names) else {
+            continue;
+        };
+
+        commands.entity(target).move_to_map_position(*position);
+    }
+}
+
 pub fn plugin(app: &mut App) {
     app
         .add_plugins((
             EntityEventRoutePlugin::<OnCloseGump, GoGump>::default(),
         ))
         .add_text_command::<Go>()
+        .add_text_command::<GoTo>()
+        .add_text_command::<Bring>()
         .add_systems(Update, (
             go,
+            go_to_player,
+            bring_player,
         ))
         .add_systems(First, (
             handle_go_gump.in_set(DefaultGameSet::HandleEvents),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh_server::world::connection::WriterAction;
+
+    use crate::commands::{TextCommandExecutor, TextCommands};
+    use crate::networking::{sent_messages, test_client};
+
+    use super::*;
+
+    fn spawn_player(app: &mut App, name: &str, position: IVec3) -> (Entity, Entity, UnboundedReceiver<WriterAction>) {
+        let character = app.world_mut()
+            .spawn((
+                CharacterName(name.into()),
+                MapPosition { position, map_id: 1, ..default() },
+            ))
+            .id();
+        let (client, rx) = test_client();
+        let client = app.world_mut().spawn((client, Possessing { entity: character })).id();
+        (client, character, rx)
+    }
+
+    fn run_command(app: &mut App, from: Entity, line: &str) {
+        let line = line.to_string();
+        app.world_mut()
+            .run_system_once(move |mut exec: TextCommandExecutor| {
+                assert!(exec.try_split_exec(from, &line));
+            })
+            .unwrap();
+        app.update();
+    }
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .add_text_command::<GoTo>()
+            .add_text_command::<Bring>()
+            .add_systems(Update, (go_to_player, bring_player));
+        app
+    }
+
+    #[test]
+    fn test_find_player() {
+        let a = Entity::from_raw(1);
+        let b = Entity::from_raw(2);
+        assert_eq!(find_player("gerome", [(a, "Gerome", true)]), PlayerLookup::Found(a));
+        assert_eq!(find_player("gerome", [(a, "Gerome", false)]), PlayerLookup::Offline);
+        assert_eq!(find_player("bob", [(a, "Gerome", true)]), PlayerLookup::NotFound);
+        assert!(matches!(
+            find_player("gerome", [(a, "Gerome", true), (b, "gerome", true)]),
+            PlayerLookup::Ambiguous(names) if names.len() == 2));
+    }
+
+    #[test]
+    fn test_go_to_player() {
+        let mut app = test_app();
+        let (gm, gm_character, _) = spawn_player(&mut app, "GM", IVec3::new(1, 2, 3));
+        let (_, _, _) = spawn_player(&mut app, "Gerome the Smith", IVec3::new(100, 200, 10));
+
+        run_command(&mut app, gm, "[goto gerome the smith");
+        let position = app.world().get::<MapPosition>(gm_character).unwrap();
+        assert_eq!(position.position, IVec3::new(100, 200, 10));
+
+        let (_, player, _) = spawn_player(&mut app, "Bob", IVec3::new(5, 5, 0));
+        run_command(&mut app, gm, "[bring Bob");
+        let position = app.world().get::<MapPosition>(player).unwrap();
+        assert_eq!(position.position, IVec3::new(100, 200, 10));
+    }
+
+    #[test]
+    fn test_go_to_missing_player() {
+        let mut app = test_app();
+        let (gm, gm_character, mut rx) = spawn_player(&mut app, "GM", IVec3::new(1, 2, 3));
+
+        run_command(&mut app, gm, "[goto Nobody");
+        let position = app.world().get::<MapPosition>(gm_character).unwrap();
+        assert_eq!(position.position, IVec3::new(1, 2, 3));
+
+        assert_eq!(sent_messages(&mut rx).first().map(String::as_str), Some("No player named 'Nobody'"));
+    }
+}
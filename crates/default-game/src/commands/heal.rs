@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh::protocol::TargetType;
+use yewoh_server::world::characters::Health;
+use yewoh_server::world::connection::Possessing;
+use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
+
+use crate::activities::combat::Healing;
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+
+/// Heal the targeted character, fully if no amount is given.
+#[derive(Parser, Resource)]
+pub struct Heal {
+    amount: Option<u16>,
+}
+
+impl TextCommand for Heal {
+    fn aliases() -> &'static [&'static str] {
+        &["heal"]
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct HealRequest(pub Option<u16>);
+
+pub fn start_heal(
+    mut exec: TextCommandQueue<Heal>,
+    mut commands: Commands,
+) {
+    for (from, args) in exec.iter() {
+        commands.spawn((
+            HealRequest(args.amount),
+            EntityTargetRequest {
+                client_entity: from,
+                target_type: TargetType::Helpful,
+            },
+        ));
+    }
+}
+
+pub fn finish_heal(
+    completed: Query<(Entity, &HealRequest, &EntityTargetRequest, &EntityTargetResponse)>,
+    clients: Query<&Possessing>,
+    targets: Query<&Health>,
+    mut healing: Healing,
+    mut commands: Commands,
+) {
+    for (entity, request, target_request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some(target) = response.target else {
+            continue;
+        };
+
+        let Ok(health) = targets.get(target) else {
+            continue;
+        };
+
+        let healer = clients.get(target_request.client_entity)
+            .map_or(target, |possessing| possessing.entity);
+        let amount = request.0.unwrap_or(health.max_hp);
+        healing.heal(target, healer, amount);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<Heal>()
+        .add_systems(Update, (
+            start_heal,
+            finish_heal,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::activities::combat::OnCharacterHealed;
+
+    use super::*;
+
+    #[test]
+    fn test_heal() {
+        let mut app = App::new();
+        app
+            .add_event::<OnCharacterHealed>()
+            .add_systems(Update, finish_heal);
+
+        let healer = app.world_mut().spawn_empty().id();
+        let client = app.world_mut().spawn(Possessing { entity: healer }).id();
+        let target = app.world_mut().spawn(Health { hp: 10, max_hp: 50 }).id();
+        app.world_mut().spawn((
+            HealRequest(None),
+            EntityTargetRequest { client_entity: client, target_type: TargetType::Helpful },
+            EntityTargetResponse { target: Some(target) },
+        ));
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(target).unwrap().hp, 50);
+        let events = app.world().resource::<Events<OnCharacterHealed>>();
+        let event = events.iter_current_update_events().next().unwrap();
+        assert_eq!((event.target, event.healer, event.amount), (target, healer, 40));
+    }
+}
@@ -7,11 +7,14 @@ use bevy::reflect::ReflectRef;
 use clap::Parser;
 use glam::ivec2;
 use yewoh::assets::map::CHUNK_SIZE;
+use yewoh::assets::tiles::{TileData, TileFlags};
 use yewoh::protocol::{GumpLayout, TargetType};
 use yewoh_server::gump_builder::{GumpBoxLayout, GumpBuilder, GumpRect, GumpRectLayout, GumpText};
 use yewoh_server::world::gump::{Gump, GumpClient};
+use yewoh_server::world::connection::NetClient;
 use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse, WorldTargetRequest, WorldTargetResponse};
-use yewoh_server::world::spatial::SpatialQuery;
+use yewoh_server::world::map::{Chunk, TileDataResource};
+use yewoh_server::world::spatial::{ChunkLookup, SpatialQuery, SpatialStaticItemLookup};
 use yewoh_server::world::view::ViewKey;
 
 use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
@@ -19,6 +22,7 @@ use crate::DefaultGameSet;
 use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
 use crate::gumps::{OnCloseGump, RESIZABLE_PAPER_3};
 use crate::gumps::page_allocator::GumpPageBoxAllocator;
+use crate::networking::NetClientExt;
 
 const ROW_HEIGHT: i32 = 20;
 
@@ -382,12 +386,58 @@ impl TextCommand for ChunkInfo {
     }
 }
 
+#[derive(Parser, Resource)]
+pub struct TileInfo;
+
+impl TextCommand for TileInfo {
+    fn aliases() -> &'static [&'static str] {
+        &["tile", "tileinfo"]
+    }
+}
+
 #[derive(Debug, Clone, Copy, Component, Reflect)]
 pub struct ShowInfoCommand;
 
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+pub struct ShowTileInfoCommand;
+
+fn format_tile_flags(flags: TileFlags) -> String {
+    if flags.is_empty() {
+        return "none".to_string();
+    }
+
+    let mut result = String::new();
+    for (name, _) in flags.iter_names() {
+        if !result.is_empty() {
+            result.push_str(", ");
+        }
+        result.push_str(name);
+    }
+    result
+}
+
+pub fn describe_land_tile(tile_data: &TileData, graphic: u16, z: i32) -> String {
+    match tile_data.land.get(graphic as usize) {
+        Some(info) => format!(
+            "Land {graphic:#06x} '{}' z={z} flags: {}",
+            info.name, format_tile_flags(info.flags)),
+        None => format!("Land {graphic:#06x} (unknown) z={z}"),
+    }
+}
+
+pub fn describe_static_tile(tile_data: &TileData, graphic: u16, z: i32) -> String {
+    match tile_data.items.get(graphic as usize) {
+        Some(info) => format!(
+            "Static {graphic:#06x} '{}' z={z} height={} flags: {}",
+            info.name, info.height, format_tile_flags(info.flags)),
+        None => format!("Static {graphic:#06x} (unknown) z={z}"),
+    }
+}
+
 pub fn start_info(
     mut exec: TextCommandQueue<EntityInfo>,
     mut exec_tile: TextCommandQueue<ChunkInfo>,
+    mut exec_tile_info: TextCommandQueue<TileInfo>,
     mut commands: Commands,
 ) {
     for (from, _) in exec.iter() {
@@ -408,6 +458,53 @@ pub fn start_info(
             ShowInfoCommand,
         ));
     }
+
+    for (from, _) in exec_tile_info.iter() {
+        commands.spawn((
+            WorldTargetRequest {
+                client_entity: from,
+                target_type: TargetType::Neutral,
+            },
+            ShowTileInfoCommand,
+        ));
+    }
+}
+
+pub fn tile_info(
+    clients: Query<(&NetClient, &ViewKey)>,
+    completed: Query<(Entity, &WorldTargetRequest, &WorldTargetResponse), With<ShowTileInfoCommand>>,
+    tile_data: Res<TileDataResource>,
+    chunk_lookup: Res<ChunkLookup>,
+    static_lookup: Res<SpatialStaticItemLookup>,
+    chunks: Query<&Chunk>,
+    mut commands: Commands,
+) {
+    for (entity, request, response) in completed.iter() {
+        commands.entity(entity).despawn();
+
+        let Ok((client, view_key)) = clients.get(request.client_entity) else {
+            continue;
+        };
+
+        let Some(position) = response.position else {
+            continue;
+        };
+
+        let map_id = view_key.map_id;
+        let position_2d = position.truncate();
+        client.send_system_message(format!(
+            "Tile at {}, {} (map {map_id})", position.x, position.y));
+
+        if let Some(chunk) = chunk_lookup.get_at(map_id, position_2d)
+            .and_then(|e| chunks.get(e).ok()) {
+            let tile = chunk.tile_at(position_2d);
+            client.send_system_message(describe_land_tile(&tile_data, tile.tile_id, tile.height as i32));
+        }
+
+        for entry in static_lookup.lookup.entries_at(map_id, position_2d) {
+            client.send_system_message(describe_static_tile(&tile_data, entry.graphic, entry.z_min));
+        }
+    }
 }
 
 pub fn info(
@@ -472,11 +569,47 @@ pub fn plugin(app: &mut App) {
         ))
         .add_text_command::<EntityInfo>()
         .add_text_command::<ChunkInfo>()
+        .add_text_command::<TileInfo>()
         .add_systems(Update, (
             start_info,
             info,
+            tile_info,
         ))
         .add_systems(First, (
             handle_info_gump.in_set(DefaultGameSet::HandleEvents),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use yewoh::assets::tiles::{ItemInfo, LandInfo};
+
+    use super::*;
+
+    #[test]
+    fn test_describe_tiles() {
+        let tile_data = TileData {
+            land: vec![LandInfo {
+                name: "grass".into(),
+                flags: TileFlags::empty(),
+                texture_id: 3,
+            }],
+            items: vec![ItemInfo {
+                name: "stone wall".into(),
+                flags: TileFlags::WALL | TileFlags::IMPASSABLE,
+                weight: 255,
+                quality: 0,
+                animation: 0,
+                quantity: 0,
+                value: 0,
+                height: 20,
+            }],
+        };
+
+        assert_eq!(describe_land_tile(&tile_data, 0, -5), "Land 0x0000 'grass' z=-5 flags: none");
+        assert_eq!(
+            describe_static_tile(&tile_data, 0, 10),
+            "Static 0x0000 'stone wall' z=10 height=20 flags: WALL, IMPASSABLE");
+        assert_eq!(describe_static_tile(&tile_data, 1, 0), "Static 0x0001 (unknown) z=0");
+    }
+}
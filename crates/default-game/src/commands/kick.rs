@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh::protocol::TargetType;
+use yewoh_server::world::characters::CharacterName;
+use yewoh_server::world::connection::{NetClient, NetServer, Possessing};
+use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
+
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+use crate::commands::go::resolve_player;
+use crate::hues;
+use crate::networking::NetClientExt;
+
+/// Disconnect a player by name, or the player controlling the targeted character.
+#[derive(Parser, Resource)]
+pub struct Kick {
+    name: Vec<String>,
+}
+
+impl TextCommand for Kick {
+    fn aliases() -> &'static [&'static str] {
+        &["kick", "disconnect"]
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct KickRequest;
+
+fn kick_character(
+    server: &NetServer,
+    from: &NetClient,
+    connections: &Query<(Entity, &NetClient, &Possessing)>,
+    character: Entity,
+) {
+    let Some((client_entity, client, _)) = connections.iter()
+        .find(|(_, _, possessing)| possessing.entity == character) else {
+        from.send_system_message_hue("That character is not online", hues::RED);
+        return;
+    };
+
+    client.disconnect();
+    server.disconnect(client_entity);
+    from.send_system_message("Player disconnected");
+}
+
+pub fn start_kick(
+    server: Res<NetServer>,
+    clients: Query<(&NetClient, &Possessing)>,
+    connections: Query<(Entity, &NetClient, &Possessing)>,
+    names: Query<(Entity, &CharacterName)>,
+    mut exec: TextCommandQueue<Kick>,
+    mut commands: Commands,
+) {
+    for (from, args) in exec.iter() {
+        if args.name.is_empty() {
+            commands.spawn((
+                KickRequest,
+                EntityTargetRequest {
+                    client_entity: from,
+                    target_type: TargetType::Neutral,
+                },
+            ));
+            continue;
+        }
+
+        let Ok((client, _)) = clients.get(from) else {
+            continue;
+        };
+
+        let name = args.name.join(" ");
+        let Some(target) = resolve_player(client, &name, &clients, &names) else {
+            continue;
+        };
+
+        kick_character(&server, client, &connections, target);
+    }
+}
+
+pub fn finish_kick(
+    server: Res<NetServer>,
+    completed: Query<(Entity, &EntityTargetRequest, &EntityTargetResponse), With<KickRequest>>,
+    connections: Query<(Entity, &NetClient, &Possessing)>,
+    clients: Query<&NetClient>,
+    mut commands: Commands,
+) {
+    for (entity, request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some(target) = response.target else {
+            continue;
+        };
+
+        let Ok(client) = clients.get(request.client_entity) else {
+            continue;
+        };
+
+        kick_character(&server, client, &connections, target);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<Kick>()
+        .add_systems(Update, (
+            start_kick,
+            finish_kick,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use tokio::sync::mpsc;
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh::protocol::AnyPacket;
+    use yewoh_server::world::connection::WriterAction;
+
+    use crate::commands::{TextCommandExecutor, TextCommands};
+    use crate::networking::{sent_messages, test_client};
+
+    use super::*;
+
+    fn spawn_player(app: &mut App, name: &str) -> (Entity, UnboundedReceiver<WriterAction>) {
+        let character = app.world_mut().spawn(CharacterName(name.into())).id();
+        let (client, rx) = test_client();
+        let client = app.world_mut().spawn((client, Possessing { entity: character })).id();
+        (client, rx)
+    }
+
+    fn run_command(app: &mut App, from: Entity, line: &'static str) {
+        app.world_mut()
+            .run_system_once(move |mut exec: TextCommandExecutor| {
+                assert!(exec.try_split_exec(from, line));
+            })
+            .unwrap();
+        app.update();
+    }
+
+    fn was_disconnected(rx: &mut UnboundedReceiver<WriterAction>) -> bool {
+        let mut logged_out = false;
+        while let Ok(action) = rx.try_recv() {
+            match action {
+                WriterAction::Send(_, AnyPacket::Logout(_)) => logged_out = true,
+                WriterAction::Close => return logged_out,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn test_kick_player() {
+        let (_, new_session_requests) = mpsc::unbounded_channel();
+        let (_, new_sessions) = mpsc::unbounded_channel();
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .insert_resource(NetServer::new(new_session_requests, new_sessions))
+            .add_plugins(plugin);
+
+        let (gm, mut gm_rx) = spawn_player(&mut app, "Admin");
+        let (_, mut player_rx) = spawn_player(&mut app, "Troublemaker");
+        app.world_mut().spawn(CharacterName("Sleeper".into()));
+
+        run_command(&mut app, gm, "[kick troublemaker");
+        assert!(was_disconnected(&mut player_rx));
+        assert!(!was_disconnected(&mut gm_rx));
+
+        run_command(&mut app, gm, "[kick sleeper");
+        assert_eq!(sent_messages(&mut gm_rx).first().map(String::as_str), Some("'sleeper' is not online"));
+    }
+}
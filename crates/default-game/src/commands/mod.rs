@@ -7,9 +7,12 @@ pub use registration::{
     TextCommandRegistrationExt,
     TextCommands,
 };
+pub use speech::{SpeechTriggerRegistrationExt, SpeechTriggers};
 
 mod registration;
 
+mod speech;
+
 pub mod test;
 
 pub mod info;
@@ -20,15 +23,71 @@ pub mod spawn;
 
 pub mod destroy;
 
+pub mod restock;
+
+pub mod channels;
+
+pub mod reload;
+
+pub mod rename;
+
+pub mod sign;
+
+pub mod reputation;
+
+pub mod freeze;
+
+pub mod heal;
+
+pub mod kick;
+
+pub mod who;
+
+/// Run a targeted text command from a fresh client and answer its target request with `target`.
+#[cfg(test)]
+pub(crate) fn run_on_target<R: Component>(app: &mut App, command: &'static str, target: Entity) {
+    use bevy::ecs::system::RunSystemOnce;
+    use yewoh_server::world::input::EntityTargetResponse;
+
+    use crate::networking::test_client;
+
+    let (client, _rx) = test_client();
+    let client = app.world_mut().spawn(client).id();
+
+    app.world_mut()
+        .run_system_once(move |mut exec: TextCommandExecutor| {
+            assert!(exec.try_split_exec(client, command));
+        })
+        .unwrap();
+    app.update();
+
+    let (request, _) = app.world_mut()
+        .query::<(Entity, &R)>()
+        .single(app.world());
+    app.world_mut().entity_mut(request).insert(EntityTargetResponse { target: Some(target) });
+    app.update();
+}
+
 pub struct CommandsPlugin;
 
 impl Plugin for CommandsPlugin {
     fn build(&self, app: &mut App) {
         app
             .insert_resource(TextCommands::new('['))
+            .init_resource::<SpeechTriggers>()
             .add_plugins((
                 spawn::plugin,
                 destroy::plugin,
+                restock::plugin,
+                channels::plugin,
+                reload::plugin,
+                rename::plugin,
+                sign::plugin,
+                reputation::plugin,
+                freeze::plugin,
+                heal::plugin,
+                kick::plugin,
+                who::plugin,
                 info::plugin,
                 go::plugin,
                 test::plugin,
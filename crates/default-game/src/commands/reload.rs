@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh_server::world::connection::NetClient;
+
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+use crate::data::static_data::{load_from_directory_blocking, DataPath};
+use crate::hues;
+use crate::networking::NetClientExt;
+
+/// Re-read the static data directory, including the optional files such as the MOTD and rates.
+///
+/// The live data is only replaced if all of the new data loads and validates. Maps are
+/// used to build the spatial lookups at startup, so map changes need a restart.
+#[derive(Parser, Resource)]
+pub struct Reload;
+
+impl TextCommand for Reload {
+    fn aliases() -> &'static [&'static str] {
+        &["reload"]
+    }
+}
+
+pub fn reload_static_data(
+    mut commands: Commands,
+    data_path: Res<DataPath>,
+    clients: Query<&NetClient>,
+    mut exec: TextCommandQueue<Reload>,
+) {
+    for (from, _) in exec.iter() {
+        let result = load_from_directory_blocking(&data_path.0);
+        let Ok(client) = clients.get(from) else {
+            continue;
+        };
+
+        match result {
+            Ok(data_files) => {
+                commands.queue(move |world: &mut World| data_files.insert_into(world));
+                client.send_system_message("Reloaded static data");
+            }
+            Err(err) => {
+                warn!("failed to reload static data: {err:#}");
+                client.send_system_message_hue(format!("Failed to reload static data: {err:#}"), hues::RED);
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<Reload>()
+        .add_systems(Update, (
+            reload_static_data,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::commands::{TextCommandExecutor, TextCommands};
+    use crate::data::static_data::StaticData;
+    use crate::motd::Motd;
+    use crate::networking::test_client;
+    use crate::rates::ServerRates;
+
+    use super::*;
+
+    fn write_data(path: &Path, city: &str) {
+        std::fs::create_dir_all(path).unwrap();
+        std::fs::write(path.join("cities.yaml"), format!("
+cities:
+  - name: {city}
+    building: Bank
+    map_id: 1
+    position: [1, 2, 3]
+")).unwrap();
+        std::fs::write(path.join("maps.yaml"), "
+maps:
+  1:
+    name: Trammel
+    size: [7168, 4096]
+").unwrap();
+        std::fs::write(path.join("skills.yaml"), "skills: {}\n").unwrap();
+        std::fs::write(path.join("locations.yaml"), "{}\n").unwrap();
+    }
+
+    #[test]
+    fn test_reload() {
+        let path = std::env::temp_dir().join(format!("yewoh-reload-{}", std::process::id()));
+        write_data(&path, "Britain");
+
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .insert_resource(DataPath(path.clone()))
+            .add_plugins(plugin);
+        load_from_directory_blocking(&path).unwrap().insert_into(app.world_mut());
+
+        let (client, _rx) = test_client();
+        let client = app.world_mut().spawn(client).id();
+        let reload = |app: &mut App| {
+            app.world_mut()
+                .run_system_once(move |mut exec: TextCommandExecutor| {
+                    assert!(exec.try_split_exec(client, "[reload"));
+                })
+                .unwrap();
+            app.update();
+            app.world().resource::<StaticData>().cities.cities[0].name.clone()
+        };
+
+        write_data(&path, "Trinsic");
+        std::fs::write(path.join("rates.yaml"), "gold_multiplier: 2\n").unwrap();
+        std::fs::write(path.join("motd.yaml"), "text: Welcome\n").unwrap();
+        assert_eq!(reload(&mut app), "Trinsic");
+        assert_eq!(app.world().resource::<ServerRates>().gold_multiplier, 2.);
+        assert_eq!(app.world().resource::<Motd>().text, "Welcome");
+
+        // Removing an optional file restores its default.
+        std::fs::remove_file(path.join("rates.yaml")).unwrap();
+        std::fs::remove_file(path.join("motd.yaml")).unwrap();
+        assert_eq!(reload(&mut app), "Trinsic");
+        assert_eq!(*app.world().resource::<ServerRates>(), ServerRates::default());
+        assert!(!app.world().contains_resource::<Motd>());
+
+        // Invalid data leaves the current data in place.
+        std::fs::write(path.join("maps.yaml"), "maps:\n  1:\n    name: Broken\n").unwrap();
+        assert_eq!(reload(&mut app), "Trinsic");
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
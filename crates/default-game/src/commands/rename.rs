@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh::protocol::TargetType;
+use yewoh_server::world::connection::NetClient;
+use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
+
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+use crate::entities::names::{validate_name, RenameEntity};
+use crate::hues;
+use crate::networking::NetClientExt;
+
+/// Rename the targeted entity.
+#[derive(Parser, Resource)]
+pub struct Rename {
+    #[clap(required = true)]
+    name: Vec<String>,
+}
+
+impl TextCommand for Rename {
+    fn aliases() -> &'static [&'static str] {
+        &["rename"]
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct RenameRequest(pub String);
+
+pub fn start_rename(
+    mut exec: TextCommandQueue<Rename>,
+    clients: Query<&NetClient>,
+    mut commands: Commands,
+) {
+    for (from, args) in exec.iter() {
+        let name = match validate_name(&args.name.join(" ")) {
+            Ok(name) => name.to_string(),
+            Err(err) => {
+                if let Ok(client) = clients.get(from) {
+                    client.send_system_message_hue(err.to_string(), hues::RED);
+                }
+                continue;
+            }
+        };
+
+        commands.spawn((
+            RenameRequest(name),
+            EntityTargetRequest {
+                client_entity: from,
+                target_type: TargetType::Neutral,
+            },
+        ));
+    }
+}
+
+pub fn finish_rename(
+    completed: Query<(Entity, &RenameRequest, &EntityTargetResponse)>,
+    mut commands: Commands,
+) {
+    for (entity, request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some(target) = response.target else {
+            continue;
+        };
+
+        commands.entity(target).queue(RenameEntity(request.0.clone()));
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<Rename>()
+        .add_systems(Update, (
+            start_rename,
+            finish_rename,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::commands::{TextCommandExecutor, TextCommands};
+    use crate::entities::names::CustomName;
+    use crate::networking::test_client;
+
+    use super::*;
+
+    #[test]
+    fn test_rename() {
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .add_plugins(plugin);
+
+        let (client, _rx) = test_client();
+        let client = app.world_mut().spawn(client).id();
+        let item = app.world_mut().spawn_empty().id();
+
+        app.world_mut()
+            .run_system_once(move |mut exec: TextCommandExecutor| {
+                assert!(exec.try_split_exec(client, "[rename Sword of Doom"));
+            })
+            .unwrap();
+        app.update();
+
+        let (request, _) = app.world_mut()
+            .query::<(Entity, &RenameRequest)>()
+            .single(app.world());
+        app.world_mut().entity_mut(request).insert(EntityTargetResponse { target: Some(item) });
+        app.update();
+
+        assert_eq!(app.world().get::<CustomName>(item).unwrap().as_str(), "Sword of Doom");
+        assert!(app.world().get_entity(request).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use bevy::prelude::*;
+use clap::{ArgAction, Parser, ValueEnum};
+use yewoh::protocol::TargetType;
+use yewoh_server::world::characters::{Criminal, Invulnerable, Murderer, Protected, WarMode};
+use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
+
+use crate::characters::reputation::MurderCount;
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NotorietyLevel {
+    Innocent,
+    Neutral,
+    Criminal,
+    Murderer,
+    Invulnerable,
+}
+
+/// Set the notoriety of the targeted character.
+#[derive(Parser, Resource)]
+pub struct SetNotoriety {
+    #[clap(value_enum)]
+    notoriety: NotorietyLevel,
+}
+
+impl TextCommand for SetNotoriety {
+    fn aliases() -> &'static [&'static str] {
+        &["notoriety", "setnotoriety"]
+    }
+}
+
+/// Set the murder count of the targeted character.
+#[derive(Parser, Resource)]
+pub struct SetKills {
+    count: u16,
+}
+
+impl TextCommand for SetKills {
+    fn aliases() -> &'static [&'static str] {
+        &["kills", "setkills"]
+    }
+}
+
+/// Put the targeted character into or out of war mode.
+#[derive(Parser, Resource)]
+pub struct SetWarMode {
+    #[clap(action = ArgAction::Set)]
+    enabled: bool,
+}
+
+impl TextCommand for SetWarMode {
+    fn aliases() -> &'static [&'static str] {
+        &["warmode", "setwarmode"]
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub enum ReputationChangeRequest {
+    Notoriety(NotorietyLevel),
+    Kills(u16),
+    WarMode(bool),
+}
+
+fn request_target(commands: &mut Commands, client_entity: Entity, request: ReputationChangeRequest) {
+    commands.spawn((
+        request,
+        EntityTargetRequest {
+            client_entity,
+            target_type: TargetType::Neutral,
+        },
+    ));
+}
+
+pub fn start_set_notoriety(
+    mut exec: TextCommandQueue<SetNotoriety>,
+    mut commands: Commands,
+) {
+    for (from, args) in exec.iter() {
+        request_target(&mut commands, from, ReputationChangeRequest::Notoriety(args.notoriety));
+    }
+}
+
+pub fn start_set_kills(
+    mut exec: TextCommandQueue<SetKills>,
+    mut commands: Commands,
+) {
+    for (from, args) in exec.iter() {
+        request_target(&mut commands, from, ReputationChangeRequest::Kills(args.count));
+    }
+}
+
+pub fn start_set_war_mode(
+    mut exec: TextCommandQueue<SetWarMode>,
+    mut commands: Commands,
+) {
+    for (from, args) in exec.iter() {
+        request_target(&mut commands, from, ReputationChangeRequest::WarMode(args.enabled));
+    }
+}
+
+pub fn finish_reputation_change(
+    completed: Query<(Entity, &ReputationChangeRequest, &EntityTargetResponse)>,
+    mut characters: Query<(
+        &mut Protected,
+        &mut Criminal,
+        &mut Murderer,
+        &mut Invulnerable,
+        &mut WarMode,
+    )>,
+    mut commands: Commands,
+) {
+    for (entity, request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some(target) = response.target else {
+            continue;
+        };
+
+        let Ok((mut protected, mut criminal, mut murderer, mut invulnerable, mut war_mode)) =
+            characters.get_mut(target) else {
+            continue;
+        };
+
+        match *request {
+            ReputationChangeRequest::Notoriety(level) => {
+                protected.set_if_neq(Protected(level == NotorietyLevel::Innocent));
+                criminal.set_if_neq(Criminal(level == NotorietyLevel::Criminal));
+                murderer.set_if_neq(Murderer(level == NotorietyLevel::Murderer));
+                invulnerable.set_if_neq(Invulnerable(level == NotorietyLevel::Invulnerable));
+            }
+            ReputationChangeRequest::Kills(count) => {
+                commands.entity(target).insert(MurderCount(count));
+            }
+            ReputationChangeRequest::WarMode(enabled) => {
+                war_mode.set_if_neq(WarMode(enabled));
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<SetNotoriety>()
+        .add_text_command::<SetKills>()
+        .add_text_command::<SetWarMode>()
+        .add_systems(Update, (
+            start_set_notoriety,
+            start_set_kills,
+            start_set_war_mode,
+            finish_reputation_change,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh::{EntityId, Notoriety};
+    use yewoh::protocol::AnyPacket;
+    use yewoh_server::world::characters::{detect_character_changes, CharacterBodyType, NotorietyQuery};
+    use yewoh_server::world::connection::{OwningClient, Possessing, WriterAction};
+    use yewoh_server::world::delta_grid::{reset_delta_grid, DeltaGrid, DeltaVersion};
+    use yewoh_server::world::entity::MapPosition;
+    use yewoh_server::world::map::{MapInfo, MapInfos};
+    use yewoh_server::world::net_id::{NetId, OnDestroyNetEntity};
+    use yewoh_server::world::view::{send_deltas, LastView, SeenEntities, Synchronized, ViewKey, ViewRect};
+
+    use crate::commands::{run_on_target, TextCommands};
+    use crate::networking::test_client;
+
+    use super::*;
+
+    fn notoriety(app: &mut App, entity: Entity) -> Notoriety {
+        app.world_mut()
+            .query::<NotorietyQuery>()
+            .get(app.world(), entity)
+            .unwrap()
+            .notoriety()
+    }
+
+    fn reputation_app() -> App {
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .add_plugins((
+                crate::characters::reputation::plugin,
+                plugin,
+            ));
+        app
+    }
+
+    #[test]
+    fn test_set_notoriety() {
+        let mut app = reputation_app();
+        let character = app.world_mut().spawn((CharacterBodyType(0x190), Protected(true))).id();
+        assert_eq!(notoriety(&mut app, character), Notoriety::Innocent);
+
+        run_on_target::<ReputationChangeRequest>(&mut app, "[notoriety murderer", character);
+        assert_eq!(notoriety(&mut app, character), Notoriety::Murderer);
+
+        run_on_target::<ReputationChangeRequest>(&mut app, "[notoriety neutral", character);
+        assert_eq!(notoriety(&mut app, character), Notoriety::Neutral);
+    }
+
+    #[test]
+    fn test_set_kills() {
+        let mut app = reputation_app();
+        let character = app.world_mut().spawn((CharacterBodyType(0x190), Protected(true))).id();
+
+        run_on_target::<ReputationChangeRequest>(&mut app, "[kills 5", character);
+        app.update();
+        assert_eq!(notoriety(&mut app, character), Notoriety::Murderer);
+
+        run_on_target::<ReputationChangeRequest>(&mut app, "[kills 0", character);
+        app.update();
+        assert_eq!(notoriety(&mut app, character), Notoriety::Innocent);
+    }
+
+    /// Add a client which observes characters near (10, 10) through the usual view updates.
+    fn spawn_observer(app: &mut App) -> UnboundedReceiver<WriterAction> {
+        let mut maps = MapInfos::default();
+        maps.maps.insert(1, MapInfo { size: UVec2::new(100, 100), ..default() });
+        app
+            .insert_resource(DeltaGrid::new(&maps))
+            .init_resource::<DeltaVersion>()
+            .add_event::<OnDestroyNetEntity>()
+            .add_systems(PostUpdate, (detect_character_changes, send_deltas).chain())
+            .add_systems(Last, reset_delta_grid);
+
+        let (client, rx) = test_client();
+        let client_entity = app.world_mut().spawn(client).id();
+        let observer = app.world_mut()
+            .spawn((
+                CharacterBodyType(0x190),
+                NetId { id: EntityId::from_u32(1) },
+                MapPosition { position: IVec3::new(10, 10, 0), map_id: 1 },
+                OwningClient { client_entity },
+            ))
+            .id();
+        app.world_mut().entity_mut(client_entity).insert((
+            Possessing { entity: observer },
+            ViewKey { possessing: observer, map_id: 1 },
+            LastView(ViewRect::from_range(IVec2::new(10, 10), 18)),
+            SeenEntities::default(),
+            Synchronized,
+        ));
+        rx
+    }
+
+    /// The last notoriety the observer was sent for `id`.
+    fn observed_notoriety(rx: &mut UnboundedReceiver<WriterAction>, id: EntityId) -> Option<Notoriety> {
+        let mut notoriety = None;
+        while let Ok(action) = rx.try_recv() {
+            let packet = match &action {
+                WriterAction::Send(_, packet) => packet,
+                WriterAction::SendArc(_, packet) => packet.as_ref(),
+                WriterAction::Close => continue,
+            };
+
+            match packet {
+                AnyPacket::UpsertEntityCharacter(packet) if packet.id == id => notoriety = Some(packet.notoriety),
+                AnyPacket::UpdateCharacter(packet) if packet.id == id => notoriety = Some(packet.notoriety),
+                _ => {}
+            }
+        }
+        notoriety
+    }
+
+    #[test]
+    fn test_observed_notoriety() {
+        let mut app = reputation_app();
+        let mut rx = spawn_observer(&mut app);
+        let id = EntityId::from_u32(2);
+        let character = app.world_mut()
+            .spawn((
+                CharacterBodyType(0x190),
+                Protected(true),
+                NetId { id },
+                MapPosition { position: IVec3::new(12, 10, 0), map_id: 1 },
+            ))
+            .id();
+        app.update();
+        assert_eq!(observed_notoriety(&mut rx, id), Some(Notoriety::Innocent));
+
+        run_on_target::<ReputationChangeRequest>(&mut app, "[kills 5", character);
+        app.update();
+        assert_eq!(observed_notoriety(&mut rx, id), Some(Notoriety::Murderer));
+
+        run_on_target::<ReputationChangeRequest>(&mut app, "[kills 0", character);
+        app.update();
+        assert_eq!(observed_notoriety(&mut rx, id), Some(Notoriety::Innocent));
+
+        run_on_target::<ReputationChangeRequest>(&mut app, "[notoriety criminal", character);
+        assert_eq!(observed_notoriety(&mut rx, id), Some(Notoriety::Criminal));
+    }
+
+    #[test]
+    fn test_set_war_mode() {
+        let mut app = reputation_app();
+        let character = app.world_mut().spawn(CharacterBodyType(0x190)).id();
+
+        run_on_target::<ReputationChangeRequest>(&mut app, "[warmode true", character);
+        assert!(**app.world().get::<WarMode>(character).unwrap());
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh::protocol::TargetType;
+use yewoh_server::world::connection::Possessing;
+use yewoh_server::world::entity::MapPosition;
+use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
+
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+use crate::entities::{Persistent, UniqueId};
+use crate::spawners::{clear_spawner, restock_spawner, Spawned, SpawnedEntities, Spawner};
+
+/// Fill spawners up to their limit immediately.
+#[derive(Parser, Resource)]
+pub struct Restock {
+    /// Restock every spawner within this many tiles, instead of targeting one.
+    range: Option<i32>,
+}
+
+impl TextCommand for Restock {
+    fn aliases() -> &'static [&'static str] {
+        &["restock", "respawn"]
+    }
+}
+
+/// Remove everything spawners have spawned.
+#[derive(Parser, Resource)]
+pub struct ClearSpawns {
+    /// Clear every spawner within this many tiles, instead of targeting one.
+    range: Option<i32>,
+}
+
+impl TextCommand for ClearSpawns {
+    fn aliases() -> &'static [&'static str] {
+        &["clearspawns"]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnerAction {
+    Restock,
+    Clear,
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct SpawnerActionRequest(pub SpawnerAction);
+
+fn in_range(a: &MapPosition, b: &MapPosition, range: i32) -> bool {
+    let delta = (a.position - b.position).abs();
+    a.map_id == b.map_id && delta.x <= range && delta.y <= range
+}
+
+fn apply_action(
+    commands: &mut Commands,
+    action: SpawnerAction,
+    spawner: &mut Spawner,
+    spawned: &mut SpawnedEntities,
+    position: MapPosition,
+    spawner_id: Option<&UniqueId>,
+    spawned_entities: &Query<(), With<Spawned>>,
+) {
+    spawned.entities.retain(|e| spawned_entities.contains(*e));
+    match action {
+        SpawnerAction::Restock => restock_spawner(commands, spawner, spawned, position, spawner_id),
+        SpawnerAction::Clear => clear_spawner(commands, spawned),
+    }
+}
+
+pub fn start_spawner_actions(
+    mut commands: Commands,
+    mut restock: TextCommandQueue<Restock>,
+    mut clear: TextCommandQueue<ClearSpawns>,
+    clients: Query<&Possessing>,
+    positions: Query<&MapPosition>,
+    mut spawners: Query<(&mut Spawner, &mut SpawnedEntities, &MapPosition, Option<&UniqueId>, Has<Persistent>)>,
+    spawned_entities: Query<(), With<Spawned>>,
+) {
+    let requests = restock.iter()
+        .map(|(from, args)| (from, SpawnerAction::Restock, args.range))
+        .chain(clear.iter().map(|(from, args)| (from, SpawnerAction::Clear, args.range)))
+        .collect::<Vec<_>>();
+
+    for (from, action, range) in requests {
+        let Some(range) = range else {
+            commands.spawn((
+                SpawnerActionRequest(action),
+                EntityTargetRequest {
+                    client_entity: from,
+                    target_type: TargetType::Neutral,
+                },
+            ));
+            continue;
+        };
+
+        let Some(origin) = clients.get(from).ok()
+            .and_then(|possessing| positions.get(possessing.entity).ok()) else {
+            continue;
+        };
+
+        for (mut spawner, mut spawned, position, spawner_id, is_persistent) in &mut spawners {
+            if in_range(origin, position, range) {
+                apply_action(
+                    &mut commands, action, &mut spawner, &mut spawned, *position,
+                    spawner_id.filter(|_| is_persistent), &spawned_entities);
+            }
+        }
+    }
+}
+
+pub fn finish_spawner_actions(
+    mut commands: Commands,
+    completed: Query<(Entity, &SpawnerActionRequest, &EntityTargetResponse)>,
+    mut spawners: Query<(&mut Spawner, &mut SpawnedEntities, &MapPosition, Option<&UniqueId>, Has<Persistent>)>,
+    spawned_entities: Query<(), With<Spawned>>,
+) {
+    for (entity, request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some((mut spawner, mut spawned, position, spawner_id, is_persistent)) = response.target
+            .and_then(|target| spawners.get_mut(target).ok()) else {
+            continue;
+        };
+
+        apply_action(
+            &mut commands, request.0, &mut spawner, &mut spawned, *position,
+            spawner_id.filter(|_| is_persistent), &spawned_entities);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<Restock>()
+        .add_text_command::<ClearSpawns>()
+        .add_systems(Update, (
+            start_spawner_actions,
+            finish_spawner_actions,
+        ));
+}
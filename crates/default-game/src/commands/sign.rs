@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh::protocol::TargetType;
+use yewoh_server::world::connection::NetClient;
+use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
+use yewoh_server::world::items::ItemGraphic;
+
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+use crate::entities::Persistent;
+use crate::hues;
+use crate::items::signs::{SetSignText, Sign};
+use crate::networking::NetClientExt;
+
+/// Set the text shown on the targeted sign.
+///
+/// Only persistent items can be changed, since static signs are recreated from
+/// their prefabs on every start and would lose the new text.
+#[derive(Parser, Resource)]
+pub struct SetSign {
+    text: Vec<String>,
+}
+
+impl TextCommand for SetSign {
+    fn aliases() -> &'static [&'static str] {
+        &["sign", "setsign"]
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct SetSignRequest(pub String);
+
+pub fn start_set_sign(
+    mut exec: TextCommandQueue<SetSign>,
+    mut commands: Commands,
+) {
+    for (from, args) in exec.iter() {
+        commands.spawn((
+            SetSignRequest(args.text.join(" ")),
+            EntityTargetRequest {
+                client_entity: from,
+                target_type: TargetType::Neutral,
+            },
+        ));
+    }
+}
+
+pub fn finish_set_sign(
+    completed: Query<(Entity, &SetSignRequest, &EntityTargetRequest, &EntityTargetResponse)>,
+    clients: Query<&NetClient>,
+    targets: Query<(Has<Sign>, Has<ItemGraphic>, Has<Persistent>)>,
+    mut commands: Commands,
+) {
+    for (entity, request, target_request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some(target) = response.target else {
+            continue;
+        };
+        let Ok((is_sign, is_item, persistent)) = targets.get(target) else {
+            continue;
+        };
+
+        let error = if !is_sign && !is_item {
+            Some("Only items can be made into signs.")
+        } else if !persistent {
+            Some("Static signs are set by their prefab and can't be changed in game.")
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            if let Ok(client) = clients.get(target_request.client_entity) {
+                client.send_system_message_hue(error, hues::RED);
+            }
+            continue;
+        }
+
+        commands.entity(target).queue(SetSignText(request.0.clone()));
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<SetSign>()
+        .add_systems(Update, (
+            start_set_sign,
+            finish_set_sign,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{run_on_target, TextCommands};
+
+    use super::*;
+
+    fn sign_app() -> App {
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .add_plugins(plugin);
+        app
+    }
+
+    #[test]
+    fn test_set_sign() {
+        let mut app = sign_app();
+        let sign = app.world_mut().spawn((Sign { text: "Closed".into() }, Persistent)).id();
+
+        run_on_target::<SetSignRequest>(&mut app, "[sign Open for business", sign);
+        assert_eq!(app.world().get::<Sign>(sign).unwrap().text, "Open for business");
+    }
+
+    #[test]
+    fn test_reject_static_sign() {
+        let mut app = sign_app();
+        let sign = app.world_mut().spawn(Sign { text: "Closed".into() }).id();
+
+        run_on_target::<SetSignRequest>(&mut app, "[sign Open for business", sign);
+        assert_eq!(app.world().get::<Sign>(sign).unwrap().text, "Closed");
+    }
+
+    #[test]
+    fn test_reject_non_item() {
+        let mut app = sign_app();
+        let character = app.world_mut().spawn(Persistent).id();
+
+        run_on_target::<SetSignRequest>(&mut app, "[sign Open for business", character);
+        assert!(app.world().get::<Sign>(character).is_none());
+    }
+}
@@ -1,9 +1,17 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
 use bevy::app::{App, Update};
 use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
-use bevy::ecs::system::{Commands, Query, Res, Resource};
+use bevy::ecs::reflect::AppTypeRegistry;
+use bevy::ecs::system::{Commands, EntityCommands, Query, Res, Resource};
 use bevy::hierarchy::BuildChildren;
+use bevy::reflect::serde::TypedReflectDeserializer;
+use bevy::reflect::{DynamicStruct, PartialReflect, TypeRegistry};
+use bevy_fabricator::Fabricator;
 use clap::Parser;
+use serde::de::DeserializeSeed;
 use yewoh::protocol::TargetType;
 use yewoh_server::world::entity::{ContainedPosition, MapPosition};
 use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse, WorldTargetRequest, WorldTargetResponse};
@@ -12,8 +20,8 @@ use yewoh_server::world::items::{Container, ItemQuantity};
 use yewoh_server::world::view::ViewKey;
 
 use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
-use crate::data::prefabs::{PrefabLibrary, PrefabLibraryWorldExt};
-use crate::entities::Persistent;
+use crate::data::prefabs::{PrefabLibrary, PrefabLibraryRequest, PrefabLibraryWorldExt};
+use crate::entities::{Persistent, PrefabInstance};
 use crate::hues;
 use crate::networking::NetClientExt;
 
@@ -24,7 +32,8 @@ pub struct Spawn {
 
     prefab: String,
 
-    quantity: Option<u16>,
+    /// An optional quantity, followed by prefab parameters as `name:value` pairs.
+    arguments: Vec<String>,
 }
 
 impl TextCommand for Spawn {
@@ -36,26 +45,113 @@ impl TextCommand for Spawn {
 #[derive(Debug, Clone, Component)]
 pub struct SpawnRequest {
     prefab_name: String,
+    parameters: Option<Arc<dyn PartialReflect>>,
     quantity: Option<u16>,
 }
 
+fn describe_parameters(fabricator: &Fabricator, registry: &TypeRegistry) -> String {
+    let mut names = fabricator.parameters.keys().collect::<Vec<_>>();
+    if names.is_empty() {
+        return "this prefab takes no parameters".to_string();
+    }
+
+    names.sort();
+    let descriptions = names.into_iter()
+        .map(|name| {
+            let parameter = &fabricator.parameters[name];
+            let type_name = registry.get(parameter.parameter_type)
+                .map_or("?", |registration| registration.type_info().type_path_table().short_path());
+            let optional = if parameter.optional { "?" } else { "" };
+            format!("{name}: {type_name}{optional}")
+        })
+        .collect::<Vec<_>>();
+    format!("expected {}", descriptions.join(", "))
+}
+
+/// Parse `name:value` pairs into a parameter struct for a fabricator.
+///
+/// Values are parsed as YAML scalars and validated against the declared parameter types.
+pub fn parse_parameters<'a>(
+    fabricator: &Fabricator,
+    registry: &TypeRegistry,
+    arguments: impl IntoIterator<Item=&'a str>,
+) -> anyhow::Result<DynamicStruct> {
+    let mut parameters = DynamicStruct::default();
+    for argument in arguments {
+        let (name, value) = argument.split_once(':')
+            .ok_or_else(|| anyhow!("parameter '{argument}' should be name:value"))?;
+        let parameter = fabricator.parameters.get(name)
+            .ok_or_else(|| anyhow!(
+                "unknown parameter '{name}', {}", describe_parameters(fabricator, registry)))?;
+        let registration = registry.get(parameter.parameter_type)
+            .ok_or_else(|| anyhow!("parameter '{name}' has an unregistered type"))?;
+        let value = TypedReflectDeserializer::new(registration, registry)
+            .deserialize(serde_yaml::Deserializer::from_str(value))
+            .map_err(|err| anyhow!(
+                "invalid value for '{name}' ({err}), {}", describe_parameters(fabricator, registry)))?;
+        parameters.insert_boxed(name, value);
+    }
+
+    for (name, parameter) in &fabricator.parameters {
+        if !parameter.optional && parameters.field(name).is_none() {
+            return Err(anyhow!(
+                "missing parameter '{name}', {}", describe_parameters(fabricator, registry)));
+        }
+    }
+
+    Ok(parameters)
+}
+
+fn parse_spawn_arguments(
+    fabricator: &Fabricator, registry: &TypeRegistry, arguments: &[String],
+) -> anyhow::Result<(Option<u16>, Option<Arc<dyn PartialReflect>>)> {
+    let (quantity, arguments) = match arguments.first() {
+        Some(first) if !first.contains(':') => {
+            let quantity = first.parse()
+                .map_err(|_| anyhow!("invalid quantity '{first}'"))?;
+            (Some(quantity), &arguments[1..])
+        }
+        _ => (None, arguments),
+    };
+
+    let parameters = if arguments.is_empty() && fabricator.parameters.values().all(|p| p.optional) {
+        None
+    } else {
+        let parameters = parse_parameters(
+            fabricator, registry, arguments.iter().map(String::as_str))?;
+        Some(Arc::new(parameters) as Arc<dyn PartialReflect>)
+    };
+
+    Ok((quantity, parameters))
+}
+
 pub fn start_spawn(
     mut exec: TextCommandQueue<Spawn>,
     mut commands: Commands,
     prefabs: Res<PrefabLibrary>,
+    type_registry: Res<AppTypeRegistry>,
     clients: Query<&NetClient>,
 ) {
+    let registry = type_registry.read();
     for (from, request) in exec.iter() {
-        if prefabs.get(&request.prefab).is_none() {
-            let client = match clients.get(from) {
-                Ok(x) => x,
-                _ => continue,
-            };
+        let Ok(client) = clients.get(from) else {
+            continue;
+        };
+
+        let Some(fabricator) = prefabs.get(&request.prefab) else {
             client.send_system_message_hue(format!("No such prefab '{}'", &request.prefab), hues::RED);
             continue;
         };
 
-        let spawn_request = SpawnRequest { prefab_name: request.prefab, quantity: request.quantity };
+        let (quantity, parameters) = match parse_spawn_arguments(fabricator, &registry, &request.arguments) {
+            Ok(x) => x,
+            Err(err) => {
+                client.send_system_message_hue(err.to_string(), hues::RED);
+                continue;
+            }
+        };
+
+        let spawn_request = SpawnRequest { prefab_name: request.prefab, parameters, quantity };
         if request.in_container {
             commands
                 .spawn((
@@ -78,6 +174,20 @@ pub fn start_spawn(
     }
 }
 
+fn fabricate_spawn<'a>(commands: &'a mut Commands, spawn: &SpawnRequest) -> EntityCommands<'a> {
+    match &spawn.parameters {
+        Some(parameters) => {
+            let mut entity_commands = commands.fabricate_from_library(PrefabLibraryRequest {
+                prefab_name: spawn.prefab_name.clone(),
+                parameters: parameters.clone(),
+            });
+            entity_commands.insert(PrefabInstance { prefab_name: spawn.prefab_name.clone() });
+            entity_commands
+        }
+        None => commands.fabricate_prefab(&spawn.prefab_name),
+    }
+}
+
 pub fn spawn(
     completed_position: Query<(Entity, &SpawnRequest, &WorldTargetRequest, &WorldTargetResponse)>,
     completed_entity: Query<(Entity, &SpawnRequest, &EntityTargetRequest, &EntityTargetResponse)>,
@@ -100,8 +210,7 @@ pub fn spawn(
         // TODO: check whether location is obstructed.
 
         let map_id = view_key.map_id;
-        let mut entity_commands = commands
-            .fabricate_prefab(&spawn.prefab_name);
+        let mut entity_commands = fabricate_spawn(&mut commands, spawn);
 
         entity_commands
             .insert((
@@ -138,8 +247,7 @@ pub fn spawn(
             }
         };
 
-        let mut entity_commands = commands
-            .fabricate_prefab(&spawn.prefab_name);
+        let mut entity_commands = fabricate_spawn(&mut commands, spawn);
 
         entity_commands
             .insert((
@@ -162,3 +270,73 @@ pub fn plugin(app: &mut App) {
             spawn,
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::any::TypeId;
+
+    use bevy::prelude::*;
+    use bevy::reflect::ReflectRef;
+    use bevy::utils::HashMap;
+    use bevy_fabricator::{FabricationParameter, Fabricated};
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Amount(u16);
+
+    fn test_fabricator() -> Fabricator {
+        let mut parameters = HashMap::new();
+        parameters.insert("amount".to_string(), FabricationParameter {
+            parameter_type: TypeId::of::<u16>(),
+            optional: false,
+        });
+        parameters.insert("label".to_string(), FabricationParameter {
+            parameter_type: TypeId::of::<String>(),
+            optional: true,
+        });
+
+        Fabricator {
+            parameters,
+            factory: Arc::new(|entity, input, world| {
+                let ReflectRef::Struct(input) = input.reflect_ref() else {
+                    return Err(anyhow!("expected struct input"));
+                };
+                let amount = input.field("amount")
+                    .and_then(|value| value.try_downcast_ref::<u16>())
+                    .ok_or_else(|| anyhow!("missing amount"))?;
+                world.entity_mut(entity).insert(Amount(*amount));
+                Ok(Fabricated::default())
+            }),
+        }
+    }
+
+    #[test]
+    fn test_add_with_parameter() {
+        let fabricator = test_fabricator();
+        let registry = TypeRegistry::new();
+        let arguments = vec!["5".to_string(), "amount:12".to_string()];
+        let (quantity, parameters) = parse_spawn_arguments(&fabricator, &registry, &arguments).unwrap();
+        assert_eq!(quantity, Some(5));
+
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        fabricator.fabricate(parameters.unwrap().as_ref(), &mut world, entity).unwrap();
+        assert_eq!(world.get::<Amount>(entity).unwrap().0, 12);
+    }
+
+    #[test]
+    fn test_add_with_unknown_parameter() {
+        let fabricator = test_fabricator();
+        let registry = TypeRegistry::new();
+
+        let err = parse_parameters(&fabricator, &registry, ["colour:red"]).unwrap_err();
+        assert_eq!(err.to_string(), "unknown parameter 'colour', expected amount: u16, label: String?");
+
+        let err = parse_parameters(&fabricator, &registry, ["amount:lots"]).unwrap_err();
+        assert!(err.to_string().starts_with("invalid value for 'amount'"));
+
+        let err = parse_parameters(&fabricator, &registry, ["label:hi"]).unwrap_err();
+        assert!(err.to_string().starts_with("missing parameter 'amount'"));
+    }
+}
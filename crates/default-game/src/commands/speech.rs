@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+
+struct SpeechTrigger {
+    keyword: Vec<String>,
+    command: Vec<String>,
+}
+
+/// Maps spoken keywords to text commands, so that saying "bank" can behave like `[bank`.
+#[derive(Default, Resource)]
+pub struct SpeechTriggers {
+    triggers: Vec<SpeechTrigger>,
+}
+
+fn split_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+impl SpeechTriggers {
+    pub fn register(
+        &mut self, keyword: &str, command: impl IntoIterator<Item=impl Into<String>>,
+    ) {
+        self.triggers.push(SpeechTrigger {
+            keyword: split_words(keyword),
+            command: command.into_iter().map(Into::into).collect(),
+        });
+    }
+
+    /// Find the commands for all keywords contained in `text`.
+    ///
+    /// Keywords are matched case-insensitively against whole words.
+    pub fn matching(&self, text: &str) -> impl Iterator<Item=&[String]> + '_ {
+        let words = split_words(text);
+        self.triggers.iter()
+            .filter(move |trigger| !trigger.keyword.is_empty()
+                && words.windows(trigger.keyword.len()).any(|window| window == trigger.keyword))
+            .map(|trigger| trigger.command.as_slice())
+    }
+}
+
+pub trait SpeechTriggerRegistrationExt {
+    fn add_speech_trigger(
+        &mut self, keyword: &str, command: impl IntoIterator<Item=impl Into<String>>,
+    ) -> &mut Self;
+}
+
+impl SpeechTriggerRegistrationExt for App {
+    fn add_speech_trigger(
+        &mut self, keyword: &str, command: impl IntoIterator<Item=impl Into<String>>,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(SpeechTriggers::default)
+            .register(keyword, command);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching() {
+        let mut triggers = SpeechTriggers::default();
+        triggers.register("bank", ["bank"]);
+        triggers.register("i wish to lock this down", ["lockdown"]);
+
+        let matches = |text: &str| triggers.matching(text)
+            .map(|command| command[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(matches("Bank!"), vec!["bank"]);
+        assert_eq!(matches("I wish to lock this down"), vec!["lockdown"]);
+        assert!(matches("banker").is_empty());
+        assert!(matches("hello there").is_empty());
+    }
+}
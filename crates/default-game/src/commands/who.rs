@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+use clap::Parser;
+use yewoh_server::world::characters::CharacterName;
+use yewoh_server::world::connection::{NetClient, OwningClient};
+
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+use crate::networking::NetClientExt;
+
+/// List the players who are currently online.
+///
+/// Account names and locations are left out, as anyone can run this.
+#[derive(Parser, Resource)]
+pub struct Who;
+
+impl TextCommand for Who {
+    fn aliases() -> &'static [&'static str] {
+        &["who", "online"]
+    }
+}
+
+pub fn who(
+    clients: Query<&NetClient>,
+    characters: Query<(&CharacterName, &OwningClient)>,
+    mut exec: TextCommandQueue<Who>,
+) {
+    for (from, _) in exec.iter() {
+        let Ok(client) = clients.get(from) else {
+            continue;
+        };
+
+        let mut online = characters.iter()
+            .filter(|(_, owner)| clients.contains(owner.client_entity))
+            .map(|(name, _)| name.0.clone())
+            .collect::<Vec<_>>();
+        online.sort();
+
+        client.send_system_message(format!("{} player(s) online", online.len()));
+        for line in online {
+            client.send_system_message(line);
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<Who>()
+        .add_systems(Update, who);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh_server::world::account::User;
+    use yewoh_server::world::connection::{Possessing, WriterAction};
+    use yewoh_server::world::entity::MapPosition;
+
+    use crate::commands::{TextCommandExecutor, TextCommands};
+    use crate::networking::{sent_messages, test_client};
+
+    use super::*;
+
+    fn spawn_player(
+        app: &mut App,
+        username: &str,
+        name: &str,
+        position: IVec3,
+    ) -> (Entity, UnboundedReceiver<WriterAction>) {
+        let (client, rx) = test_client();
+        let client_entity = app.world_mut().spawn((client, User { username: username.into() })).id();
+        let character = app.world_mut()
+            .spawn((
+                CharacterName(name.into()),
+                MapPosition { position, map_id: 1 },
+                OwningClient { client_entity },
+            ))
+            .id();
+        app.world_mut().entity_mut(client_entity).insert(Possessing { entity: character });
+        (client_entity, rx)
+    }
+
+    #[test]
+    fn test_who() {
+        let mut app = App::new();
+        app
+            .insert_resource(TextCommands::new('['))
+            .add_plugins(plugin);
+
+        let (alice, mut rx) = spawn_player(&mut app, "alice", "Alice", IVec3::new(10, 20, 0));
+        spawn_player(&mut app, "bob", "Bob", IVec3::new(30, 40, 5));
+
+        // Any player can ask, so neither account names nor locations are shown.
+        app.world_mut()
+            .run_system_once(move |mut exec: TextCommandExecutor| {
+                assert!(exec.try_split_exec(alice, "[who"));
+            })
+            .unwrap();
+        app.update();
+
+        assert_eq!(sent_messages(&mut rx), vec![
+            "2 player(s) online".to_string(),
+            "Alice".to_string(),
+            "Bob".to_string(),
+        ]);
+    }
+}
@@ -1,8 +1,12 @@
+use anyhow::bail;
 use bevy::prelude::*;
 use glam::IVec3;
 use serde::{Deserialize, Serialize};
 
 use yewoh::protocol::StartingCity;
+use yewoh_server::world::entity::MapPosition;
+
+use crate::data::maps::Maps;
 
 #[derive(Debug, Clone, Default, Reflect, Serialize, Deserialize)]
 #[serde(default)]
@@ -14,12 +18,35 @@ pub struct City {
     pub position: IVec3,
 }
 
+impl City {
+    pub fn map_position(&self) -> MapPosition {
+        MapPosition {
+            position: self.position,
+            map_id: self.map_id as u8,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Reflect, Serialize, Deserialize)]
 pub struct Cities {
     pub cities: Vec<City>,
 }
 
 impl Cities {
+    /// Check that every city is on a known map.
+    pub fn validate(&self, maps: &Maps) -> anyhow::Result<()> {
+        for city in &self.cities {
+            if !maps.contains(city.map_id) {
+                bail!("city '{}' references unknown map {}", city.name, city.map_id);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn starting_position(&self, city_index: usize) -> Option<MapPosition> {
+        self.cities.get(city_index).map(City::map_position)
+    }
+
     pub fn to_starting_cities(&self) -> Vec<StartingCity> {
         self.cities.iter()
             .enumerate()
@@ -34,3 +61,36 @@ impl Cities {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::ivec3;
+
+    use super::*;
+
+    #[test]
+    fn test_starting_position() {
+        let cities: Cities = serde_yaml::from_str(r#"
+cities:
+  - name: New Haven
+    building: New Haven Bank
+    description_id: 1150168
+    map_id: 1
+    position: [3503, 2574, 14]
+  - name: Yew
+    building: The Empath Abbey
+    description_id: 1075072
+    map_id: 1
+    position: [633, 858, 0]
+"#).unwrap();
+
+        let position = cities.starting_position(1).unwrap();
+        assert_eq!(position.position, ivec3(633, 858, 0));
+        assert_eq!(position.map_id, 1);
+        assert!(cities.starting_position(2).is_none());
+
+        let starting_cities = cities.to_starting_cities();
+        assert_eq!(starting_cities[1].index, 1);
+        assert_eq!(starting_cities[1].city, "Yew");
+    }
+}
@@ -1,9 +1,11 @@
+use anyhow::bail;
 use bevy::prelude::*;
 use glam::IVec3;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 use crate::data::cities::Cities;
+use crate::data::maps::Maps;
 
 #[derive(Debug, Clone, Default, Reflect, Serialize, Deserialize)]
 pub struct Location {
@@ -18,6 +20,16 @@ pub struct Locations {
 }
 
 impl Locations {
+    /// Check that every location is on a known map.
+    pub fn validate(&self, maps: &Maps) -> anyhow::Result<()> {
+        for (name, location) in &self.locations {
+            if !maps.contains(location.map_id) {
+                bail!("location '{name}' references unknown map {}", location.map_id);
+            }
+        }
+        Ok(())
+    }
+
     pub fn add_cities(&mut self, cities: &Cities) {
         for city in &cities.cities {
             let key = format!("Cities/{}", city.name);
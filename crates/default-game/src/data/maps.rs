@@ -1,7 +1,11 @@
 use std::collections::HashMap;
+use std::fmt::Formatter;
+
+use anyhow::bail;
 use bevy::prelude::*;
 use glam::UVec2;
-use serde::{Deserialize, Serialize};
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use yewoh_server::world::map::{MapInfo, MapInfos};
 
@@ -12,26 +16,127 @@ pub struct Map {
     pub size: UVec2,
     pub season: u8,
     pub no_assets: bool,
+    pub wrap: bool,
 }
 
 #[derive(Debug, Clone, Default, Reflect, Serialize, Deserialize)]
 pub struct Maps {
+    #[serde(deserialize_with = "deserialize_unique_maps")]
     pub maps: HashMap<u8, Map>,
 }
 
+fn deserialize_unique_maps<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<u8, Map>, D::Error> {
+    struct UniqueMapsVisitor;
+
+    impl<'de> Visitor<'de> for UniqueMapsVisitor {
+        type Value = HashMap<u8, Map>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+            formatter.write_str("a map of map IDs to maps")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+            let mut maps = HashMap::new();
+            while let Some((id, map)) = access.next_entry::<u8, Map>()? {
+                if maps.insert(id, map).is_some() {
+                    return Err(serde::de::Error::custom(format!("duplicate map ID {id}")));
+                }
+            }
+            Ok(maps)
+        }
+    }
+
+    deserializer.deserialize_map(UniqueMapsVisitor)
+}
+
 impl Maps {
-    pub fn map_infos(&self) -> MapInfos {
+    pub fn contains(&self, map_id: u32) -> bool {
+        u8::try_from(map_id).is_ok_and(|map_id| self.maps.contains_key(&map_id))
+    }
+
+    pub fn map_infos(&self) -> anyhow::Result<MapInfos> {
         let mut maps = HashMap::with_capacity(self.maps.len());
         for (key, map) in self.maps.iter() {
+            if map.size.x == 0 || map.size.y == 0 {
+                bail!("map {key} ({}) has invalid size {}", map.name, map.size);
+            }
+
             maps.insert(*key, MapInfo {
                 size: map.size,
                 season: map.season,
                 is_virtual: map.no_assets,
+                wrap: map.wrap,
             });
         }
 
-        MapInfos {
+        Ok(MapInfos {
             maps,
-        }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_map() {
+        let maps: Maps = serde_yaml::from_str(r#"
+maps:
+  0:
+    name: Felucca
+    size: [7168, 4096]
+    season: 4
+    wrap: true
+  1:
+    name: Trammel
+    size: [7168, 4096]
+  2:
+    name: Ilshenar
+    size: [2304, 1600]
+  3:
+    name: Malas
+    size: [2560, 2048]
+  4:
+    name: Tokuno
+    size: [1448, 1448]
+  5:
+    name: TerMur
+    size: [1280, 4096]
+  6:
+    name: Sosaria
+    size: [1024, 512]
+    season: 2
+    no_assets: true
+    wrap: true
+"#).unwrap();
+
+        let map_infos = maps.map_infos().unwrap();
+        assert_eq!(map_infos.maps.len(), 7);
+        let custom = &map_infos.maps[&6];
+        assert_eq!(custom.size, UVec2::new(1024, 512));
+        assert_eq!(custom.season, 2);
+        assert!(custom.is_virtual);
+        assert!(custom.wrap);
+        assert!(!map_infos.maps[&1].wrap);
+    }
+
+    #[test]
+    fn test_invalid_maps() {
+        let result = serde_yaml::from_str::<Maps>(r#"
+maps:
+  7:
+    size: [16, 16]
+  7:
+    size: [32, 32]
+"#);
+        assert!(result.is_err());
+
+        let maps: Maps = serde_yaml::from_str(r#"
+maps:
+  7:
+    size: [0, 16]
+"#).unwrap();
+        assert!(maps.map_infos().is_err());
     }
 }
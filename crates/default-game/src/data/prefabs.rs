@@ -24,6 +24,26 @@ impl PrefabLibrary {
         self.prefabs.get(prefab_name)
     }
 
+    pub fn contains(&self, prefab_name: &str) -> bool {
+        self.prefabs.contains_key(prefab_name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item=&str> + '_ {
+        self.prefabs.keys().map(String::as_str)
+    }
+
+    /// Find all prefab names starting with `prefix`, ignoring case.
+    ///
+    /// Results are sorted, with exact matches first.
+    pub fn find(&self, prefix: &str) -> Vec<&str> {
+        let prefix = prefix.to_lowercase();
+        let mut matches = self.names()
+            .filter(|name| name.to_lowercase().starts_with(&prefix))
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|name| (name.len() != prefix.len(), *name));
+        matches
+    }
+
     pub fn insert(&mut self, prefab_name: String, fabricator: Fabricator) {
         self.prefabs.insert(prefab_name, fabricator);
     }
@@ -117,6 +137,85 @@ pub fn fabricate_prefab(
     }
 }
 
+/// A component which refers to other prefabs by name.
+pub trait PrefabReferences {
+    fn prefab_references(&self) -> Vec<&str>;
+}
+
+#[derive(Clone, Default, Resource)]
+pub struct PrefabReferenceExtractors(Vec<fn(&World) -> Vec<String>>);
+
+fn extract_prefab_references<C: Component + PrefabReferences>(world: &World) -> Vec<String> {
+    world.iter_entities()
+        .filter_map(|entity| entity.get::<C>())
+        .flat_map(|component| component.prefab_references())
+        .map(str::to_string)
+        .collect()
+}
+
+pub trait PrefabReferencesAppExt {
+    fn register_prefab_references<C: Component + PrefabReferences>(&mut self) -> &mut Self;
+}
+
+impl PrefabReferencesAppExt for App {
+    fn register_prefab_references<C: Component + PrefabReferences>(&mut self) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(PrefabReferenceExtractors::default)
+            .0.push(extract_prefab_references::<C>);
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DanglingPrefabReference {
+    pub prefab_name: String,
+    pub reference: String,
+}
+
+/// Check that every prefab referenced by a prefab in the library exists.
+///
+/// Each prefab is fabricated into a scratch world so that references from any registered
+/// component can be found. Prefabs which can't be fabricated without parameters are skipped.
+pub fn find_dangling_references(world: &World, library: &PrefabLibrary) -> Vec<DanglingPrefabReference> {
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let extractors = world.get_resource::<PrefabReferenceExtractors>()
+        .cloned()
+        .unwrap_or_default();
+
+    let mut prefab_names = library.names().collect::<Vec<_>>();
+    prefab_names.sort();
+
+    let mut dangling = Vec::new();
+    for prefab_name in prefab_names {
+        let mut scratch = World::new();
+        scratch.insert_resource(type_registry.clone());
+        scratch.insert_resource(library.clone());
+        let entity = scratch.spawn_empty().id();
+        let fabricator = library.get(prefab_name).unwrap();
+        if let Err(err) = fabricator.fabricate(empty_reflect().as_ref(), &mut scratch, entity) {
+            debug!("skipping reference validation for '{prefab_name}': {err}");
+            continue;
+        }
+
+        let mut references = extractors.0.iter()
+            .flat_map(|extract| extract(&scratch))
+            .collect::<Vec<_>>();
+        references.sort();
+        references.dedup();
+
+        for reference in references {
+            if !library.contains(&reference) {
+                dangling.push(DanglingPrefabReference {
+                    prefab_name: prefab_name.to_string(),
+                    reference,
+                });
+            }
+        }
+    }
+
+    dangling
+}
+
 pub trait PrefabLibraryWorldExt {
     type EntityMut<'a> where Self: 'a;
 
@@ -228,3 +327,65 @@ impl PrefabLibraryEntityExt for EntityWorldMut<'_> {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(names: &[&str]) -> PrefabLibrary {
+        let mut library = PrefabLibrary::default();
+        for name in names {
+            library.insert(name.to_string(), Fabricator {
+                parameters: HashMap::new(),
+                factory: Arc::new(|_, _, _| Ok(Fabricated::default())),
+            });
+        }
+        library
+    }
+
+    #[test]
+    fn test_names() {
+        let library = library(&["rat", "gold", "backpack"]);
+        let mut names = library.names().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["backpack", "gold", "rat"]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let library = library(&["rat"]);
+        assert!(library.contains("rat"));
+        assert!(!library.contains("ra"));
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let mut app = App::new();
+        app.register_prefab_references::<PrefabInstance>();
+
+        let mut library = library(&["gold"]);
+        let reference = |name: &'static str| Fabricator {
+            parameters: HashMap::new(),
+            factory: Arc::new(move |entity, _, world| {
+                world.entity_mut(entity).insert(PrefabInstance { prefab_name: name.to_string() });
+                Ok(Fabricated::default())
+            }),
+        };
+        library.insert("rat_loot".to_string(), reference("gold"));
+        library.insert("bat_loot".to_string(), reference("silver"));
+
+        let dangling = find_dangling_references(app.world(), &library);
+        assert_eq!(dangling, vec![DanglingPrefabReference {
+            prefab_name: "bat_loot".to_string(),
+            reference: "silver".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn test_find() {
+        let library = library(&["rat_loot", "rat", "Rat_butchering", "gold"]);
+        assert_eq!(library.find("rat"), vec!["rat", "Rat_butchering", "rat_loot"]);
+        assert_eq!(library.find("G"), vec!["gold"]);
+        assert!(library.find("door").is_empty());
+    }
+}
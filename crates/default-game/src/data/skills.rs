@@ -2,6 +2,9 @@ use std::collections::HashMap;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::characters::skills::Stat;
+use crate::rates::ServerRates;
+
 #[derive(Debug, Clone, Reflect, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Skill {
@@ -32,6 +35,22 @@ impl Default for Skill {
     }
 }
 
+impl Skill {
+    /// How quickly this skill is gained, after applying the shard's rates.
+    pub fn gain_scale(&self, rates: &ServerRates) -> f32 {
+        rates.scale_skill_gain(self.gain_scale)
+    }
+
+    /// How strongly using this skill trains a stat, usually summing to 1 across all stats.
+    pub fn stat_gain(&self, stat: Stat) -> f32 {
+        match stat {
+            Stat::Str => self.str_gain,
+            Stat::Dex => self.dex_gain,
+            Stat::Int => self.int_gain,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Reflect, Serialize, Deserialize)]
 pub struct Skills {
     pub skills: HashMap<u8, Skill>,
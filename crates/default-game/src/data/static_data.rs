@@ -1,11 +1,18 @@
 use std::path::{Path, PathBuf};
+
+use anyhow::Context;
 use bevy::prelude::*;
+use serde::de::DeserializeOwned;
 use tokio::fs;
+use yewoh_server::world::account::ServerFeatures;
 
+use crate::characters::player::StartingBackpack;
 use crate::data::cities::Cities;
 use crate::data::locations::Locations;
 use crate::data::maps::Maps;
 use crate::data::skills::Skills;
+use crate::motd::Motd;
+use crate::rates::ServerRates;
 
 #[derive(Debug, Clone, Reflect, Resource)]
 #[reflect(Resource)]
@@ -19,22 +26,199 @@ pub struct StaticData {
     pub locations: Locations,
 }
 
-pub async fn load_from_directory(data_path: &Path) -> anyhow::Result<StaticData> {
-    let cities = serde_yaml::from_slice(&fs::read(data_path.join("cities.yaml")).await?)?;
-    let maps = serde_yaml::from_slice(&fs::read(data_path.join("maps.yaml")).await?)?;
-    let skills = serde_yaml::from_slice(&fs::read(data_path.join("skills.yaml")).await?)?;
-    let mut locations = serde_yaml::from_slice::<Locations>(&fs::read(data_path.join("locations.yaml")).await?)?;
-    locations.add_cities(&cities);
-    locations.sort();
-    Ok(StaticData {
-        cities,
-        maps,
-        skills,
-        locations,
-    })
+pub const STATIC_DATA_FILES: [&str; 4] = ["cities.yaml", "maps.yaml", "skills.yaml", "locations.yaml"];
+
+impl StaticData {
+    /// Parse and validate static data from the contents of each of [`STATIC_DATA_FILES`], in order.
+    pub fn from_files([cities, maps, skills, locations]: [&[u8]; 4]) -> anyhow::Result<StaticData> {
+        let [cities_file, maps_file, skills_file, locations_file] = STATIC_DATA_FILES;
+        let cities = serde_yaml::from_slice::<Cities>(cities)
+            .with_context(|| format!("parsing {cities_file}"))?;
+        let maps = serde_yaml::from_slice(maps)
+            .with_context(|| format!("parsing {maps_file}"))?;
+        let skills = serde_yaml::from_slice(skills)
+            .with_context(|| format!("parsing {skills_file}"))?;
+        let mut locations = serde_yaml::from_slice::<Locations>(locations)
+            .with_context(|| format!("parsing {locations_file}"))?;
+        locations.validate(&maps)
+            .with_context(|| format!("validating {locations_file}"))?;
+        cities.validate(&maps)
+            .with_context(|| format!("validating {cities_file}"))?;
+        locations.add_cities(&cities);
+        locations.sort();
+
+        let static_data = StaticData {
+            cities,
+            maps,
+            skills,
+            locations,
+        };
+        static_data.validate()?;
+        Ok(static_data)
+    }
+
+    /// Check that the data is usable before it replaces any live data.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.maps.map_infos()
+            .with_context(|| format!("validating {}", STATIC_DATA_FILES[1]))?;
+        Ok(())
+    }
+}
+
+pub const OPTIONAL_DATA_FILES: [&str; 4] = ["motd.yaml", "rates.yaml", "starting_backpack.yaml", "features.yaml"];
+
+fn parse_optional<T: DeserializeOwned>(name: &str, contents: Option<&[u8]>) -> anyhow::Result<Option<T>> {
+    contents
+        .map(|contents| serde_yaml::from_slice(contents).with_context(|| format!("parsing {name}")))
+        .transpose()
+}
+
+/// Everything loaded from the data directory, which is replaced as a whole on reload.
+#[derive(Debug, Clone)]
+pub struct DataFiles {
+    pub static_data: StaticData,
+    pub motd: Option<Motd>,
+    pub rates: Option<ServerRates>,
+    pub starting_backpack: Option<StartingBackpack>,
+    pub features: Option<ServerFeatures>,
+}
+
+impl DataFiles {
+    /// Parse the contents of each of [`STATIC_DATA_FILES`] and, where present, [`OPTIONAL_DATA_FILES`].
+    pub fn from_files(
+        required: [&[u8]; 4],
+        [motd, rates, starting_backpack, features]: [Option<&[u8]>; 4],
+    ) -> anyhow::Result<DataFiles> {
+        let [motd_file, rates_file, starting_backpack_file, features_file] = OPTIONAL_DATA_FILES;
+        Ok(DataFiles {
+            static_data: StaticData::from_files(required)?,
+            motd: parse_optional(motd_file, motd)?,
+            rates: parse_optional(rates_file, rates)?,
+            starting_backpack: parse_optional(starting_backpack_file, starting_backpack)?,
+            features: parse_optional(features_file, features)?,
+        })
+    }
+
+    /// Replace the live resources, falling back to the defaults for missing optional files.
+    pub fn insert_into(self, world: &mut World) {
+        world.insert_resource(self.static_data);
+        world.insert_resource(self.rates.unwrap_or_default());
+        world.insert_resource(self.starting_backpack.unwrap_or_default());
+        world.insert_resource(self.features.unwrap_or_default());
+        match self.motd {
+            Some(motd) => world.insert_resource(motd),
+            None => {
+                world.remove_resource::<Motd>();
+            }
+        }
+    }
+}
+
+pub async fn load_from_directory(data_path: &Path) -> anyhow::Result<DataFiles> {
+    let mut required = Vec::with_capacity(STATIC_DATA_FILES.len());
+    for name in STATIC_DATA_FILES {
+        let path = data_path.join(name);
+        required.push(fs::read(&path).await
+            .with_context(|| format!("reading {}", path.display()))?);
+    }
+
+    let mut optional = Vec::with_capacity(OPTIONAL_DATA_FILES.len());
+    for name in OPTIONAL_DATA_FILES {
+        let path = data_path.join(name);
+        optional.push(if fs::try_exists(&path).await? {
+            Some(fs::read(&path).await
+                .with_context(|| format!("reading {}", path.display()))?)
+        } else {
+            None
+        });
+    }
+
+    DataFiles::from_files(
+        std::array::from_fn(|i| required[i].as_slice()),
+        std::array::from_fn(|i| optional[i].as_deref()))
+}
+
+/// Load the data directory without an async runtime, for reloading from within systems.
+pub fn load_from_directory_blocking(data_path: &Path) -> anyhow::Result<DataFiles> {
+    let required = STATIC_DATA_FILES.iter()
+        .map(|name| {
+            let path = data_path.join(name);
+            std::fs::read(&path).with_context(|| format!("reading {}", path.display()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let optional = OPTIONAL_DATA_FILES.iter()
+        .map(|name| {
+            let path = data_path.join(name);
+            if !path.try_exists()? {
+                return Ok(None);
+            }
+            std::fs::read(&path)
+                .map(Some)
+                .with_context(|| format!("reading {}", path.display()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    DataFiles::from_files(
+        std::array::from_fn(|i| required[i].as_slice()),
+        std::array::from_fn(|i| optional[i].as_deref()))
 }
 
 pub fn plugin(app: &mut App) {
     app
         .register_type::<DataPath>();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CITIES: &str = "
+cities:
+  - name: Britain
+    map_id: 1
+    position: [1, 2, 3]
+";
+    const MAPS: &str = "
+maps:
+  1:
+    name: Trammel
+    size: [7168, 4096]
+";
+    const SKILLS: &str = "skills: {}";
+    const LOCATIONS: &str = "
+Dungeons/Despise:
+  map_id: 1
+  position: [1, 2, 3]
+";
+
+    fn load(cities: &str, locations: &str) -> anyhow::Result<StaticData> {
+        StaticData::from_files([cities, MAPS, SKILLS, locations].map(str::as_bytes))
+    }
+
+    #[test]
+    fn test_load() {
+        let static_data = load(CITIES, LOCATIONS).unwrap();
+        assert_eq!(static_data.locations.locations.len(), 2);
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let err = load(CITIES, "Dungeons/Despise:\n  map_id: 1\n").unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("locations.yaml"), "{message}");
+        assert!(message.contains("position"), "{message}");
+    }
+
+    #[test]
+    fn test_bad_map_reference() {
+        let err = load(&CITIES.replace("map_id: 1", "map_id: 9"), LOCATIONS).unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("cities.yaml"), "{message}");
+        assert!(message.contains("Britain") && message.contains("map 9"), "{message}");
+
+        let err = load(CITIES, &LOCATIONS.replace("map_id: 1", "map_id: 9")).unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("locations.yaml"), "{message}");
+        assert!(message.contains("Dungeons/Despise"), "{message}");
+    }
+}
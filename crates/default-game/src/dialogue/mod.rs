@@ -0,0 +1,312 @@
+use bevy::prelude::*;
+use yewoh::protocol::GumpLayout;
+use yewoh_server::gump_builder::{GumpBuilder, GumpRect, GumpRectLayout, GumpText};
+use yewoh_server::world::characters::CharacterName;
+use yewoh_server::world::gump::{Gump, GumpClient};
+
+use crate::DefaultGameSet;
+use crate::entities::interactions::{DoubleClickAppExt, OnEntityDoubleClick};
+use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
+use crate::gumps::{OnCloseGump, RESIZABLE_PAPER_3};
+use crate::quests::{OnStartQuest, QuestGiver};
+
+pub const DIALOGUE_GUMP_ID: u32 = 0x444c4f47;
+
+/// An effect which happens when a dialogue node is reached.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub enum DialogueAction {
+    /// Offer the speaker's [`QuestGiver`] quest.
+    #[default]
+    StartQuest,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct DialogueResponse {
+    pub text: String,
+    /// The node to move to, or `None` to end the conversation.
+    #[reflect(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct DialogueNode {
+    pub id: String,
+    pub text: String,
+    #[reflect(default)]
+    pub responses: Vec<DialogueResponse>,
+    #[reflect(default)]
+    pub action: Option<DialogueAction>,
+}
+
+/// A conversation tree, usually authored on an NPC prefab.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct Dialogue {
+    pub start: String,
+    pub nodes: Vec<DialogueNode>,
+}
+
+impl Dialogue {
+    pub fn node(&self, id: &str) -> Option<&DialogueNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct OnDialogueAction {
+    pub speaker: Entity,
+    pub character: Entity,
+    pub action: DialogueAction,
+}
+
+/// A dialogue gump showing `node` of the `speaker`'s [`Dialogue`].
+#[derive(Debug, Clone, Component)]
+pub struct DialogueGump {
+    pub speaker: Entity,
+    pub character: Entity,
+    pub node: String,
+}
+
+impl DialogueGump {
+    pub fn render(&self, name: &str, node: &DialogueNode) -> GumpLayout {
+        let size = IVec2::new(400, 300);
+        let row = 20;
+
+        let mut text = GumpText::new();
+        let mut builder = GumpBuilder::new();
+        let mut layout = GumpRectLayout::new(&mut builder, &mut text, GumpRect::from_zero(size))
+            .background(|builder| builder.image_sliced(RESIZABLE_PAPER_3))
+            .with_padding(16)
+            .into_vbox();
+
+        layout
+            .allocate(row, |builder| builder
+                .html(format!("<center>{name}</center>")))
+            .gap(row / 2)
+            .allocate(row * 6, |builder| builder
+                .html_ex(node.text.as_str(), false, true))
+            .gap(row / 2);
+
+        for (index, response) in node.responses.iter().enumerate() {
+            layout.allocate(row, |builder| builder
+                .background(|builder| builder
+                    .html(response.text.as_str()))
+                .right(16)
+                .close_button(0x15e1, 0x15e5, index + 1));
+        }
+
+        builder.into_layout(text)
+    }
+}
+
+fn enter_node(
+    dialogue_gump: &DialogueGump,
+    gump: &mut Gump,
+    dialogue: &Dialogue,
+    name: &str,
+    actions: &mut EventWriter<OnDialogueAction>,
+) -> bool {
+    let Some(node) = dialogue.node(&dialogue_gump.node) else {
+        warn!("missing dialogue node {}", dialogue_gump.node);
+        return false;
+    };
+
+    gump.set_layout(dialogue_gump.render(name, node));
+    if let Some(action) = &node.action {
+        actions.send(OnDialogueAction {
+            speaker: dialogue_gump.speaker,
+            character: dialogue_gump.character,
+            action: action.clone(),
+        });
+    }
+    true
+}
+
+pub fn open_dialogue(
+    In(event): In<OnEntityDoubleClick>,
+    speakers: Query<(&Dialogue, Option<&CharacterName>)>,
+    mut commands: Commands,
+    mut actions: EventWriter<OnDialogueAction>,
+) {
+    let Ok((dialogue, name)) = speakers.get(event.target) else {
+        return;
+    };
+
+    let dialogue_gump = DialogueGump {
+        speaker: event.target,
+        character: event.character,
+        node: dialogue.start.clone(),
+    };
+    let mut gump = Gump::empty(DIALOGUE_GUMP_ID);
+    let name = name.map_or("", |name| name.as_str());
+    if enter_node(&dialogue_gump, &mut gump, dialogue, name, &mut actions) {
+        commands.spawn((
+            gump,
+            GumpClient(event.client_entity),
+            dialogue_gump,
+        ));
+    }
+}
+
+pub fn handle_dialogue_gump(
+    mut commands: Commands,
+    speakers: Query<(&Dialogue, Option<&CharacterName>)>,
+    mut gumps: Query<(&mut DialogueGump, &mut Gump)>,
+    mut events: EntityEventReader<OnCloseGump, DialogueGump>,
+    mut actions: EventWriter<OnDialogueAction>,
+) {
+    for event in events.read() {
+        let Ok((mut dialogue_gump, mut gump)) = gumps.get_mut(event.gump) else {
+            continue;
+        };
+        let Ok((dialogue, name)) = speakers.get(dialogue_gump.speaker) else {
+            commands.entity(event.gump).despawn_recursive();
+            continue;
+        };
+
+        let next = (event.button_id as usize).checked_sub(1)
+            .and_then(|index| dialogue.node(&dialogue_gump.node)?.responses.get(index))
+            .and_then(|response| response.next.clone());
+        let Some(next) = next else {
+            commands.entity(event.gump).despawn_recursive();
+            continue;
+        };
+
+        dialogue_gump.node = next;
+        let name = name.map_or("", |name| name.as_str());
+        if !enter_node(&dialogue_gump, &mut gump, dialogue, name, &mut actions) {
+            commands.entity(event.gump).despawn_recursive();
+        }
+    }
+}
+
+pub fn start_dialogue_quests(
+    quest_givers: Query<&QuestGiver>,
+    mut events: EventReader<OnDialogueAction>,
+    mut quest_events: EventWriter<OnStartQuest>,
+) {
+    for event in events.read() {
+        match event.action {
+            DialogueAction::StartQuest => {
+                let Ok(quest_giver) = quest_givers.get(event.speaker) else {
+                    continue;
+                };
+
+                quest_events.send(OnStartQuest {
+                    character: event.character,
+                    quest: quest_giver.quest.clone(),
+                });
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<DialogueAction>()
+        .register_type::<DialogueResponse>()
+        .register_type::<DialogueNode>()
+        .register_type::<Dialogue>()
+        .add_plugins((
+            EntityEventRoutePlugin::<OnCloseGump, DialogueGump>::default(),
+        ))
+        .add_double_click_handler::<Dialogue, _>(open_dialogue)
+        .add_event::<OnDialogueAction>()
+        .add_systems(First, (
+            handle_dialogue_gump.in_set(DefaultGameSet::HandleEvents),
+        ))
+        .add_systems(Update, (
+            start_dialogue_quests,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::SmallVec;
+
+    use crate::entity_events::EntityEventPlugin;
+    use crate::quests::Quest;
+
+    use super::*;
+
+    fn node(id: &str, responses: &[(&str, Option<&str>)], action: Option<DialogueAction>) -> DialogueNode {
+        DialogueNode {
+            id: id.into(),
+            text: format!("{id} text"),
+            responses: responses.iter()
+                .map(|(text, next)| DialogueResponse {
+                    text: text.to_string(),
+                    next: next.map(str::to_string),
+                })
+                .collect(),
+            action,
+        }
+    }
+
+    #[test]
+    fn test_dialogue_response() {
+        let mut app = App::new();
+        app
+            .configure_sets(First, (
+                DefaultGameSet::DispatchEvents,
+                DefaultGameSet::HandleEvents,
+            ).chain())
+            .add_event::<OnStartQuest>()
+            .add_plugins((
+                EntityEventPlugin::<OnCloseGump>::default(),
+                plugin,
+            ));
+
+        let dialogue = Dialogue {
+            start: "greeting".into(),
+            nodes: vec![
+                node("greeting", &[("Rumours?", Some("rumours")), ("Work?", Some("work"))], None),
+                node("rumours", &[("Farewell", None)], None),
+                node("work", &[("Farewell", None)], Some(DialogueAction::StartQuest)),
+            ],
+        };
+        let quest = Quest { id: "rat_hunt".into(), ..default() };
+        let speaker = app.world_mut()
+            .spawn((dialogue, QuestGiver { quest }))
+            .id();
+        let character = app.world_mut().spawn_empty().id();
+        let gump = app.world_mut()
+            .spawn((
+                Gump::empty(DIALOGUE_GUMP_ID),
+                GumpClient(Entity::PLACEHOLDER),
+                DialogueGump { speaker, character, node: "greeting".into() },
+            ))
+            .id();
+
+        let press = |app: &mut App, button_id: u32| {
+            app.world_mut().send_event(OnCloseGump {
+                client_entity: Entity::PLACEHOLDER,
+                gump,
+                button_id,
+                on_switches: SmallVec::new(),
+                text_fields: Vec::new(),
+            });
+            app.update();
+        };
+        let quests_started = |app: &App| app.world()
+            .resource::<Events<OnStartQuest>>()
+            .iter_current_update_events()
+            .filter(|event| event.character == character && event.quest.id == "rat_hunt")
+            .count();
+
+        press(&mut app, 1);
+        assert_eq!(app.world().get::<DialogueGump>(gump).unwrap().node, "rumours");
+        assert_eq!(quests_started(&app), 0);
+
+        app.world_mut().get_mut::<DialogueGump>(gump).unwrap().node = "greeting".into();
+        press(&mut app, 2);
+        assert_eq!(app.world().get::<DialogueGump>(gump).unwrap().node, "work");
+        assert_eq!(quests_started(&app), 1);
+
+        press(&mut app, 1);
+        assert!(app.world().get_entity(gump).is_err());
+    }
+}
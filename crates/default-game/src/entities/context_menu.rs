@@ -1,3 +1,6 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
 use bevy::prelude::*;
 use yewoh::protocol;
 use yewoh::protocol::{ContextMenu, ContextMenuFlags, ExtendedCommand};
@@ -10,6 +13,37 @@ use crate::entity_events::{EntityEvent, EntityEventPlugin, EntityEventReader, En
 use crate::DefaultGameSet;
 use crate::entities::interactions::OnEntitySingleClick;
 
+pub type ContextMenuCallback = Arc<dyn Fn(&mut Commands, &OnEntityContextMenuAction) + Send + Sync>;
+
+/// Handler which is run when a client picks the context menu entry it is attached to.
+#[derive(Clone)]
+pub struct ContextMenuAction(ContextMenuCallback);
+
+impl ContextMenuAction {
+    pub fn new(f: impl Fn(&mut Commands, &OnEntityContextMenuAction) + Send + Sync + 'static) -> ContextMenuAction {
+        ContextMenuAction(Arc::new(f))
+    }
+
+    pub fn event<E: Event + Clone>(event: E) -> ContextMenuAction {
+        ContextMenuAction::new(move |commands, _| {
+            let event = event.clone();
+            commands.queue(move |world: &mut World| {
+                world.send_event(event);
+            });
+        })
+    }
+
+    pub fn run(&self, commands: &mut Commands, event: &OnEntityContextMenuAction) {
+        (self.0)(commands, event)
+    }
+}
+
+impl Debug for ContextMenuAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ContextMenuAction").finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug, Clone, Default, Reflect)]
 pub struct ContextMenuEntry {
     pub id: u16,
@@ -19,9 +53,23 @@ pub struct ContextMenuEntry {
     pub highlighted: bool,
     pub arrow: bool,
     pub priority: u32,
+    #[reflect(ignore)]
+    pub action: Option<ContextMenuAction>,
 }
 
 impl ContextMenuEntry {
+    pub fn with_action(
+        mut self, f: impl Fn(&mut Commands, &OnEntityContextMenuAction) + Send + Sync + 'static,
+    ) -> Self {
+        self.action = Some(ContextMenuAction::new(f));
+        self
+    }
+
+    pub fn with_event<E: Event + Clone>(mut self, event: E) -> Self {
+        self.action = Some(ContextMenuAction::event(event));
+        self
+    }
+
     pub fn flags(&self) -> ContextMenuFlags {
         let mut flags = ContextMenuFlags::empty();
 
@@ -75,6 +123,15 @@ impl EntityEvent for OnEntityContextMenuAction {
 #[reflect(Component)]
 pub struct SingleClickContextMenu;
 
+/// The context menu most recently sent to a client.
+///
+/// Responses are only accepted for entries in this menu.
+#[derive(Clone, Debug, Component)]
+pub struct OpenContextMenu {
+    pub target: Entity,
+    pub entries: Vec<ContextMenuEntry>,
+}
+
 pub fn on_client_context_menu_request(
     mut events: EventReader<OnClientContextMenuRequest>,
     mut out_events: EventWriter<OnEntityContextMenuRequest>,
@@ -89,15 +146,12 @@ pub fn on_client_context_menu_request(
 }
 
 pub fn finish_context_menu(
+    mut commands: Commands,
     clients: Query<&NetClient>,
     net_objects: Query<&NetId>,
     mut events: EntityEventReader<OnEntityContextMenuRequest, ()>,
 ) {
     for event in events.read() {
-        let Ok(client) = clients.get(event.client_entity) else {
-            continue;
-        };
-
         let Ok(net_id) = net_objects.get(event.target) else {
             continue;
         };
@@ -107,32 +161,63 @@ pub fn finish_context_menu(
         }
 
         event.entries.sort_by_key(|l| (l.priority, l.id, l.text_id));
-        let entries = event.entries.drain(..)
-            .map(|l| protocol::ContextMenuEntry {
-                id: l.id,
-                text_id: l.text_id,
-                hue: l.hue,
-                flags: l.flags(),
-            })
-            .collect();
-
-        client.send_packet(ExtendedCommand::ContextMenu(ContextMenu {
-            target_id: net_id.id,
-            entries,
-        }));
+        let entries = event.entries.drain(..).collect::<Vec<_>>();
+
+        if let Ok(client) = clients.get(event.client_entity) {
+            client.send_packet(ExtendedCommand::ContextMenu(ContextMenu {
+                target_id: net_id.id,
+                entries: entries.iter()
+                    .map(|l| protocol::ContextMenuEntry {
+                        id: l.id,
+                        text_id: l.text_id,
+                        hue: l.hue,
+                        flags: l.flags(),
+                    })
+                    .collect(),
+            }));
+        }
+
+        if let Some(mut client_commands) = commands.get_entity(event.client_entity) {
+            client_commands.insert(OpenContextMenu {
+                target: event.target,
+                entries,
+            });
+        }
     }
 }
 
 pub fn on_client_context_menu_action(
+    mut commands: Commands,
+    open_menus: Query<&OpenContextMenu>,
     mut events: EventReader<OnClientContextMenuAction>,
     mut out_events: EventWriter<OnEntityContextMenuAction>,
 ) {
     for request in events.read() {
-        out_events.send(OnEntityContextMenuAction {
+        let Ok(menu) = open_menus.get(request.client_entity) else {
+            continue;
+        };
+
+        if menu.target != request.target {
+            continue;
+        }
+
+        let Some(entry) = menu.entries.iter()
+            .find(|e| e.id == request.action_id && !e.disabled) else {
+            continue;
+        };
+
+        let event = OnEntityContextMenuAction {
             client_entity: request.client_entity,
             target: request.target,
             id: request.action_id,
-        });
+        };
+
+        if let Some(action) = &entry.action {
+            action.run(&mut commands, &event);
+        }
+
+        commands.entity(request.client_entity).remove::<OpenContextMenu>();
+        out_events.send(event);
     }
 }
 
@@ -166,3 +251,78 @@ pub fn plugin(app: &mut App) {
             finish_context_menu.in_set(DefaultGameSet::FinishEvents),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use yewoh::EntityId;
+
+    use super::*;
+
+    #[derive(Clone, Debug, Default, Component)]
+    struct TestMenu;
+
+    #[derive(Clone, Debug, Default, Resource)]
+    struct FiredEntries(Vec<u16>);
+
+    fn record_entry(commands: &mut Commands, event: &OnEntityContextMenuAction) {
+        let id = event.id;
+        commands.queue(move |world: &mut World| {
+            world.resource_mut::<FiredEntries>().0.push(id);
+        });
+    }
+
+    fn test_context_menu(
+        mut events: EntityEventReader<OnEntityContextMenuRequest, TestMenu>,
+    ) {
+        for event in events.read() {
+            event.entries.push(ContextMenuEntry {
+                id: 1,
+                text_id: 3000001,
+                ..default()
+            }.with_action(record_entry));
+            event.entries.push(ContextMenuEntry {
+                id: 2,
+                text_id: 3000002,
+                ..default()
+            }.with_action(record_entry));
+        }
+    }
+
+    #[test]
+    fn test_action_dispatch() {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientContextMenuRequest>()
+            .add_event::<OnClientContextMenuAction>()
+            .init_resource::<FiredEntries>()
+            .add_plugins((
+                EntityEventPlugin::<OnEntitySingleClick>::default(),
+                EntityEventRoutePlugin::<OnEntityContextMenuRequest, TestMenu>::default(),
+                plugin,
+            ))
+            .configure_sets(First, (
+                DefaultGameSet::DispatchEvents.after(ServerSet::HandlePackets),
+                DefaultGameSet::HandleEvents,
+                DefaultGameSet::FinishEvents,
+            ).chain())
+            .add_systems(First, test_context_menu.in_set(DefaultGameSet::HandleEvents));
+
+        let client_entity = app.world_mut().spawn_empty().id();
+        let target = app.world_mut()
+            .spawn((TestMenu, NetId { id: EntityId::from_u32(0x40000001) }))
+            .id();
+
+        app.world_mut().send_event(OnClientContextMenuRequest { client_entity, target });
+        app.update();
+
+        let menu = app.world().get::<OpenContextMenu>(client_entity).unwrap();
+        assert_eq!(menu.target, target);
+        assert_eq!(menu.entries.len(), 2);
+
+        app.world_mut().send_event(OnClientContextMenuAction { client_entity, target, action_id: 2 });
+        app.update();
+
+        assert_eq!(app.world().resource::<FiredEntries>().0, vec![2]);
+        assert!(app.world().get::<OpenContextMenu>(client_entity).is_none());
+    }
+}
@@ -1,9 +1,14 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemId;
 use bevy::prelude::*;
 use yewoh_server::world::connection::Possessing;
+use yewoh_server::world::entity::Frozen;
 use yewoh_server::world::input::{OnClientDoubleClick, OnClientSingleClick};
 use yewoh_server::world::ServerSet;
 
-use crate::entity_events::{EntityEvent, EntityEventPlugin};
+use crate::DefaultGameSet;
+use crate::entity_events::{EntityEvent, EntityEventPlugin, EntityEventReader, EntityEventRoutePlugin};
 
 #[derive(Clone, Debug, Event)]
 pub struct OnEntitySingleClick {
@@ -22,6 +27,7 @@ pub struct OnEntityDoubleClick {
     pub client_entity: Entity,
     pub character: Entity,
     pub target: Entity,
+    pub paperdoll: bool,
 }
 
 impl EntityEvent for OnEntityDoubleClick {
@@ -46,20 +52,79 @@ pub fn on_client_double_click(
     mut events: EventReader<OnClientDoubleClick>,
     mut out_events: EventWriter<OnEntityDoubleClick>,
     possessing: Query<&Possessing>,
+    frozen: Query<&Frozen>,
 ) {
     for request in events.read() {
         let Ok(possessing) = possessing.get(request.client_entity) else {
             continue;
         };
 
+        if frozen.get(possessing.entity).is_ok_and(|f| **f) {
+            continue;
+        }
+
         out_events.send(OnEntityDoubleClick {
             client_entity: request.client_entity,
             character: possessing.entity,
             target: request.target,
+            paperdoll: request.paperdoll,
         });
     }
 }
 
+#[derive(Resource)]
+pub struct DoubleClickHandlers<B: Bundle> {
+    handlers: Vec<SystemId<In<OnEntityDoubleClick>>>,
+    _marker: PhantomData<B>,
+}
+
+impl<B: Bundle> Default for DoubleClickHandlers<B> {
+    fn default() -> Self {
+        DoubleClickHandlers {
+            handlers: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub fn dispatch_double_click<B: Bundle>(
+    mut commands: Commands,
+    handlers: Res<DoubleClickHandlers<B>>,
+    mut events: EntityEventReader<OnEntityDoubleClick, B>,
+) {
+    for event in events.read() {
+        for handler in &handlers.handlers {
+            commands.run_system_with_input(*handler, event.clone());
+        }
+    }
+}
+
+pub trait DoubleClickAppExt {
+    /// Run `handler` whenever an entity matching `B` is double-clicked.
+    fn add_double_click_handler<B: Bundle, M>(
+        &mut self, handler: impl IntoSystem<In<OnEntityDoubleClick>, (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl DoubleClickAppExt for App {
+    fn add_double_click_handler<B: Bundle, M>(
+        &mut self, handler: impl IntoSystem<In<OnEntityDoubleClick>, (), M> + 'static,
+    ) -> &mut Self {
+        if !self.world().contains_resource::<DoubleClickHandlers<B>>() {
+            self
+                .init_resource::<DoubleClickHandlers<B>>()
+                .add_plugins(EntityEventRoutePlugin::<OnEntityDoubleClick, B>::default())
+                .add_systems(First, (
+                    dispatch_double_click::<B>.in_set(DefaultGameSet::HandleEvents),
+                ));
+        }
+
+        let handler = self.world_mut().register_system(handler);
+        self.world_mut().resource_mut::<DoubleClickHandlers<B>>().handlers.push(handler);
+        self
+    }
+}
+
 pub fn plugin(app: &mut App) {
     app
         .add_plugins((
@@ -73,3 +138,75 @@ pub fn plugin(app: &mut App) {
             ).in_set(ServerSet::HandlePackets),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, Default, Component)]
+    struct Apple;
+
+    #[derive(Clone, Debug, Default, Component)]
+    struct Pear;
+
+    #[derive(Clone, Debug, Default, Resource)]
+    struct Eaten(Vec<(&'static str, Entity)>);
+
+    fn eat_apple(In(event): In<OnEntityDoubleClick>, mut eaten: ResMut<Eaten>) {
+        eaten.0.push(("apple", event.target));
+    }
+
+    fn eat_pear(In(event): In<OnEntityDoubleClick>, mut eaten: ResMut<Eaten>) {
+        eaten.0.push(("pear", event.target));
+    }
+
+    #[test]
+    fn test_double_click_handler() {
+        let mut app = App::new();
+        app
+            .init_resource::<Eaten>()
+            .add_plugins(EntityEventPlugin::<OnEntityDoubleClick>::default())
+            .configure_sets(First, (
+                DefaultGameSet::DispatchEvents,
+                DefaultGameSet::HandleEvents,
+            ).chain())
+            .add_double_click_handler::<Apple, _>(eat_apple)
+            .add_double_click_handler::<Pear, _>(eat_pear);
+
+        let client_entity = app.world_mut().spawn_empty().id();
+        let apple = app.world_mut().spawn(Apple).id();
+        app.world_mut().spawn(Pear);
+
+        app.world_mut().send_event(OnEntityDoubleClick {
+            client_entity,
+            character: client_entity,
+            target: apple,
+            paperdoll: false,
+        });
+        app.update();
+
+        assert_eq!(app.world().resource::<Eaten>().0, vec![("apple", apple)]);
+    }
+
+    #[test]
+    fn test_frozen_double_click_ignored() {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientDoubleClick>()
+            .add_event::<OnEntityDoubleClick>()
+            .add_systems(Update, on_client_double_click);
+
+        let character = app.world_mut().spawn(Frozen(true)).id();
+        let client_entity = app.world_mut().spawn(Possessing { entity: character }).id();
+        let target = app.world_mut().spawn_empty().id();
+
+        app.world_mut().send_event(OnClientDoubleClick { client_entity, target, paperdoll: false });
+        app.update();
+        assert!(app.world().resource::<Events<OnEntityDoubleClick>>().is_empty());
+
+        app.world_mut().entity_mut(character).insert(Frozen(false));
+        app.world_mut().send_event(OnClientDoubleClick { client_entity, target, paperdoll: false });
+        app.update();
+        assert!(!app.world().resource::<Events<OnEntityDoubleClick>>().is_empty());
+    }
+}
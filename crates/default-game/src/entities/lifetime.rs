@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use bevy_fabricator::traits::{Apply, Context, ReflectApply};
+use yewoh_server::world::entity::MapPosition;
+use yewoh_server::world::sound::{OnSound, SoundKind};
+
+/// Despawns an entity once the timer finishes, i.e. for summons and field spells.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct Lifetime {
+    pub remaining: Timer,
+}
+
+impl Lifetime {
+    pub fn new(duration: Duration) -> Lifetime {
+        Lifetime {
+            remaining: Timer::new(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Plays a sound where an entity was when its [`Lifetime`] expires.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct FadeOnExpire {
+    pub sound_id: u16,
+}
+
+pub fn expire_lifetimes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entities: Query<(Entity, &mut Lifetime, Option<&FadeOnExpire>, Option<&MapPosition>)>,
+    mut sounds: EventWriter<OnSound>,
+) {
+    for (entity, mut lifetime, fade, position) in &mut entities {
+        if !lifetime.remaining.tick(time.delta()).finished() {
+            continue;
+        }
+
+        if let (Some(fade), Some(position)) = (fade, position) {
+            sounds.send(OnSound {
+                kind: SoundKind::OneShot,
+                sound_id: fade.sound_id,
+                position: *position,
+            });
+        }
+
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[derive(Clone, Default, Reflect, Deserialize)]
+#[reflect(Default, Apply, Deserialize)]
+pub struct LifetimePrefab {
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+    #[serde(default)]
+    pub fade_sound: Option<u16>,
+}
+
+impl Apply for LifetimePrefab {
+    fn apply(&self, ctx: &mut Context, entity: Entity) -> anyhow::Result<()> {
+        let mut entity_mut = ctx.world.entity_mut(entity);
+        entity_mut.insert(Lifetime::new(self.duration));
+        if let Some(sound_id) = self.fade_sound {
+            entity_mut.insert(FadeOnExpire { sound_id });
+        }
+        Ok(())
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<Lifetime>()
+        .register_type::<FadeOnExpire>()
+        .register_type::<LifetimePrefab>()
+        .add_systems(Update, (
+            expire_lifetimes,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifetime_expires() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .add_event::<OnSound>()
+            .add_systems(Update, expire_lifetimes);
+
+        let position = MapPosition { position: IVec3::new(5, 5, 0), map_id: 1 };
+        let summon = app.world_mut()
+            .spawn((Lifetime::new(Duration::from_secs(10)), FadeOnExpire { sound_id: 0x1fe }, position))
+            .id();
+        let permanent = app.world_mut().spawn(position).id();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(6));
+        app.update();
+        assert!(app.world().get_entity(summon).is_ok());
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(6));
+        app.update();
+        assert!(app.world().get_entity(summon).is_err());
+        assert!(app.world().get_entity(permanent).is_ok());
+
+        let sounds = app.world().resource::<Events<OnSound>>();
+        let sounds = sounds.iter_current_update_events().map(|s| s.sound_id).collect::<Vec<_>>();
+        assert_eq!(sounds, vec![0x1fe]);
+    }
+}
@@ -4,6 +4,8 @@ use rand::{thread_rng, RngCore};
 use serde::{Deserialize, Serialize};
 use uuid::{Bytes, Uuid};
 
+use crate::data::prefabs::{PrefabReferences, PrefabReferencesAppExt};
+
 pub mod persistence;
 
 pub mod position;
@@ -18,6 +20,10 @@ pub mod interactions;
 
 pub mod common;
 
+pub mod lifetime;
+
+pub mod names;
+
 #[derive(Debug, Clone, Copy, Default, Reflect, Component)]
 #[reflect(Component)]
 pub struct Persistent;
@@ -28,6 +34,12 @@ pub struct PrefabInstance {
     pub prefab_name: String,
 }
 
+impl PrefabReferences for PrefabInstance {
+    fn prefab_references(&self) -> Vec<&str> {
+        vec![self.prefab_name.as_str()]
+    }
+}
+
 #[derive(Debug, Clone, Component, Reflect, Serialize, Deserialize)]
 #[reflect(opaque, Component, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -60,12 +72,15 @@ impl Plugin for EntitiesPlugin {
                 context_menu::plugin,
                 interactions::plugin,
                 common::plugin,
+                lifetime::plugin,
+                names::plugin,
             ))
             .register_type::<UniqueId>()
             .register_type::<Persistent>()
             .register_type::<prefabs::Prefab>()
             .register_type::<prefabs::AtMapPosition>()
             .register_type::<prefabs::EquippedBy>()
-            .register_type::<prefabs::ContainedBy>();
+            .register_type::<prefabs::ContainedBy>()
+            .register_prefab_references::<PrefabInstance>();
     }
 }
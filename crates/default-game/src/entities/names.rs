@@ -0,0 +1,232 @@
+use anyhow::bail;
+use bevy::ecs::query::WorldQuery;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use yewoh::assets::tiles::TileData;
+use yewoh_server::world::characters::CharacterName;
+use yewoh_server::world::items::ItemGraphic;
+use yewoh_server::world::map::TileDataResource;
+
+use crate::characters::persistence::PersistName;
+use crate::entities::Persistent;
+use crate::entities::tooltips::MarkTooltipChanged;
+use crate::items::common::ItemName;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+
+pub const MAX_NAME_LENGTH: usize = 16;
+
+/// A name given to an entity during play, which takes priority over any other name.
+#[derive(Clone, Debug, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component)]
+pub struct CustomName(pub String);
+
+/// Resolve the name to show for an entity.
+///
+/// This is the custom name if there is one, then the name given by the entity's prefab,
+/// then the tile data name of its graphic.
+pub fn display_name(
+    custom_name: Option<&CustomName>,
+    prefab_name: Option<&str>,
+    graphic: Option<&ItemGraphic>,
+    tile_data: Option<&TileData>,
+) -> String {
+    if let Some(name) = custom_name {
+        return name.0.clone();
+    }
+
+    if let Some(name) = prefab_name {
+        return name.to_string();
+    }
+
+    graphic
+        .and_then(|graphic| tile_data?.items.get(**graphic as usize))
+        .map(|info| info.name.clone())
+        .unwrap_or_default()
+}
+
+/// Check a name chosen in play, returning it without surrounding whitespace.
+pub fn validate_name(name: &str) -> anyhow::Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        bail!("Names must be between 1 and {MAX_NAME_LENGTH} characters long.");
+    }
+
+    if !name.chars().all(|c| c.is_alphabetic() || matches!(c, ' ' | '\'' | '-')) {
+        bail!("Names may only contain letters, spaces, apostrophes and hyphens.");
+    }
+
+    Ok(name)
+}
+
+/// Rename an entity, replacing a character's name or giving anything else a [`CustomName`].
+#[derive(Clone, Debug)]
+pub struct RenameEntity(pub String);
+
+impl EntityCommand for RenameEntity {
+    fn apply(self, entity: Entity, world: &mut World) {
+        let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+            return;
+        };
+
+        if entity_mut.contains::<CharacterName>() {
+            entity_mut.insert((CharacterName(self.0), PersistName));
+        } else {
+            entity_mut.insert(CustomName(self.0));
+        }
+
+        MarkTooltipChanged.apply(entity, world);
+    }
+}
+
+#[derive(SystemParam)]
+pub struct DisplayNames<'w, 's> {
+    names: Query<'w, 's, (
+        Option<&'static CustomName>,
+        Option<&'static CharacterName>,
+        Option<&'static ItemName>,
+        Option<&'static ItemGraphic>,
+    )>,
+    tile_data: Option<Res<'w, TileDataResource>>,
+}
+
+impl DisplayNames<'_, '_> {
+    pub fn display_name(&self, entity: Entity) -> String {
+        let Ok((custom_name, character_name, item_name, graphic)) = self.names.get(entity) else {
+            return String::new();
+        };
+
+        // Localised names can only be resolved by the client.
+        let prefab_name = character_name.map(|name| name.as_str())
+            .or_else(|| match item_name {
+                Some(ItemName::Dynamic(name)) => Some(name.as_ref()),
+                _ => None,
+            });
+        let tile_data = self.tile_data.as_ref().map(|t| &t.tile_data);
+        display_name(custom_name, prefab_name, graphic, tile_data)
+    }
+
+    /// Resolve the name to show for an entity, leaving localised names for the client.
+    pub fn item_name(&self, entity: Entity) -> Option<ItemName> {
+        let (custom_name, character_name, item_name, _) = self.names.get(entity).ok()?;
+        match (custom_name, character_name, item_name) {
+            (Some(name), _, _) => Some(ItemName::Dynamic(name.0.clone().into())),
+            (None, Some(name), _) => Some(ItemName::Dynamic(name.0.clone().into())),
+            (None, None, Some(name)) => Some(name.clone()),
+            (None, None, None) => {
+                let name = self.display_name(entity);
+                (!name.is_empty()).then(|| ItemName::Dynamic(name.into()))
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CustomNameSerializer;
+
+impl BundleSerializer for CustomNameSerializer {
+    type Query = &'static CustomName;
+    type Filter = With<Persistent>;
+    type Bundle = String;
+
+    fn id() -> &'static str {
+        "CustomName"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        item.0.clone()
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(CustomName(bundle));
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<CustomName>()
+        .register_serializer::<CustomNameSerializer>();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use yewoh::assets::tiles::{ItemInfo, TileFlags};
+    use yewoh_server::world::entity::Tooltip;
+
+    use super::*;
+
+    fn tile_data() -> TileData {
+        let item = ItemInfo {
+            name: "dagger".into(),
+            flags: TileFlags::empty(),
+            weight: 1,
+            quality: 0,
+            animation: 0,
+            quantity: 0,
+            value: 0,
+            height: 0,
+        };
+        TileData {
+            land: Vec::new(),
+            items: vec![item.clone(); 4],
+        }
+    }
+
+    #[test]
+    fn test_display_name_tiers() {
+        let tile_data = tile_data();
+        let custom = CustomName("Widowmaker".into());
+        let graphic = ItemGraphic(3);
+
+        assert_eq!(display_name(Some(&custom), Some("fine dagger"), Some(&graphic), Some(&tile_data)), "Widowmaker");
+        assert_eq!(display_name(None, Some("fine dagger"), Some(&graphic), Some(&tile_data)), "fine dagger");
+        assert_eq!(display_name(None, None, Some(&graphic), Some(&tile_data)), "dagger");
+        assert_eq!(display_name(None, None, Some(&ItemGraphic(100)), Some(&tile_data)), "");
+        assert_eq!(display_name(None, None, None, None), "");
+    }
+
+    #[test]
+    fn test_display_names() {
+        let mut world = World::new();
+        world.insert_resource(TileDataResource { tile_data: tile_data() });
+        let renamed = world.spawn((
+            CustomName("Widowmaker".into()),
+            ItemName::Dynamic("fine dagger".into()),
+            ItemGraphic(3),
+        )).id();
+        let character = world.spawn(CharacterName("Gerome".into())).id();
+        let named = world.spawn((ItemName::Dynamic("fine dagger".into()), ItemGraphic(3))).id();
+        let localised = world.spawn((ItemName::Localised(default()), ItemGraphic(3))).id();
+
+        let names = world
+            .run_system_once(move |names: DisplayNames| {
+                [renamed, character, named, localised].map(|entity| names.display_name(entity))
+            })
+            .unwrap();
+        assert_eq!(names, ["Widowmaker", "Gerome", "fine dagger", "dagger"]);
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert_eq!(validate_name("  Sir Fluffy ").unwrap(), "Sir Fluffy");
+        assert!(validate_name("").is_err());
+        assert!(validate_name("An Exceedingly Long Name").is_err());
+        assert!(validate_name("<b>Fluffy</b>").is_err());
+    }
+
+    #[test]
+    fn test_rename_entity() {
+        let mut world = World::new();
+        let character = world.spawn((CharacterName("Gerome".into()), Tooltip::default())).id();
+        let item = world.spawn((ItemName::Dynamic("fine dagger".into()), Tooltip::default())).id();
+
+        RenameEntity("Fluffy".into()).apply(character, &mut world);
+        RenameEntity("Widowmaker".into()).apply(item, &mut world);
+
+        assert_eq!(world.get::<CharacterName>(character).unwrap().as_str(), "Fluffy");
+        assert!(world.get::<PersistName>(character).is_some());
+        assert_eq!(world.get::<Tooltip>(character).unwrap().version, 1);
+        assert_eq!(world.get::<CustomName>(item).unwrap().as_str(), "Widowmaker");
+        assert_eq!(world.get::<Tooltip>(item).unwrap().version, 1);
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use smallvec::SmallVec;
+use yewoh::protocol::GumpTextEntry;
 use yewoh_server::world::gump::OnClientCloseGump;
 use yewoh_server::world::ServerSet;
 
@@ -39,7 +40,15 @@ pub struct OnCloseGump {
     pub gump: Entity,
     pub button_id: u32,
     pub on_switches: SmallVec<[u32; 16]>,
-    pub text_fields: Vec<String>,
+    pub text_fields: Vec<GumpTextEntry>,
+}
+
+impl OnCloseGump {
+    pub fn text_field(&self, id: u16) -> Option<&str> {
+        self.text_fields.iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| entry.text.as_str())
+    }
 }
 
 impl EntityEvent for OnCloseGump {
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use clap::{Parser, ValueEnum};
+use yewoh::protocol::TargetType;
+use yewoh_server::world::characters::CharacterName;
+use yewoh_server::world::connection::{NetClient, Possessing};
+use yewoh_server::world::entity::MapPosition;
+use yewoh_server::world::input::{EntityTargetRequest, EntityTargetResponse};
+
+use crate::commands::{TextCommand, TextCommandQueue, TextCommandRegistrationExt};
+use crate::housing::{HouseAccess, HouseList, HouseOwnership, HousePermissions};
+use crate::hues;
+use crate::networking::NetClientExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HouseListAction {
+    Add,
+    Remove,
+    Show,
+}
+
+/// Manage the co-owner, friend and ban lists of the house you are standing in.
+#[derive(Parser, Resource)]
+pub struct House {
+    #[clap(value_enum)]
+    action: HouseListAction,
+    #[clap(value_enum)]
+    list: HouseList,
+}
+
+impl TextCommand for House {
+    fn aliases() -> &'static [&'static str] {
+        &["house"]
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct HouseListRequest {
+    pub house: Entity,
+    pub list: HouseList,
+    pub action: HouseListAction,
+}
+
+pub fn start_house_command(
+    mut exec: TextCommandQueue<House>,
+    clients: Query<(&NetClient, &Possessing)>,
+    positions: Query<&MapPosition>,
+    houses: Query<&HouseOwnership>,
+    names: Query<&CharacterName>,
+    permissions: HousePermissions,
+    mut commands: Commands,
+) {
+    for (from, args) in exec.iter() {
+        let Ok((client, owned)) = clients.get(from) else {
+            continue;
+        };
+
+        let Some(house) = positions.get(owned.entity).ok()
+            .and_then(|position| permissions.house_at(position)) else {
+            client.send_system_message_hue("You must be inside a house.", hues::RED);
+            continue;
+        };
+
+        let access = permissions.access(house, owned.entity);
+        if args.action == HouseListAction::Show {
+            if access < HouseAccess::Friend {
+                client.send_system_message_hue("You are not trusted by this house.", hues::RED);
+                continue;
+            }
+
+            let Ok(ownership) = houses.get(house) else {
+                continue;
+            };
+            let members = ownership.list(args.list).iter()
+                .filter_map(|entity| names.get(*entity).ok())
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>();
+            if members.is_empty() {
+                client.send_system_message(format!("The {} list is empty.", args.list.label()));
+            } else {
+                client.send_system_message(format!("{} list: {}", args.list.label(), members.join(", ")));
+            }
+            continue;
+        }
+
+        if access < args.list.manage_access() {
+            client.send_system_message_hue(
+                format!("You may not change the {} list.", args.list.label()), hues::RED);
+            continue;
+        }
+
+        client.send_system_message("Target the character.");
+        commands.spawn((
+            HouseListRequest {
+                house,
+                list: args.list,
+                action: args.action,
+            },
+            EntityTargetRequest {
+                client_entity: from,
+                target_type: TargetType::Neutral,
+            },
+        ));
+    }
+}
+
+pub fn finish_house_command(
+    completed: Query<(Entity, &HouseListRequest, &EntityTargetRequest, &EntityTargetResponse)>,
+    clients: Query<(&NetClient, &Possessing)>,
+    characters: Query<&CharacterName>,
+    mut houses: Query<&mut HouseOwnership>,
+    mut commands: Commands,
+) {
+    for (entity, request, target_request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some(target) = response.target else {
+            continue;
+        };
+        let Ok((client, owned)) = clients.get(target_request.client_entity) else {
+            continue;
+        };
+        let Ok(name) = characters.get(target) else {
+            client.send_system_message_hue("That is not a character.", hues::RED);
+            continue;
+        };
+        let Ok(mut ownership) = houses.get_mut(request.house) else {
+            continue;
+        };
+
+        if target == ownership.owner {
+            client.send_system_message_hue("That is the owner of this house.", hues::RED);
+            continue;
+        }
+
+        // Access may have changed since the command was started.
+        if !ownership.can_manage(owned.entity, request.list, target) {
+            client.send_system_message_hue(
+                format!("You may not change the {} list for {}.", request.list.label(), name.as_str()), hues::RED);
+            continue;
+        }
+
+        match request.action {
+            HouseListAction::Add => {
+                ownership.add_to_list(owned.entity, request.list, target);
+                client.send_system_message(
+                    format!("{} added to the {} list.", name.as_str(), request.list.label()));
+            }
+            HouseListAction::Remove => {
+                if ownership.remove_from_list(owned.entity, request.list, target) {
+                    client.send_system_message(
+                        format!("{} removed from the {} list.", name.as_str(), request.list.label()));
+                } else {
+                    client.send_system_message_hue(
+                        format!("{} is not on the {} list.", name.as_str(), request.list.label()), hues::RED);
+                }
+            }
+            HouseListAction::Show => {}
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_text_command::<House>()
+        .add_systems(Update, (
+            start_house_command,
+            finish_house_command,
+        ));
+}
@@ -0,0 +1,158 @@
+use bevy::ecs::entity::{MapEntities, VisitEntities, VisitEntitiesMut};
+use bevy::ecs::query::WorldQuery;
+use bevy::ecs::reflect::{ReflectMapEntities, ReflectVisitEntities, ReflectVisitEntitiesMut};
+use bevy::prelude::*;
+
+use crate::entities::Persistent;
+use crate::housing::HouseAccess;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+
+/// An item fixed in place within a house.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Component, VisitEntities, VisitEntitiesMut)]
+#[reflect(Component, VisitEntities, VisitEntitiesMut, MapEntities)]
+pub struct LockedDown {
+    pub house: Entity,
+}
+
+/// A locked down item which only trusted characters may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Component)]
+#[reflect(Component, Default)]
+pub struct Secure {
+    pub level: HouseAccess,
+}
+
+impl Default for Secure {
+    fn default() -> Self {
+        Secure {
+            level: HouseAccess::CoOwner,
+        }
+    }
+}
+
+/// The access needed to move a locked down item, if no other level is set.
+pub const LOCKDOWN_ACCESS: HouseAccess = HouseAccess::Friend;
+
+#[derive(Clone, Debug, Reflect)]
+#[reflect(MapEntities)]
+pub struct LockedDownDto {
+    pub house: Entity,
+    pub secure: Option<HouseAccess>,
+}
+
+impl MapEntities for LockedDownDto {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.house = entity_mapper.map_entity(self.house);
+    }
+}
+
+#[derive(Default)]
+pub struct LockedDownSerializer;
+
+impl BundleSerializer for LockedDownSerializer {
+    type Query = (&'static LockedDown, Option<&'static Secure>);
+    type Filter = With<Persistent>;
+    type Bundle = LockedDownDto;
+
+    fn id() -> &'static str {
+        "LockedDown"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        let (locked_down, secure) = item;
+        LockedDownDto {
+            house: locked_down.house,
+            secure: secure.map(|secure| secure.level),
+        }
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        let mut entity = world.entity_mut(entity);
+        entity.insert(LockedDown { house: bundle.house });
+        if let Some(level) = bundle.secure {
+            entity.insert(Secure { level });
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<LockedDown>()
+        .register_type::<Secure>()
+        .register_serializer::<LockedDownSerializer>();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use yewoh_server::world::entity::Multi;
+
+    use crate::housing::{HouseFootprint, HouseOwnership, HousePermissions};
+    use crate::housing::placement::HouseSerializer;
+    use crate::persistence::{save_and_load, PersistencePlugin};
+
+    use super::*;
+
+    #[test]
+    fn test_can_move() {
+        let mut world = World::new();
+        let owner = world.spawn_empty().id();
+        let friend = world.spawn_empty().id();
+        let stranger = world.spawn_empty().id();
+        let mut ownership = HouseOwnership::new(owner);
+        ownership.friends.insert(friend);
+        let house = world.spawn(ownership).id();
+        let chair = world.spawn(LockedDown { house }).id();
+        let chest = world.spawn((LockedDown { house }, Secure::default())).id();
+        let loose = world.spawn_empty().id();
+
+        let checks = [
+            (owner, chair, true),
+            (friend, chair, true),
+            (stranger, chair, false),
+            (owner, chest, true),
+            (friend, chest, false),
+            (stranger, loose, true),
+        ];
+        for (character, item, expected) in checks {
+            let result = world
+                .run_system_once(move |permissions: HousePermissions| permissions.can_move(character, item))
+                .unwrap();
+            assert_eq!(result, expected);
+        }
+    }
+    fn persistence_app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins((
+                MinimalPlugins,
+                PersistencePlugin,
+                plugin,
+            ))
+            .register_serializer::<HouseSerializer>();
+        app
+    }
+
+    #[test]
+    fn test_persist_lockdown() {
+        let mut app = persistence_app();
+        let world = app.world_mut();
+        let owner = world.spawn_empty().id();
+        let house = world
+            .spawn((Persistent, Multi(0x64), HouseFootprint::default(), HouseOwnership::new(owner)))
+            .id();
+        world.spawn((Persistent, LockedDown { house }));
+        world.spawn((Persistent, LockedDown { house }, Secure { level: HouseAccess::Owner }));
+
+        let mut loaded = persistence_app();
+        save_and_load(world, loaded.world_mut());
+
+        let world = loaded.world_mut();
+        let house = world.query_filtered::<Entity, With<HouseOwnership>>().single(world);
+        let mut items = world.query::<(&LockedDown, Option<&Secure>)>()
+            .iter(world)
+            .map(|(locked_down, secure)| (locked_down.house, secure.map(|secure| secure.level)))
+            .collect::<Vec<_>>();
+        items.sort_by_key(|(_, level)| *level);
+        assert_eq!(items, vec![(house, None), (house, Some(HouseAccess::Owner))]);
+    }
+}
@@ -0,0 +1,371 @@
+use bevy::ecs::entity::MapEntities;
+use bevy::ecs::reflect::ReflectMapEntities;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use clap::ValueEnum;
+use yewoh_server::world::characters::CharacterBodyType;
+use yewoh_server::world::entity::MapPosition;
+
+use crate::housing::lockdown::{LockedDown, Secure, LOCKDOWN_ACCESS};
+
+pub mod commands;
+
+pub mod lockdown;
+
+pub mod placement;
+
+/// How much a character is trusted within a house, from least to most.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+#[reflect(Default)]
+pub enum HouseAccess {
+    Banned,
+    #[default]
+    Stranger,
+    Friend,
+    CoOwner,
+    Owner,
+}
+
+/// The characters who own or are trusted by a house.
+#[derive(Debug, Clone, Reflect, Component)]
+#[reflect(Component, MapEntities)]
+pub struct HouseOwnership {
+    pub owner: Entity,
+    pub co_owners: HashSet<Entity>,
+    pub friends: HashSet<Entity>,
+    pub bans: HashSet<Entity>,
+}
+
+impl HouseOwnership {
+    pub fn new(owner: Entity) -> HouseOwnership {
+        HouseOwnership {
+            owner,
+            co_owners: HashSet::new(),
+            friends: HashSet::new(),
+            bans: HashSet::new(),
+        }
+    }
+
+    pub fn access(&self, character: Entity) -> HouseAccess {
+        if character == self.owner {
+            HouseAccess::Owner
+        } else if self.co_owners.contains(&character) {
+            HouseAccess::CoOwner
+        } else if self.friends.contains(&character) {
+            HouseAccess::Friend
+        } else if self.bans.contains(&character) {
+            HouseAccess::Banned
+        } else {
+            HouseAccess::Stranger
+        }
+    }
+
+    pub fn list(&self, list: HouseList) -> &HashSet<Entity> {
+        match list {
+            HouseList::CoOwner => &self.co_owners,
+            HouseList::Friend => &self.friends,
+            HouseList::Ban => &self.bans,
+        }
+    }
+
+    pub fn list_mut(&mut self, list: HouseList) -> &mut HashSet<Entity> {
+        match list {
+            HouseList::CoOwner => &mut self.co_owners,
+            HouseList::Friend => &mut self.friends,
+            HouseList::Ban => &mut self.bans,
+        }
+    }
+
+    /// Whether `actor` may change which list `character` is on.
+    pub fn can_manage(&self, actor: Entity, list: HouseList, character: Entity) -> bool {
+        let access = self.access(actor);
+        access >= list.manage_access() && self.access(character) < access
+    }
+
+    /// Add `character` to `list`, removing them from any other list.
+    ///
+    /// Returns false without changing anything if `actor` may not do this.
+    pub fn add_to_list(&mut self, actor: Entity, list: HouseList, character: Entity) -> bool {
+        if !self.can_manage(actor, list, character) {
+            return false;
+        }
+
+        for other in [HouseList::CoOwner, HouseList::Friend, HouseList::Ban] {
+            self.list_mut(other).remove(&character);
+        }
+        self.list_mut(list).insert(character);
+        true
+    }
+
+    /// Remove `character` from `list`.
+    ///
+    /// Returns false if `actor` may not do this or `character` was not on the list.
+    pub fn remove_from_list(&mut self, actor: Entity, list: HouseList, character: Entity) -> bool {
+        self.can_manage(actor, list, character) && self.list_mut(list).remove(&character)
+    }
+}
+
+/// One of the managed lists of characters on a house.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum HouseList {
+    CoOwner,
+    Friend,
+    Ban,
+}
+
+impl HouseList {
+    pub fn label(self) -> &'static str {
+        match self {
+            HouseList::CoOwner => "co-owner",
+            HouseList::Friend => "friend",
+            HouseList::Ban => "ban",
+        }
+    }
+
+    /// The access needed to change this list.
+    pub fn manage_access(self) -> HouseAccess {
+        match self {
+            HouseList::CoOwner => HouseAccess::Owner,
+            HouseList::Friend | HouseList::Ban => HouseAccess::CoOwner,
+        }
+    }
+}
+
+/// The area of the world covered by a house, inclusive of both corners.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Component)]
+#[reflect(Component, Default)]
+pub struct HouseFootprint {
+    pub map_id: u8,
+    pub min: IVec2,
+    pub max: IVec2,
+}
+
+impl HouseFootprint {
+    pub fn contains(&self, position: &MapPosition) -> bool {
+        let tile = position.position.truncate();
+        position.map_id == self.map_id
+            && tile.cmpge(self.min).all()
+            && tile.cmple(self.max).all()
+    }
+
+    pub fn intersects(&self, other: &HouseFootprint) -> bool {
+        self.map_id == other.map_id
+            && self.min.cmple(other.max).all()
+            && other.min.cmple(self.max).all()
+    }
+
+    /// The closest tile outside this footprint to `tile`.
+    pub fn nearest_outside(&self, tile: IVec2) -> IVec2 {
+        [
+            (tile.x - self.min.x, IVec2::new(self.min.x - 1, tile.y)),
+            (self.max.x - tile.x, IVec2::new(self.max.x + 1, tile.y)),
+            (tile.y - self.min.y, IVec2::new(tile.x, self.min.y - 1)),
+            (self.max.y - tile.y, IVec2::new(tile.x, self.max.y + 1)),
+        ]
+            .into_iter()
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, outside)| outside)
+            .unwrap()
+    }
+}
+
+fn map_entity_set(set: &mut HashSet<Entity>, entity_mapper: &mut impl EntityMapper) {
+    *set = std::mem::take(set).into_iter()
+        .map(|entity| entity_mapper.map_entity(entity))
+        .collect();
+}
+
+impl MapEntities for HouseOwnership {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.owner = entity_mapper.map_entity(self.owner);
+        map_entity_set(&mut self.co_owners, entity_mapper);
+        map_entity_set(&mut self.friends, entity_mapper);
+        map_entity_set(&mut self.bans, entity_mapper);
+    }
+}
+
+/// The access needed to open doors within a house.
+pub const DOOR_ACCESS: HouseAccess = HouseAccess::Friend;
+
+#[derive(SystemParam)]
+pub struct HousePermissions<'w, 's> {
+    houses: Query<'w, 's, (Entity, &'static HouseOwnership, Option<&'static HouseFootprint>)>,
+    locked: Query<'w, 's, (&'static LockedDown, Option<&'static Secure>)>,
+}
+
+impl HousePermissions<'_, '_> {
+    pub fn access(&self, house: Entity, character: Entity) -> HouseAccess {
+        self.houses.get(house)
+            .map_or(HouseAccess::Stranger, |(_, ownership, _)| ownership.access(character))
+    }
+
+    /// The house whose footprint covers `position`, if any.
+    pub fn house_at(&self, position: &MapPosition) -> Option<Entity> {
+        self.houses.iter()
+            .find(|(_, _, footprint)| footprint.is_some_and(|f| f.contains(position)))
+            .map(|(entity, _, _)| entity)
+    }
+
+    /// Whether `character` is allowed to move `item`.
+    pub fn can_move(&self, character: Entity, item: Entity) -> bool {
+        let Ok((locked_down, secure)) = self.locked.get(item) else {
+            return true;
+        };
+
+        let required = secure.map_or(LOCKDOWN_ACCESS, |secure| secure.level);
+        self.access(locked_down.house, character) >= required
+    }
+
+    /// Whether `character` is allowed to open the door at `position`.
+    pub fn can_open_door(&self, character: Entity, position: &MapPosition) -> bool {
+        match self.house_at(position) {
+            Some(house) => self.access(house, character) >= DOOR_ACCESS,
+            None => true,
+        }
+    }
+
+    /// Whether `character` is allowed to look inside `container`.
+    pub fn can_open_container(&self, character: Entity, container: Entity) -> bool {
+        let Ok((locked_down, secure)) = self.locked.get(container) else {
+            return true;
+        };
+
+        let required = secure.map_or(HouseAccess::Stranger, |secure| secure.level);
+        self.access(locked_down.house, character) >= required
+    }
+}
+
+/// Move banned characters out of houses when they are banned, or when they walk back in.
+pub fn eject_banned_characters(
+    houses: Query<(Ref<HouseOwnership>, &HouseFootprint)>,
+    mut characters: Query<&mut MapPosition, With<CharacterBodyType>>,
+) {
+    for (ownership, footprint) in &houses {
+        for banned in &ownership.bans {
+            let Ok(mut position) = characters.get_mut(*banned) else {
+                continue;
+            };
+
+            if !ownership.is_changed() && !position.is_changed() {
+                continue;
+            }
+
+            if !footprint.contains(&position) {
+                continue;
+            }
+
+            let outside = footprint.nearest_outside(position.position.truncate());
+            position.position = outside.extend(position.position.z);
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<HouseAccess>()
+        .register_type::<HouseOwnership>()
+        .register_type::<HouseFootprint>()
+        .add_plugins((
+            lockdown::plugin,
+            commands::plugin,
+            placement::plugin,
+        ))
+        .add_systems(Update, eject_banned_characters);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_house_access() {
+        let owner = Entity::from_raw(1);
+        let co_owner = Entity::from_raw(2);
+        let friend = Entity::from_raw(3);
+        let stranger = Entity::from_raw(4);
+        let mut house = HouseOwnership::new(owner);
+        house.co_owners.insert(co_owner);
+        house.friends.insert(friend);
+        assert!(house.add_to_list(owner, HouseList::Ban, stranger));
+        assert_eq!(house.access(stranger), HouseAccess::Banned);
+        house.bans.remove(&stranger);
+
+        assert_eq!(house.access(owner), HouseAccess::Owner);
+        assert_eq!(house.access(co_owner), HouseAccess::CoOwner);
+        assert_eq!(house.access(friend), HouseAccess::Friend);
+        assert_eq!(house.access(stranger), HouseAccess::Stranger);
+        assert!(HouseAccess::CoOwner > HouseAccess::Friend);
+        assert!(HouseAccess::Banned < HouseAccess::Stranger);
+
+        assert!(house.add_to_list(owner, HouseList::CoOwner, friend));
+        assert!(!house.friends.contains(&friend));
+        assert_eq!(house.access(friend), HouseAccess::CoOwner);
+    }
+
+    #[test]
+    fn test_list_changes_need_higher_access() {
+        let owner = Entity::from_raw(1);
+        let co_owner = Entity::from_raw(2);
+        let other_co_owner = Entity::from_raw(3);
+        let friend = Entity::from_raw(4);
+        let mut house = HouseOwnership::new(owner);
+        house.co_owners.extend([co_owner, other_co_owner]);
+        house.friends.insert(friend);
+
+        assert!(!house.add_to_list(co_owner, HouseList::Ban, other_co_owner));
+        assert!(!house.remove_from_list(co_owner, HouseList::CoOwner, other_co_owner));
+        assert!(!house.add_to_list(co_owner, HouseList::Ban, owner));
+        assert!(!house.add_to_list(friend, HouseList::Ban, co_owner));
+        assert_eq!(house.access(other_co_owner), HouseAccess::CoOwner);
+
+        assert!(house.add_to_list(co_owner, HouseList::Ban, friend));
+        assert_eq!(house.access(friend), HouseAccess::Banned);
+        assert!(house.remove_from_list(owner, HouseList::CoOwner, other_co_owner));
+        assert_eq!(house.access(other_co_owner), HouseAccess::Stranger);
+    }
+
+    #[test]
+    fn test_banned_character_ejected() {
+        let mut app = App::new();
+        app.add_systems(Update, eject_banned_characters);
+
+        let owner = app.world_mut().spawn_empty().id();
+        let inside = MapPosition { position: IVec3::new(11, 12, 5), map_id: 1 };
+        let guest = app.world_mut().spawn((CharacterBodyType(0x190), inside)).id();
+        let footprint = HouseFootprint { map_id: 1, min: IVec2::new(10, 10), max: IVec2::new(15, 14) };
+        let house = app.world_mut().spawn((HouseOwnership::new(owner), footprint)).id();
+        app.update();
+        assert_eq!(*app.world().get::<MapPosition>(guest).unwrap(), inside);
+
+        app.world_mut().get_mut::<HouseOwnership>(house).unwrap().add_to_list(owner, HouseList::Ban, guest);
+        app.update();
+        let position = *app.world().get::<MapPosition>(guest).unwrap();
+        assert!(!footprint.contains(&position));
+        assert_eq!(position.position, IVec3::new(9, 12, 5));
+
+        app.world_mut().entity_mut(guest).insert(inside);
+        app.update();
+        let position = *app.world().get::<MapPosition>(guest).unwrap();
+        assert!(!footprint.contains(&position));
+    }
+
+    #[test]
+    fn test_footprint_contains() {
+        let footprint = HouseFootprint {
+            map_id: 1,
+            min: IVec2::new(10, 10),
+            max: IVec2::new(15, 14),
+        };
+        let at = |x, y, map_id| MapPosition { position: IVec3::new(x, y, 0), map_id };
+        assert!(footprint.contains(&at(10, 10, 1)));
+        assert!(footprint.contains(&at(15, 14, 1)));
+        assert!(!footprint.contains(&at(16, 14, 1)));
+        assert!(!footprint.contains(&at(12, 12, 0)));
+
+        let neighbour = HouseFootprint { min: IVec2::new(16, 10), max: IVec2::new(20, 14), ..footprint };
+        assert!(!footprint.intersects(&neighbour));
+        let overlapping = HouseFootprint { min: IVec2::new(15, 14), ..neighbour };
+        assert!(footprint.intersects(&overlapping));
+    }
+}
@@ -0,0 +1,387 @@
+use bevy::ecs::entity::MapEntities;
+use bevy::ecs::query::WorldQuery;
+use bevy::ecs::reflect::ReflectMapEntities;
+use bevy::prelude::*;
+use yewoh::protocol::TargetType;
+use yewoh_server::world::connection::NetClient;
+use yewoh_server::world::entity::{EquipmentSlot, EquippedPosition, MapPosition, Multi};
+use yewoh_server::world::input::{WorldTargetRequest, WorldTargetResponse};
+use yewoh_server::world::items::ItemGraphic;
+use yewoh_server::world::map::{Chunk, TileDataResource};
+use yewoh_server::world::spatial::{Area2Iter, SpatialQuery};
+
+use crate::entities::Persistent;
+use crate::entities::interactions::{DoubleClickAppExt, OnEntityDoubleClick};
+use crate::housing::{HouseFootprint, HouseOwnership};
+use crate::hues;
+use crate::networking::NetClientExt;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+
+/// How far from the character a house may be placed.
+pub const HOUSE_PLACEMENT_RANGE: i32 = 12;
+
+/// A deed which places a house when used.
+///
+/// `min` and `max` are the corners of the house footprint, relative to the targeted tile.
+#[derive(Debug, Clone, Default, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct HouseDeed {
+    pub multi_id: u16,
+    pub min: IVec2,
+    pub max: IVec2,
+}
+
+impl HouseDeed {
+    pub fn footprint_at(&self, position: &MapPosition) -> HouseFootprint {
+        let origin = position.position.truncate();
+        HouseFootprint {
+            map_id: position.map_id,
+            min: origin + self.min,
+            max: origin + self.max,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlacementError {
+    NotInBackpack,
+    OutOfRange,
+    BadTerrain,
+    Blocked,
+    Overlap,
+}
+
+impl PlacementError {
+    pub fn message(self) -> &'static str {
+        match self {
+            PlacementError::NotInBackpack => "The deed must be in your backpack to use it.",
+            PlacementError::OutOfRange => "That location is too far away.",
+            PlacementError::BadTerrain => "The house cannot be built on this terrain.",
+            PlacementError::Blocked => "Something is blocking the house from being built here.",
+            PlacementError::Overlap => "The house would overlap another house.",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+pub struct HousePlacementRequest {
+    pub deed: Entity,
+    pub character: Entity,
+}
+
+/// Check that `footprint` is clear land which is not already part of another house.
+pub fn validate_house_placement<'a>(
+    query: &SpatialQuery,
+    chunk_query: &Query<(&MapPosition, &Chunk)>,
+    tile_data: &TileDataResource,
+    houses: impl IntoIterator<Item = &'a HouseFootprint>,
+    footprint: &HouseFootprint,
+) -> Result<(), PlacementError> {
+    if houses.into_iter().any(|house| house.intersects(footprint)) {
+        return Err(PlacementError::Overlap);
+    }
+
+    let map_id = footprint.map_id;
+    for position in Area2Iter::new(footprint.min, footprint.max + IVec2::ONE) {
+        let land = query.chunks.get_at(map_id, position)
+            .and_then(|entity| chunk_query.get(entity).ok())
+            .map(|(_, chunk)| chunk.tile_at(position));
+        let Some(land) = land else {
+            return Err(PlacementError::BadTerrain);
+        };
+        if tile_data.is_land_impassable(land.tile_id) || tile_data.is_land_wet(land.tile_id) {
+            return Err(PlacementError::BadTerrain);
+        }
+
+        let blocked = query.static_items.lookup.entries_at(map_id, position).iter()
+            .chain(query.dynamic_items.lookup.entries_at(map_id, position))
+            .any(|item| tile_data.is_impassable(item.graphic));
+        if blocked {
+            return Err(PlacementError::Blocked);
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `item` is somewhere inside the backpack of `character`.
+pub fn in_backpack(
+    parents: &Query<&Parent>,
+    equipment: &Query<&EquippedPosition>,
+    item: Entity,
+    character: Entity,
+) -> bool {
+    let mut current = item;
+    while let Ok(parent) = parents.get(current) {
+        if parent.get() == character {
+            return equipment.get(current).is_ok_and(|e| e.slot == EquipmentSlot::Backpack);
+        }
+        current = parent.get();
+    }
+    false
+}
+
+pub fn use_house_deed(
+    In(event): In<OnEntityDoubleClick>,
+    clients: Query<&NetClient>,
+    parents: Query<&Parent>,
+    equipment: Query<&EquippedPosition>,
+    mut commands: Commands,
+) {
+    if !in_backpack(&parents, &equipment, event.target, event.character) {
+        if let Ok(client) = clients.get(event.client_entity) {
+            client.send_system_message_hue(PlacementError::NotInBackpack.message(), hues::RED);
+        }
+        return;
+    }
+
+    if let Ok(client) = clients.get(event.client_entity) {
+        client.send_system_message("Where would you like to place the house?");
+    }
+
+    commands.spawn((
+        HousePlacementRequest {
+            deed: event.target,
+            character: event.character,
+        },
+        WorldTargetRequest {
+            client_entity: event.client_entity,
+            target_type: TargetType::Neutral,
+        },
+    ));
+}
+
+pub fn place_house(
+    completed: Query<(Entity, &HousePlacementRequest, &WorldTargetRequest, &WorldTargetResponse)>,
+    clients: Query<&NetClient>,
+    deeds: Query<&HouseDeed>,
+    parents: Query<&Parent>,
+    equipment: Query<&EquippedPosition>,
+    positions: Query<&MapPosition>,
+    houses: Query<&HouseFootprint>,
+    spatial_query: SpatialQuery,
+    chunk_query: Query<(&MapPosition, &Chunk)>,
+    tile_data: Res<TileDataResource>,
+    mut commands: Commands,
+) {
+    // Houses placed this frame are not visible to the query yet.
+    let mut placed = Vec::new();
+
+    for (entity, request, target_request, response) in &completed {
+        commands.entity(entity).despawn();
+
+        let Some(target) = response.position else {
+            continue;
+        };
+        let Ok(deed) = deeds.get(request.deed) else {
+            continue;
+        };
+        let Ok(character_position) = positions.get(request.character) else {
+            continue;
+        };
+
+        let position = MapPosition { position: target, map_id: character_position.map_id };
+        let footprint = deed.footprint_at(&position);
+        let existing = houses.iter().chain(placed.iter());
+        // The deed may have been moved while the client was targeting.
+        let result = if !in_backpack(&parents, &equipment, request.deed, request.character) {
+            Err(PlacementError::NotInBackpack)
+        } else if !character_position.in_range_2d(&position, HOUSE_PLACEMENT_RANGE) {
+            Err(PlacementError::OutOfRange)
+        } else {
+            validate_house_placement(&spatial_query, &chunk_query, &tile_data, existing, &footprint)
+        };
+        if let Err(err) = result {
+            if let Ok(client) = clients.get(target_request.client_entity) {
+                client.send_system_message_hue(err.message(), hues::RED);
+            }
+            continue;
+        }
+
+        commands.spawn((
+            Multi(deed.multi_id),
+            ItemGraphic(deed.multi_id),
+            position,
+            footprint,
+            HouseOwnership::new(request.character),
+            Persistent,
+        ));
+        commands.entity(request.deed).despawn_recursive();
+        placed.push(footprint);
+
+        if let Ok(client) = clients.get(target_request.client_entity) {
+            client.send_system_message("You have placed the house.");
+        }
+    }
+}
+
+#[derive(Clone, Debug, Reflect)]
+#[reflect(MapEntities)]
+pub struct HouseDto {
+    pub multi_id: u16,
+    pub footprint: HouseFootprint,
+    pub ownership: HouseOwnership,
+}
+
+impl MapEntities for HouseDto {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.ownership.map_entities(entity_mapper);
+    }
+}
+
+#[derive(Default)]
+pub struct HouseSerializer;
+
+impl BundleSerializer for HouseSerializer {
+    type Query = (&'static Multi, &'static HouseFootprint, &'static HouseOwnership);
+    type Filter = With<Persistent>;
+    type Bundle = HouseDto;
+
+    fn id() -> &'static str {
+        "House"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        let (multi, footprint, ownership) = item;
+        HouseDto {
+            multi_id: **multi,
+            footprint: *footprint,
+            ownership: ownership.clone(),
+        }
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity)
+            .insert((
+                Multi(bundle.multi_id),
+                ItemGraphic(bundle.multi_id),
+                bundle.footprint,
+                bundle.ownership,
+            ));
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<HouseDeed>()
+        .register_serializer::<HouseSerializer>()
+        .add_double_click_handler::<HouseDeed, _>(use_house_deed)
+        .add_systems(Update, (
+            place_house,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use yewoh_server::world::spatial::{ChunkLookup, SpatialCharacterLookup, SpatialDynamicItemLookup, SpatialStaticItemLookup};
+
+    use super::*;
+
+    fn setup() -> (App, Entity) {
+        let mut app = App::new();
+        app
+            .init_resource::<TileDataResource>()
+            .init_resource::<SpatialCharacterLookup>()
+            .init_resource::<SpatialDynamicItemLookup>()
+            .init_resource::<SpatialStaticItemLookup>()
+            .add_systems(Update, place_house);
+
+        // Cover the whole map with a single flat chunk.
+        let chunk = app.world_mut()
+            .spawn((MapPosition { position: IVec3::ZERO, map_id: 1 }, Chunk::default()))
+            .id();
+        let mut chunks = ChunkLookup::default();
+        chunks.insert_map(1, IVec2::splat(64));
+        for position in Area2Iter::new(IVec2::ZERO, IVec2::splat(8)) {
+            chunks.insert(1, position * 8, chunk);
+        }
+        app.insert_resource(chunks);
+
+        let character = app.world_mut()
+            .spawn(MapPosition { position: IVec3::new(20, 20, 0), map_id: 1 })
+            .id();
+        (app, character)
+    }
+
+    fn place(app: &mut App, character: Entity, target: IVec3) -> Entity {
+        let backpack = app.world_mut()
+            .spawn(EquippedPosition { slot: EquipmentSlot::Backpack })
+            .set_parent(character)
+            .id();
+        let deed = app.world_mut()
+            .spawn(HouseDeed { multi_id: 0x64, min: IVec2::new(-3, -3), max: IVec2::new(3, 3) })
+            .set_parent(backpack)
+            .id();
+        place_deed(app, character, deed, target);
+        deed
+    }
+
+    fn place_deed(app: &mut App, character: Entity, deed: Entity, target: IVec3) {
+        app.world_mut().spawn((
+            HousePlacementRequest { deed, character },
+            WorldTargetRequest { client_entity: Entity::PLACEHOLDER, target_type: TargetType::Neutral },
+            WorldTargetResponse { position: Some(target) },
+        ));
+        app.update();
+    }
+
+    fn houses(app: &mut App) -> Vec<(HouseFootprint, Entity)> {
+        app.world_mut()
+            .query::<(&HouseFootprint, &HouseOwnership)>()
+            .iter(app.world())
+            .map(|(footprint, ownership)| (*footprint, ownership.owner))
+            .collect()
+    }
+
+    #[test]
+    fn test_place_house() {
+        let (mut app, character) = setup();
+        let deed = place(&mut app, character, IVec3::new(20, 20, 0));
+
+        assert!(app.world().get::<HouseDeed>(deed).is_none());
+        assert_eq!(houses(&mut app), vec![(HouseFootprint {
+            map_id: 1,
+            min: IVec2::new(17, 17),
+            max: IVec2::new(23, 23),
+        }, character)]);
+        let persistent = app.world_mut()
+            .query_filtered::<(), (With<HouseOwnership>, With<Persistent>)>()
+            .iter(app.world())
+            .count();
+        assert_eq!(persistent, 1);
+    }
+
+    #[test]
+    fn test_reject_out_of_range() {
+        let (mut app, character) = setup();
+        let deed = place(&mut app, character, IVec3::new(40, 40, 0));
+
+        assert!(app.world().get::<HouseDeed>(deed).is_some());
+        assert!(houses(&mut app).is_empty());
+    }
+
+    #[test]
+    fn test_reject_deed_outside_backpack() {
+        let (mut app, character) = setup();
+        let deed = app.world_mut()
+            .spawn((
+                HouseDeed { multi_id: 0x64, min: IVec2::new(-3, -3), max: IVec2::new(3, 3) },
+                MapPosition { position: IVec3::new(20, 20, 0), map_id: 1 },
+            ))
+            .id();
+        place_deed(&mut app, character, deed, IVec3::new(20, 20, 0));
+
+        assert!(app.world().get::<HouseDeed>(deed).is_some());
+        assert!(houses(&mut app).is_empty());
+    }
+
+    #[test]
+    fn test_reject_overlapping_house() {
+        let (mut app, character) = setup();
+        place(&mut app, character, IVec3::new(20, 20, 0));
+        let deed = place(&mut app, character, IVec3::new(25, 25, 0));
+
+        assert!(app.world().get::<HouseDeed>(deed).is_some());
+        assert_eq!(houses(&mut app).len(), 1);
+    }
+}
@@ -1,11 +1,12 @@
 use bevy::prelude::*;
 use glam::ivec3;
+use yewoh_server::world::connection::NetClient;
 use yewoh_server::world::entity::{Direction, MapPosition};
 use yewoh_server::world::items::ItemGraphic;
 use yewoh_server::world::sound::OnSound;
-use crate::DefaultGameSet;
-use crate::entities::interactions::OnEntityDoubleClick;
-use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
+use crate::entities::interactions::{DoubleClickAppExt, OnEntityDoubleClick};
+use crate::housing::HousePermissions;
+use crate::networking::NetClientExt;
 
 #[derive(Clone, Default, Debug, Reflect, Component)]
 #[reflect(Default, Component)]
@@ -81,52 +82,54 @@ pub fn update_door_graphic(
     }
 }
 
-pub fn double_click_doors(
-    mut events: EntityEventReader<OnEntityDoubleClick, Door>,
+pub fn double_click_door(
+    In(event): In<OnEntityDoubleClick>,
+    clients: Query<&NetClient>,
+    permissions: HousePermissions,
     mut doors: Query<(&mut Door, &mut MapPosition)>,
     mut sounds: EventWriter<OnSound>,
 ) {
-    for event in events.read() {
-        let Ok((mut door, mut position)) = doors.get_mut(event.target) else {
-            continue;
-        };
+    let Ok((mut door, mut position)) = doors.get_mut(event.target) else {
+        return;
+    };
 
-        let sound_id = if door.opened {
-            door.close_sound
-        } else {
-            door.open_sound
-        };
-        if sound_id != 0 {
-            sounds.send(OnSound {
-                sound_id,
-                position: *position,
-                ..default()
-            });
+    if !permissions.can_open_door(event.character, &position) {
+        if let Ok(client) = clients.get(event.client_entity) {
+            client.send_system_message("That is locked.");
         }
+        return;
+    }
 
-        if door.opened {
-            position.position -= door.open_offset;
-        }
+    let sound_id = if door.opened {
+        door.close_sound
+    } else {
+        door.open_sound
+    };
+    if sound_id != 0 {
+        sounds.send(OnSound {
+            sound_id,
+            position: *position,
+            ..default()
+        });
+    }
+
+    if door.opened {
+        position.position -= door.open_offset;
+    }
 
-        door.opened = !door.opened;
+    door.opened = !door.opened;
 
-        if door.opened {
-            position.position += door.open_offset;
-        }
+    if door.opened {
+        position.position += door.open_offset;
     }
 }
 
 pub fn plugin(app: &mut App) {
     app
-        .add_plugins((
-            EntityEventRoutePlugin::<OnEntityDoubleClick, Door>::default(),
-        ))
+        .add_double_click_handler::<Door, _>(double_click_door)
         .register_type::<Door>()
         .register_type::<FourWayDoor>()
         .register_type::<DoorCcw>()
-        .add_systems(First, (
-            double_click_doors.in_set(DefaultGameSet::HandleEvents),
-        ))
         .add_systems(Update, (
             (
                 update_four_way_doors,
@@ -134,3 +137,57 @@ pub fn plugin(app: &mut App) {
             ).chain(),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::housing::{HouseFootprint, HouseOwnership};
+
+    use super::*;
+
+    #[test]
+    fn test_house_door_access() {
+        let mut world = World::new();
+        world.init_resource::<Events<OnSound>>();
+        let owner = world.spawn_empty().id();
+        let co_owner = world.spawn_empty().id();
+        let friend = world.spawn_empty().id();
+        let stranger = world.spawn_empty().id();
+        let banned = world.spawn_empty().id();
+        let mut ownership = HouseOwnership::new(owner);
+        ownership.co_owners.insert(co_owner);
+        ownership.friends.insert(friend);
+        ownership.bans.insert(banned);
+        world.spawn((ownership, HouseFootprint {
+            map_id: 1,
+            min: IVec2::new(10, 10),
+            max: IVec2::new(20, 20),
+        }));
+        let door = world
+            .spawn((
+                Door::default(),
+                MapPosition { position: IVec3::new(15, 20, 0), map_id: 1 },
+            ))
+            .id();
+
+        let checks = [
+            (owner, true),
+            (co_owner, true),
+            (friend, true),
+            (stranger, false),
+            (banned, false),
+        ];
+        for (character, expected) in checks {
+            world.get_mut::<Door>(door).unwrap().opened = false;
+            let event = OnEntityDoubleClick {
+                client_entity: Entity::PLACEHOLDER,
+                character,
+                target: door,
+                paperdoll: false,
+            };
+            world.run_system_once_with(event, double_click_door).unwrap();
+            assert_eq!(world.get::<Door>(door).unwrap().opened, expected);
+        }
+    }
+}
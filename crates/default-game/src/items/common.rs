@@ -4,6 +4,7 @@ use bevy::prelude::*;
 use yewoh_server::world::items::{ItemGraphic, ItemGraphicOffset, ItemQuantity};
 use crate::characters::corpses::Corpse;
 use crate::DefaultGameSet;
+use crate::entities::names::DisplayNames;
 use crate::entities::tooltips::{OnRequestEntityTooltip, TooltipLine, TOOLTIP_NAME_PRIORITY};
 use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
 use crate::format::FormatInteger;
@@ -28,22 +29,26 @@ pub fn add_item_names(
 }
 
 pub fn add_item_name_tooltip(
-    names: Query<(&ItemName, &ItemQuantity), Without<Corpse>>,
+    names: DisplayNames,
+    quantities: Query<&ItemQuantity, Without<Corpse>>,
     mut events: EntityEventReader<OnRequestEntityTooltip, (ItemName, ItemQuantity)>,
 ) {
     for event in events.read() {
-        let Ok((name, quantity)) = names.get(event.target) else {
+        let Ok(quantity) = quantities.get(event.target) else {
+            continue;
+        };
+        let Some(name) = names.item_name(event.target) else {
             continue;
         };
 
         let text = if **quantity == 1 {
-            match name {
+            match &name {
                 ItemName::Localised(index) => index.clone(),
                 ItemName::Dynamic(name) =>
                     LocalisedString::from_str(name.to_string()),
             }
         } else {
-            let arguments = match name {
+            let arguments = match &name {
                 ItemName::Localised(s) =>
                     format!("{}\t{}", FormatInteger::from(**quantity), s.as_argument()),
                 ItemName::Dynamic(name) =>
@@ -61,6 +66,27 @@ pub fn add_item_name_tooltip(
     }
 }
 
+pub fn add_item_quantity_tooltip(
+    quantities: Query<&ItemQuantity, (Without<ItemName>, Without<Corpse>)>,
+    mut events: EntityEventReader<OnRequestEntityTooltip, ItemQuantity>,
+) {
+    for event in events.read() {
+        // Named items already include the quantity in their name line.
+        let Ok(quantity) = quantities.get(event.target) else {
+            continue;
+        };
+
+        if **quantity <= 1 {
+            continue;
+        }
+
+        event.lines.push(TooltipLine::from_str(
+            format!("({})", FormatInteger::from(**quantity)),
+            TOOLTIP_NAME_PRIORITY + 1,
+        ));
+    }
+}
+
 #[derive(Clone, Debug, Default, Component, Reflect)]
 #[reflect(Component)]
 pub struct CanLift;
@@ -69,6 +95,11 @@ pub struct CanLift;
 #[reflect(Component)]
 pub struct Stackable;
 
+#[derive(Clone, Copy, Debug, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component)]
+#[require(Stackable)]
+pub struct MaxStack(pub u16);
+
 #[derive(Clone, Copy, Debug, Default, Deref, DerefMut, Reflect, Component)]
 #[reflect(Default, Component)]
 pub struct DropSound(pub u16);
@@ -143,10 +174,12 @@ pub fn plugin(app: &mut App) {
     app
         .add_plugins((
             EntityEventRoutePlugin::<OnRequestEntityTooltip, (ItemName, ItemQuantity)>::default(),
+            EntityEventRoutePlugin::<OnRequestEntityTooltip, ItemQuantity>::default(),
         ))
         .register_type::<ItemName>()
         .register_type::<CanLift>()
         .register_type::<Stackable>()
+        .register_type::<MaxStack>()
         .register_type::<DropSound>()
         .register_type::<DropSoundByQuantityEntry>()
         .register_type::<DropSoundByQuantity>()
@@ -155,6 +188,7 @@ pub fn plugin(app: &mut App) {
         .register_type_data::<Vec<GraphicOffsetEntry>, ReflectFromReflect>()
         .add_systems(First, (
             add_item_name_tooltip.in_set(DefaultGameSet::HandleEvents),
+            add_item_quantity_tooltip.in_set(DefaultGameSet::HandleEvents),
         ))
         .add_systems(Update, (
             update_graphic_offset_by_quantity,
@@ -162,3 +196,98 @@ pub fn plugin(app: &mut App) {
             add_item_names,
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::names::CustomName;
+    use crate::entity_events::EntityEventPlugin;
+
+    use super::*;
+
+    #[derive(Clone, Debug, Default, Resource)]
+    struct CollectedLines(Vec<(Entity, Vec<TooltipLine>)>);
+
+    fn collect_lines(
+        mut events: EntityEventReader<OnRequestEntityTooltip, ()>,
+        mut collected: ResMut<CollectedLines>,
+    ) {
+        for event in events.read() {
+            collected.0.push((event.target, event.lines.clone()));
+        }
+    }
+
+    fn tooltip_app() -> App {
+        let mut app = App::new();
+        app
+            .init_resource::<CollectedLines>()
+            .add_plugins((
+                EntityEventPlugin::<OnRequestEntityTooltip>::default(),
+                EntityEventRoutePlugin::<OnRequestEntityTooltip, ()>::default(),
+                plugin,
+            ))
+            .configure_sets(First, (
+                DefaultGameSet::DispatchEvents,
+                DefaultGameSet::HandleEvents,
+                DefaultGameSet::FinishEvents,
+            ).chain())
+            .add_systems(First, collect_lines.in_set(DefaultGameSet::FinishEvents));
+        app
+    }
+
+    fn request_tooltips(app: &mut App, targets: &[Entity]) -> Vec<Vec<TooltipLine>> {
+        let client_entity = app.world_mut().spawn_empty().id();
+        for target in targets {
+            app.world_mut().send_event(OnRequestEntityTooltip {
+                client_entity,
+                target: *target,
+                lines: Vec::new(),
+            });
+        }
+        app.update();
+
+        let collected = &app.world().resource::<CollectedLines>().0;
+        targets.iter()
+            .map(|target| collected.iter()
+                .find(|(entity, _)| entity == target)
+                .map(|(_, lines)| lines.clone())
+                .unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_name_tooltip() {
+        let mut app = tooltip_app();
+        let localised = app.world_mut()
+            .spawn((ItemName::Localised(LocalisedString::from_id(1020001)), ItemQuantity(1)))
+            .id();
+        let renamed = app.world_mut()
+            .spawn((
+                ItemName::Localised(LocalisedString::from_id(1020001)),
+                CustomName("Widowmaker".into()),
+                ItemQuantity(1),
+            ))
+            .id();
+        let stacked = app.world_mut()
+            .spawn((ItemName::Dynamic("arrow".into()), ItemQuantity(5)))
+            .id();
+
+        let lines = request_tooltips(&mut app, &[localised, renamed, stacked]);
+        assert_eq!(lines[0], vec![TooltipLine { text: LocalisedString::from_id(1020001), priority: TOOLTIP_NAME_PRIORITY }]);
+        assert_eq!(lines[1], vec![TooltipLine { text: LocalisedString::from_str("Widowmaker"), priority: TOOLTIP_NAME_PRIORITY }]);
+        assert_eq!(lines[2], vec![TooltipLine {
+            text: LocalisedString { text_id: 1050039, arguments: "5\tarrow".into() },
+            priority: TOOLTIP_NAME_PRIORITY,
+        }]);
+    }
+
+    #[test]
+    fn test_quantity_tooltip() {
+        let mut app = tooltip_app();
+        let stacked = app.world_mut().spawn(ItemQuantity(25)).id();
+        let single = app.world_mut().spawn(ItemQuantity(1)).id();
+
+        let lines = request_tooltips(&mut app, &[stacked, single]);
+        assert_eq!(lines[0], vec![TooltipLine::from_str("(25)", TOOLTIP_NAME_PRIORITY + 1)]);
+        assert!(lines[1].is_empty());
+    }
+}
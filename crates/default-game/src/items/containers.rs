@@ -1,33 +1,273 @@
 use bevy::prelude::*;
-use yewoh_server::world::items::OnContainerOpen;
+use bevy::utils::{HashMap, HashSet};
+use yewoh_server::world::characters::{CharacterBodyType, Encumbrance};
+use yewoh_server::world::connection::{NetClient, Possessing};
+use yewoh_server::world::items::{Container, ItemQuantity, OnContainerOpen};
 
 use crate::DefaultGameSet;
-use crate::entities::interactions::OnEntityDoubleClick;
+use crate::entities::common::Weight;
+use crate::entities::context_menu::{ContextMenuEntry, OnEntityContextMenuRequest};
+use crate::entities::interactions::{DoubleClickAppExt, OnEntityDoubleClick};
 use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
+use crate::housing::HousePermissions;
+use crate::networking::NetClientExt;
+
+const OPEN_CONTAINER_ID: u16 = 1;
 
 #[derive(Clone, Debug, Default, Component, Reflect)]
 #[reflect(Component)]
 pub struct DoubleClickOpenContainer;
 
-pub fn open_containers(
-    mut events: EntityEventReader<OnEntityDoubleClick, DoubleClickOpenContainer>,
+/// The weight of an entity including everything it contains or carries.
+#[derive(Clone, Copy, Debug, Default, Deref, DerefMut, Eq, PartialEq, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct TotalWeight(pub u32);
+
+/// Scales the weight of a container's contents, i.e. for bags of holding.
+#[derive(Clone, Copy, Debug, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component)]
+pub struct ContentsWeightScale(pub f32);
+
+pub fn open_container(
+    In(event): In<OnEntityDoubleClick>,
+    clients: Query<&NetClient>,
+    permissions: HousePermissions,
     mut out_events: EventWriter<OnContainerOpen>,
+) {
+    if !permissions.can_open_container(event.character, event.target) {
+        if let Ok(client) = clients.get(event.client_entity) {
+            client.send_system_message("That is secure.");
+        }
+        return;
+    }
+
+    out_events.send(OnContainerOpen {
+        client_entity: event.client_entity,
+        container: event.target,
+    });
+}
+
+pub fn container_context_menu(
+    clients: Query<&Possessing>,
+    permissions: HousePermissions,
+    mut events: EntityEventReader<OnEntityContextMenuRequest, DoubleClickOpenContainer>,
 ) {
     for event in events.read() {
-        out_events.send(OnContainerOpen {
+        let Ok(possessing) = clients.get(event.client_entity) else {
+            continue;
+        };
+
+        if !permissions.can_open_container(possessing.entity, event.target) {
+            continue;
+        }
+
+        let request = OnContainerOpen {
             client_entity: event.client_entity,
             container: event.target,
-        });
+        };
+        event.entries.push(ContextMenuEntry {
+            id: OPEN_CONTAINER_ID,
+            text_id: 3000362,
+            ..default()
+        }.with_event(request));
+    }
+}
+
+pub fn add_total_weight(
+    mut commands: Commands,
+    query: Query<Entity, (Or<(With<Container>, With<CharacterBodyType>)>, Without<TotalWeight>)>,
+) {
+    for entity in &query {
+        commands.entity(entity).insert(TotalWeight::default());
+    }
+}
+
+type WeightQuery<'w, 's> = Query<'w, 's, (
+    Option<&'static Weight>,
+    Option<&'static ItemQuantity>,
+    Option<&'static Children>,
+    Option<&'static ContentsWeightScale>,
+)>;
+
+fn calculate_total_weight(
+    entity: Entity,
+    weights: &WeightQuery,
+    cached: &impl Fn(Entity) -> Option<u32>,
+    memo: &mut HashMap<Entity, Option<u32>>,
+) -> u32 {
+    match memo.get(&entity) {
+        Some(Some(total)) => return *total,
+        Some(None) => {
+            warn!("weight calculation for {entity} contains a cycle");
+            return 0;
+        }
+        None => {}
+    }
+
+    // Mark as in-progress so that cycles terminate.
+    memo.insert(entity, None);
+
+    let total = if let Ok((weight, quantity, children, scale)) = weights.get(entity) {
+        let own = weight
+            .map_or(0, |w| w.stack_weight(quantity.map_or(1, |q| **q)) as u32);
+        let contents = children.iter()
+            .flat_map(|children| children.iter())
+            .map(|child| cached(*child)
+                .unwrap_or_else(|| calculate_total_weight(*child, weights, cached, memo)))
+            .fold(0u32, |a, b| a.saturating_add(b));
+        let contents = match scale {
+            Some(scale) => (contents as f32 * **scale).ceil() as u32,
+            None => contents,
+        };
+        own.saturating_add(contents)
+    } else {
+        0
+    };
+
+    memo.insert(entity, Some(total));
+    total
+}
+
+/// Recalculate the total weight of changed entities and everything which contains them.
+pub fn propagate_container_weight(
+    weights: WeightQuery,
+    changed: Query<Entity, Or<(
+        Changed<Weight>,
+        Changed<ItemQuantity>,
+        Changed<Children>,
+        Changed<Parent>,
+        Changed<ContentsWeightScale>,
+        Added<TotalWeight>,
+    )>>,
+    parents: Query<&Parent>,
+    mut removed_parents: RemovedComponents<Parent>,
+    mut removed_children: RemovedComponents<Children>,
+    mut removed_weights: RemovedComponents<Weight>,
+    mut totals: Query<(&mut TotalWeight, Option<&mut Encumbrance>)>,
+) {
+    let mut dirty = HashSet::new();
+    let seeds = changed.iter()
+        .chain(removed_parents.read())
+        .chain(removed_children.read())
+        .chain(removed_weights.read());
+    for seed in seeds {
+        let mut next = Some(seed);
+        while let Some(entity) = next {
+            if !dirty.insert(entity) {
+                break;
+            }
+            next = parents.get(entity).ok().map(|parent| parent.get());
+        }
+    }
+
+    if dirty.is_empty() {
+        return;
+    }
+
+    let cached = |entity: Entity| if dirty.contains(&entity) {
+        None
+    } else {
+        totals.get(entity).ok().map(|(total, _)| **total)
+    };
+    let mut memo = HashMap::new();
+    let updated = dirty.iter()
+        .filter(|entity| totals.contains(**entity))
+        .map(|entity| (*entity, calculate_total_weight(*entity, &weights, &cached, &mut memo)))
+        .collect::<Vec<_>>();
+
+    for (entity, total) in updated {
+        let Ok((mut total_weight, encumbrance)) = totals.get_mut(entity) else {
+            continue;
+        };
+        total_weight.set_if_neq(TotalWeight(total));
+
+        if let Some(mut encumbrance) = encumbrance {
+            let value = total.min(u16::MAX as u32) as u16;
+            if encumbrance.encumbrance != value {
+                encumbrance.encumbrance = value;
+            }
+        }
     }
 }
 
 pub fn plugin(app: &mut App) {
     app
         .register_type::<DoubleClickOpenContainer>()
+        .register_type::<TotalWeight>()
+        .register_type::<ContentsWeightScale>()
+        .add_double_click_handler::<DoubleClickOpenContainer, _>(open_container)
         .add_plugins((
-            EntityEventRoutePlugin::<OnEntityDoubleClick, DoubleClickOpenContainer>::default(),
+            EntityEventRoutePlugin::<OnEntityContextMenuRequest, DoubleClickOpenContainer>::default(),
         ))
         .add_systems(First, (
-            open_containers.in_set(DefaultGameSet::HandleEvents),
-        ));
+            container_context_menu.in_set(DefaultGameSet::HandleEvents),
+        ))
+        .add_systems(Update, (
+            add_total_weight,
+            propagate_container_weight,
+        ).chain());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn test_nested_container_weight() {
+        let mut world = World::new();
+        let gold = world.spawn((Weight(0.25), ItemQuantity(100))).id();
+        let ingots = world.spawn((Weight(1.0), ItemQuantity(10))).id();
+        let pouch = world.spawn((Container::default(), Weight(1.0))).id();
+        let bag = world.spawn((Container::default(), Weight(2.0), ContentsWeightScale(0.5))).id();
+        let backpack = world.spawn((Container::default(), Weight(3.0))).id();
+        let character = world.spawn((TotalWeight::default(), Encumbrance::default())).id();
+        world.entity_mut(pouch).add_child(gold);
+        world.entity_mut(bag).add_child(ingots);
+        world.entity_mut(backpack).add_children(&[pouch, bag]);
+        world.entity_mut(character).add_child(backpack);
+
+        world.run_system_once(add_total_weight).unwrap();
+        world.run_system_once(propagate_container_weight).unwrap();
+
+        assert_eq!(**world.get::<TotalWeight>(pouch).unwrap(), 26);
+        assert_eq!(**world.get::<TotalWeight>(bag).unwrap(), 7);
+        assert_eq!(**world.get::<TotalWeight>(backpack).unwrap(), 36);
+        assert_eq!(**world.get::<TotalWeight>(character).unwrap(), 36);
+        assert_eq!(world.get::<Encumbrance>(character).unwrap().encumbrance, 36);
+    }
+
+    #[test]
+    fn test_only_ancestors_updated() {
+        let mut app = App::new();
+        app.add_systems(Update, (add_total_weight, propagate_container_weight).chain());
+
+        let world = app.world_mut();
+        let gold = world.spawn((Weight(1.0), ItemQuantity(10))).id();
+        let pouch = world.spawn((Container::default(), Weight(1.0))).id();
+        let backpack = world.spawn((Container::default(), Weight(3.0))).id();
+        let other = world.spawn((Container::default(), Weight(2.0))).id();
+        world.entity_mut(pouch).add_child(gold);
+        world.entity_mut(backpack).add_child(pouch);
+        app.update();
+
+        assert_eq!(**app.world().get::<TotalWeight>(backpack).unwrap(), 14);
+        assert_eq!(**app.world().get::<TotalWeight>(other).unwrap(), 2);
+
+        // Corrupt an unrelated total, which should be left alone.
+        app.world_mut().entity_mut(other).insert(TotalWeight(999));
+        app.world_mut().entity_mut(gold).insert(ItemQuantity(20));
+        app.update();
+
+        assert_eq!(**app.world().get::<TotalWeight>(pouch).unwrap(), 21);
+        assert_eq!(**app.world().get::<TotalWeight>(backpack).unwrap(), 24);
+        assert_eq!(**app.world().get::<TotalWeight>(other).unwrap(), 999);
+
+        app.world_mut().entity_mut(gold).despawn_recursive();
+        app.update();
+
+        assert_eq!(**app.world().get::<TotalWeight>(pouch).unwrap(), 1);
+        assert_eq!(**app.world().get::<TotalWeight>(backpack).unwrap(), 4);
+    }
 }
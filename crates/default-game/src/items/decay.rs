@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use bevy::ecs::query::WorldQuery;
+use bevy::prelude::*;
+use yewoh_server::world::entity::{MapPosition, Multi};
+use yewoh_server::world::input::OnClientDrop;
+use yewoh_server::world::items::ItemGraphic;
+
+use crate::DefaultGameSet;
+use crate::entities::Persistent;
+use crate::housing::lockdown::LockedDown;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+
+/// How long an item can be left on the ground before it decays.
+pub const ITEM_DECAY_TIME: Duration = Duration::from_secs(60 * 60);
+
+/// Removes an item which a player has left on the ground, unless it is locked down.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct Decay {
+    pub timer: Timer,
+}
+
+impl Default for Decay {
+    fn default() -> Self {
+        Decay {
+            timer: Timer::new(ITEM_DECAY_TIME, TimerMode::Once),
+        }
+    }
+}
+
+pub fn mark_dropped_items(
+    mut commands: Commands,
+    items: Query<(), (With<ItemGraphic>, With<MapPosition>, Without<Multi>, Without<LockedDown>)>,
+    mut events: EventReader<OnClientDrop>,
+) {
+    for request in events.read() {
+        if items.contains(request.target) {
+            commands.entity(request.target).insert(Decay::default());
+        }
+    }
+}
+
+pub fn remove_decay(
+    mut commands: Commands,
+    items: Query<Entity, (With<Decay>, Or<(Without<MapPosition>, With<LockedDown>)>)>,
+) {
+    for entity in &items {
+        commands.entity(entity).remove::<Decay>();
+    }
+}
+
+pub fn reset_moved_decay(mut items: Query<&mut Decay, Changed<MapPosition>>) {
+    for mut decay in &mut items {
+        decay.timer.reset();
+    }
+}
+
+pub fn decay_items(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut items: Query<(Entity, &mut Decay)>,
+) {
+    for (entity, mut decay) in &mut items {
+        if decay.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DecaySerializer;
+
+impl BundleSerializer for DecaySerializer {
+    type Query = &'static Decay;
+    type Filter = With<Persistent>;
+    type Bundle = Duration;
+
+    fn id() -> &'static str {
+        "Decay"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        item.timer.remaining()
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        let mut decay = Decay::default();
+        decay.timer.set_elapsed(ITEM_DECAY_TIME.saturating_sub(bundle));
+        world.entity_mut(entity).insert(decay);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<Decay>()
+        .register_serializer::<DecaySerializer>()
+        .add_systems(First, mark_dropped_items.in_set(DefaultGameSet::HandleEvents))
+        .add_systems(Update, (
+            remove_decay,
+            reset_moved_decay,
+            decay_items,
+        ).chain());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decay_app() -> App {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .add_event::<OnClientDrop>()
+            .add_systems(First, mark_dropped_items)
+            .add_systems(Update, (
+                remove_decay,
+                reset_moved_decay,
+                decay_items,
+            ).chain());
+        app
+    }
+
+    fn spawn_item(app: &mut App) -> Entity {
+        app.world_mut()
+            .spawn((ItemGraphic(0xeed), MapPosition { position: IVec3::new(1, 1, 0), map_id: 1 }))
+            .id()
+    }
+
+    fn drop_item(app: &mut App) -> Entity {
+        let item = spawn_item(app);
+        let client_entity = app.world_mut().spawn_empty().id();
+        app.world_mut().send_event(OnClientDrop {
+            client_entity,
+            target: item,
+            position: IVec3::new(1, 1, 0),
+            grid_index: 0,
+            dropped_on: None,
+        });
+        item
+    }
+
+    fn advance(app: &mut App, duration: Duration) {
+        app.world_mut().resource_mut::<Time>().advance_by(duration);
+        app.update();
+    }
+
+    #[test]
+    fn test_dropped_item_decays() {
+        let mut app = decay_app();
+        let item = drop_item(&mut app);
+        app.update();
+        assert!(app.world().get::<Decay>(item).is_some());
+
+        advance(&mut app, ITEM_DECAY_TIME / 2);
+        assert!(app.world().get_entity(item).is_ok());
+
+        // Moving the item restarts the timer.
+        app.world_mut().get_mut::<MapPosition>(item).unwrap().position.x += 1;
+        advance(&mut app, ITEM_DECAY_TIME * 3 / 4);
+        assert!(app.world().get_entity(item).is_ok());
+
+        advance(&mut app, ITEM_DECAY_TIME / 2);
+        assert!(app.world().get_entity(item).is_err());
+    }
+
+    #[test]
+    fn test_placed_item_does_not_decay() {
+        let mut app = decay_app();
+        let item = spawn_item(&mut app);
+        app.world_mut().entity_mut(item).insert(Persistent);
+        let locked = drop_item(&mut app);
+        app.world_mut().entity_mut(locked).insert(LockedDown { house: Entity::PLACEHOLDER });
+        app.update();
+
+        advance(&mut app, ITEM_DECAY_TIME * 2);
+        assert!(app.world().get_entity(item).is_ok());
+        assert!(app.world().get_entity(locked).is_ok());
+        assert!(app.world().get::<Decay>(item).is_none());
+        assert!(app.world().get::<Decay>(locked).is_none());
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use yewoh::protocol::EntityLightLevel;
+use yewoh_server::world::characters::CharacterBodyType;
+use yewoh_server::world::connection::{NetClient, OwningClient};
+use yewoh_server::world::entity::EquippedPosition;
+use yewoh_server::world::net_id::NetId;
+
+/// An item which lights up the surroundings of whoever is holding or wearing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Component, Deserialize)]
+#[reflect(Component, Default, Deserialize)]
+pub struct LightSource {
+    pub level: u8,
+}
+
+/// The light level around a character from the light sources it has equipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deref, DerefMut, Reflect, Component)]
+#[reflect(Component, Default)]
+pub struct PersonalLight(pub u8);
+
+/// Take the brightest equipped [`LightSource`] as each character's [`PersonalLight`].
+pub fn update_personal_light(
+    mut commands: Commands,
+    mut characters: Query<(Option<&Children>, Option<&mut PersonalLight>), With<CharacterBodyType>>,
+    changed_characters: Query<Entity, (With<CharacterBodyType>, Changed<Children>)>,
+    mut removed_children: RemovedComponents<Children>,
+    changed_lights: Query<
+        &Parent,
+        (Without<CharacterBodyType>, Or<(Changed<LightSource>, Changed<EquippedPosition>)>),
+    >,
+    lights: Query<&LightSource, (With<EquippedPosition>, Without<CharacterBodyType>)>,
+) {
+    let mut dirty = changed_characters.iter()
+        .chain(removed_children.read())
+        .chain(changed_lights.iter().map(|parent| parent.get()))
+        .collect::<Vec<_>>();
+    dirty.sort();
+    dirty.dedup();
+
+    for entity in dirty {
+        let Ok((children, personal_light)) = characters.get_mut(entity) else {
+            continue;
+        };
+
+        let level = children.map_or(&[][..], |children| &children[..]).iter()
+            .filter_map(|child| lights.get(*child).ok())
+            .map(|light| light.level)
+            .max()
+            .unwrap_or(0);
+
+        match personal_light {
+            Some(mut personal_light) => {
+                personal_light.set_if_neq(PersonalLight(level));
+            }
+            None if level > 0 => {
+                commands.entity(entity).insert(PersonalLight(level));
+            }
+            None => {}
+        }
+    }
+}
+
+pub fn send_personal_light(
+    clients: Query<&NetClient>,
+    characters: Query<(&NetId, &PersonalLight, &OwningClient), Changed<PersonalLight>>,
+) {
+    for (net_id, personal_light, owning_client) in &characters {
+        let Ok(client) = clients.get(owning_client.client_entity) else {
+            continue;
+        };
+
+        client.send_packet(EntityLightLevel {
+            id: net_id.id,
+            light_level: **personal_light,
+        });
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<LightSource>()
+        .register_type::<PersonalLight>()
+        .add_systems(Update, (
+            update_personal_light,
+            send_personal_light,
+        ).chain());
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh::EntityId;
+    use yewoh_server::world::connection::WriterAction;
+    use yewoh_server::world::entity::EquipmentSlot;
+
+    use crate::networking::{sent_packets, test_client};
+
+    use super::*;
+
+    fn light_levels(rx: &mut UnboundedReceiver<WriterAction>) -> Vec<u8> {
+        sent_packets::<EntityLightLevel>(rx).into_iter()
+            .map(|packet| packet.light_level)
+            .collect()
+    }
+
+    #[test]
+    fn test_equip_light_source() {
+        let mut app = App::new();
+        app.add_plugins(plugin);
+
+        let (client, mut rx) = test_client();
+        let client_entity = app.world_mut().spawn(client).id();
+        let character = app.world_mut()
+            .spawn((
+                CharacterBodyType(0x190),
+                NetId { id: EntityId::from_u32(1) },
+                OwningClient { client_entity },
+            ))
+            .id();
+        app.update();
+        assert!(app.world().get::<PersonalLight>(character).is_none());
+
+        let torch = app.world_mut()
+            .spawn((LightSource { level: 20 }, EquippedPosition { slot: EquipmentSlot::OffHand }))
+            .set_parent(character)
+            .id();
+        app.update();
+        app.update();
+        assert_eq!(**app.world().get::<PersonalLight>(character).unwrap(), 20);
+        assert_eq!(light_levels(&mut rx), vec![20]);
+
+        app.world_mut().entity_mut(torch).remove_parent();
+        app.update();
+        assert_eq!(**app.world().get::<PersonalLight>(character).unwrap(), 0);
+        assert_eq!(light_levels(&mut rx), vec![0]);
+    }
+}
@@ -6,8 +6,18 @@ pub mod common;
 
 pub mod containers;
 
+pub mod prefabs;
+
 pub mod buildings;
 
+pub mod decay;
+
+pub mod teleporters;
+
+pub mod lights;
+
+pub mod signs;
+
 pub const MAX_STACK: u16 = 60000;
 
 #[derive(Default)]
@@ -20,7 +30,12 @@ impl Plugin for ItemsPlugin {
                 persistence::plugin,
                 common::plugin,
                 containers::plugin,
+                prefabs::plugin,
                 buildings::plugin,
+                decay::plugin,
+                teleporters::plugin,
+                lights::plugin,
+                signs::plugin,
             ));
     }
 }
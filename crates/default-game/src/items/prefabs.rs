@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use bevy_fabricator::traits::{Apply, Context, ReflectApply};
+use yewoh_server::world::entity::Hue;
+use yewoh_server::world::items::ItemGraphic;
+
+use crate::entities::common::Weight;
+use crate::items::common::{MaxStack, Stackable};
+
+#[derive(Clone, Debug, Default, Reflect)]
+#[reflect(Default, Apply)]
+pub struct ItemPrefab {
+    pub graphic: u16,
+    #[reflect(default)]
+    pub hue: Option<u16>,
+    #[reflect(default)]
+    pub weight: Option<f32>,
+    #[reflect(default)]
+    pub stackable: bool,
+    /// Implies `stackable`.
+    #[reflect(default)]
+    pub max_stack: Option<u16>,
+}
+
+impl Apply for ItemPrefab {
+    fn apply(&self, ctx: &mut Context, entity: Entity) -> anyhow::Result<()> {
+        let mut entity_mut = ctx.world.entity_mut(entity);
+        entity_mut.insert(ItemGraphic(self.graphic));
+
+        if let Some(hue) = self.hue {
+            entity_mut.insert(Hue(hue));
+        }
+
+        if let Some(weight) = self.weight {
+            entity_mut.insert(Weight(weight));
+        }
+
+        if self.stackable {
+            entity_mut.insert(Stackable);
+        }
+
+        if let Some(max_stack) = self.max_stack {
+            entity_mut.insert(MaxStack(max_stack));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<ItemPrefab>();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_fabricator::Fabricated;
+
+    use super::*;
+
+    #[test]
+    fn test_item_prefab() {
+        let mut world = World::new();
+        let plain = world.spawn_empty().id();
+        let heavy = world.spawn_empty().id();
+        let mut ctx = Context {
+            world: &mut world,
+            fabricated: Fabricated::default(),
+        };
+
+        ItemPrefab { graphic: 0xeed, ..default() }.apply(&mut ctx, plain).unwrap();
+        ItemPrefab {
+            graphic: 0x1bdd,
+            weight: Some(2.0),
+            max_stack: Some(100),
+            ..default()
+        }.apply(&mut ctx, heavy).unwrap();
+
+        assert_eq!(**world.get::<ItemGraphic>(plain).unwrap(), 0xeed);
+        assert!(world.get::<Weight>(plain).is_none());
+        assert!(world.get::<Stackable>(plain).is_none());
+        assert_eq!(**world.get::<Weight>(heavy).unwrap(), 2.0);
+        assert_eq!(**world.get::<MaxStack>(heavy).unwrap(), 100);
+        assert!(world.get::<Stackable>(heavy).is_some());
+    }
+}
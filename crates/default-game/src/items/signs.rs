@@ -0,0 +1,186 @@
+use bevy::ecs::query::WorldQuery;
+use bevy::prelude::*;
+use yewoh::protocol::{MessageKind, UnicodeTextMessage};
+use yewoh::types::FixedString;
+use yewoh_server::world::connection::NetClient;
+use yewoh_server::world::entity::Tooltip;
+use yewoh_server::world::items::ItemGraphic;
+use yewoh_server::world::net_id::NetId;
+
+use crate::DefaultGameSet;
+use crate::entities::Persistent;
+use crate::entities::interactions::OnEntitySingleClick;
+use crate::entities::tooltips::{MarkTooltipChanged, OnRequestEntityTooltip, TooltipLine};
+use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
+use crate::hues;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+
+/// An item which displays some text, i.e. a shop sign.
+#[derive(Clone, Debug, Default, Component, Reflect)]
+#[reflect(Component, Default)]
+#[require(Tooltip)]
+pub struct Sign {
+    pub text: String,
+}
+
+/// Replace the text on a sign, turning an item into a sign if it isn't one already.
+///
+/// Anything which is neither a sign nor an item is left alone.
+#[derive(Clone, Debug)]
+pub struct SetSignText(pub String);
+
+impl EntityCommand for SetSignText {
+    fn apply(self, entity: Entity, world: &mut World) {
+        let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+            return;
+        };
+
+        if !entity_mut.contains::<Sign>() && !entity_mut.contains::<ItemGraphic>() {
+            return;
+        }
+
+        entity_mut.insert(Sign { text: self.0 });
+        MarkTooltipChanged.apply(entity, world);
+    }
+}
+
+pub fn add_sign_tooltip(
+    signs: Query<&Sign>,
+    mut events: EntityEventReader<OnRequestEntityTooltip, Sign>,
+) {
+    for event in events.read() {
+        let Ok(sign) = signs.get(event.target) else {
+            continue;
+        };
+
+        if sign.text.is_empty() {
+            continue;
+        }
+
+        event.lines.push(TooltipLine::from_str(sign.text.clone(), 0));
+    }
+}
+
+pub fn show_sign_label(
+    clients: Query<&NetClient>,
+    signs: Query<(&Sign, &NetId)>,
+    mut events: EntityEventReader<OnEntitySingleClick, Sign>,
+) {
+    for event in events.read() {
+        let Ok(client) = clients.get(event.client_entity) else {
+            continue;
+        };
+
+        let Ok((sign, net_id)) = signs.get(event.target) else {
+            continue;
+        };
+
+        client.send_packet(UnicodeTextMessage {
+            entity_id: Some(net_id.id),
+            kind: MessageKind::Label,
+            language: FixedString::from_str("ENU"),
+            text: sign.text.clone(),
+            hue: hues::GREY,
+            font: 3,
+            ..Default::default()
+        });
+    }
+}
+
+#[derive(Default)]
+pub struct SignSerializer;
+
+impl BundleSerializer for SignSerializer {
+    type Query = &'static Sign;
+    type Filter = With<Persistent>;
+    type Bundle = String;
+
+    fn id() -> &'static str {
+        "Sign"
+    }
+
+    fn extract(sign: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        sign.text.clone()
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(Sign { text: bundle });
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<Sign>()
+        .register_serializer::<SignSerializer>()
+        .add_plugins((
+            EntityEventRoutePlugin::<OnRequestEntityTooltip, Sign>::default(),
+            EntityEventRoutePlugin::<OnEntitySingleClick, Sign>::default(),
+        ))
+        .add_systems(First, (
+            (
+                add_sign_tooltip,
+                show_sign_label,
+            ).in_set(DefaultGameSet::HandleEvents),
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity_events::EntityEventPlugin;
+
+    use super::*;
+
+    #[derive(Clone, Debug, Default, Resource)]
+    struct CollectedLines(Vec<TooltipLine>);
+
+    fn collect_lines(
+        mut events: EntityEventReader<OnRequestEntityTooltip, ()>,
+        mut collected: ResMut<CollectedLines>,
+    ) {
+        for event in events.read() {
+            collected.0.extend(event.lines.iter().cloned());
+        }
+    }
+
+    #[test]
+    fn test_set_sign_text() {
+        let mut world = World::new();
+        let item = world.spawn(ItemGraphic(0xbd2)).id();
+        let character = world.spawn_empty().id();
+
+        SetSignText("The Sweaty Sheep".into()).apply(item, &mut world);
+        SetSignText("The Sweaty Sheep".into()).apply(character, &mut world);
+        assert_eq!(world.get::<Sign>(item).unwrap().text, "The Sweaty Sheep");
+        assert!(world.get::<Sign>(character).is_none());
+    }
+
+    #[test]
+    fn test_sign_tooltip() {
+        let mut app = App::new();
+        app
+            .init_resource::<CollectedLines>()
+            .add_plugins((
+                EntityEventPlugin::<OnRequestEntityTooltip>::default(),
+                EntityEventPlugin::<OnEntitySingleClick>::default(),
+                EntityEventRoutePlugin::<OnRequestEntityTooltip, ()>::default(),
+                plugin,
+            ))
+            .configure_sets(First, (
+                DefaultGameSet::DispatchEvents,
+                DefaultGameSet::HandleEvents,
+                DefaultGameSet::FinishEvents,
+            ).chain())
+            .add_systems(First, collect_lines.in_set(DefaultGameSet::FinishEvents));
+
+        let client_entity = app.world_mut().spawn_empty().id();
+        let sign = app.world_mut().spawn(Sign { text: "The Sweaty Sheep".into() }).id();
+        app.world_mut().send_event(OnRequestEntityTooltip {
+            client_entity,
+            target: sign,
+            lines: Vec::new(),
+        });
+        app.update();
+
+        assert_eq!(app.world().resource::<CollectedLines>().0, vec![TooltipLine::from_str("The Sweaty Sheep", 0)]);
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use yewoh_server::world::characters::CharacterBodyType;
+use yewoh_server::world::entity::MapPosition;
+use yewoh_server::world::sound::OnSound;
+use yewoh_server::world::spatial::SpatialQuery;
+
+use crate::entities::position::PositionExt;
+
+/// Moves characters which step onto this item's tile to `destination`.
+#[derive(Debug, Clone, Default, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct Teleporter {
+    pub destination: MapPosition,
+    pub sound_id: u16,
+}
+
+/// Where a character last arrived by teleporter, so that a teleporter at the
+/// destination does not immediately send them back.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct TeleportArrival(pub MapPosition);
+
+pub fn trigger_teleporters(
+    spatial_query: SpatialQuery,
+    teleporters: Query<&Teleporter>,
+    characters: Query<
+        (Entity, &MapPosition, Option<&TeleportArrival>),
+        (With<CharacterBodyType>, Changed<MapPosition>),
+    >,
+    mut commands: Commands,
+    mut sounds: EventWriter<OnSound>,
+) {
+    for (entity, position, arrival) in &characters {
+        if let Some(arrival) = arrival {
+            if arrival.0 == *position {
+                continue;
+            }
+            commands.entity(entity).remove::<TeleportArrival>();
+        }
+
+        let tile = position.position.truncate();
+        let teleporter = spatial_query.static_items.lookup.entries_at(position.map_id, tile).iter()
+            .chain(spatial_query.dynamic_items.lookup.entries_at(position.map_id, tile))
+            .find_map(|entry| teleporters.get(entry.entity).ok());
+        let Some(teleporter) = teleporter else {
+            continue;
+        };
+
+        if teleporter.sound_id != 0 {
+            sounds.send(OnSound {
+                sound_id: teleporter.sound_id,
+                position: *position,
+                ..default()
+            });
+        }
+
+        commands.entity(entity)
+            .move_to_map_position(teleporter.destination)
+            .insert(TeleportArrival(teleporter.destination));
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<Teleporter>()
+        .add_systems(Update, (
+            trigger_teleporters,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use yewoh_server::world::spatial::{
+        ChunkLookup,
+        ItemEntry,
+        SpatialCharacterLookup,
+        SpatialDynamicItemLookup,
+        SpatialStaticItemLookup,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_teleport_once() {
+        let mut app = App::new();
+        app
+            .add_event::<OnSound>()
+            .init_resource::<SpatialCharacterLookup>()
+            .init_resource::<SpatialStaticItemLookup>()
+            .init_resource::<ChunkLookup>()
+            .add_systems(Update, trigger_teleporters);
+
+        let at = |x, y| MapPosition { position: IVec3::new(x, y, 0), map_id: 1 };
+        let mut lookup = SpatialDynamicItemLookup::default();
+        lookup.lookup.insert_map(1, IVec2::splat(32));
+
+        // Two teleporters which lead to each other.
+        for (from, to) in [(at(5, 5), at(20, 20)), (at(20, 20), at(5, 5))] {
+            let entity = app.world_mut()
+                .spawn((from, Teleporter { destination: to, sound_id: 0x1fe }))
+                .id();
+            lookup.lookup.insert(1, from.position.truncate(), ItemEntry {
+                entity,
+                z_min: 0,
+                z_max: 0,
+                graphic: 0,
+            });
+        }
+        app.insert_resource(lookup);
+
+        let character = app.world_mut().spawn((CharacterBodyType(0x190), at(5, 5))).id();
+        app.update();
+        assert_eq!(*app.world().get::<MapPosition>(character).unwrap(), at(20, 20));
+        assert_eq!(app.world().resource::<Events<OnSound>>().len(), 1);
+
+        app.update();
+        app.update();
+        assert_eq!(*app.world().get::<MapPosition>(character).unwrap(), at(20, 20));
+    }
+}
@@ -8,13 +8,13 @@ use yewoh_server::world::ServerSet;
 use crate::accounts::AccountsPlugin;
 use crate::activities::ActivitiesPlugin;
 use crate::ai::AiPlugin;
-use crate::chat::on_client_chat_message;
+use crate::chat::{forget_disconnected_chat_clients, on_client_chat_message, ActiveChatFilter, OnCharacterSpeech};
 use crate::commands::CommandsPlugin;
 use crate::entities::EntitiesPlugin;
 use crate::items::ItemsPlugin;
 use crate::persistence::PersistencePlugin;
 use crate::spawners::SpawnersPlugin;
-use crate::time::send_time;
+use crate::time::{send_season, send_time, WorldClock};
 
 pub mod entity_events;
 
@@ -56,6 +56,26 @@ pub mod reflect;
 
 pub mod gumps;
 
+pub mod rng;
+
+pub mod motd;
+
+pub mod pets;
+
+pub mod housing;
+
+pub mod regions;
+
+pub mod quests;
+
+pub mod dialogue;
+
+pub mod npcs;
+
+pub mod weather;
+
+pub mod rates;
+
 pub mod worldgen;
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, SystemSet)]
@@ -96,6 +116,16 @@ impl Plugin for DefaultGamePlugin {
                 l10n::plugin,
                 gumps::plugin,
                 worldgen::plugin,
+                rng::plugin,
+                motd::plugin,
+                pets::plugin,
+                housing::plugin,
+                regions::plugin,
+                quests::plugin,
+                dialogue::plugin,
+                npcs::plugin,
+                weather::plugin,
+                rates::plugin,
             ))
             .configure_sets(First, (
                 (
@@ -104,11 +134,16 @@ impl Plugin for DefaultGamePlugin {
                     DefaultGameSet::FinishEvents,
                 ).chain(),
             ))
+            .init_resource::<ActiveChatFilter>()
+            .init_resource::<WorldClock>()
+            .add_event::<OnCharacterSpeech>()
             .add_systems(First, (
                 on_client_chat_message.in_set(ServerSet::HandlePackets),
             ))
             .add_systems(Last, (
+                forget_disconnected_chat_clients,
                 send_time.in_set(ServerSet::Send),
+                send_season.in_set(ServerSet::Send),
             ));
     }
 
@@ -0,0 +1,151 @@
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use yewoh::protocol::{GumpLayout, MessageKind, UnicodeTextMessage};
+use yewoh_server::gump_builder::{GumpBuilder, GumpRect, GumpRectLayout, GumpText};
+use yewoh_server::world::connection::NetClient;
+use yewoh_server::world::gump::{Gump, GumpClient};
+use yewoh_server::world::view::OnClientSynchronized;
+
+use crate::DefaultGameSet;
+use crate::gumps::RESIZABLE_PAPER_3;
+
+pub const MOTD_GUMP_ID: u32 = 0x4d4f5444;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MotdStyle {
+    #[default]
+    Gump,
+    Message,
+}
+
+/// The message of the day, shown to players when they log in.
+#[derive(Debug, Clone, Default, Deserialize, Resource)]
+pub struct Motd {
+    #[serde(default)]
+    pub title: String,
+    pub text: String,
+    #[serde(default)]
+    pub style: MotdStyle,
+}
+
+impl Motd {
+    pub fn render(&self) -> GumpLayout {
+        let size = IVec2::new(400, 300);
+        let row = 20;
+
+        let mut text = GumpText::new();
+        let mut builder = GumpBuilder::new();
+        let mut layout = GumpRectLayout::new(&mut builder, &mut text, GumpRect::from_zero(size))
+            .background(|builder| builder.image_sliced(RESIZABLE_PAPER_3))
+            .with_padding(16)
+            .into_vbox();
+
+        if !self.title.is_empty() {
+            layout
+                .allocate(row, |builder| builder
+                    .html(format!("<center>{}</center>", self.title)))
+                .gap(row / 2);
+        }
+
+        layout.rest().html_ex(self.text.as_str(), false, true);
+        builder.into_layout(text)
+    }
+}
+
+pub fn send_motd(
+    motd: Option<Res<Motd>>,
+    clients: Query<&NetClient>,
+    mut commands: Commands,
+    mut events: EventReader<OnClientSynchronized>,
+) {
+    let Some(motd) = motd else {
+        events.clear();
+        return;
+    };
+
+    for event in events.read() {
+        let Ok(client) = clients.get(event.client_entity) else {
+            continue;
+        };
+
+        match motd.style {
+            MotdStyle::Gump => {
+                let mut gump = Gump::empty(MOTD_GUMP_ID);
+                gump.set_layout(motd.render());
+                commands.spawn((
+                    gump,
+                    GumpClient(event.client_entity),
+                ));
+            }
+            MotdStyle::Message => {
+                client.send_packet(UnicodeTextMessage {
+                    kind: MessageKind::System,
+                    text: motd.text.clone(),
+                    hue: 0x35,
+                    font: 1,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_systems(First, (
+            send_motd.in_set(DefaultGameSet::HandleEvents),
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+    use yewoh_server::world::connection::WriterAction;
+
+    use crate::networking::{sent_messages, test_client};
+
+    use super::*;
+
+    fn synchronize(motd: Motd) -> (App, Entity, mpsc::UnboundedReceiver<WriterAction>) {
+        let mut app = App::new();
+        app
+            .insert_resource(motd)
+            .add_event::<OnClientSynchronized>()
+            .add_systems(Update, send_motd);
+
+        let (client, rx) = test_client();
+        let client_entity = app.world_mut().spawn(client).id();
+        app.world_mut().send_event(OnClientSynchronized { client_entity });
+        app.update();
+        (app, client_entity, rx)
+    }
+
+    #[test]
+    fn test_motd_message() {
+        let (_app, _, mut rx) = synchronize(Motd {
+            text: "Welcome to Yewoh".into(),
+            style: MotdStyle::Message,
+            ..default()
+        });
+
+        assert_eq!(sent_messages(&mut rx).first().map(String::as_str), Some("Welcome to Yewoh"));
+    }
+
+    #[test]
+    fn test_motd_gump() {
+        let (mut app, client_entity, _rx) = synchronize(Motd {
+            title: "News".into(),
+            text: "Welcome to Yewoh".into(),
+            style: MotdStyle::Gump,
+        });
+
+        let gumps = app.world_mut()
+            .query::<(&Gump, &GumpClient)>()
+            .iter(app.world())
+            .filter(|(_, client)| client.0 == client_entity)
+            .count();
+        assert_eq!(gumps, 1);
+    }
+}
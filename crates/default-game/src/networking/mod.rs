@@ -1,6 +1,12 @@
 use yewoh::protocol::{MessageKind, UnicodeTextMessage};
 use yewoh::types::FixedString;
 use yewoh_server::world::connection::NetClient;
+#[cfg(test)]
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+#[cfg(test)]
+use yewoh::protocol::{AnyDowncast, ClientVersion};
+#[cfg(test)]
+use yewoh_server::world::connection::WriterAction;
 
 use crate::hues;
 
@@ -33,3 +39,34 @@ impl NetClientExt for NetClient {
         });
     }
 }
+
+/// A client which isn't connected to anything, along with everything that is sent to it.
+#[cfg(test)]
+pub(crate) fn test_client() -> (NetClient, UnboundedReceiver<WriterAction>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let client = NetClient::new("127.0.0.1:2593".parse().unwrap(), ClientVersion::new(7, 0, 9, 0), tx);
+    (client, rx)
+}
+
+/// Drain everything sent to a [`test_client`], keeping the packets of type `T`.
+#[cfg(test)]
+pub(crate) fn sent_packets<T: AnyDowncast + Clone>(rx: &mut UnboundedReceiver<WriterAction>) -> Vec<T> {
+    let mut packets = Vec::new();
+    while let Ok(action) = rx.try_recv() {
+        let packet = match &action {
+            WriterAction::Send(_, packet) => packet.downcast_ref::<T>(),
+            WriterAction::SendArc(_, packet) => packet.downcast_ref::<T>(),
+            WriterAction::Close => None,
+        };
+        packets.extend(packet.cloned());
+    }
+    packets
+}
+
+/// The text of every message sent to a [`test_client`].
+#[cfg(test)]
+pub(crate) fn sent_messages(rx: &mut UnboundedReceiver<WriterAction>) -> Vec<String> {
+    sent_packets::<UnicodeTextMessage>(rx).into_iter()
+        .map(|packet| packet.text)
+        .collect()
+}
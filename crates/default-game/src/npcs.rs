@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+use yewoh_server::world::characters::CharacterName;
+use yewoh_server::world::connection::{NetClient, Possessing};
+use yewoh_server::world::entity::MapPosition;
+use yewoh_server::world::net_id::NetId;
+use yewoh_server::world::view::DEFAULT_VIEW_RANGE;
+
+use crate::chat::{speech_message, OnCharacterSpeech};
+use crate::DefaultGameSet;
+
+/// How close a speaker must be for an NPC to hear them.
+pub const SPEECH_RANGE: i32 = 12;
+
+/// The hue NPCs speak in.
+pub const NPC_SPEECH_HUE: u16 = 0x3b2;
+
+/// Replies to speech containing any of a set of keywords.
+///
+/// Each entry pairs the keywords (which may be several words long) with the reply.
+#[derive(Debug, Clone, Default, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct KeywordResponder {
+    pub responses: Vec<(Vec<String>, String)>,
+}
+
+fn split_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl KeywordResponder {
+    /// Find the response to `text`, if it contains any keyword.
+    pub fn respond(&self, text: &str) -> Option<&str> {
+        let words = split_words(text);
+        self.responses.iter()
+            .find(|(keywords, _)| keywords.iter().any(|keyword| {
+                let keyword = split_words(keyword);
+                !keyword.is_empty() && words.windows(keyword.len()).any(|window| window == keyword)
+            }))
+            .map(|(_, response)| response.as_str())
+    }
+}
+
+pub fn respond_to_keywords(
+    clients: Query<(&NetClient, &Possessing)>,
+    positions: Query<&MapPosition>,
+    responders: Query<(&KeywordResponder, &MapPosition, &NetId, &CharacterName)>,
+    mut events: EventReader<OnCharacterSpeech>,
+) {
+    for event in events.read() {
+        let Ok(speaker_position) = positions.get(event.speaker) else {
+            continue;
+        };
+
+        for (responder, position, net_id, name) in &responders {
+            if !position.in_range_2d(speaker_position, SPEECH_RANGE) {
+                continue;
+            }
+
+            let Some(response) = responder.respond(&event.text) else {
+                continue;
+            };
+
+            let message = speech_message(net_id, name.as_str(), response.to_string(), NPC_SPEECH_HUE);
+            for (client, owned) in &clients {
+                let in_view = positions.get(owned.entity)
+                    .is_ok_and(|listener| listener.in_range_2d(position, DEFAULT_VIEW_RANGE));
+                if in_view {
+                    client.send_packet(message.clone());
+                }
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<KeywordResponder>()
+        .add_systems(First, (
+            respond_to_keywords.in_set(DefaultGameSet::HandleEvents),
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh::EntityId;
+    use yewoh::protocol::UnicodeTextMessage;
+    use yewoh_server::world::connection::WriterAction;
+
+    use crate::networking::{sent_packets, test_client};
+
+    use super::*;
+
+    fn at(x: i32) -> MapPosition {
+        MapPosition { position: IVec3::new(x, 10, 0), map_id: 1 }
+    }
+
+    fn setup(speaker_x: i32) -> (App, Entity, UnboundedReceiver<WriterAction>) {
+        let mut app = App::new();
+        app
+            .add_event::<OnCharacterSpeech>()
+            .add_systems(Update, respond_to_keywords);
+
+        app.world_mut().spawn((
+            KeywordResponder {
+                responses: vec![
+                    (vec!["hail".into(), "hello".into()], "Well met, traveller.".into()),
+                    (vec!["town crier".into()], "Hear ye!".into()),
+                ],
+            },
+            at(10),
+            NetId { id: EntityId::from_u32(2) },
+            CharacterName("Guard".into()),
+        ));
+
+        let character = app.world_mut().spawn(at(speaker_x)).id();
+        let (client, rx) = test_client();
+        app.world_mut().spawn((client, Possessing { entity: character }));
+        (app, character, rx)
+    }
+
+    fn say(app: &mut App, speaker: Entity, text: &str) {
+        app.world_mut().send_event(OnCharacterSpeech {
+            client_entity: Entity::PLACEHOLDER,
+            speaker,
+            text: text.into(),
+        });
+        app.update();
+    }
+
+    fn responses(rx: &mut UnboundedReceiver<WriterAction>) -> Vec<(String, String)> {
+        sent_packets::<UnicodeTextMessage>(rx).into_iter()
+            .map(|packet| (packet.name.as_str().to_string(), packet.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_keyword_match() {
+        let responder = KeywordResponder {
+            responses: vec![(vec!["town crier".into()], "Hear ye!".into())],
+        };
+        assert_eq!(responder.respond("Where is the Town  Crier?"), Some("Hear ye!"));
+        assert_eq!(responder.respond("town"), None);
+        assert_eq!(responder.respond("downtown crier"), None);
+    }
+
+    #[test]
+    fn test_keyword_response() {
+        let (mut app, speaker, mut rx) = setup(12);
+        say(&mut app, speaker, "Hail, guard!");
+        assert_eq!(responses(&mut rx), vec![("Guard".to_string(), "Well met, traveller.".to_string())]);
+
+        say(&mut app, speaker, "Nice weather today");
+        assert!(responses(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let (mut app, speaker, mut rx) = setup(10 + SPEECH_RANGE + 1);
+        say(&mut app, speaker, "hello");
+        assert!(responses(&mut rx).is_empty());
+    }
+}
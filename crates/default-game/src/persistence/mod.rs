@@ -310,6 +310,15 @@ impl SerializationWorldExt for World {
     }
 }
 
+/// Save the persistent entities in `world` and load them into `target`, as a restart would.
+#[cfg(test)]
+pub(crate) fn save_and_load(world: &mut World, target: &mut World) {
+    let buffers = world.serialize();
+    let mut saved = Vec::new();
+    buffers.serialize(&mut serde_yaml::Serializer::new(&mut saved)).unwrap();
+    target.deserialize(serde_yaml::Deserializer::from_slice(&saved)).unwrap();
+}
+
 pub trait SerializationSetupExt {
     fn register_serializer<T: BundleSerializer>(&mut self) -> &mut Self;
 }
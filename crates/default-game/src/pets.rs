@@ -0,0 +1,656 @@
+use std::time::Duration;
+
+use bevy::ecs::entity::{MapEntities, VisitEntities, VisitEntitiesMut};
+use bevy::ecs::query::WorldQuery;
+use bevy::ecs::reflect::{ReflectMapEntities, ReflectVisitEntities, ReflectVisitEntitiesMut};
+use bevy::prelude::*;
+use yewoh::protocol::GumpLayout;
+use yewoh_server::gump_builder::{GumpBuilder, GumpRect, GumpRectLayout, GumpText};
+use yewoh_server::world::characters::CharacterName;
+use yewoh_server::world::combat::AttackTarget;
+use yewoh_server::world::connection::{NetClient, Possessing};
+use yewoh_server::world::entity::{Direction, MapPosition};
+use yewoh_server::world::gump::{Gump, GumpClient};
+use yewoh_server::world::input::OnClientDrop;
+use yewoh_server::world::items::ItemQuantity;
+use yewoh_server::world::map::{Chunk, TileDataResource};
+use yewoh_server::world::navigation::try_move_in_direction;
+use yewoh_server::world::spatial::SpatialQuery;
+
+use crate::DefaultGameSet;
+use crate::chat::OnCharacterSpeech;
+use crate::entities::Persistent;
+use crate::entities::context_menu::{ContextMenuEntry, OnEntityContextMenuRequest};
+use crate::entities::names::{validate_name, DisplayNames, RenameEntity, MAX_NAME_LENGTH};
+use crate::entities::tooltips::MarkTooltipChanged;
+use crate::entity_events::{EntityEventReader, EntityEventRoutePlugin};
+use crate::gumps::{OnCloseGump, RESIZABLE_PAPER_3};
+use crate::hues;
+use crate::networking::NetClientExt;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+
+pub const MAX_LOYALTY: f32 = 100.0;
+
+/// How much loyalty a pet loses each hour.
+pub const LOYALTY_DECAY_PER_HOUR: f32 = 10.0;
+
+/// Pets following their owner try to stay within this many tiles.
+pub const FOLLOW_DISTANCE: i32 = 1;
+
+pub const FOLLOW_INTERVAL: Duration = Duration::from_millis(400);
+
+pub const PET_RENAME_GUMP_ID: u32 = 0x50524e4d;
+
+const RENAME_ID: u16 = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub enum PetCommand {
+    #[default]
+    Follow,
+    Stay,
+    Guard,
+    Attack,
+}
+
+impl PetCommand {
+    pub fn from_word(word: &str) -> Option<PetCommand> {
+        match word {
+            "follow" => Some(PetCommand::Follow),
+            "stay" | "stop" => Some(PetCommand::Stay),
+            "guard" => Some(PetCommand::Guard),
+            "attack" | "kill" => Some(PetCommand::Attack),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PetSelector {
+    All,
+    Named(String),
+}
+
+impl PetSelector {
+    pub fn matches(&self, name: Option<&CharacterName>) -> bool {
+        match self {
+            PetSelector::All => true,
+            PetSelector::Named(selected) => name.is_some_and(|name| name.to_lowercase() == *selected),
+        }
+    }
+}
+
+/// Parse speech such as "all follow" or "fluffy attack" into a pet command.
+pub fn parse_pet_command(text: &str) -> Option<(PetSelector, PetCommand)> {
+    let text = text.trim().to_lowercase();
+    let (selector, command) = text.rsplit_once(char::is_whitespace)?;
+    let command = PetCommand::from_word(command)?;
+    let selector = selector.split_whitespace().collect::<Vec<_>>().join(" ");
+    let selector = match selector.as_str() {
+        "" => return None,
+        "all" => PetSelector::All,
+        _ => PetSelector::Named(selector),
+    };
+    Some((selector, command))
+}
+
+/// The player a tamed creature belongs to.
+#[derive(Debug, Clone, Eq, PartialEq, Reflect, Component, VisitEntities, VisitEntitiesMut)]
+#[reflect(Component, VisitEntities, VisitEntitiesMut, MapEntities)]
+#[require(Loyalty, PetOrders, FollowTimer)]
+pub struct Owner {
+    pub player: Entity,
+}
+
+/// How devoted a pet is to its owner, pets run wild when this reaches zero.
+#[derive(Debug, Clone, Copy, PartialEq, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct Loyalty(pub f32);
+
+impl Default for Loyalty {
+    fn default() -> Self {
+        Loyalty(MAX_LOYALTY)
+    }
+}
+
+/// Food which restores this much loyalty when dropped on a pet by its owner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deref, DerefMut, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct PetFood(pub f32);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct PetOrders {
+    pub command: PetCommand,
+}
+
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct FollowTimer(pub Timer);
+
+impl Default for FollowTimer {
+    fn default() -> Self {
+        FollowTimer(Timer::new(FOLLOW_INTERVAL, TimerMode::Repeating))
+    }
+}
+
+pub fn on_pet_command_speech(
+    mut commands: Commands,
+    attack_targets: Query<&AttackTarget>,
+    mut pets: Query<(Entity, &Owner, Option<&CharacterName>, &mut PetOrders)>,
+    mut events: EventReader<OnCharacterSpeech>,
+) {
+    for event in events.read() {
+        let Some((selector, command)) = parse_pet_command(&event.text) else {
+            continue;
+        };
+
+        let owner = event.speaker;
+        let target = attack_targets.get(owner).ok().map(|t| t.target);
+        for (pet, pet_owner, name, mut orders) in &mut pets {
+            if pet_owner.player != owner || !selector.matches(name) {
+                continue;
+            }
+
+            match command {
+                PetCommand::Attack => {
+                    // Pets attack whatever their owner is fighting.
+                    let Some(target) = target.filter(|target| *target != pet) else {
+                        continue;
+                    };
+                    commands.entity(pet).insert(AttackTarget { target });
+                }
+                PetCommand::Follow | PetCommand::Stay => {
+                    commands.entity(pet).remove::<AttackTarget>();
+                }
+                PetCommand::Guard => {}
+            }
+
+            orders.command = command;
+        }
+    }
+}
+
+pub fn guard_owners(
+    mut commands: Commands,
+    owners: Query<&AttackTarget, Without<Owner>>,
+    pets: Query<(Entity, &Owner, &PetOrders, Option<&AttackTarget>)>,
+) {
+    for (pet, owner, orders, current_target) in &pets {
+        if orders.command != PetCommand::Guard {
+            continue;
+        }
+
+        let Ok(owner_target) = owners.get(owner.player) else {
+            continue;
+        };
+
+        if owner_target.target != pet && current_target != Some(owner_target) {
+            commands.entity(pet).insert(owner_target.clone());
+        }
+    }
+}
+
+pub fn follow_owners(
+    time: Res<Time>,
+    tile_data: Res<TileDataResource>,
+    spatial_query: SpatialQuery,
+    chunk_query: Query<(&MapPosition, &Chunk)>,
+    owners: Query<&MapPosition, (Without<Owner>, Without<Chunk>)>,
+    mut pets: Query<
+        (Entity, &Owner, &PetOrders, &mut FollowTimer, &mut MapPosition, &mut Direction),
+        (Without<Chunk>, Without<AttackTarget>),
+    >,
+) {
+    for (entity, owner, orders, mut timer, mut position, mut direction) in &mut pets {
+        if !matches!(orders.command, PetCommand::Follow | PetCommand::Guard) {
+            continue;
+        }
+
+        if !timer.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let Ok(owner_position) = owners.get(owner.player) else {
+            continue;
+        };
+
+        if position.in_range_2d(owner_position, FOLLOW_DISTANCE) {
+            continue;
+        }
+
+        let Some(new_direction) = position.direction_to(owner_position) else {
+            continue;
+        };
+
+        if let Ok(new_position) = try_move_in_direction(&spatial_query, &chunk_query, &tile_data, *position, new_direction, Some(entity)) {
+            *position = new_position;
+        }
+        direction.set_if_neq(new_direction);
+    }
+}
+
+pub fn decay_loyalty(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pets: Query<(Entity, &mut Loyalty), With<Owner>>,
+) {
+    let decay = LOYALTY_DECAY_PER_HOUR * time.delta_secs() / 3600.0;
+    for (entity, mut loyalty) in &mut pets {
+        loyalty.0 -= decay;
+        if loyalty.0 <= 0.0 {
+            commands.entity(entity).remove::<(Owner, Loyalty, PetOrders, FollowTimer, AttackTarget)>();
+        }
+    }
+}
+
+pub fn feed_pets(
+    mut commands: Commands,
+    clients: Query<&Possessing>,
+    mut pets: Query<(&Owner, &mut Loyalty)>,
+    food: Query<(&PetFood, Option<&ItemQuantity>)>,
+    mut events: EventReader<OnClientDrop>,
+) {
+    for request in events.read() {
+        let Some(pet) = request.dropped_on else {
+            continue;
+        };
+
+        let Ok((owner, mut loyalty)) = pets.get_mut(pet) else {
+            continue;
+        };
+
+        if !is_owner(&clients, request.client_entity, owner) {
+            continue;
+        }
+
+        let Ok((food, quantity)) = food.get(request.target) else {
+            continue;
+        };
+
+        // Pets eat one item at a time, leaving the rest of the stack at their owner's feet.
+        loyalty.0 = (loyalty.0 + **food).min(MAX_LOYALTY);
+        match quantity {
+            Some(quantity) if **quantity > 1 => {
+                commands.entity(request.target)
+                    .insert(ItemQuantity(**quantity - 1))
+                    .queue(MarkTooltipChanged);
+            }
+            _ => {
+                commands.entity(request.target).despawn_recursive();
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Event)]
+pub struct OnPetRenameRequest {
+    pub client_entity: Entity,
+    pub pet: Entity,
+}
+
+/// A gump letting the owner of `pet` choose a new name for it.
+#[derive(Debug, Clone, Component)]
+pub struct PetRenameGump {
+    pub pet: Entity,
+}
+
+impl PetRenameGump {
+    pub fn render(&self, name: &str) -> GumpLayout {
+        let size = IVec2::new(300, 120);
+        let row = 20;
+
+        let mut text = GumpText::new();
+        let mut builder = GumpBuilder::new();
+        let mut layout = GumpRectLayout::new(&mut builder, &mut text, GumpRect::from_zero(size))
+            .background(|builder| builder.image_sliced(RESIZABLE_PAPER_3))
+            .with_padding(16)
+            .into_vbox();
+
+        layout
+            .allocate(row, |builder| builder
+                .html("<center>Rename your pet</center>"))
+            .gap(row / 2)
+            .allocate(row, |builder| builder
+                .text_entry_limited(1, MAX_NAME_LENGTH, name, 0))
+            .gap(row / 2)
+            .allocate(row, |builder| builder
+                .background(|builder| builder
+                    .html("<center>OK</center>"))
+                .right(16)
+                .close_button(0x15e1, 0x15e5, 1));
+
+        builder.into_layout(text)
+    }
+}
+
+fn is_owner(clients: &Query<&Possessing>, client_entity: Entity, owner: &Owner) -> bool {
+    clients.get(client_entity).is_ok_and(|possessing| possessing.entity == owner.player)
+}
+
+pub fn pet_context_menu(
+    clients: Query<&Possessing>,
+    pets: Query<&Owner>,
+    mut events: EntityEventReader<OnEntityContextMenuRequest, Owner>,
+) {
+    for event in events.read() {
+        let Ok(owner) = pets.get(event.target) else {
+            continue;
+        };
+
+        if !is_owner(&clients, event.client_entity, owner) {
+            continue;
+        }
+
+        let request = OnPetRenameRequest {
+            client_entity: event.client_entity,
+            pet: event.target,
+        };
+        event.entries.push(ContextMenuEntry {
+            id: RENAME_ID,
+            text_id: 1111680,
+            ..default()
+        }.with_event(request));
+    }
+}
+
+pub fn open_pet_rename_gump(
+    mut commands: Commands,
+    clients: Query<&Possessing>,
+    pets: Query<&Owner>,
+    names: DisplayNames,
+    mut events: EventReader<OnPetRenameRequest>,
+) {
+    for event in events.read() {
+        let Ok(owner) = pets.get(event.pet) else {
+            continue;
+        };
+
+        if !is_owner(&clients, event.client_entity, owner) {
+            continue;
+        }
+
+        let rename_gump = PetRenameGump { pet: event.pet };
+        let mut gump = Gump::empty(PET_RENAME_GUMP_ID);
+        gump.set_layout(rename_gump.render(&names.display_name(event.pet)));
+        commands.spawn((
+            gump,
+            GumpClient(event.client_entity),
+            rename_gump,
+        ));
+    }
+}
+
+pub fn handle_pet_rename_gump(
+    mut commands: Commands,
+    clients: Query<&Possessing>,
+    net_clients: Query<&NetClient>,
+    gumps: Query<&PetRenameGump>,
+    pets: Query<&Owner>,
+    mut events: EntityEventReader<OnCloseGump, PetRenameGump>,
+) {
+    for event in events.read() {
+        let Ok(rename_gump) = gumps.get(event.gump) else {
+            continue;
+        };
+        commands.entity(event.gump).despawn_recursive();
+
+        if event.button_id != 1 {
+            continue;
+        }
+
+        // Ownership may have changed since the gump was opened.
+        let Ok(owner) = pets.get(rename_gump.pet) else {
+            continue;
+        };
+        if !is_owner(&clients, event.client_entity, owner) {
+            continue;
+        }
+
+        match validate_name(event.text_field(1).unwrap_or_default()) {
+            Ok(name) => {
+                commands.entity(rename_gump.pet).queue(RenameEntity(name.to_string()));
+            }
+            Err(err) => {
+                if let Ok(client) = net_clients.get(event.client_entity) {
+                    client.send_system_message_hue(err.to_string(), hues::RED);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Reflect)]
+#[reflect(MapEntities)]
+pub struct PetDto {
+    pub owner: Entity,
+    pub loyalty: f32,
+    pub command: PetCommand,
+}
+
+impl MapEntities for PetDto {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.owner = entity_mapper.map_entity(self.owner);
+    }
+}
+
+#[derive(Default)]
+pub struct PetSerializer;
+
+impl BundleSerializer for PetSerializer {
+    type Query = (&'static Owner, &'static Loyalty, &'static PetOrders);
+    type Filter = With<Persistent>;
+    type Bundle = PetDto;
+
+    fn id() -> &'static str {
+        "Pet"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        let (owner, loyalty, orders) = item;
+        PetDto {
+            owner: owner.player,
+            loyalty: **loyalty,
+            command: orders.command,
+        }
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity)
+            .insert((
+                Owner { player: bundle.owner },
+                Loyalty(bundle.loyalty),
+                PetOrders { command: bundle.command },
+            ));
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<PetCommand>()
+        .register_type::<Owner>()
+        .register_type::<Loyalty>()
+        .register_type::<PetFood>()
+        .register_type::<PetOrders>()
+        .register_type::<FollowTimer>()
+        .register_serializer::<PetSerializer>()
+        .add_event::<OnPetRenameRequest>()
+        .add_plugins((
+            EntityEventRoutePlugin::<OnEntityContextMenuRequest, Owner>::default(),
+            EntityEventRoutePlugin::<OnCloseGump, PetRenameGump>::default(),
+        ))
+        .add_systems(First, (
+            (
+                on_pet_command_speech,
+                feed_pets,
+                pet_context_menu,
+                handle_pet_rename_gump,
+            ).in_set(DefaultGameSet::HandleEvents),
+            open_pet_rename_gump.in_set(DefaultGameSet::FinishEvents),
+        ))
+        .add_systems(Update, (
+            guard_owners,
+            follow_owners,
+            decay_loyalty,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::SmallVec;
+    use yewoh::protocol::GumpTextEntry;
+
+    use crate::characters::persistence::PersistName;
+    use crate::entity_events::EntityEventPlugin;
+    use crate::persistence::{save_and_load, PersistencePlugin};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_pet_command() {
+        assert_eq!(parse_pet_command("All Follow"), Some((PetSelector::All, PetCommand::Follow)));
+        assert_eq!(parse_pet_command("mr  whiskers attack"),
+            Some((PetSelector::Named("mr whiskers".into()), PetCommand::Attack)));
+        assert_eq!(parse_pet_command("follow"), None);
+        assert_eq!(parse_pet_command("all dance"), None);
+    }
+
+    #[test]
+    fn test_all_follow() {
+        let mut app = App::new();
+        app
+            .add_event::<OnCharacterSpeech>()
+            .add_systems(Update, on_pet_command_speech);
+
+        let owner = app.world_mut().spawn_empty().id();
+        let stranger = app.world_mut().spawn_empty().id();
+        let client_entity = app.world_mut().spawn(Possessing { entity: owner }).id();
+        let stay = PetOrders { command: PetCommand::Stay };
+        let dog = app.world_mut().spawn((Owner { player: owner }, stay, CharacterName("Rex".into()))).id();
+        let horse = app.world_mut().spawn((Owner { player: owner }, stay)).id();
+        let other = app.world_mut().spawn((Owner { player: stranger }, stay)).id();
+
+        app.world_mut().send_event(OnCharacterSpeech {
+            client_entity,
+            speaker: owner,
+            text: "all follow".into(),
+        });
+        app.update();
+
+        let command = |app: &App, entity| app.world().get::<PetOrders>(entity).unwrap().command;
+        assert_eq!(command(&app, dog), PetCommand::Follow);
+        assert_eq!(command(&app, horse), PetCommand::Follow);
+        assert_eq!(command(&app, other), PetCommand::Stay);
+    }
+
+    #[test]
+    fn test_rename_pet() {
+        let mut app = App::new();
+        app
+            .configure_sets(First, (
+                DefaultGameSet::DispatchEvents,
+                DefaultGameSet::HandleEvents,
+            ).chain())
+            .add_plugins((
+                EntityEventPlugin::<OnCloseGump>::default(),
+                EntityEventRoutePlugin::<OnCloseGump, PetRenameGump>::default(),
+            ))
+            .add_systems(First, handle_pet_rename_gump.in_set(DefaultGameSet::HandleEvents));
+
+        let owner = app.world_mut().spawn_empty().id();
+        let stranger = app.world_mut().spawn_empty().id();
+        let owner_client = app.world_mut().spawn(Possessing { entity: owner }).id();
+        let stranger_client = app.world_mut().spawn(Possessing { entity: stranger }).id();
+        let pet = app.world_mut().spawn((Owner { player: owner }, CharacterName("a dog".into()))).id();
+
+        let submit = |app: &mut App, client_entity: Entity, name: &str| {
+            let gump = app.world_mut()
+                .spawn((
+                    Gump::empty(PET_RENAME_GUMP_ID),
+                    GumpClient(client_entity),
+                    PetRenameGump { pet },
+                ))
+                .id();
+            app.world_mut().send_event(OnCloseGump {
+                client_entity,
+                gump,
+                button_id: 1,
+                on_switches: SmallVec::new(),
+                text_fields: vec![GumpTextEntry { id: 1, text: name.into() }],
+            });
+            app.update();
+            assert!(app.world().get_entity(gump).is_err());
+            app.world().get::<CharacterName>(pet).unwrap().0.clone()
+        };
+
+        assert_eq!(submit(&mut app, stranger_client, "Stolen"), "a dog");
+        assert_eq!(submit(&mut app, owner_client, "<Rex>"), "a dog");
+        assert_eq!(submit(&mut app, owner_client, "Rex"), "Rex");
+    }
+
+    #[test]
+    fn test_feed_pet() {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientDrop>()
+            .add_systems(Update, feed_pets);
+
+        let owner = app.world_mut().spawn_empty().id();
+        let stranger = app.world_mut().spawn_empty().id();
+        let owner_client = app.world_mut().spawn(Possessing { entity: owner }).id();
+        let stranger_client = app.world_mut().spawn(Possessing { entity: stranger }).id();
+        let pet = app.world_mut().spawn((Owner { player: owner }, Loyalty(20.0))).id();
+        let ribs = app.world_mut().spawn((PetFood(15.0), ItemQuantity(2))).id();
+
+        let feed = |app: &mut App, client_entity| {
+            app.world_mut().send_event(OnClientDrop {
+                client_entity,
+                target: ribs,
+                position: IVec3::ZERO,
+                grid_index: 0,
+                dropped_on: Some(pet),
+            });
+            app.update();
+            **app.world().get::<Loyalty>(pet).unwrap()
+        };
+
+        assert_eq!(feed(&mut app, stranger_client), 20.0);
+        assert_eq!(feed(&mut app, owner_client), 35.0);
+        assert_eq!(**app.world().get::<ItemQuantity>(ribs).unwrap(), 1);
+        assert_eq!(feed(&mut app, owner_client), 50.0);
+        assert!(app.world().get_entity(ribs).is_err());
+    }
+
+    fn persistence_app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins((
+                MinimalPlugins,
+                PersistencePlugin,
+                crate::characters::persistence::plugin,
+            ))
+            .register_serializer::<PetSerializer>();
+        app
+    }
+
+    #[test]
+    fn test_persist_owner() {
+        let mut app = persistence_app();
+        let world = app.world_mut();
+        let owner = world.spawn((Persistent, PersistName, CharacterName("Gerome".into()))).id();
+        world.spawn((
+            Persistent,
+            Owner { player: owner },
+            Loyalty(40.0),
+            PetOrders { command: PetCommand::Guard },
+        ));
+
+        let mut loaded = persistence_app();
+        save_and_load(world, loaded.world_mut());
+
+        let world = loaded.world_mut();
+        let (owner, loyalty, orders) = world.query::<(&Owner, &Loyalty, &PetOrders)>().single(world);
+        assert_eq!(**loyalty, 40.0);
+        assert_eq!(orders.command, PetCommand::Guard);
+        assert_eq!(world.get::<CharacterName>(owner.player).unwrap().as_str(), "Gerome");
+    }
+}
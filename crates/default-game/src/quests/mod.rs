@@ -0,0 +1,570 @@
+use bevy::ecs::query::WorldQuery;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use glam::ivec2;
+use yewoh_server::world::entity::{ContainedPosition, EquipmentSlot, EquippedPosition};
+use yewoh_server::world::items::ItemQuantity;
+
+use crate::characters::reputation::AwardReputation;
+use crate::characters::rewards::{distribute_kill_rewards, OnCreatureKilled};
+use crate::data::prefabs::PrefabLibraryWorldExt;
+use crate::entities::position::PositionExt;
+use crate::entities::{Persistent, PrefabInstance};
+use crate::items::MAX_STACK;
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+use crate::rates::{ServerRates, GOLD_PREFAB};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub enum ObjectiveKind {
+    #[default]
+    Kill,
+    Collect,
+}
+
+/// Something which must be done a number of times, matched by prefab name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct QuestObjective {
+    pub kind: ObjectiveKind,
+    pub prefab: String,
+    pub required: u32,
+    #[reflect(default)]
+    pub progress: u32,
+}
+
+impl QuestObjective {
+    pub fn is_complete(&self) -> bool {
+        self.progress >= self.required
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct QuestReward {
+    pub fame: i32,
+    pub karma: i32,
+    pub gold: u32,
+    /// Prefabs to place in the character's backpack.
+    pub items: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct Quest {
+    pub id: String,
+    pub title: String,
+    pub objectives: Vec<QuestObjective>,
+    #[reflect(default)]
+    pub reward: QuestReward,
+}
+
+impl Quest {
+    pub fn is_complete(&self) -> bool {
+        self.objectives.iter().all(QuestObjective::is_complete)
+    }
+}
+
+/// The quests a character is working on, and the IDs of those they have finished.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct QuestLog {
+    pub active: Vec<Quest>,
+    pub completed: Vec<String>,
+}
+
+impl QuestLog {
+    pub fn has_quest(&self, id: &str) -> bool {
+        self.active.iter().any(|quest| quest.id == id) ||
+            self.completed.iter().any(|completed| completed == id)
+    }
+
+    /// Add `quest` to the log, if it is not already active or completed.
+    pub fn start(&mut self, quest: Quest) -> bool {
+        if self.has_quest(&quest.id) {
+            false
+        } else {
+            self.active.push(quest);
+            true
+        }
+    }
+
+    /// Advance every matching objective by `amount`, returning any quests which are now complete.
+    pub fn advance(&mut self, kind: ObjectiveKind, prefab: &str, amount: u32) -> Vec<Quest> {
+        for quest in &mut self.active {
+            for objective in &mut quest.objectives {
+                if objective.kind == kind && objective.prefab == prefab && !objective.is_complete() {
+                    objective.progress = (objective.progress + amount).min(objective.required);
+                }
+            }
+        }
+
+        self.take_completed()
+    }
+
+    /// Whether updating collect objectives from `held` would change anything.
+    pub fn collected_changed(&self, held: impl Fn(&str) -> u32) -> bool {
+        self.active.iter()
+            .flat_map(|quest| &quest.objectives)
+            .filter(|objective| objective.kind == ObjectiveKind::Collect)
+            .any(|objective| objective.progress != held(&objective.prefab).min(objective.required))
+    }
+
+    /// Set the progress of collect objectives from how many of each prefab is held,
+    /// returning any quests which are now complete.
+    pub fn update_collected(&mut self, held: impl Fn(&str) -> u32) -> Vec<Quest> {
+        for quest in &mut self.active {
+            for objective in &mut quest.objectives {
+                if objective.kind == ObjectiveKind::Collect {
+                    objective.progress = held(&objective.prefab).min(objective.required);
+                }
+            }
+        }
+
+        self.take_completed()
+    }
+
+    fn take_completed(&mut self) -> Vec<Quest> {
+        let (completed, active) = std::mem::take(&mut self.active)
+            .into_iter()
+            .partition::<Vec<_>, _>(Quest::is_complete);
+        self.active = active;
+        self.completed.extend(completed.iter().map(|quest| quest.id.clone()));
+        completed
+    }
+}
+
+/// Offers a quest, as defined by this entity's prefab.
+#[derive(Debug, Clone, Default, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct QuestGiver {
+    pub quest: Quest,
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct OnStartQuest {
+    pub character: Entity,
+    pub quest: Quest,
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct OnAdvanceObjective {
+    pub character: Entity,
+    pub kind: ObjectiveKind,
+    pub prefab: String,
+    pub amount: u32,
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct OnQuestCompleted {
+    pub character: Entity,
+    pub quest: String,
+    pub reward: QuestReward,
+}
+
+pub fn start_quests(
+    mut commands: Commands,
+    mut logs: Query<Option<&mut QuestLog>>,
+    mut events: EventReader<OnStartQuest>,
+) {
+    for event in events.read() {
+        let Ok(log) = logs.get_mut(event.character) else {
+            continue;
+        };
+
+        match log {
+            Some(mut log) => {
+                log.start(event.quest.clone());
+            }
+            None => {
+                let mut log = QuestLog::default();
+                log.start(event.quest.clone());
+                commands.entity(event.character).insert(log);
+            }
+        }
+    }
+}
+
+pub fn advance_kill_objectives(
+    prefabs: Query<&PrefabInstance>,
+    mut events: EventReader<OnCreatureKilled>,
+    mut advance_events: EventWriter<OnAdvanceObjective>,
+) {
+    for event in events.read() {
+        let Ok(prefab) = prefabs.get(event.creature) else {
+            continue;
+        };
+
+        for recipient in &event.recipients {
+            advance_events.send(OnAdvanceObjective {
+                character: recipient.entity,
+                kind: ObjectiveKind::Kill,
+                prefab: prefab.prefab_name.clone(),
+                amount: 1,
+            });
+        }
+    }
+}
+
+/// Counts the quest items each character is carrying towards their collect objectives.
+pub fn update_collect_objectives(
+    mut logs: Query<(Entity, &mut QuestLog)>,
+    children: Query<&Children>,
+    parents: Query<&Parent>,
+    items: Query<(&PrefabInstance, Option<&ItemQuantity>)>,
+    changed_children: Query<Entity, Changed<Children>>,
+    changed_quantities: Query<Entity, Changed<ItemQuantity>>,
+    mut removed_children: RemovedComponents<Children>,
+    mut completed_events: EventWriter<OnQuestCompleted>,
+) {
+    // Only recount characters whose quests or carried items have changed.
+    let dirty = changed_children.iter()
+        .chain(changed_quantities.iter())
+        .chain(removed_children.read())
+        .flat_map(|entity| std::iter::once(entity).chain(parents.iter_ancestors(entity)))
+        .collect::<HashSet<_>>();
+
+    for (character, mut log) in &mut logs {
+        if !log.is_changed() && !dirty.contains(&character) {
+            continue;
+        }
+
+        let mut held = log.active.iter()
+            .flat_map(|quest| &quest.objectives)
+            .filter(|objective| objective.kind == ObjectiveKind::Collect)
+            .map(|objective| (objective.prefab.clone(), 0u32))
+            .collect::<HashMap<_, _>>();
+        if held.is_empty() {
+            continue;
+        }
+
+        for item in children.iter_descendants(character) {
+            let Ok((prefab, quantity)) = items.get(item) else {
+                continue;
+            };
+            if let Some(count) = held.get_mut(&prefab.prefab_name) {
+                *count += quantity.map_or(1, |q| **q as u32);
+            }
+        }
+
+        let held = |prefab: &str| held.get(prefab).copied().unwrap_or_default();
+        if !log.collected_changed(held) {
+            continue;
+        }
+
+        for quest in log.update_collected(held) {
+            completed_events.send(OnQuestCompleted {
+                character,
+                quest: quest.id,
+                reward: quest.reward,
+            });
+        }
+    }
+}
+
+pub fn advance_objectives(
+    mut logs: Query<&mut QuestLog>,
+    mut events: EventReader<OnAdvanceObjective>,
+    mut completed_events: EventWriter<OnQuestCompleted>,
+) {
+    for event in events.read() {
+        let Ok(mut log) = logs.get_mut(event.character) else {
+            continue;
+        };
+
+        for quest in log.advance(event.kind, &event.prefab, event.amount) {
+            completed_events.send(OnQuestCompleted {
+                character: event.character,
+                quest: quest.id,
+                reward: quest.reward,
+            });
+        }
+    }
+}
+
+pub fn grant_quest_rewards(
+    mut commands: Commands,
+    children: Query<&Children>,
+    equipment: Query<&EquippedPosition>,
+    rates: Res<ServerRates>,
+    mut events: EventReader<OnQuestCompleted>,
+) {
+    for event in events.read() {
+        if event.reward.fame != 0 || event.reward.karma != 0 {
+            commands.entity(event.character).queue(AwardReputation {
+                fame: event.reward.fame,
+                karma: event.reward.karma,
+            });
+        }
+
+        let backpack = children.get(event.character).ok()
+            .and_then(|children| children.iter()
+                .find(|child| equipment.get(**child).is_ok_and(|e| e.slot == EquipmentSlot::Backpack)))
+            .copied();
+        let Some(backpack) = backpack else {
+            warn!("no backpack to place quest rewards for {}", event.character);
+            continue;
+        };
+
+        let position = ContainedPosition {
+            position: ivec2(0, 0),
+            grid_index: 0,
+        };
+
+        // Large rewards are split into as many full stacks as needed.
+        let mut gold = rates.scale_gold(event.reward.gold);
+        while gold > 0 {
+            let quantity = gold.min(MAX_STACK as u32);
+            gold -= quantity;
+            commands.fabricate_prefab(GOLD_PREFAB)
+                .insert((
+                    Persistent,
+                    ItemQuantity(quantity as u16),
+                ))
+                .move_to_container_position(backpack, position);
+        }
+
+        for prefab in &event.reward.items {
+            commands.fabricate_prefab(prefab)
+                .insert(Persistent)
+                .move_to_container_position(backpack, position);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct QuestLogSerializer;
+
+impl BundleSerializer for QuestLogSerializer {
+    type Query = &'static QuestLog;
+    type Filter = With<Persistent>;
+    type Bundle = QuestLog;
+
+    fn id() -> &'static str {
+        "QuestLog"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        item.clone()
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(bundle);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<ObjectiveKind>()
+        .register_type::<QuestObjective>()
+        .register_type::<QuestReward>()
+        .register_type::<Quest>()
+        .register_type::<QuestLog>()
+        .register_type::<QuestGiver>()
+        .register_serializer::<QuestLogSerializer>()
+        .add_event::<OnStartQuest>()
+        .add_event::<OnAdvanceObjective>()
+        .add_event::<OnQuestCompleted>()
+        .add_systems(Update, (
+            (
+                start_quests,
+                advance_kill_objectives.after(distribute_kill_rewards),
+                advance_objectives,
+                update_collect_objectives,
+                grant_quest_rewards,
+            ).chain(),
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::characters::reputation::Reputation;
+    use crate::characters::rewards::{KillRewardShare, KillRewards};
+    use crate::data::prefabs::PrefabLibrary;
+    use crate::persistence::{save_and_load, PersistencePlugin};
+
+    use super::*;
+
+    fn rat_quest() -> Quest {
+        Quest {
+            id: "rat_hunt".into(),
+            title: "Rat Hunt".into(),
+            objectives: vec![QuestObjective {
+                kind: ObjectiveKind::Kill,
+                prefab: "rat".into(),
+                required: 2,
+                progress: 0,
+            }],
+            reward: QuestReward {
+                gold: 50,
+                ..default()
+            },
+        }
+    }
+
+    fn quest_app() -> App {
+        let mut app = App::new();
+        app
+            .init_resource::<PrefabLibrary>()
+            .init_resource::<ServerRates>()
+            .add_event::<OnCreatureKilled>()
+            .add_plugins(plugin);
+        app
+    }
+
+    fn spawn_character(app: &mut App) -> (Entity, Entity) {
+        let character = app.world_mut().spawn_empty().id();
+        let backpack = app.world_mut()
+            .spawn(EquippedPosition { slot: EquipmentSlot::Backpack })
+            .set_parent(character)
+            .id();
+        (character, backpack)
+    }
+
+    fn gold_stacks(app: &mut App) -> Vec<(u16, Entity)> {
+        app.world_mut()
+            .query::<(&ItemQuantity, &Parent)>()
+            .iter(app.world())
+            .map(|(quantity, parent)| (**quantity, parent.get()))
+            .collect()
+    }
+
+    #[test]
+    fn test_kill_advances_objective() {
+        let mut app = quest_app();
+        let (character, backpack) = spawn_character(&mut app);
+        app.world_mut().send_event(OnStartQuest { character, quest: rat_quest() });
+        app.update();
+
+        let kill = |app: &mut App, prefab_name: &str| {
+            let creature = app.world_mut()
+                .spawn(PrefabInstance { prefab_name: prefab_name.into() })
+                .id();
+            app.world_mut().send_event(OnCreatureKilled {
+                creature,
+                rewards: KillRewards::default(),
+                recipients: vec![KillRewardShare { entity: character, share: 1.0 }],
+            });
+            app.update();
+        };
+
+        kill(&mut app, "rat");
+        kill(&mut app, "mongbat");
+        let log = app.world().get::<QuestLog>(character).unwrap();
+        assert_eq!(log.active[0].objectives[0].progress, 1);
+        assert!(app.world().resource::<Events<OnQuestCompleted>>().is_empty());
+
+        kill(&mut app, "rat");
+        let log = app.world().get::<QuestLog>(character).unwrap();
+        assert!(log.active.is_empty());
+        assert_eq!(log.completed, vec!["rat_hunt".to_string()]);
+        let completed = app.world().resource::<Events<OnQuestCompleted>>()
+            .iter_current_update_events()
+            .map(|event| event.quest.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(completed, vec!["rat_hunt".to_string()]);
+        assert_eq!(gold_stacks(&mut app), vec![(50, backpack)]);
+    }
+
+    #[test]
+    fn test_collect_counts_held_items() {
+        let mut app = quest_app();
+        let (character, backpack) = spawn_character(&mut app);
+        let quest = Quest {
+            id: "tails".into(),
+            title: "Tails".into(),
+            objectives: vec![QuestObjective {
+                kind: ObjectiveKind::Collect,
+                prefab: "rat_tail".into(),
+                required: 5,
+                progress: 0,
+            }],
+            reward: default(),
+        };
+        app.world_mut().send_event(OnStartQuest { character, quest });
+        app.update();
+
+        let tails = app.world_mut()
+            .spawn((PrefabInstance { prefab_name: "rat_tail".into() }, ItemQuantity(3)))
+            .set_parent(backpack)
+            .id();
+        app.update();
+        let progress = |app: &App| app.world().get::<QuestLog>(character).unwrap().active[0].objectives[0].progress;
+        assert_eq!(progress(&app), 3);
+
+        // Picking the same items up again does not count them twice.
+        app.world_mut().entity_mut(tails).remove_parent();
+        app.update();
+        assert_eq!(progress(&app), 0);
+        app.world_mut().entity_mut(tails).set_parent(backpack);
+        app.update();
+        assert_eq!(progress(&app), 3);
+
+        **app.world_mut().get_mut::<ItemQuantity>(tails).unwrap() = 4;
+        app.update();
+        assert_eq!(progress(&app), 4);
+
+        app.world_mut()
+            .spawn((PrefabInstance { prefab_name: "rat_tail".into() }, ItemQuantity(2)))
+            .set_parent(backpack);
+        app.update();
+        let log = app.world().get::<QuestLog>(character).unwrap();
+        assert!(log.active.is_empty());
+        assert_eq!(log.completed, vec!["tails".to_string()]);
+    }
+
+    #[test]
+    fn test_rewards() {
+        let mut app = quest_app();
+        let (character, backpack) = spawn_character(&mut app);
+        app.world_mut().send_event(OnQuestCompleted {
+            character,
+            quest: "hoard".into(),
+            reward: QuestReward {
+                fame: 200,
+                karma: -100,
+                gold: 70000,
+                items: Vec::new(),
+            },
+        });
+        app.update();
+
+        let mut gold = gold_stacks(&mut app);
+        gold.sort();
+        assert_eq!(gold, vec![(10000, backpack), (60000, backpack)]);
+        assert_eq!(app.world().get::<Reputation>(character), Some(&Reputation { fame: 200, karma: -100 }));
+    }
+
+    fn persistence_app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins((
+                MinimalPlugins,
+                PersistencePlugin,
+            ))
+            .register_type::<QuestLog>()
+            .register_serializer::<QuestLogSerializer>();
+        app
+    }
+
+    #[test]
+    fn test_persist_quest_log() {
+        let mut quest = rat_quest();
+        quest.objectives[0].progress = 1;
+        let log = QuestLog {
+            active: vec![quest],
+            completed: vec!["welcome".into()],
+        };
+
+        let mut app = persistence_app();
+        let world = app.world_mut();
+        world.spawn((Persistent, log.clone()));
+
+        let mut loaded = persistence_app();
+        save_and_load(world, loaded.world_mut());
+
+        let world = loaded.world_mut();
+        let loaded = world.query::<&QuestLog>().single(world);
+        assert_eq!(loaded, &log);
+    }
+}
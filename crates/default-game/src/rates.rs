@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// The prefab used for gold coins, which is scaled by the gold rate rather than the loot rate.
+pub const GOLD_PREFAB: &str = "gold";
+
+/// Shard-wide multipliers for tuning the game without recompiling.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Resource, Deserialize)]
+#[reflect(Default, Resource)]
+#[serde(default)]
+pub struct ServerRates {
+    pub loot_multiplier: f32,
+    pub gold_multiplier: f32,
+    pub spawn_rate_multiplier: f32,
+    pub skill_gain_multiplier: f32,
+}
+
+impl Default for ServerRates {
+    fn default() -> Self {
+        Self {
+            loot_multiplier: 1.,
+            gold_multiplier: 1.,
+            spawn_rate_multiplier: 1.,
+            skill_gain_multiplier: 1.,
+        }
+    }
+}
+
+fn scale(value: u32, multiplier: f32) -> u32 {
+    (value as f64 * multiplier.max(0.) as f64).round() as u32
+}
+
+impl ServerRates {
+    pub fn scale_gold(&self, gold: u32) -> u32 {
+        scale(gold, self.gold_multiplier)
+    }
+
+    /// Scale the quantity of a dropped item, using the gold rate for gold.
+    pub fn scale_loot(&self, prefab: &str, quantity: u16) -> u16 {
+        let multiplier = if prefab == GOLD_PREFAB {
+            self.gold_multiplier
+        } else {
+            self.loot_multiplier
+        };
+        scale(quantity as u32, multiplier).min(u16::MAX as u32) as u16
+    }
+
+    /// Scale elapsed time for spawn timers, so that a higher rate spawns more often.
+    pub fn scale_spawn_time(&self, delta: Duration) -> Duration {
+        delta.mul_f32(self.spawn_rate_multiplier.max(0.))
+    }
+
+    pub fn scale_skill_gain(&self, gain: f32) -> f32 {
+        gain * self.skill_gain_multiplier.max(0.)
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<ServerRates>()
+        .init_resource::<ServerRates>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rates() {
+        let rates: ServerRates = serde_yaml::from_str("loot_multiplier: 2\nspawn_rate_multiplier: 0.5").unwrap();
+        assert_eq!(rates, ServerRates {
+            loot_multiplier: 2.,
+            spawn_rate_multiplier: 0.5,
+            ..default()
+        });
+    }
+
+    #[test]
+    fn test_scale_loot() {
+        let rates = ServerRates {
+            loot_multiplier: 2.,
+            gold_multiplier: 3.,
+            ..default()
+        };
+        assert_eq!(rates.scale_loot("bandage", 5), 10);
+        assert_eq!(rates.scale_loot(GOLD_PREFAB, 5), 15);
+        assert_eq!(rates.scale_loot("bandage", u16::MAX), u16::MAX);
+    }
+}
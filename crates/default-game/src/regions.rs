@@ -0,0 +1,183 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use yewoh_server::world::characters::CharacterBodyType;
+use yewoh_server::world::entity::MapPosition;
+
+/// How long a character must stay outside a region before they are considered to have left it.
+///
+/// This stops characters walking along a boundary from repeatedly entering and leaving.
+pub const REGION_EXIT_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// A named area of a map, inclusive of both corners.
+#[derive(Debug, Clone, Default, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct Region {
+    pub name: String,
+    pub map_id: u8,
+    pub min: IVec2,
+    pub max: IVec2,
+}
+
+impl Region {
+    pub fn contains(&self, position: &MapPosition) -> bool {
+        let tile = position.position.truncate();
+        position.map_id == self.map_id
+            && tile.cmpge(self.min).all()
+            && tile.cmple(self.max).all()
+    }
+}
+
+/// The regions a character is in, along with when they stepped outside each one, if they have.
+#[derive(Debug, Clone, Default, Component)]
+pub struct RegionPresence {
+    pub regions: HashMap<Entity, Option<Duration>>,
+}
+
+impl RegionPresence {
+    pub fn is_in(&self, region: Entity) -> bool {
+        self.regions.contains_key(&region)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Event)]
+pub struct OnRegionEnter {
+    pub character: Entity,
+    pub region: Entity,
+}
+
+#[derive(Debug, Clone, Copy, Event)]
+pub struct OnRegionExit {
+    pub character: Entity,
+    pub region: Entity,
+}
+
+pub fn update_region_presence(
+    time: Res<Time>,
+    regions: Query<(Entity, &Region)>,
+    mut characters: Query<
+        (Entity, Ref<MapPosition>, Option<&mut RegionPresence>),
+        With<CharacterBodyType>,
+    >,
+    mut commands: Commands,
+    mut enter_events: EventWriter<OnRegionEnter>,
+    mut exit_events: EventWriter<OnRegionExit>,
+) {
+    let now = time.elapsed();
+
+    for (character, position, presence) in &mut characters {
+        let has_pending = presence.as_ref()
+            .is_some_and(|p| p.regions.values().any(Option::is_some));
+        if !position.is_changed() && !has_pending {
+            continue;
+        }
+
+        let mut new_presence = None;
+        let presence = match presence {
+            Some(presence) => presence.into_inner(),
+            None => new_presence.insert(RegionPresence::default()),
+        };
+
+        for (region_entity, region) in &regions {
+            let inside = region.contains(&position);
+            match (inside, presence.regions.get_mut(&region_entity)) {
+                (true, Some(left_at)) => *left_at = None,
+                (true, None) => {
+                    presence.regions.insert(region_entity, None);
+                    enter_events.send(OnRegionEnter { character, region: region_entity });
+                }
+                (false, Some(left_at)) => {
+                    let left_at = *left_at.get_or_insert(now);
+                    if now.saturating_sub(left_at) >= REGION_EXIT_DEBOUNCE {
+                        presence.regions.remove(&region_entity);
+                        exit_events.send(OnRegionExit { character, region: region_entity });
+                    }
+                }
+                (false, None) => {}
+            }
+        }
+
+        if let Some(presence) = new_presence {
+            if !presence.regions.is_empty() {
+                commands.entity(character).insert(presence);
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<Region>()
+        .add_event::<OnRegionEnter>()
+        .add_event::<OnRegionExit>()
+        .add_systems(Update, (
+            update_region_presence,
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (App, Entity, Entity) {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .add_plugins(plugin);
+
+        let region = app.world_mut()
+            .spawn(Region {
+                name: "Ambush".into(),
+                map_id: 1,
+                min: IVec2::new(10, 10),
+                max: IVec2::new(20, 20),
+            })
+            .id();
+        let character = app.world_mut()
+            .spawn((CharacterBodyType(0x190), MapPosition { position: IVec3::new(5, 15, 0), map_id: 1 }))
+            .id();
+        app.update();
+        (app, region, character)
+    }
+
+    fn move_to(app: &mut App, character: Entity, x: i32) {
+        app.world_mut().get_mut::<MapPosition>(character).unwrap().position.x = x;
+        app.update();
+    }
+
+    fn events(app: &mut App) -> (usize, usize) {
+        let entered = app.world_mut().resource_mut::<Events<OnRegionEnter>>().drain().count();
+        let exited = app.world_mut().resource_mut::<Events<OnRegionExit>>().drain().count();
+        (entered, exited)
+    }
+
+    #[test]
+    fn test_enter_and_exit() {
+        let (mut app, region, character) = setup();
+        assert_eq!(events(&mut app), (0, 0));
+
+        move_to(&mut app, character, 10);
+        move_to(&mut app, character, 12);
+        assert_eq!(events(&mut app), (1, 0));
+        assert!(app.world().get::<RegionPresence>(character).unwrap().is_in(region));
+
+        move_to(&mut app, character, 9);
+        app.world_mut().resource_mut::<Time>().advance_by(REGION_EXIT_DEBOUNCE);
+        app.update();
+        app.update();
+        assert_eq!(events(&mut app), (0, 1));
+        assert!(!app.world().get::<RegionPresence>(character).unwrap().is_in(region));
+    }
+
+    #[test]
+    fn test_boundary_debounce() {
+        let (mut app, _, character) = setup();
+        move_to(&mut app, character, 10);
+        for _ in 0..3 {
+            move_to(&mut app, character, 9);
+            move_to(&mut app, character, 10);
+        }
+        assert_eq!(events(&mut app), (1, 0));
+    }
+}
@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The random number generator used for game rolls.
+///
+/// Seeding it makes a shard's rolls reproducible. ChaCha8 is used rather than
+/// `StdRng` because its output is stable across `rand` releases.
+#[derive(Resource)]
+pub struct GameRng(ChaCha8Rng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> GameRng {
+        GameRng(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(ChaCha8Rng::from_entropy())
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<GameRng>();
+}
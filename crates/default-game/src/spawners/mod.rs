@@ -5,17 +5,27 @@ use bevy::ecs::component::Component;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::system::{Commands, Query, Res};
 use bevy::prelude::*;
-use bevy::reflect::{DynamicList, DynamicStruct};
+use bevy::reflect::{DynamicList, DynamicStruct, List, ReflectRef, Tuple};
 use bevy::time::{Time, Timer, TimerMode};
 use bevy::utils::HashMap;
 use bevy_fabricator::traits::{Apply, Context, ReflectApply};
 use serde::Deserialize;
-use serde_yaml::Value;
+use serde_yaml::{Mapping, Value};
 use yewoh_server::world::entity::MapPosition;
 
-use crate::data::prefabs::{PrefabLibraryEntityExt, PrefabLibraryRequest};
+use crate::data::prefabs::{
+    PrefabLibraryEntityExt,
+    PrefabLibraryRequest,
+    PrefabReferences,
+    PrefabReferencesAppExt,
+};
+use crate::entities::{Persistent, PrefabInstance, UniqueId};
+use crate::rates::ServerRates;
+use crate::time::WorldClock;
 
-fn to_reflect(value: &Value) -> anyhow::Result<Box<dyn PartialReflect>> {
+pub mod persistence;
+
+pub(crate) fn to_reflect(value: &Value) -> anyhow::Result<Box<dyn PartialReflect>> {
     let v = match value {
         Value::Null => Box::new(()) as Box<dyn PartialReflect>,
         Value::Bool(v) => Box::new(*v),
@@ -51,6 +61,39 @@ fn to_reflect(value: &Value) -> anyhow::Result<Box<dyn PartialReflect>> {
     Ok(v)
 }
 
+pub(crate) fn to_value(value: &dyn PartialReflect) -> anyhow::Result<Value> {
+    let v = match value.reflect_ref() {
+        ReflectRef::Struct(s) => {
+            let mut map = Mapping::new();
+            for (index, field) in s.iter_fields().enumerate() {
+                let name = s.name_at(index).unwrap_or_default();
+                map.insert(Value::String(name.to_string()), to_value(field)?);
+            }
+            Value::Mapping(map)
+        }
+        ReflectRef::List(list) => Value::Sequence(list.iter()
+            .map(to_value)
+            .collect::<anyhow::Result<_>>()?),
+        ReflectRef::Tuple(tuple) if tuple.field_len() == 0 => Value::Null,
+        _ => {
+            if let Some(v) = value.try_downcast_ref::<bool>() {
+                Value::Bool(*v)
+            } else if let Some(v) = value.try_downcast_ref::<i64>() {
+                Value::Number((*v).into())
+            } else if let Some(v) = value.try_downcast_ref::<u64>() {
+                Value::Number((*v).into())
+            } else if let Some(v) = value.try_downcast_ref::<f64>() {
+                Value::Number((*v).into())
+            } else if let Some(v) = value.try_downcast_ref::<String>() {
+                Value::String(v.clone())
+            } else {
+                anyhow::bail!("unsupported spawner parameter type {}", value.reflect_type_path());
+            }
+        }
+    };
+    Ok(v)
+}
+
 #[derive(Clone, Default, Reflect, Deserialize)]
 #[reflect(Apply, Deserialize)]
 pub struct SpawnerPrefab {
@@ -61,6 +104,8 @@ pub struct SpawnerPrefab {
     #[serde(with = "humantime_serde")]
     interval: Duration,
     limit: usize,
+    #[serde(default)]
+    hours: Option<SpawnHours>,
 }
 
 impl Apply for SpawnerPrefab {
@@ -72,7 +117,11 @@ impl Apply for SpawnerPrefab {
         }
 
         let parameters = Arc::new(parameters) as Arc<dyn PartialReflect>;
-        ctx.world.entity_mut(entity)
+        let mut entity_mut = ctx.world.entity_mut(entity);
+        if !entity_mut.contains::<UniqueId>() {
+            entity_mut.insert(UniqueId::new());
+        }
+        entity_mut
             .insert(Spawner {
                 prefab: self.prefab.clone(),
                 parameters,
@@ -80,10 +129,31 @@ impl Apply for SpawnerPrefab {
                 limit: self.limit,
             })
             .insert(SpawnedEntities::default());
+        if let Some(hours) = self.hours {
+            entity_mut.insert(hours);
+        }
         Ok(())
     }
 }
 
+/// Restricts a spawner to the world hours from `start` up to `end`, wrapping past midnight.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, Component, Deserialize)]
+#[reflect(Default, Component, Deserialize)]
+pub struct SpawnHours {
+    pub start: u8,
+    pub end: u8,
+}
+
+impl SpawnHours {
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.start <= self.end {
+            hour >= self.start && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
 #[derive(Clone, Component)]
 pub struct Spawner {
     pub prefab: String,
@@ -92,43 +162,135 @@ pub struct Spawner {
     pub limit: usize,
 }
 
+impl Spawner {
+    /// Spawn a new entity from this spawner.
+    ///
+    /// Spawns are only persisted if `spawner_id` is given, which should only be
+    /// the case for persistent spawners, as other spawners are assigned a new ID
+    /// each time they are loaded.
+    pub fn spawn(
+        &self, commands: &mut Commands, position: MapPosition, spawner_id: Option<&UniqueId>,
+    ) -> Entity {
+        let request = PrefabLibraryRequest {
+            prefab_name: self.prefab.clone(),
+            parameters: self.parameters.clone(),
+        };
+        let mut entity = commands.spawn_empty();
+        entity
+            .fabricate_from_library(request)
+            .insert((
+                Spawned,
+                position,
+            ));
+
+        if let Some(spawner_id) = spawner_id {
+            entity.insert((
+                Persistent,
+                UniqueId::new(),
+                PrefabInstance { prefab_name: self.prefab.clone() },
+                SpawnedBy(spawner_id.clone()),
+            ));
+        }
+
+        entity.id()
+    }
+}
+
+impl PrefabReferences for Spawner {
+    fn prefab_references(&self) -> Vec<&str> {
+        vec![self.prefab.as_str()]
+    }
+}
+
 #[derive(Debug, Clone, Component, Reflect)]
 pub struct Spawned;
 
+/// The spawner which spawned an entity, used to reattach persisted spawns after loading.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct SpawnedBy(pub UniqueId);
+
 #[derive(Default, Debug, Clone, Component, Reflect)]
 pub struct SpawnedEntities {
     pub entities: Vec<Entity>,
 }
 
+pub fn reattach_spawned(
+    mut commands: Commands,
+    mut spawners: Query<(&UniqueId, &mut SpawnedEntities), With<Spawner>>,
+    orphans: Query<(Entity, &SpawnedBy), Without<Spawned>>,
+) {
+    for (entity, spawned_by) in &orphans {
+        let spawner = spawners.iter_mut()
+            .find(|(id, _)| id.id == spawned_by.0.id);
+        match spawner {
+            Some((_, mut spawned)) => {
+                spawned.entities.push(entity);
+                commands.entity(entity).insert(Spawned);
+            }
+            None => {
+                commands.entity(entity).remove::<SpawnedBy>();
+            }
+        }
+    }
+}
+
 pub fn spawn_from_spawners(
     time: Res<Time>,
-    mut spawners: Query<(&mut Spawner, &mut SpawnedEntities, &MapPosition)>,
+    clock: Res<WorldClock>,
+    rates: Res<ServerRates>,
+    mut spawners: Query<(
+        &mut Spawner,
+        &mut SpawnedEntities,
+        &MapPosition,
+        Option<&UniqueId>,
+        Has<Persistent>,
+        Option<&SpawnHours>,
+    )>,
     spawned_entities: Query<(), With<Spawned>>,
     mut commands: Commands,
 ) {
-    for (mut spawner, mut spawned, position) in spawners.iter_mut() {
+    let hour = clock.now().hour();
+    let delta = rates.scale_spawn_time(time.delta());
+    for (mut spawner, mut spawned, position, spawner_id, is_persistent, hours) in spawners.iter_mut() {
         spawned.entities.retain(|e| spawned_entities.contains(*e));
-        if !spawner.next_spawn.tick(time.delta()).just_finished() || spawner.limit <= spawned.entities.len() {
+        if !spawner.next_spawn.tick(delta).just_finished() || spawner.limit <= spawned.entities.len() {
             continue;
         }
 
-        let request = PrefabLibraryRequest {
-            prefab_name: spawner.prefab.clone(),
-            parameters: spawner.parameters.clone(),
-        };
-        let spawned_entity = commands.spawn_empty()
-            .fabricate_from_library(request)
-            .insert((
-                Spawned,
-                *position,
-            ))
-            .insert(*position)
-            .id();
+        if hours.is_some_and(|hours| !hours.contains(hour)) {
+            continue;
+        }
 
+        let spawner_id = spawner_id.filter(|_| is_persistent);
+        let spawned_entity = spawner.spawn(&mut commands, *position, spawner_id);
         spawned.entities.push(spawned_entity);
     }
 }
 
+/// Spawn until the spawner reaches its limit, regardless of its interval.
+pub fn restock_spawner(
+    commands: &mut Commands,
+    spawner: &mut Spawner,
+    spawned: &mut SpawnedEntities,
+    position: MapPosition,
+    spawner_id: Option<&UniqueId>,
+) {
+    while spawned.entities.len() < spawner.limit {
+        spawned.entities.push(spawner.spawn(commands, position, spawner_id));
+    }
+    spawner.next_spawn.reset();
+}
+
+/// Despawn everything which a spawner has spawned.
+pub fn clear_spawner(commands: &mut Commands, spawned: &mut SpawnedEntities) {
+    for entity in spawned.entities.drain(..) {
+        if let Some(entity) = commands.get_entity(entity) {
+            entity.despawn_recursive();
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct SpawnersPlugin;
 
@@ -136,10 +298,219 @@ impl Plugin for SpawnersPlugin {
     fn build(&self, app: &mut App) {
         app
             .register_type::<SpawnerPrefab>()
+            .register_type::<SpawnHours>()
+            .init_resource::<WorldClock>()
             .register_type::<Spawned>()
             .register_type::<SpawnedEntities>()
+            .register_type::<SpawnedBy>()
+            .register_prefab_references::<Spawner>()
+            .add_plugins(persistence::plugin)
             .add_systems(Update, (
+                reattach_spawned,
                 spawn_from_spawners,
+            ).chain());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::CommandQueue;
+    use bevy_fabricator::{Fabricated, Fabricator};
+
+    use crate::data::prefabs::PrefabLibrary;
+    use crate::persistence::{save_and_load, PersistencePlugin};
+    use crate::time::WorldTime;
+
+    use super::*;
+
+    fn rat_library() -> PrefabLibrary {
+        let mut library = PrefabLibrary::default();
+        library.insert("rat".to_string(), Fabricator {
+            parameters: HashMap::new(),
+            factory: Arc::new(|_, _, _| Ok(Fabricated::default())),
+        });
+        library
+    }
+
+    #[test]
+    fn test_restock_and_clear() {
+        let mut world = World::new();
+        world.insert_resource(rat_library());
+
+        let mut spawner = Spawner {
+            prefab: "rat".to_string(),
+            parameters: Arc::new(DynamicStruct::default()),
+            next_spawn: Timer::new(Duration::from_secs(60), TimerMode::Repeating),
+            limit: 3,
+        };
+        let mut spawned = SpawnedEntities::default();
+        let position = MapPosition::default();
+
+        let mut queue = CommandQueue::default();
+        restock_spawner(&mut Commands::new(&mut queue, &world), &mut spawner, &mut spawned, position, None);
+        queue.apply(&mut world);
+        assert_eq!(spawned.entities.len(), 3);
+        assert_eq!(world.query_filtered::<(), With<Spawned>>().iter(&world).count(), 3);
+
+        clear_spawner(&mut Commands::new(&mut queue, &world), &mut spawned);
+        queue.apply(&mut world);
+        assert!(spawned.entities.is_empty());
+        assert_eq!(world.query_filtered::<(), With<Spawned>>().iter(&world).count(), 0);
+    }
+
+    #[test]
+    fn test_spawn_hours() {
+        let night = SpawnHours { start: 20, end: 6 };
+        assert!(night.contains(23));
+        assert!(night.contains(2));
+        assert!(!night.contains(6));
+        assert!(!night.contains(12));
+
+        let spawn_at = |hour: f64| {
+            let mut app = App::new();
+            app
+                .init_resource::<Time>()
+                .insert_resource(rat_library())
+                .insert_resource(WorldClock::Fixed(WorldTime { seconds: hour * 3600. }))
+                .init_resource::<ServerRates>()
+                .add_systems(Update, spawn_from_spawners);
+            app.world_mut().spawn((
+                Spawner {
+                    prefab: "rat".to_string(),
+                    parameters: Arc::new(DynamicStruct::default()),
+                    next_spawn: Timer::new(Duration::from_secs(5), TimerMode::Repeating),
+                    limit: 3,
+                },
+                SpawnedEntities::default(),
+                MapPosition::default(),
+                night,
             ));
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(5));
+            app.update();
+            let world = app.world_mut();
+            world.query_filtered::<(), With<Spawned>>().iter(world).count()
+        };
+
+        assert_eq!(spawn_at(22.), 1);
+        assert_eq!(spawn_at(12.), 0);
+    }
+
+    #[test]
+    fn test_spawn_rate_multiplier() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .insert_resource(rat_library())
+            .init_resource::<WorldClock>()
+            .insert_resource(ServerRates { spawn_rate_multiplier: 0.5, ..default() })
+            .add_systems(Update, spawn_from_spawners);
+        app.world_mut().spawn((
+            Spawner {
+                prefab: "rat".to_string(),
+                parameters: Arc::new(DynamicStruct::default()),
+                next_spawn: Timer::new(Duration::from_secs(5), TimerMode::Repeating),
+                limit: 3,
+            },
+            SpawnedEntities::default(),
+            MapPosition::default(),
+        ));
+
+        let step = |app: &mut App| {
+            app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(5));
+            app.update();
+            let world = app.world_mut();
+            world.query_filtered::<(), With<Spawned>>().iter(world).count()
+        };
+        assert_eq!(step(&mut app), 0);
+        assert_eq!(step(&mut app), 1);
+    }
+
+    fn persistence_app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins((
+                MinimalPlugins,
+                PersistencePlugin,
+                crate::entities::persistence::plugin,
+                SpawnersPlugin,
+            ))
+            .init_resource::<ServerRates>()
+            .insert_resource(rat_library());
+        app
+    }
+
+    fn count<F: bevy::ecs::query::QueryFilter>(world: &mut World) -> usize {
+        world.query_filtered::<(), F>().iter(world).count()
+    }
+
+    #[test]
+    fn test_persist_spawns() {
+        let mut app = persistence_app();
+        let world = app.world_mut();
+        let spawner_id = UniqueId::new();
+        let mut parameters = DynamicStruct::default();
+        parameters.insert("hue", 5i64);
+        parameters.insert("name", "Giant Rat".to_string());
+        let mut spawner = Spawner {
+            prefab: "rat".to_string(),
+            parameters: Arc::new(parameters),
+            next_spawn: Timer::new(Duration::from_secs(60), TimerMode::Repeating),
+            limit: 2,
+        };
+        let mut spawned = SpawnedEntities::default();
+
+        let mut queue = CommandQueue::default();
+        restock_spawner(
+            &mut Commands::new(&mut queue, world), &mut spawner, &mut spawned,
+            MapPosition::default(), Some(&spawner_id));
+        queue.apply(world);
+        world.spawn((Persistent, spawner_id, spawner, spawned, MapPosition::default()));
+
+        let mut loaded = persistence_app();
+        save_and_load(world, loaded.world_mut());
+        loaded.update();
+
+        let world = loaded.world_mut();
+        let spawned = world.query::<&SpawnedEntities>().single(world);
+        assert_eq!(spawned.entities.len(), 2);
+        assert_eq!(count::<With<Spawner>>(world), 1);
+        assert_eq!(count::<With<Spawned>>(world), 2);
+        assert_eq!(count::<With<SpawnedBy>>(world), 2);
+
+        let spawner = world.query::<&Spawner>().single(world);
+        let ReflectRef::Struct(parameters) = spawner.parameters.reflect_ref() else {
+            panic!("spawner parameters should be a struct");
+        };
+        assert_eq!(parameters.field("hue").and_then(|v| v.try_downcast_ref::<i64>()), Some(&5));
+        assert_eq!(parameters.field("name").and_then(|v| v.try_downcast_ref::<String>()).map(String::as_str), Some("Giant Rat"));
+    }
+
+    #[test]
+    fn test_static_spawns_not_persisted() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .insert_resource(rat_library())
+            .init_resource::<WorldClock>()
+            .init_resource::<ServerRates>()
+            .add_systems(Update, spawn_from_spawners);
+        app.world_mut().spawn((
+            UniqueId::new(),
+            Spawner {
+                prefab: "rat".to_string(),
+                parameters: Arc::new(DynamicStruct::default()),
+                next_spawn: Timer::new(Duration::from_secs(5), TimerMode::Repeating),
+                limit: 1,
+            },
+            SpawnedEntities::default(),
+            MapPosition::default(),
+        ));
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(5));
+        app.update();
+
+        let world = app.world_mut();
+        assert_eq!(count::<With<Spawned>>(world), 1);
+        assert_eq!(count::<With<SpawnedBy>>(world), 0);
+        assert_eq!(count::<With<Persistent>>(world), 0);
     }
 }
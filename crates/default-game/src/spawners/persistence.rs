@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::ecs::query::WorldQuery;
+use bevy::prelude::*;
+use bevy::reflect::DynamicStruct;
+use serde_yaml::Value;
+
+use crate::entities::{Persistent, UniqueId};
+use crate::persistence::{BundleSerializer, SerializationSetupExt};
+use crate::spawners::{to_reflect, to_value, SpawnedBy, SpawnedEntities, Spawner};
+
+#[derive(Clone, Debug, Default, Reflect)]
+#[reflect(Default)]
+pub struct SpawnerDto {
+    pub prefab: String,
+    pub interval: Duration,
+    pub limit: usize,
+    pub elapsed: Duration,
+    /// The prefab parameters, as YAML.
+    #[reflect(default)]
+    pub parameters: String,
+}
+
+fn serialize_parameters(parameters: &dyn PartialReflect) -> anyhow::Result<String> {
+    Ok(serde_yaml::to_string(&to_value(parameters)?)?)
+}
+
+fn deserialize_parameters(parameters: &str) -> anyhow::Result<Arc<dyn PartialReflect>> {
+    let value = serde_yaml::from_str::<Value>(parameters)?;
+    Ok(Arc::from(to_reflect(&value)?))
+}
+
+#[derive(Default)]
+pub struct SpawnerSerializer;
+
+impl BundleSerializer for SpawnerSerializer {
+    type Query = &'static Spawner;
+    type Filter = With<Persistent>;
+    type Bundle = SpawnerDto;
+
+    fn id() -> &'static str {
+        "Spawner"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        let parameters = serialize_parameters(item.parameters.as_ref())
+            .unwrap_or_else(|err| {
+                warn!("failed to save parameters for spawner of '{}': {err}", item.prefab);
+                String::new()
+            });
+        SpawnerDto {
+            prefab: item.prefab.clone(),
+            interval: item.next_spawn.duration(),
+            limit: item.limit,
+            elapsed: item.next_spawn.elapsed(),
+            parameters,
+        }
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        let mut entity_mut = world.entity_mut(entity);
+
+        // Fall back to any parameters set up by the spawner's prefab.
+        let existing = entity_mut.get::<Spawner>()
+            .map_or_else(|| Arc::new(DynamicStruct::default()) as Arc<dyn PartialReflect>,
+                |spawner| spawner.parameters.clone());
+        let parameters = if bundle.parameters.is_empty() {
+            existing
+        } else {
+            deserialize_parameters(&bundle.parameters).unwrap_or_else(|err| {
+                warn!("failed to load parameters for spawner of '{}': {err}", bundle.prefab);
+                existing
+            })
+        };
+        let mut next_spawn = Timer::new(bundle.interval, TimerMode::Repeating);
+        next_spawn.set_elapsed(bundle.elapsed);
+
+        entity_mut.insert(Spawner {
+            prefab: bundle.prefab,
+            parameters,
+            next_spawn,
+            limit: bundle.limit,
+        });
+        if !entity_mut.contains::<SpawnedEntities>() {
+            entity_mut.insert(SpawnedEntities::default());
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SpawnedBySerializer;
+
+impl BundleSerializer for SpawnedBySerializer {
+    type Query = &'static SpawnedBy;
+    type Filter = With<Persistent>;
+    type Bundle = UniqueId;
+
+    fn id() -> &'static str {
+        "SpawnedBy"
+    }
+
+    fn extract(item: <Self::Query as WorldQuery>::Item<'_>) -> Self::Bundle {
+        item.0.clone()
+    }
+
+    fn insert(world: &mut World, entity: Entity, bundle: Self::Bundle) {
+        world.entity_mut(entity).insert(SpawnedBy(bundle));
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_serializer::<SpawnerSerializer>()
+        .register_serializer::<SpawnedBySerializer>();
+}
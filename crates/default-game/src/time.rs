@@ -1,11 +1,31 @@
 use bevy::prelude::*;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
-use yewoh::protocol::{GlobalLightLevel, IntoAnyPacket, SetTime};
-use yewoh_server::world::connection::NetClient;
+use yewoh::protocol::{ChangeSeason, GlobalLightLevel, IntoAnyPacket, SetTime};
+use yewoh_server::world::connection::{NetClient, Possessing};
+use yewoh_server::world::entity::MapPosition;
+use yewoh_server::world::map::MapInfos;
 use yewoh_server::world::view::Synchronizing;
 
 pub const MULTIPLIER: f64 = 12.;
 
+/// How many world days each season lasts.
+pub const DAYS_PER_SEASON: i64 = 30;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Spring = 0,
+    Summer = 1,
+    Autumn = 2,
+    Winter = 3,
+    /// Felucca's permanent season, which is never changed by the calendar.
+    Desolation = 4,
+}
+
+impl Season {
+    const CYCLE: [Season; 4] = [Season::Spring, Season::Summer, Season::Autumn, Season::Winter];
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct WorldTime {
     pub seconds: f64,
@@ -25,6 +45,19 @@ impl WorldTime {
         (float_light_level * 6.) as u8
     }
 
+    pub fn day(&self) -> i64 {
+        (self.seconds / 86400.).floor() as i64
+    }
+
+    pub fn season(&self) -> Season {
+        let index = self.day().div_euclid(DAYS_PER_SEASON).rem_euclid(Season::CYCLE.len() as i64);
+        Season::CYCLE[index as usize]
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hms().0
+    }
+
     pub fn hms(&self) -> (u8, u8, u8) {
         let total_seconds = self.seconds as i64;
         let seconds = total_seconds % 60;
@@ -49,12 +82,30 @@ impl<T: TimeZone> From<DateTime<T>> for WorldTime {
     }
 }
 
+/// Where the current world time comes from, so that it can be fixed for testing.
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub enum WorldClock {
+    #[default]
+    RealTime,
+    Fixed(WorldTime),
+}
+
+impl WorldClock {
+    pub fn now(&self) -> WorldTime {
+        match self {
+            WorldClock::RealTime => WorldTime::now(),
+            WorldClock::Fixed(time) => *time,
+        }
+    }
+}
+
 pub fn send_time(
+    clock: Res<WorldClock>,
     new_clients: Query<&NetClient, With<Synchronizing>>,
     all_clients: Query<&NetClient>,
     mut last_light_level: Local<u8>,
 ) {
-    let now = WorldTime::now();
+    let now = clock.now();
     let (hour, minute, second) = now.hms();
     let light_level = now.light_level();
 
@@ -77,3 +128,103 @@ pub fn send_time(
         }
     }
 }
+
+/// Advance the season of every seasonal map with the calendar and tell the clients on them.
+pub fn send_season(
+    clock: Res<WorldClock>,
+    mut maps: ResMut<MapInfos>,
+    clients: Query<(&NetClient, &Possessing)>,
+    positions: Query<&MapPosition>,
+    mut last_season: Local<Option<Season>>,
+) {
+    let season = clock.now().season();
+    if *last_season == Some(season) {
+        return;
+    }
+    let is_change = last_season.is_some();
+    *last_season = Some(season);
+
+    for map in maps.maps.values_mut() {
+        if map.season != Season::Desolation as u8 {
+            map.season = season as u8;
+        }
+    }
+
+    if !is_change {
+        return;
+    }
+
+    for (client, owned) in &clients {
+        let is_seasonal = positions.get(owned.entity).ok()
+            .and_then(|position| maps.maps.get(&position.map_id))
+            .is_some_and(|map| map.season != Season::Desolation as u8);
+        if is_seasonal {
+            client.send_packet(ChangeSeason { season: season as u8, play_sound: true });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh_server::world::connection::WriterAction;
+    use yewoh_server::world::map::MapInfo;
+
+    use crate::networking::{sent_packets, test_client};
+
+    use super::*;
+
+    fn day(day: i64) -> WorldClock {
+        WorldClock::Fixed(WorldTime { seconds: day as f64 * 86400. + 3600. })
+    }
+
+    fn seasons(rx: &mut UnboundedReceiver<WriterAction>) -> Vec<u8> {
+        sent_packets::<ChangeSeason>(rx).into_iter()
+            .map(|packet| packet.season)
+            .collect()
+    }
+
+    #[test]
+    fn test_season_calendar() {
+        assert_eq!(day(0).now().season(), Season::Spring);
+        assert_eq!(day(DAYS_PER_SEASON - 1).now().season(), Season::Spring);
+        assert_eq!(day(DAYS_PER_SEASON).now().season(), Season::Summer);
+        assert_eq!(day(DAYS_PER_SEASON * 3).now().season(), Season::Winter);
+        assert_eq!(day(DAYS_PER_SEASON * 4).now().season(), Season::Spring);
+        assert_eq!(day(-1).now().season(), Season::Winter);
+    }
+
+    #[test]
+    fn test_season_broadcast() {
+        let mut app = App::new();
+        let mut maps = MapInfos::default();
+        maps.maps.insert(0, MapInfo { season: Season::Desolation as u8, ..default() });
+        maps.maps.insert(1, MapInfo::default());
+        app
+            .insert_resource(maps)
+            .insert_resource(day(DAYS_PER_SEASON - 1))
+            .add_systems(Update, send_season);
+
+        let mut spawn_client = |map_id: u8| {
+            let character = app.world_mut().spawn(MapPosition { map_id, ..default() }).id();
+            let (client, rx) = test_client();
+            app.world_mut().spawn((client, Possessing { entity: character }));
+            rx
+        };
+        let mut felucca = spawn_client(0);
+        let mut trammel = spawn_client(1);
+
+        app.update();
+        app.update();
+        assert!(seasons(&mut trammel).is_empty());
+
+        app.insert_resource(day(DAYS_PER_SEASON));
+        app.update();
+        assert_eq!(seasons(&mut trammel), vec![Season::Summer as u8]);
+        assert!(seasons(&mut felucca).is_empty());
+
+        let maps = app.world().resource::<MapInfos>();
+        assert_eq!(maps.maps[&1].season, Season::Summer as u8);
+        assert_eq!(maps.maps[&0].season, Season::Desolation as u8);
+    }
+}
@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+use yewoh::protocol::{SetWeather, WeatherKind};
+use yewoh_server::world::connection::{NetClient, Possessing};
+
+use crate::regions::{update_region_presence, OnRegionEnter, OnRegionExit, RegionPresence};
+use crate::rng::GameRng;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub enum Precipitation {
+    #[default]
+    None,
+    Rain,
+    Storm,
+    StormBrewing,
+    Snow,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct Weather {
+    pub precipitation: Precipitation,
+    pub intensity: u8,
+    pub temperature: u8,
+}
+
+impl Weather {
+    pub fn to_packet(&self) -> SetWeather {
+        let kind = match self.precipitation {
+            Precipitation::None => WeatherKind::None,
+            Precipitation::Rain => WeatherKind::Rain,
+            Precipitation::Storm => WeatherKind::Storm,
+            Precipitation::StormBrewing => WeatherKind::StormBrewing,
+            Precipitation::Snow => WeatherKind::Snow,
+        };
+        SetWeather {
+            kind,
+            intensity: self.intensity.min(70),
+            temperature: self.temperature,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Reflect)]
+#[reflect(Default)]
+pub struct Forecast {
+    pub weather: Weather,
+    pub weight: u32,
+}
+
+/// Periodically rolls new weather for the [`Region`](crate::regions::Region) on the same entity.
+#[derive(Debug, Clone, Default, Reflect, Component)]
+#[reflect(Default, Component)]
+pub struct RegionWeather {
+    pub interval: Duration,
+    pub forecasts: Vec<Forecast>,
+    #[reflect(ignore)]
+    pub current: Weather,
+    #[reflect(ignore)]
+    pub next_change: Duration,
+}
+
+impl RegionWeather {
+    pub fn roll(&self, rng: &mut impl Rng) -> Weather {
+        let total = self.forecasts.iter().map(|forecast| forecast.weight).sum::<u32>();
+        if total == 0 {
+            return Weather::default();
+        }
+
+        let mut pick = rng.gen_range(0..total);
+        for forecast in &self.forecasts {
+            if pick < forecast.weight {
+                return forecast.weather;
+            }
+            pick -= forecast.weight;
+        }
+        unreachable!()
+    }
+}
+
+fn send_to_region(
+    clients: &Query<(&NetClient, &Possessing)>,
+    presences: &Query<&RegionPresence>,
+    region: Entity,
+    packet: SetWeather,
+) {
+    for (client, owned) in clients {
+        if presences.get(owned.entity).is_ok_and(|presence| presence.is_in(region)) {
+            client.send_packet(packet.clone());
+        }
+    }
+}
+
+pub fn update_region_weather(
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    clients: Query<(&NetClient, &Possessing)>,
+    presences: Query<&RegionPresence>,
+    mut regions: Query<(Entity, &mut RegionWeather)>,
+) {
+    let now = time.elapsed();
+    for (region, mut weather) in &mut regions {
+        if now < weather.next_change {
+            continue;
+        }
+
+        weather.next_change = now + weather.interval;
+        let next = weather.roll(&mut *rng);
+        if next != weather.current {
+            weather.current = next;
+            send_to_region(&clients, &presences, region, next.to_packet());
+        }
+    }
+}
+
+pub fn send_weather_on_region_change(
+    clients: Query<(&NetClient, &Possessing)>,
+    regions: Query<&RegionWeather>,
+    mut enter_events: EventReader<OnRegionEnter>,
+    mut exit_events: EventReader<OnRegionExit>,
+) {
+    let weather_changes = exit_events.read()
+        .filter(|event| regions.contains(event.region))
+        .map(|event| (event.character, Weather::default()))
+        .chain(enter_events.read()
+            .filter_map(|event| Some((event.character, regions.get(event.region).ok()?.current))));
+
+    for (character, weather) in weather_changes {
+        for (client, owned) in &clients {
+            if owned.entity == character {
+                client.send_packet(weather.to_packet());
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .register_type::<Precipitation>()
+        .register_type::<Weather>()
+        .register_type::<Forecast>()
+        .register_type::<RegionWeather>()
+        .add_systems(Update, (
+            send_weather_on_region_change,
+            update_region_weather,
+        ).chain().after(update_region_presence));
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use yewoh_server::world::connection::WriterAction;
+
+    use crate::networking::{sent_packets, test_client};
+
+    use super::*;
+
+    fn spawn_client(app: &mut App, presence: RegionPresence) -> UnboundedReceiver<WriterAction> {
+        let character = app.world_mut().spawn(presence).id();
+        let (client, rx) = test_client();
+        app.world_mut().spawn((client, Possessing { entity: character }));
+        rx
+    }
+
+    fn weather_packets(rx: &mut UnboundedReceiver<WriterAction>) -> Vec<WeatherKind> {
+        sent_packets::<SetWeather>(rx).into_iter()
+            .map(|packet| packet.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_broadcast_in_region() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .insert_resource(GameRng::from_seed(1))
+            .add_event::<OnRegionEnter>()
+            .add_event::<OnRegionExit>()
+            .add_systems(Update, (
+                send_weather_on_region_change,
+                update_region_weather,
+            ).chain());
+
+        let rain = Weather { precipitation: Precipitation::Rain, intensity: 30, temperature: 10 };
+        let region = app.world_mut()
+            .spawn(RegionWeather {
+                interval: Duration::from_secs(60),
+                forecasts: vec![Forecast { weather: rain, weight: 1 }],
+                ..default()
+            })
+            .id();
+
+        let mut presence = RegionPresence::default();
+        presence.regions.insert(region, None);
+        let mut inside = spawn_client(&mut app, presence);
+        let mut outside = spawn_client(&mut app, RegionPresence::default());
+
+        app.update();
+        assert_eq!(weather_packets(&mut inside), vec![WeatherKind::Rain]);
+        assert!(weather_packets(&mut outside).is_empty());
+
+        // The same weather is not sent again.
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs(60));
+        app.update();
+        assert!(weather_packets(&mut inside).is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch};
+use axum::{Json, Router};
+use bevy::ecs::entity::Entities;
+use bevy::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::watch;
+
+use yewoh_server::world::characters::{CharacterBodyType, CharacterName};
+use yewoh_server::world::connection::{NetClient, Possessing};
+use yewoh_server::world::entity::MapPosition;
+use yewoh_server::world::items::ItemGraphic;
+use yewoh::EntityId;
+use yewoh_server::metrics::METRICS;
+use yewoh_server::remote_reflect::{EntityTarget, RemoteReflectClient};
+
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServerStatus {
+    pub uptime_secs: u64,
+    pub connected_clients: usize,
+    pub entities: usize,
+    pub characters: usize,
+    pub items: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlayerStatus {
+    pub name: String,
+    pub map_id: u8,
+    pub position: [i32; 3],
+}
+
+/// A copy of the world state which can be served without touching the ECS.
+#[derive(Debug, Clone, Default)]
+pub struct StatusSnapshot {
+    pub status: ServerStatus,
+    pub players: Vec<PlayerStatus>,
+}
+
+#[derive(Resource)]
+pub struct StatusPublisher {
+    tx: watch::Sender<StatusSnapshot>,
+    timer: Timer,
+    published: bool,
+}
+
+impl StatusPublisher {
+    pub fn new() -> (StatusPublisher, watch::Receiver<StatusSnapshot>) {
+        let (tx, rx) = watch::channel(StatusSnapshot::default());
+        let publisher = StatusPublisher {
+            tx,
+            timer: Timer::new(SNAPSHOT_INTERVAL, TimerMode::Repeating),
+            published: false,
+        };
+        (publisher, rx)
+    }
+}
+
+pub fn publish_status(
+    time: Res<Time<Real>>,
+    mut publisher: ResMut<StatusPublisher>,
+    entities: &Entities,
+    clients: Query<&Possessing, With<NetClient>>,
+    characters: Query<(Option<&CharacterName>, Option<&MapPosition>), With<CharacterBodyType>>,
+    items: Query<(), With<ItemGraphic>>,
+) {
+    let publisher = &mut *publisher;
+    if !publisher.timer.tick(time.delta()).just_finished() && publisher.published {
+        return;
+    }
+    publisher.published = true;
+
+    let players = clients.iter()
+        .filter_map(|possessing| characters.get(possessing.entity).ok())
+        .map(|(name, position)| {
+            let position = position.copied().unwrap_or_default();
+            PlayerStatus {
+                name: name.map_or_else(String::new, |n| n.0.clone()),
+                map_id: position.map_id,
+                position: position.position.to_array(),
+            }
+        })
+        .collect();
+
+    let status = ServerStatus {
+        uptime_secs: time.elapsed().as_secs(),
+        connected_clients: clients.iter().len(),
+        entities: entities.len() as usize,
+        characters: characters.iter().len(),
+        items: items.iter().len(),
+    };
+
+    publisher.tx.send_replace(StatusSnapshot { status, players });
+}
+
+async fn get_status(State(rx): State<watch::Receiver<StatusSnapshot>>) -> Json<ServerStatus> {
+    Json(rx.borrow().status.clone())
+}
+
+async fn get_players(State(rx): State<watch::Receiver<StatusSnapshot>>) -> Json<Vec<PlayerStatus>> {
+    Json(rx.borrow().players.clone())
+}
+
+async fn get_metrics() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], METRICS.render())
+}
+
+fn parse_net_id(id: &str) -> Option<u32> {
+    match id.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => id.parse().ok(),
+    }
+}
+
+async fn inspect(
+    client: &RemoteReflectClient, target: EntityTarget,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    client.inspect(target).await
+        .map(Json)
+        .map_err(|err| (StatusCode::NOT_FOUND, err.to_string()))
+}
+
+async fn get_net_entity(
+    State(client): State<RemoteReflectClient>, Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let id = parse_net_id(&id)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("invalid net id '{id}'")))?;
+    inspect(&client, EntityTarget::Net(EntityId::from_u32(id))).await
+}
+
+async fn get_local_entity(
+    State(client): State<RemoteReflectClient>, Path(bits): Path<u64>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let entity = Entity::try_from_bits(bits)
+        .map_err(|_| (StatusCode::BAD_REQUEST, format!("invalid entity {bits}")))?;
+    inspect(&client, EntityTarget::Local(entity)).await
+}
+
+async fn patch_net_entity(
+    State(client): State<RemoteReflectClient>,
+    Path((id, component)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let id = parse_net_id(&id)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("invalid net id '{id}'")))?;
+    client.patch(EntityTarget::Net(EntityId::from_u32(id)), component, body).await
+        .map(Json)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))
+}
+
+/// The endpoints which expose players or can read and modify the world directly.
+///
+/// These are only served if an admin token is configured, and every request
+/// must present it as a bearer token.
+#[derive(Clone)]
+pub struct AdminApi {
+    pub reflect: RemoteReflectClient,
+    pub token: Arc<str>,
+}
+
+async fn require_admin_token(State(token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let authorized = request.headers().get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|value| value == &*token);
+    if authorized {
+        next.run(request).await
+    } else {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+pub fn router(rx: watch::Receiver<StatusSnapshot>, admin: Option<AdminApi>) -> Router {
+    let router = Router::new()
+        .route("/status", get(get_status))
+        .route("/metrics", get(get_metrics))
+        .with_state(rx.clone());
+
+    let Some(admin) = admin else {
+        return router;
+    };
+
+    let players = Router::new()
+        .route("/players", get(get_players))
+        .with_state(rx);
+    let entities = Router::new()
+        .route("/entities/:id", get(get_net_entity))
+        .route("/entities/:id/:component", patch(patch_net_entity))
+        .route("/entities/local/:bits", get(get_local_entity))
+        .with_state(admin.reflect);
+    let admin_routes = players
+        .merge(entities)
+        .route_layer(middleware::from_fn_with_state(admin.token, require_admin_token));
+    router.merge(admin_routes)
+}
+
+pub fn add_status_publisher(app: &mut App) -> watch::Receiver<StatusSnapshot> {
+    let (publisher, rx) = StatusPublisher::new();
+    app
+        .insert_resource(publisher)
+        .add_systems(Last, publish_status);
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use bevy::tasks::block_on;
+    use tower::ServiceExt;
+    use yewoh_server::remote_reflect;
+
+    use super::*;
+
+    #[test]
+    fn test_status_endpoint() {
+        let mut app = App::new();
+        app.init_resource::<Time<Real>>();
+        let rx = add_status_publisher(&mut app);
+
+        let character = app.world_mut()
+            .spawn((
+                CharacterBodyType(0x190),
+                CharacterName("Gerome".into()),
+                MapPosition { map_id: 1, ..default() },
+            ))
+            .id();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        app.world_mut().spawn((
+            NetClient::new("127.0.0.1:2593".parse().unwrap(), Default::default(), tx),
+            Possessing { entity: character },
+        ));
+        app.world_mut().spawn(ItemGraphic(0xeed));
+        app.update();
+
+        let response = block_on(router(rx, None).oneshot(
+            Request::get("/status").body(Body::empty()).unwrap())).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = block_on(to_bytes(response.into_body(), usize::MAX)).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(value["uptime_secs"].is_u64());
+        assert_eq!(value["connected_clients"], 1);
+        assert_eq!(value["entities"], 3);
+        assert_eq!(value["characters"], 1);
+        assert_eq!(value["items"], 1);
+    }
+
+    #[test]
+    fn test_metrics_endpoint() {
+        let (_, rx) = StatusPublisher::new();
+        METRICS.packets_received.inc();
+        METRICS.packets_sent.add(2);
+
+        let response = block_on(router(rx, None).oneshot(
+            Request::get("/metrics").body(Body::empty()).unwrap())).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = block_on(to_bytes(response.into_body(), usize::MAX)).unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let counter = |name: &str| body.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap();
+        assert!(counter("yewoh_packets_received_total") > 0);
+        assert!(counter("yewoh_packets_sent_total") > 0);
+    }
+
+    #[test]
+    fn test_admin_endpoints_require_token() {
+        let (_, rx) = StatusPublisher::new();
+        let fetch = |uri: &str, admin: Option<AdminApi>, token: Option<&str>| {
+            let mut request = Request::get(uri);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            let response = block_on(router(rx.clone(), admin)
+                .oneshot(request.body(Body::empty()).unwrap())).unwrap();
+            response.status()
+        };
+        let admin = AdminApi {
+            reflect: remote_reflect::channel().0,
+            token: "secret".into(),
+        };
+
+        for uri in ["/players", "/entities/0x40000001", "/entities/local/1"] {
+            assert_eq!(fetch(uri, None, Some("secret")), StatusCode::NOT_FOUND);
+            assert_eq!(fetch(uri, Some(admin.clone()), None), StatusCode::UNAUTHORIZED);
+            assert_eq!(fetch(uri, Some(admin.clone()), Some("wrong")), StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(fetch("/players", Some(admin), Some("secret")), StatusCode::OK);
+    }
+}
@@ -9,10 +9,12 @@ use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
 use bevy::asset::{handle_internal_asset_events, AssetPath, LoadState};
+use bevy::diagnostic::{DiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use bevy::tasks::block_on;
 use bevy::time::Time;
+use bevy::utils::HashMap;
 use clap::Parser;
 use futures::stream::FuturesUnordered;
 use futures::{FutureExt, StreamExt, TryFutureExt};
@@ -26,23 +28,31 @@ use yewoh::assets::tiles::load_tile_data;
 use yewoh_default_game::data::static_data;
 use yewoh_default_game::persistence::{migrate, SerializationWorldExt, SerializedBuffers};
 use yewoh_default_game::DefaultGamePlugins;
+use yewoh_default_game::rng::GameRng;
 use yewoh_server::async_runtime::AsyncRuntime;
 use yewoh_server::game_server::listen_for_game;
 use yewoh_server::lobby::{listen_for_lobby, LocalServerRepository};
-use yewoh_server::world::connection::NetServer;
-use yewoh_server::world::map::{self, Chunk, MultiDataResource, Static, TileDataResource};
+use yewoh_server::metrics::METRICS;
+use yewoh_server::remote_reflect;
+use yewoh_server::world::connection::{close_connections, NetServer};
+use yewoh_server::world::map::{self, Chunk, MultiDataResource, StaticChunks, TileDataResource};
 use yewoh_server::world::ServerPlugin;
 
 use bevy_fabricator::hot_reload::{FabricatorChanged, WatchForFabricatorChanges};
+use bevy_fabricator::document::Document;
+use bevy_fabricator::parser::FilePosition;
+use bevy_fabricator::validate::validate_documents;
 use bevy_fabricator::{empty_reflect, Fabricate, FabricateExt, Fabricated, Fabricator};
 use sqlx::postgres::PgPool;
-use yewoh_default_game::accounts::sql::{SqlAccountRepository, SqlAccountRepositoryConfig};
-use yewoh_default_game::data::prefabs::PrefabLibrary;
+use yewoh_default_game::accounts::sql::{AutoCreatePolicy, SqlAccountRepository, SqlAccountRepositoryConfig};
+use yewoh_default_game::data::prefabs::{find_dangling_references, PrefabLibrary};
 use yewoh_default_game::data::static_data::DataPath;
 use yewoh_default_game::persistence::db::WorldRepository;
 use yewoh_server::world::delta_grid::DeltaGrid;
 use yewoh_server::world::spatial::{ChunkLookup, SpatialCharacterLookup, SpatialDynamicItemLookup, SpatialStaticItemLookup};
 
+mod http;
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
 struct Args {
@@ -63,9 +73,13 @@ struct Args {
     advertise_address: String,
 
     /// The bind address for the HTTP server.
-    #[clap(long, default_value = "0.0.0.0:2595", env = "YEWOH_HTTP_BIND")]
+    #[clap(long, default_value = "127.0.0.1:2595", env = "YEWOH_HTTP_BIND")]
     http_bind: String,
 
+    /// Serve the player list and entity inspection endpoints, requiring this bearer token.
+    #[clap(long, env = "YEWOH_HTTP_ADMIN_TOKEN")]
+    http_admin_token: Option<String>,
+
     /// The bind address for the lobby server.
     #[clap(long, default_value = "0.0.0.0:2593", env = "YEWOH_LOBBY_BIND")]
     lobby_bind: String,
@@ -88,6 +102,34 @@ struct Args {
 
     #[clap(long, default_value = "false", env = "YEWOH_AUTO_CREATE_ACCOUNTS")]
     auto_create_accounts: bool,
+
+    /// Only automatically create accounts whose usernames match this pattern (`*` and `?` wildcards).
+    #[clap(long, env = "YEWOH_AUTO_CREATE_USERNAME_PATTERN")]
+    auto_create_username_pattern: Option<String>,
+
+    /// The shortest password accepted when automatically creating an account.
+    #[clap(long, default_value = "0", env = "YEWOH_AUTO_CREATE_MIN_PASSWORD_LENGTH")]
+    auto_create_min_password_length: usize,
+
+    /// Require new accounts to log in with `<code>:<password>` the first time.
+    #[clap(long, env = "YEWOH_AUTO_CREATE_INVITE_CODE")]
+    auto_create_invite_code: Option<String>,
+
+    /// How often to log frame timing diagnostics, if at all.
+    #[clap(long, env = "YEWOH_LOG_DIAGNOSTICS", value_parser = humantime::parse_duration)]
+    log_diagnostics: Option<Duration>,
+
+    /// Check that all fabricator files compile, then exit without starting the server.
+    #[clap(long, default_value = "false")]
+    validate: bool,
+
+    /// Print the graphviz graph of a fabricator file, then exit without starting the server.
+    #[clap(long)]
+    dump_dot: Option<PathBuf>,
+
+    /// Seed for the game's random rolls, to make a shard reproducible.
+    #[clap(long, env = "YEWOH_RNG_SEED")]
+    rng_seed: Option<u64>,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -98,7 +140,22 @@ fn main() -> anyhow::Result<()> {
 
     let frame_wait = Duration::from_millis(20);
     let load_wait = Duration::from_millis(100);
+    let shutdown_wait = Duration::from_secs(10);
     let args = Args::parse();
+    if args.validate {
+        return validate_fabricators(&args.data_path);
+    }
+
+    if let Some(path) = &args.dump_dot {
+        let src = std::fs::read_to_string(path)?;
+        let file_path = path.to_string_lossy();
+        let doc = Document::parse(&src)
+            .map_err(|e| e.map_position(|p|
+                FilePosition::from_file_and_str(file_path.as_ref(), &src, p)))?;
+        print!("{}", doc.to_dot());
+        return Ok(());
+    }
+
     let pool = block_on(async move {
         let pool = Arc::new(PgPool::connect(&args.postgres).await?);
         migrate(&pool).await?;
@@ -119,6 +176,11 @@ fn main() -> anyhow::Result<()> {
         args.server_display_name.clone(), external_ip, game_port, 0, new_session_requests_tx.clone());
     let accounts_repo = SqlAccountRepository::new(SqlAccountRepositoryConfig {
         auto_create_accounts: args.auto_create_accounts,
+        auto_create_policy: AutoCreatePolicy {
+            username_pattern: args.auto_create_username_pattern.clone(),
+            min_password_length: args.auto_create_min_password_length,
+            invite_code: args.auto_create_invite_code.clone(),
+        },
     }, pool.clone());
     let world_repo = WorldRepository::new(pool.clone(), args.shard_id.clone());
 
@@ -128,6 +190,7 @@ fn main() -> anyhow::Result<()> {
     app
         .add_plugins((
             MinimalPlugins,
+            DiagnosticsPlugin,
             LogPlugin::default(),
             AssetPlugin {
                 file_path: abs_data_path.to_string_lossy().to_string(),
@@ -136,6 +199,16 @@ fn main() -> anyhow::Result<()> {
             DefaultGamePlugins,
             ServerPlugin,
         ));
+    if let Some(wait_duration) = args.log_diagnostics {
+        app.add_plugins(LogDiagnosticsPlugin {
+            wait_duration,
+            ..default()
+        });
+    }
+
+    if let Some(seed) = args.rng_seed {
+        app.insert_resource(GameRng::from_seed(seed));
+    }
     app.finish();
     app.cleanup();
 
@@ -146,9 +219,9 @@ fn main() -> anyhow::Result<()> {
         .insert_resource(prefabs)
         .insert_resource(prefab_handles);
 
-    let (static_data, map_infos, tile_data, multi_data, map_entities, static_entities) = block_on(async {
-        let static_data = static_data::load_from_directory(&args.data_path).await?;
-        let map_infos = static_data.maps.map_infos();
+    let (data_files, map_infos, tile_data, multi_data, map_entities, static_entities) = block_on(async {
+        let data_files = static_data::load_from_directory(&args.data_path).await?;
+        let map_infos = data_files.static_data.maps.map_infos()?;
         let tile_data = load_tile_data(&args.uo_data_path).await?;
         let multi_data = load_multi_data(&args.uo_data_path).await?;
 
@@ -158,23 +231,31 @@ fn main() -> anyhow::Result<()> {
         info!("Loading statics...");
         let static_entities = map::load_static_entities(&map_infos, &args.uo_data_path).await?;
 
-        Ok::<_, anyhow::Error>((static_data, map_infos, tile_data, multi_data, map_entities, static_entities))
+        Ok::<_, anyhow::Error>((data_files, map_infos, tile_data, multi_data, map_entities, static_entities))
     })?;
+    data_files.insert_into(app.world_mut());
 
     // Spawn map
     info!("Spawning map...");
     map::spawn_map_entities(app.world_mut(), map_entities.into_iter());
-    info!("Spawning statics...");
-    map::spawn_static_entities(app.world_mut(), &tile_data, &static_entities);
 
     // Spawn map data
     {
         let mut query = app.world_mut().query_filtered::<(), With<Chunk>>();
         info!("Spawned {} map chunks", query.iter(app.world()).count());
-        let mut query = app.world_mut().query_filtered::<(), With<Static>>();
-        info!("Spawned {} statics", query.iter(app.world()).count());
     }
 
+    // Collision must be available everywhere, but the rest of the statics are only streamed in
+    // as players approach them.
+    let (collision_statics, other_statics): (Vec<_>, Vec<_>) = static_entities.into_iter()
+        .partition(|item| map::has_collision(&tile_data, item.graphic_id));
+    info!("Spawning statics...");
+    map::spawn_static_entities(app.world_mut(), &tile_data, &collision_statics);
+    info!("Spawned {} statics with collision", collision_statics.len());
+    let static_chunks = StaticChunks::new(other_statics);
+    info!("Loaded statics for {} chunks", static_chunks.num_chunks());
+    app.insert_resource(static_chunks);
+
     // Initialise spatial lookups
     app
         .insert_resource(SpatialCharacterLookup::new(&map_infos))
@@ -187,12 +268,14 @@ fn main() -> anyhow::Result<()> {
     load_static_entities(&mut app, load_wait, &args.data_path, "entities")?;
 
     let mut listen_futures = FuturesUnordered::new();
+    let mut listener_aborts = Vec::new();
     let (new_session_tx, new_session_rx) = mpsc::unbounded_channel();
 
     // Listen for game traffic
     {
         let game_listener = block_on(TcpListener::bind(&args.game_bind))?;
         let game_handle = tokio::spawn(listen_for_game(game_listener, new_session_tx.clone()));
+        listener_aborts.push(game_handle.abort_handle());
         listen_futures.push(async move {
             game_handle.await??;
             return Err(anyhow!("failed to serve game"));
@@ -207,6 +290,7 @@ fn main() -> anyhow::Result<()> {
         let lobby_handle = tokio::spawn(listen_for_lobby(
             lobby_listener, true,
             move || server_repo.clone(), move || accounts_repo_clone.clone()));
+        listener_aborts.push(lobby_handle.abort_handle());
         listen_futures.push(async move {
             lobby_handle.await??;
             return Err(anyhow!("failed to serve lobby"));
@@ -220,15 +304,24 @@ fn main() -> anyhow::Result<()> {
         let lobby_handle = tokio::spawn(listen_for_lobby(
             lobby_listener, false,
             move || server_repo.clone(), move || accounts_repo_clone.clone()));
+        listener_aborts.push(lobby_handle.abort_handle());
         listen_futures.push(async move {
             lobby_handle.await??;
             return Err(anyhow!("failed to serve unencrypted lobby"));
         }.boxed());
     }
 
-    let http_app = axum::Router::new();
+    let status_rx = http::add_status_publisher(&mut app);
+    let http_admin = args.http_admin_token.as_deref().map(|token| {
+        let (reflect, reflect_queue) = remote_reflect::channel();
+        app.insert_resource(reflect_queue);
+        http::AdminApi { reflect, token: token.into() }
+    });
+    let http_app = http::router(status_rx, http_admin);
     let http_server_handle = tokio::spawn(axum_server::bind(SocketAddr::from_str(&args.http_bind)?)
-        .serve(http_app.into_make_service()))
+        .serve(http_app.into_make_service()));
+    listener_aborts.push(http_server_handle.abort_handle());
+    let http_server_handle = http_server_handle
         .map_err(|e| anyhow::Error::from(e))
         .boxed();
     listen_futures.push(http_server_handle);
@@ -237,7 +330,6 @@ fn main() -> anyhow::Result<()> {
         .insert_resource(AsyncRuntime::from(tokio::runtime::Handle::current()))
         .insert_resource(NetServer::new(new_session_requests, new_session_rx))
         .insert_resource(map_infos)
-        .insert_resource(static_data)
         .insert_resource(DataPath(abs_data_path))
         .insert_resource(TileDataResource { tile_data })
         .insert_resource(MultiDataResource { multi_data })
@@ -277,6 +369,18 @@ fn main() -> anyhow::Result<()> {
 
     loop {
         if SHOULD_EXIT.load(Ordering::Relaxed) {
+            // Stop accepting connections, then let each writer drain before saving.
+            for abort in &listener_aborts {
+                abort.abort();
+            }
+            serve_handle.abort();
+
+            close_connections(app.world_mut());
+            let async_runtime = app.world().resource::<AsyncRuntime>();
+            if block_on(tokio::time::timeout(shutdown_wait, async_runtime.wait_for_tracked())).is_err() {
+                warn!("Timed out waiting for pending tasks");
+            }
+
             let contents = app.world_mut().serialize();
             let repo = app.world().resource::<WorldRepository>();
             block_on(write_save(repo, contents))?;
@@ -298,6 +402,7 @@ fn main() -> anyhow::Result<()> {
         let _span = info_span!("frame sleep").entered();
         let end_time = Instant::now();
         let frame_duration = end_time - start_time;
+        METRICS.frame_time_seconds.set(frame_duration.as_secs_f64());
         if frame_duration < frame_wait {
             std::thread::sleep(frame_wait - frame_duration);
         }
@@ -308,6 +413,60 @@ fn main() -> anyhow::Result<()> {
 #[allow(dead_code)]
 struct PrefabHandles(Vec<Handle<Fabricator>>);
 
+async fn read_fabricator_sources(root_path: &Path) -> anyhow::Result<HashMap<String, String>> {
+    let mut sources = HashMap::new();
+    let mut to_visit = VecDeque::new();
+    to_visit.push_back(PathBuf::new());
+
+    while let Some(dir_path) = to_visit.pop_front() {
+        let mut entries = fs::read_dir(root_path.join(&dir_path)).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = dir_path.join(entry.file_name());
+            if entry.metadata().await?.is_dir() {
+                to_visit.push_back(path);
+            } else if path.extension().is_some_and(|ext| ext == "fab") {
+                let contents = fs::read_to_string(root_path.join(&path)).await?;
+                let key = path.iter()
+                    .map(|part| part.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                sources.insert(key, contents);
+            }
+        }
+    }
+
+    Ok(sources)
+}
+
+fn validate_fabricators(data_path: &Path) -> anyhow::Result<()> {
+    let mut app = App::new();
+    app
+        .add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: data_path.to_string_lossy().to_string(),
+                ..default()
+            },
+            DefaultGamePlugins,
+            ServerPlugin,
+        ));
+
+    let sources = block_on(read_fabricator_sources(data_path))?;
+    let type_registry = app.world().resource::<AppTypeRegistry>().read();
+    match validate_documents(&type_registry, &sources) {
+        Ok(()) => {
+            println!("{} fabricators are valid", sources.len());
+            Ok(())
+        }
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("{error}");
+            }
+            Err(anyhow!("{} fabricators failed validation", errors.len()))
+        }
+    }
+}
+
 fn load_prefabs(
     app: &mut App,
     step_duration: Duration,
@@ -372,6 +531,9 @@ fn load_prefabs(
     }
 
     info!("Loaded {} prefabs", library.len());
+    for dangling in find_dangling_references(app.world(), &library) {
+        warn!("prefab '{}' references missing prefab '{}'", dangling.prefab_name, dangling.reference);
+    }
     Ok((library, PrefabHandles(handles)))
 }
 
@@ -562,12 +724,14 @@ fn scheduled_save(world: &mut World, mut timer: Local<SaveTimer>) {
         return;
     }
 
+    let start_time = Instant::now();
     let buffers = world.serialize();
     let repo = world.resource::<WorldRepository>().clone();
-    world.resource::<AsyncRuntime>().spawn(async move {
+    world.resource::<AsyncRuntime>().spawn_tracked(async move {
         if let Err(e) = write_save(&repo, buffers).await {
             warn!("failed to save: {e}");
         } else {
+            METRICS.save_duration_seconds.set(start_time.elapsed().as_secs_f64());
             info!("Saved snapshot");
         }
     });
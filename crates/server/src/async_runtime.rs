@@ -1,21 +1,50 @@
+use std::future::Future;
 use std::ops::Deref;
+use std::sync::{Arc, Mutex};
 
 use bevy::ecs::system::Resource;
 use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
 
 #[derive(Resource, Clone)]
-pub struct AsyncRuntime(Handle);
+pub struct AsyncRuntime {
+    handle: Handle,
+    tracked: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl AsyncRuntime {
+    /// Spawn a task which must be allowed to finish before the server exits.
+    pub fn spawn_tracked(&self, future: impl Future<Output=()> + Send + 'static) {
+        let handle = self.handle.spawn(future);
+        let mut tracked = self.tracked.lock().unwrap();
+        tracked.retain(|task| !task.is_finished());
+        tracked.push(handle);
+    }
+
+    /// Wait for all tasks spawned with `spawn_tracked` to finish.
+    pub fn wait_for_tracked(&self) -> impl Future<Output=()> + Send + 'static {
+        let tasks = std::mem::take(&mut *self.tracked.lock().unwrap());
+        async move {
+            for task in tasks {
+                task.await.ok();
+            }
+        }
+    }
+}
 
 impl Deref for AsyncRuntime {
     type Target = Handle;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.handle
     }
 }
 
 impl From<Handle> for AsyncRuntime {
-    fn from(value: Handle) -> Self {
-        Self(value)
+    fn from(handle: Handle) -> Self {
+        Self {
+            handle,
+            tracked: Default::default(),
+        }
     }
 }
@@ -6,3 +6,5 @@ pub mod world;
 pub mod gump_builder;
 pub mod async_runtime;
 pub mod math;
+pub mod metrics;
+pub mod remote_reflect;
@@ -1,8 +1,9 @@
 use std::collections::{hash_map, HashMap};
 use std::fmt::{Debug, Formatter};
-use std::net::Ipv4Addr;
-use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::atomic::{AtomicU32, AtomicU8, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
 use async_trait::async_trait;
@@ -18,7 +19,7 @@ use yewoh::types::FixedString;
 
 #[async_trait]
 pub trait AccountRepository {
-    async fn login(&mut self, username: &str, password: &str) -> anyhow::Result<()>;
+    async fn login(&mut self, username: &str, password: &str, address: IpAddr) -> anyhow::Result<()>;
 }
 
 #[async_trait]
@@ -37,6 +38,7 @@ pub async fn serve_lobby(
     mut servers: impl ServerRepository, mut accounts: impl AccountRepository,
     encrypted: bool, stream: TcpStream,
 ) -> anyhow::Result<()> {
+    let address = stream.peer_addr()?.ip();
     let (mut reader, mut writer) = new_io::<true>(stream);
     let seed = reader.recv(ClientVersion::default()).await?
         .and_then(|r| r.downcast::<Seed>().ok())
@@ -52,7 +54,7 @@ pub async fn serve_lobby(
         .downcast::<AccountLogin>()
         .map_err(|_| anyhow!("expected account login attempt"))?;
 
-    if let Err(err) = accounts.login(&login.username, &login.password).await {
+    if let Err(err) = accounts.login(&login.username, &login.password, address).await {
         writer.send(seed.client_version, &LoginError::InvalidUsernamePassword).await.ok();
         return Err(err);
     }
@@ -206,6 +208,100 @@ impl ServerRepository for LocalServerRepository {
     }
 }
 
+/// How a `ServerPool` chooses which game server to offer a client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ServerSelectionPolicy {
+    #[default]
+    RoundRobin,
+    LeastLoaded,
+}
+
+impl ServerSelectionPolicy {
+    pub fn select(self, servers: &[GameServer], counter: &AtomicUsize) -> Option<usize> {
+        if servers.is_empty() {
+            return None;
+        }
+
+        match self {
+            ServerSelectionPolicy::RoundRobin =>
+                Some(counter.fetch_add(1, Ordering::Relaxed) % servers.len()),
+            ServerSelectionPolicy::LeastLoaded => servers.iter()
+                .enumerate()
+                .min_by_key(|(_, server)| server.load_percent)
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+/// A server repository which balances clients across several other repositories.
+///
+/// Only the selected server is advertised to the client, and session allocation is routed
+/// to the repository which provided it.
+#[derive(Debug, Clone)]
+pub struct ServerPool<S> {
+    repositories: Vec<S>,
+    policy: ServerSelectionPolicy,
+    counter: Arc<AtomicUsize>,
+    selected: Vec<(usize, u16)>,
+}
+
+impl<S> ServerPool<S> {
+    pub fn new(repositories: Vec<S>, policy: ServerSelectionPolicy) -> ServerPool<S> {
+        ServerPool {
+            repositories,
+            policy,
+            counter: Default::default(),
+            selected: Vec::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: ServerRepository + Send> ServerRepository for ServerPool<S> {
+    async fn list_servers(&mut self, username: &str) -> anyhow::Result<ServerList> {
+        let mut routes = Vec::new();
+        let mut servers = Vec::new();
+        for (repository_index, repository) in self.repositories.iter_mut().enumerate() {
+            let list = repository.list_servers(username).await?;
+            for server in list.game_servers {
+                routes.push((repository_index, server.server_index));
+                servers.push(server);
+            }
+        }
+
+        self.selected.clear();
+        let mut game_servers = smallvec![];
+        if let Some(index) = self.policy.select(&servers, &self.counter) {
+            self.selected.push(routes[index]);
+            game_servers.push(GameServer {
+                server_index: 0,
+                ..servers[index].clone()
+            });
+        }
+
+        Ok(ServerList {
+            system_info_flags: 0,
+            game_servers,
+        })
+    }
+
+    async fn allocate_session(
+        &mut self,
+        username: &str,
+        server_id: u16,
+        seed: u32,
+        client_version: ClientVersion,
+        encrypted: bool,
+    ) -> anyhow::Result<SwitchServer> {
+        let (repository_index, server_id) = self.selected.get(server_id as usize)
+            .copied()
+            .ok_or_else(|| anyhow!("unknown server {server_id}"))?;
+        self.repositories[repository_index]
+            .allocate_session(username, server_id, seed, client_version, encrypted)
+            .await
+    }
+}
+
 pub struct NewSession {
     pub username: String,
 }
@@ -226,22 +322,40 @@ impl Debug for SessionParameters {
     }
 }
 
+/// How long a client has to connect to the game server after being issued a token.
+pub const SESSION_TOKEN_LIFETIME: Duration = Duration::from_secs(180);
+
 #[derive(Debug)]
 struct PendingSession {
     pub username: String,
     pub parameters: SessionParameters,
+    pub expires_at: Instant,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct SessionAllocator {
+    token_lifetime: Duration,
     pending_sessions: HashMap<u32, PendingSession>,
 }
 
+impl Default for SessionAllocator {
+    fn default() -> Self {
+        SessionAllocator::with_token_lifetime(SESSION_TOKEN_LIFETIME)
+    }
+}
+
 impl SessionAllocator {
     pub fn new() -> SessionAllocator {
         SessionAllocator::default()
     }
 
+    pub fn with_token_lifetime(token_lifetime: Duration) -> SessionAllocator {
+        SessionAllocator {
+            token_lifetime,
+            pending_sessions: HashMap::new(),
+        }
+    }
+
     pub fn allocate_session(&mut self, session: NewSessionRequest) {
         let entry = self.pending_sessions.entry(session.token);
         let result = if matches!(entry, hash_map::Entry::Vacant(_)) {
@@ -251,6 +365,7 @@ impl SessionAllocator {
                     client_version: session.client_version,
                     encryption: session.encryption,
                 },
+                expires_at: Instant::now() + self.token_lifetime,
             });
             Ok(())
         } else {
@@ -261,7 +376,15 @@ impl SessionAllocator {
     }
 
     pub fn session_parameters(&self, token: u32) -> Option<SessionParameters> {
-        self.pending_sessions.get(&token).map(|t| t.parameters.clone())
+        self.pending_sessions.get(&token)
+            .filter(|t| t.expires_at > Instant::now())
+            .map(|t| t.parameters.clone())
+    }
+
+    /// Forget tokens which were never used.
+    pub fn remove_expired(&mut self) {
+        let now = Instant::now();
+        self.pending_sessions.retain(|_, session| session.expires_at > now);
     }
 
     pub fn start_session(&mut self, token: u32, login: GameServerLogin) -> anyhow::Result<NewSession> {
@@ -270,13 +393,118 @@ impl SessionAllocator {
             None => return Err(anyhow!("no such session token")),
         };
 
+        if test_session.expires_at <= Instant::now() {
+            self.pending_sessions.remove(&token);
+            return Err(anyhow!("session token expired"));
+        }
+
+        if login.token != token {
+            return Err(anyhow!("mismatched session token"));
+        }
+
         if login.username.as_str() != test_session.username.as_str() {
             return Err(anyhow!("wrong user for session"));
         }
 
+        // Tokens are single-use.
         self.pending_sessions.remove(&token);
         Ok(NewSession {
             username: login.username.to_string(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::block_on;
+
+    use super::*;
+
+    fn local_server(name: &str, load: u8) -> LocalServerRepository {
+        let (tx, _) = mpsc::unbounded_channel();
+        let server = LocalServerRepository::new(name.to_string(), Ipv4Addr::LOCALHOST, 2594, 0, tx);
+        server.set_load(load);
+        server
+    }
+
+    fn selected_name(pool: &mut ServerPool<LocalServerRepository>) -> String {
+        let list = block_on(pool.list_servers("admin")).unwrap();
+        assert_eq!(list.game_servers.len(), 1);
+        assert_eq!(list.game_servers[0].server_index, 0);
+        list.game_servers[0].server_name.to_string()
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut pool = ServerPool::new(
+            vec![local_server("A", 0), local_server("B", 0)],
+            ServerSelectionPolicy::RoundRobin);
+        assert_eq!(selected_name(&mut pool), "A");
+        assert_eq!(selected_name(&mut pool), "B");
+        assert_eq!(selected_name(&mut pool), "A");
+        assert_eq!(pool.selected, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_least_loaded() {
+        let servers = vec![local_server("A", 80), local_server("B", 20)];
+        let mut pool = ServerPool::new(servers.clone(), ServerSelectionPolicy::LeastLoaded);
+        assert_eq!(selected_name(&mut pool), "B");
+        assert_eq!(selected_name(&mut pool), "B");
+        assert_eq!(pool.selected, vec![(1, 0)]);
+
+        servers[1].set_load(90);
+        assert_eq!(selected_name(&mut pool), "A");
+    }
+
+    fn request_session(allocator: &mut SessionAllocator, token: u32) {
+        let (done, mut result) = oneshot::channel();
+        allocator.allocate_session(NewSessionRequest {
+            username: "admin".into(),
+            seed: 0,
+            client_version: ClientVersion::new(7, 0, 9, 0),
+            token,
+            encryption: None,
+            done,
+        });
+        result.try_recv().unwrap().unwrap();
+    }
+
+    fn login(token: u32) -> GameServerLogin {
+        GameServerLogin {
+            token,
+            username: FixedString::from_str("admin"),
+            password: FixedString::from_str("password"),
+        }
+    }
+
+    #[test]
+    fn test_valid_token() {
+        let mut allocator = SessionAllocator::new();
+        request_session(&mut allocator, 5);
+        assert!(allocator.session_parameters(5).is_some());
+        let session = allocator.start_session(5, login(5)).unwrap();
+        assert_eq!(session.username, "admin");
+    }
+
+    #[test]
+    fn test_reused_token() {
+        let mut allocator = SessionAllocator::new();
+        request_session(&mut allocator, 5);
+        allocator.start_session(5, login(5)).unwrap();
+        assert!(allocator.session_parameters(5).is_none());
+        assert!(allocator.start_session(5, login(5)).is_err());
+    }
+
+    #[test]
+    fn test_expired_token() {
+        let mut allocator = SessionAllocator::with_token_lifetime(Duration::ZERO);
+        request_session(&mut allocator, 5);
+        assert!(allocator.session_parameters(5).is_none());
+        assert!(allocator.start_session(5, login(5)).is_err());
+
+        request_session(&mut allocator, 6);
+        allocator.remove_expired();
+        assert!(allocator.pending_sessions.is_empty());
+    }
+}
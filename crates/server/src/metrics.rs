@@ -0,0 +1,124 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::ecs::entity::Entities;
+use bevy::prelude::*;
+
+use crate::world::connection::NetClient;
+
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub const fn new() -> Counter {
+        Counter(AtomicU64::new(0))
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicU64);
+
+impl Gauge {
+    pub const fn new() -> Gauge {
+        Gauge(AtomicU64::new(0))
+    }
+
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub packets_received: Counter,
+    pub packets_sent: Counter,
+    pub connected_clients: Gauge,
+    pub entities: Gauge,
+    pub frame_time_seconds: Gauge,
+    pub save_duration_seconds: Gauge,
+}
+
+impl Metrics {
+    pub const fn new() -> Metrics {
+        Metrics {
+            packets_received: Counter::new(),
+            packets_sent: Counter::new(),
+            connected_clients: Gauge::new(),
+            entities: Gauge::new(),
+            frame_time_seconds: Gauge::new(),
+            save_duration_seconds: Gauge::new(),
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        let mut counter = |name: &str, help: &str, counter: &Counter| {
+            writeln!(&mut output, "# HELP yewoh_{name} {help}").unwrap();
+            writeln!(&mut output, "# TYPE yewoh_{name} counter").unwrap();
+            writeln!(&mut output, "yewoh_{name} {}", counter.get()).unwrap();
+        };
+        counter("packets_received_total", "Packets received from clients.", &self.packets_received);
+        counter("packets_sent_total", "Packets sent to clients.", &self.packets_sent);
+
+        let mut gauge = |name: &str, help: &str, gauge: &Gauge| {
+            writeln!(&mut output, "# HELP yewoh_{name} {help}").unwrap();
+            writeln!(&mut output, "# TYPE yewoh_{name} gauge").unwrap();
+            writeln!(&mut output, "yewoh_{name} {}", gauge.get()).unwrap();
+        };
+        gauge("connected_clients", "Clients connected to the game server.", &self.connected_clients);
+        gauge("entities", "Entities in the world.", &self.entities);
+        gauge("frame_time_seconds", "Duration of the last frame.", &self.frame_time_seconds);
+        gauge("save_duration_seconds", "Duration of the last world save.", &self.save_duration_seconds);
+
+        output
+    }
+}
+
+pub static METRICS: Metrics = Metrics::new();
+
+pub fn update_world_metrics(
+    entities: &Entities,
+    clients: Query<(), With<NetClient>>,
+) {
+    METRICS.entities.set(entities.len() as f64);
+    METRICS.connected_clients.set(clients.iter().len() as f64);
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_systems(Last, update_world_metrics);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.packets_sent.add(3);
+        metrics.frame_time_seconds.set(0.25);
+
+        let output = metrics.render();
+        assert!(output.contains("# TYPE yewoh_packets_sent_total counter\nyewoh_packets_sent_total 3\n"));
+        assert!(output.contains("yewoh_packets_received_total 0\n"));
+        assert!(output.contains("yewoh_frame_time_seconds 0.25\n"));
+    }
+}
@@ -0,0 +1,304 @@
+use anyhow::anyhow;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::prelude::*;
+use bevy::reflect::serde::{TypedReflectDeserializer, TypedReflectSerializer};
+use bevy::reflect::GetPath;
+use serde::de::DeserializeSeed;
+use serde_json::{Map, Value};
+use tokio::sync::{mpsc, oneshot};
+
+use yewoh::EntityId;
+
+use crate::world::net_id::{NetEntityLookup, NetId};
+
+/// Identifies an entity for remote inspection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityTarget {
+    Net(EntityId),
+    Local(Entity),
+}
+
+pub enum RemoteReflectRequest {
+    Inspect {
+        target: EntityTarget,
+        respond: oneshot::Sender<anyhow::Result<Value>>,
+    },
+    Patch {
+        target: EntityTarget,
+        component: String,
+        patch: Value,
+        respond: oneshot::Sender<anyhow::Result<Value>>,
+    },
+}
+
+/// A handle for making reflection requests from outside of the ECS.
+#[derive(Clone)]
+pub struct RemoteReflectClient {
+    tx: mpsc::UnboundedSender<RemoteReflectRequest>,
+}
+
+impl RemoteReflectClient {
+    pub async fn inspect(&self, target: EntityTarget) -> anyhow::Result<Value> {
+        let (respond, rx) = oneshot::channel();
+        self.tx.send(RemoteReflectRequest::Inspect { target, respond })
+            .map_err(|_| anyhow!("world is gone"))?;
+        rx.await?
+    }
+
+    pub async fn patch(
+        &self, target: EntityTarget, component: impl Into<String>, patch: Value,
+    ) -> anyhow::Result<Value> {
+        let (respond, rx) = oneshot::channel();
+        self.tx.send(RemoteReflectRequest::Patch {
+            target,
+            component: component.into(),
+            patch,
+            respond,
+        }).map_err(|_| anyhow!("world is gone"))?;
+        rx.await?
+    }
+}
+
+#[derive(Resource)]
+pub struct RemoteReflectQueue {
+    rx: mpsc::UnboundedReceiver<RemoteReflectRequest>,
+}
+
+pub fn channel() -> (RemoteReflectClient, RemoteReflectQueue) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (RemoteReflectClient { tx }, RemoteReflectQueue { rx })
+}
+
+pub fn resolve_target(world: &World, target: EntityTarget) -> anyhow::Result<Entity> {
+    let entity = match target {
+        EntityTarget::Net(id) => world.get_resource::<NetEntityLookup>()
+            .and_then(|lookup| lookup.net_to_ecs(id))
+            .ok_or_else(|| anyhow!("no entity with net id {id:?}"))?,
+        EntityTarget::Local(entity) => entity,
+    };
+
+    if !world.entities().contains(entity) {
+        return Err(anyhow!("no such entity {entity}"));
+    }
+
+    Ok(entity)
+}
+
+/// Serialize all reflected components on an entity.
+///
+/// Components which are not registered for reflection, or which fail to serialize, are
+/// reported in `errors` rather than failing the whole request.
+pub fn inspect_entity(world: &World, target: EntityTarget) -> anyhow::Result<Value> {
+    let entity = resolve_target(world, target)?;
+    let entity_ref = world.entity(entity);
+    let registry = world.resource::<AppTypeRegistry>().read();
+
+    let mut components = Map::new();
+    let mut errors = Map::new();
+    for info in world.inspect_entity(entity) {
+        let name = info.name().to_string();
+        let Some(registration) = info.type_id().and_then(|id| registry.get(id)) else {
+            errors.insert(name, "not registered".into());
+            continue;
+        };
+        let Some(reflect) = registration.data::<ReflectComponent>()
+            .and_then(|reflect| reflect.reflect(entity_ref)) else {
+            errors.insert(name, "not reflectable".into());
+            continue;
+        };
+
+        let name = registration.type_info().type_path().to_string();
+        let serializer = TypedReflectSerializer::new(reflect.as_partial_reflect(), &registry);
+        match serde_json::to_value(&serializer) {
+            Ok(value) => {
+                components.insert(name, value);
+            }
+            Err(err) => {
+                errors.insert(name, err.to_string().into());
+            }
+        }
+    }
+
+    let net_id = entity_ref.get::<NetId>().map(|net_id| net_id.id.as_u32());
+    Ok(serde_json::json!({
+        "entity": entity.to_bits(),
+        "net_id": net_id,
+        "components": components,
+        "errors": errors,
+    }))
+}
+
+/// Apply a set of field values to a component on an entity.
+///
+/// `patch` must be an object mapping reflection paths (such as `stats.strength`) to new
+/// values. Every field is validated before any are applied, so a bad patch leaves the
+/// component untouched. Returns the updated component.
+pub fn patch_component(
+    world: &mut World, target: EntityTarget, component: &str, patch: &Value,
+) -> anyhow::Result<Value> {
+    let entity = resolve_target(world, target)?;
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let registration = registry.get_with_type_path(component)
+        .or_else(|| registry.get_with_short_type_path(component))
+        .ok_or_else(|| anyhow!("unknown component '{component}'"))?;
+    let reflect_component = registration.data::<ReflectComponent>()
+        .ok_or_else(|| anyhow!("'{component}' is not a reflected component"))?;
+    let fields = patch.as_object()
+        .ok_or_else(|| anyhow!("patch must be an object of field paths"))?;
+
+    let current = reflect_component.reflect(world.entity(entity))
+        .ok_or_else(|| anyhow!("entity does not have '{component}'"))?;
+    let mut values = Vec::with_capacity(fields.len());
+    for (path, value) in fields {
+        let field = current.reflect_path(path.as_str())
+            .map_err(|err| anyhow!("invalid field '{path}': {err}"))?;
+        let field_registration = field.get_represented_type_info()
+            .and_then(|info| registry.get(info.type_id()))
+            .ok_or_else(|| anyhow!("field '{path}' is not registered"))?;
+        let value = TypedReflectDeserializer::new(field_registration, &registry)
+            .deserialize(value.clone())
+            .map_err(|err| anyhow!("invalid value for '{path}': {err}"))?;
+        values.push((path.as_str(), value));
+    }
+
+    let mut entity_mut = world.entity_mut(entity);
+    let mut reflected = reflect_component.reflect_mut(&mut entity_mut)
+        .ok_or_else(|| anyhow!("entity does not have '{component}'"))?;
+    for (path, value) in values {
+        reflected.reflect_path_mut(path)
+            .map_err(|err| anyhow!("invalid field '{path}': {err}"))?
+            .try_apply(value.as_ref())
+            .map_err(|err| anyhow!("failed to apply '{path}': {err}"))?;
+    }
+
+    let serializer = TypedReflectSerializer::new(reflected.as_partial_reflect(), &registry);
+    Ok(serde_json::to_value(&serializer)?)
+}
+
+pub fn handle_remote_reflect_requests(world: &mut World) {
+    let mut requests = Vec::new();
+    {
+        let mut queue = world.resource_mut::<RemoteReflectQueue>();
+        while let Ok(request) = queue.rx.try_recv() {
+            requests.push(request);
+        }
+    }
+
+    for request in requests {
+        match request {
+            RemoteReflectRequest::Inspect { target, respond } => {
+                respond.send(inspect_entity(world, target)).ok();
+            }
+            RemoteReflectRequest::Patch { target, component, patch, respond } => {
+                respond.send(patch_component(world, target, &component, &patch)).ok();
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app
+        .add_systems(Update, handle_remote_reflect_requests
+            .run_if(resource_exists::<RemoteReflectQueue>));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    struct Health {
+        current: u16,
+    }
+
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    struct Stats {
+        health: Health,
+        name: String,
+    }
+
+    #[derive(Component)]
+    struct Unreflected;
+
+    #[test]
+    fn test_inspect_entity() {
+        let mut app = App::new();
+        app.register_type::<Health>();
+        let entity = app.world_mut().spawn((Health { current: 42 }, Unreflected)).id();
+
+        let value = inspect_entity(app.world(), EntityTarget::Local(entity)).unwrap();
+        let health_path = Health::type_path();
+        assert_eq!(value["components"][health_path], serde_json::json!({ "current": 42 }));
+        assert_eq!(value["net_id"], Value::Null);
+        assert_eq!(value["errors"].as_object().unwrap().len(), 1);
+
+        let missing = inspect_entity(app.world(), EntityTarget::Net(EntityId::from_u32(1)));
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_inspect_request() {
+        let mut app = App::new();
+        let (client, queue) = channel();
+        app
+            .register_type::<Health>()
+            .insert_resource(queue)
+            .add_plugins(plugin);
+        let entity = app.world_mut().spawn(Health { current: 7 }).id();
+
+        let (respond, mut rx) = oneshot::channel();
+        client.tx.send(RemoteReflectRequest::Inspect {
+            target: EntityTarget::Local(entity),
+            respond,
+        }).ok();
+        app.update();
+
+        let value = rx.try_recv().unwrap().unwrap();
+        assert_eq!(value["components"][Health::type_path()], serde_json::json!({ "current": 7 }));
+    }
+
+    fn stats_app() -> (App, Entity) {
+        let mut app = App::new();
+        app.register_type::<Stats>();
+        let entity = app.world_mut()
+            .spawn(Stats {
+                health: Health { current: 10 },
+                name: "Gerome".into(),
+            })
+            .id();
+        (app, entity)
+    }
+
+    #[test]
+    fn test_patch_component() {
+        let (mut app, entity) = stats_app();
+        let patch = serde_json::json!({ "health.current": 25, "name": "Bob" });
+        let value = patch_component(
+            app.world_mut(), EntityTarget::Local(entity), Stats::type_path(), &patch).unwrap();
+        assert_eq!(value["name"], "Bob");
+
+        let stats = app.world().get::<Stats>(entity).unwrap();
+        assert_eq!(stats.health.current, 25);
+        assert_eq!(stats.name, "Bob");
+    }
+
+    #[test]
+    fn test_patch_rejects_invalid() {
+        let (mut app, entity) = stats_app();
+        let target = EntityTarget::Local(entity);
+
+        let patch = serde_json::json!({ "name": "Bob", "health.maximum": 25 });
+        assert!(patch_component(app.world_mut(), target, Stats::type_path(), &patch).is_err());
+        assert_eq!(app.world().get::<Stats>(entity).unwrap().name, "Gerome");
+
+        let patch = serde_json::json!({ "name": 12 });
+        assert!(patch_component(app.world_mut(), target, Stats::type_path(), &patch).is_err());
+
+        let patch = serde_json::json!({ "current": 1 });
+        assert!(patch_component(app.world_mut(), target, "Unknown", &patch).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
-use yewoh::protocol::{CreateCharacter, DeleteCharacter, SelectCharacter};
+use serde::Deserialize;
+use yewoh::protocol::{CharacterListFlags, CreateCharacter, DeleteCharacter, FeatureFlags, SelectCharacter};
 
 #[derive(Debug, Clone, Component, Reflect)]
 #[reflect(Component)]
@@ -11,6 +12,43 @@ pub struct User {
     pub username: String,
 }
 
+/// The expansions and client features advertised to clients when they log in.
+#[derive(Debug, Clone, Resource, Deserialize)]
+#[serde(default)]
+pub struct ServerFeatures {
+    pub feature_flags: FeatureFlags,
+    pub character_list_flags: CharacterListFlags,
+}
+
+impl Default for ServerFeatures {
+    fn default() -> Self {
+        Self {
+            feature_flags: FeatureFlags::T2A
+                | FeatureFlags::UOR
+                | FeatureFlags::LBR
+                | FeatureFlags::AOS
+                | FeatureFlags::SE
+                | FeatureFlags::ML
+                | FeatureFlags::NINTH_AGE
+                | FeatureFlags::LIVE_ACCOUNT
+                | FeatureFlags::SA
+                | FeatureFlags::HS
+                | FeatureFlags::GOTHIC
+                | FeatureFlags::RUSTIC
+                | FeatureFlags::JUNGLE
+                | FeatureFlags::SHADOWGUARD
+                | FeatureFlags::TOL
+                | FeatureFlags::EJ,
+            character_list_flags: CharacterListFlags::CONTEXT_MENU
+                | CharacterListFlags::PALADIN_NECROMANCER_TOOLTIPS
+                | CharacterListFlags::SAMURAI_NINJA
+                | CharacterListFlags::ELVES
+                | CharacterListFlags::NEW_MOVEMENT_SYSTEM
+                | CharacterListFlags::ALLOW_FELUCCA,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Event)]
 pub struct OnClientCharacterListRequest {
     pub client_entity: Entity,
@@ -38,6 +76,7 @@ pub fn plugin(app: &mut App) {
     app
         .register_type::<SentCharacterList>()
         .register_type::<User>()
+        .init_resource::<ServerFeatures>()
         .add_event::<OnClientCharacterListRequest>()
         .add_event::<OnClientCreateCharacter>()
         .add_event::<OnClientSelectCharacter>()
@@ -7,13 +7,14 @@ use bevy::prelude::*;
 use bevy::utils::{Entry, HashSet};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
-use yewoh::protocol::{AnyPacket, CharacterAnimation, CharacterEquipment, CharacterPredefinedAnimation, DeleteEntity, EntityFlags, EntityTooltipVersion, IntoAnyPacket, Race, UpdateCharacter, UpsertEntityCharacter, UpsertEntityStats, UpsertLocalPlayer};
+use yewoh::protocol::{AnyPacket, CharacterAnimation, CharacterEquipment, CharacterPredefinedAnimation, DeleteEntity, EntityFlags, EntityTooltipVersion, IntoAnyPacket, Race, SkillLock, UpdateCharacter, UpsertEntityCharacter, UpsertEntityStats, UpsertLocalPlayer};
 use yewoh::{EntityId, Notoriety};
 use yewoh::types::FixedString;
 
-use crate::world::connection::{NetClient, OwningClient};
+use crate::world::connection::{NetClient, OwningClient, Possessing};
 use crate::world::delta_grid::{delta_grid_cell, DeltaEntry, DeltaGrid, DeltaVersion};
 use crate::world::entity::{Direction, Frozen, Hidden, Hue, MapPosition, RootPosition, Tooltip};
+use crate::world::input::{Emote, OnClientEmote};
 use crate::world::items::ValidItemPosition;
 use crate::world::net_id::{OnDestroyNetEntity, NetId};
 use crate::world::ServerSet;
@@ -278,6 +279,26 @@ pub struct OnClientSkillsRequest {
     pub target: Entity,
 }
 
+#[derive(Debug, Clone, Event)]
+pub struct OnClientSkillLockChange {
+    pub client_entity: Entity,
+    pub skill_id: u16,
+    pub lock: SkillLock,
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct OnClientUseSkill {
+    pub client_entity: Entity,
+    pub skill_id: u16,
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct OnClientStatLockChange {
+    pub client_entity: Entity,
+    pub stat: u8,
+    pub lock: SkillLock,
+}
+
 #[derive(QueryData)]
 pub struct NotorietyQuery {
     pub protected: Ref<'static, Protected>,
@@ -693,6 +714,44 @@ impl Animation {
     }
 }
 
+pub const ANIMATION_KIND_EMOTE: u16 = 7;
+
+impl From<Emote> for Animation {
+    fn from(emote: Emote) -> Self {
+        let action = match emote {
+            Emote::Bow => 0,
+            Emote::Salute => 1,
+        };
+        Animation::Predefined(PredefinedAnimation {
+            kind: ANIMATION_KIND_EMOTE,
+            action,
+            variant: 0,
+        })
+    }
+}
+
+pub fn play_emotes(
+    clients: Query<&Possessing>,
+    characters: Query<&MapPosition, With<CharacterBodyType>>,
+    mut events: EventReader<OnClientEmote>,
+    mut animation_events: EventWriter<OnCharacterAnimationStart>,
+) {
+    for event in events.read() {
+        let Ok(possessing) = clients.get(event.client_entity) else {
+            continue;
+        };
+
+        let Ok(location) = characters.get(possessing.entity) else {
+            continue;
+        };
+
+        animation_events.send(OnCharacterAnimationStart {
+            entity: possessing.entity,
+            location: *location,
+            animation: event.emote.into(),
+        });
+    }
+}
 
 pub fn queue_animations(
     delta_version: Res<DeltaVersion>,
@@ -744,10 +803,44 @@ pub fn plugin(app: &mut App) {
         .add_event::<OnClientProfileUpdateRequest>()
         .add_event::<OnClientProfileRequest>()
         .add_event::<OnClientSkillsRequest>()
+        .add_event::<OnClientSkillLockChange>()
+        .add_event::<OnClientStatLockChange>()
+        .add_event::<OnClientUseSkill>()
         .add_event::<OnClientStatusRequest>()
+        .add_systems(Update, (
+            play_emotes,
+        ))
         .add_systems(Last, (
             queue_animations.in_set(ServerSet::QueueDeltas),
             detect_character_changes.in_set(ServerSet::DetectChanges),
             send_updated_full_status.in_set(ServerSet::Send),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emote_animation() {
+        let mut app = App::new();
+        app
+            .add_event::<OnClientEmote>()
+            .add_event::<OnCharacterAnimationStart>()
+            .add_systems(Update, play_emotes);
+
+        let location = MapPosition { position: IVec3::new(10, 20, 0), map_id: 1 };
+        let character = app.world_mut().spawn((CharacterBodyType(0x190), location)).id();
+        let client_entity = app.world_mut().spawn(Possessing { entity: character }).id();
+        app.world_mut().send_event(OnClientEmote { client_entity, emote: Emote::Salute });
+        app.update();
+
+        let events = app.world().resource::<Events<OnCharacterAnimationStart>>();
+        let started = events.iter_current_update_events().collect::<Vec<_>>();
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].entity, character);
+        assert_eq!(started[0].location, location);
+        assert!(matches!(&started[0].animation,
+            Animation::Predefined(PredefinedAnimation { kind: ANIMATION_KIND_EMOTE, action: 1, .. })));
+    }
+}
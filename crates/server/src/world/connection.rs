@@ -3,21 +3,24 @@ use bevy::prelude::*;
 use std::fmt::Debug;
 use std::io::ErrorKind;
 use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::mpsc;
 use tracing::{info, trace, warn};
-use yewoh::protocol::{AnyPacket, ClientVersion, ClientVersionRequest, EntityRequestKind, ExtendedCommand, FeatureFlags, GameServerLogin, IntoAnyPacket, SetAttackTarget, SupportedFeatures, UnicodeTextMessageRequest, ViewRange};
+use yewoh::protocol::{AnyPacket, ClientFlags, ClientVersion, ClientVersionRequest, EntityRequestKind, ExtendedClientVersion, ExtendedCommand, GameServerLogin, IntoAnyPacket, Logout, SetAttackTarget, SupportedFeatures, TextCommandKind, UnicodeTextMessageRequest, ViewRange, Writer};
 
 use crate::async_runtime::AsyncRuntime;
 use crate::game_server::NewSessionAttempt;
 use crate::lobby::{NewSessionRequest, SessionAllocator};
-use crate::world::account::{OnClientCharacterListRequest, OnClientCreateCharacter, OnClientDeleteCharacter, OnClientSelectCharacter, SentCharacterList, User};
-use crate::world::characters::{OnClientProfileRequest, OnClientProfileUpdateRequest, OnClientSkillsRequest, OnClientStatusRequest};
+use crate::metrics::METRICS;
+use crate::world::account::{OnClientCharacterListRequest, OnClientCreateCharacter, OnClientDeleteCharacter, OnClientSelectCharacter, SentCharacterList, ServerFeatures, User};
+use crate::world::characters::{OnClientProfileRequest, OnClientProfileUpdateRequest, OnClientSkillLockChange, OnClientSkillsRequest, OnClientStatLockChange, OnClientStatusRequest, OnClientUseSkill};
 use crate::world::chat::OnClientChatMessage;
 use crate::world::combat::{OnClientAttackRequest, OnClientWarModeChanged};
 use crate::world::entity::{EquipmentSlot, OnClientTooltipRequest};
 use crate::world::gump::{GumpIdAllocator, GumpLookup, GumpSent, OnClientCloseGump};
-use crate::world::input::{EntityTargetResponse, OnClientContextMenuAction, OnClientContextMenuRequest, OnClientDoubleClick, OnClientDrop, OnClientEquip, OnClientMove, OnClientPickUp, OnClientSingleClick, Targeting, WorldTargetResponse};
+use crate::world::input::{Emote, EntityTargetResponse, OnClientContextMenuAction, OnClientContextMenuRequest, OnClientDoubleClick, OnClientDrop, OnClientEmote, OnClientEquip, MoveThrottle, OnClientMove, OnClientPickUp, OnClientSingleClick, Targeting, WorldTargetResponse};
 use crate::world::net_id::NetEntityLookup;
 use crate::world::view::{View, MAX_VIEW_RANGE, MIN_VIEW_RANGE};
 use crate::world::ServerSet;
@@ -25,6 +28,7 @@ use crate::world::ServerSet;
 pub enum WriterAction {
     Send(ClientVersion, AnyPacket),
     SendArc(ClientVersion, Arc<AnyPacket>),
+    Close,
 }
 
 #[derive(Debug, Clone, Component, Reflect)]
@@ -39,18 +43,65 @@ pub struct OwningClient {
 }
 
 #[derive(Debug, Clone, Component)]
-#[require(Targeting, View, GumpIdAllocator)]
+#[require(Targeting, MoveThrottle, View, GumpIdAllocator)]
 pub struct NetClient {
     address: SocketAddr,
     client_version: ClientVersion,
+    client_flags: ClientFlags,
+    language: String,
     tx: mpsc::UnboundedSender<WriterAction>,
 }
 
 impl NetClient {
+    pub fn new(
+        address: SocketAddr, client_version: ClientVersion, tx: mpsc::UnboundedSender<WriterAction>,
+    ) -> NetClient {
+        NetClient {
+            address,
+            client_version,
+            client_flags: ClientFlags::empty(),
+            language: String::new(),
+            tx,
+        }
+    }
+
     pub fn address(&self) -> SocketAddr { self.address }
 
     pub fn client_version(&self) -> ClientVersion { self.client_version }
 
+    /// Change the version used to encode packets sent to this client from now on.
+    pub fn set_client_version(&mut self, client_version: ClientVersion) {
+        self.client_version = client_version;
+    }
+
+    pub fn client_flags(&self) -> ClientFlags { self.client_flags }
+
+    pub fn set_client_flags(&mut self, client_flags: ClientFlags) {
+        self.client_flags = client_flags;
+    }
+
+    /// The language code reported by the client, i.e. "ENU".
+    pub fn language(&self) -> &str { &self.language }
+
+    pub fn set_language(&mut self, language: impl Into<String>) {
+        self.language = language.into();
+    }
+
+    /// Apply the version a client reports in response to a [`ClientVersionRequest`].
+    pub fn apply_reported_version(&mut self, version: &str) {
+        match ExtendedClientVersion::from_str(version) {
+            Ok(version) if version.is_valid() => {
+                if *version != self.client_version {
+                    debug!("Client {} reported version {}", self.address, *version);
+                    self.client_version = *version;
+                }
+            }
+            Ok(_) => {}
+            Err(err) => warn!("Client {} reported invalid version {version:?}: {err}", self.address),
+        }
+    }
+
+    /// Queue a packet to be encoded for this client's negotiated version and sent.
     pub fn send_packet(&self, packet: impl IntoAnyPacket) {
         let action = match packet.into_any_maybe_arc() {
             Ok(p) => WriterAction::Send(self.client_version, p),
@@ -58,6 +109,41 @@ impl NetClient {
         };
         self.tx.send(action).ok();
     }
+
+    pub fn close(&self) {
+        self.tx.send(WriterAction::Close).ok();
+    }
+
+    /// Log the client out and close the connection once queued packets have been written.
+    pub fn disconnect(&self) {
+        self.send_packet(Logout);
+        self.close();
+    }
+}
+
+/// A client which isn't connected to anything, along with everything that is sent to it.
+#[cfg(test)]
+pub(crate) fn test_client() -> (NetClient, mpsc::UnboundedReceiver<WriterAction>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let client = NetClient::new("127.0.0.1:2593".parse().unwrap(), ClientVersion::new(7, 0, 9, 0), tx);
+    (client, rx)
+}
+
+/// Drain everything sent to a [`test_client`], keeping the packets of type `T`.
+#[cfg(test)]
+pub(crate) fn sent_packets<T: yewoh::protocol::AnyDowncast + Clone>(
+    rx: &mut mpsc::UnboundedReceiver<WriterAction>,
+) -> Vec<T> {
+    let mut packets = Vec::new();
+    while let Ok(action) = rx.try_recv() {
+        let packet = match &action {
+            WriterAction::Send(_, packet) => packet.downcast_ref::<T>(),
+            WriterAction::SendArc(_, packet) => packet.downcast_ref::<T>(),
+            WriterAction::Close => None,
+        };
+        packets.extend(packet.cloned());
+    }
+    packets
 }
 
 #[derive(Resource)]
@@ -70,8 +156,8 @@ pub struct NetServer {
 
     session_allocator: SessionAllocator,
 
-    received_packets_rx: mpsc::UnboundedReceiver<(Entity, AnyPacket)>,
-    received_packets_tx: mpsc::UnboundedSender<(Entity, AnyPacket)>,
+    received_packets_rx: mpsc::UnboundedReceiver<(Entity, Instant, AnyPacket)>,
+    received_packets_tx: mpsc::UnboundedSender<(Entity, Instant, AnyPacket)>,
 
     closed_tx: mpsc::UnboundedSender<Entity>,
     closed_rx: mpsc::UnboundedReceiver<Entity>,
@@ -98,6 +184,11 @@ impl NetServer {
             closed_rx,
         }
     }
+
+    /// Treat a client as disconnected, despawning it on the next update.
+    pub fn disconnect(&self, client_entity: Entity) {
+        self.closed_tx.send(client_entity).ok();
+    }
 }
 
 pub fn broadcast<'a>(clients: impl Iterator<Item=&'a NetClient>, packet: impl IntoAnyPacket) {
@@ -107,12 +198,51 @@ pub fn broadcast<'a>(clients: impl Iterator<Item=&'a NetClient>, packet: impl In
     }
 }
 
+/// Send queued packets to a client until the connection is closed.
+pub async fn write_packets(
+    address: SocketAddr, mut writer: Writer<true>, mut rx: mpsc::UnboundedReceiver<WriterAction>,
+) {
+    while let Some(action) = rx.recv().await {
+        let result = match action {
+            WriterAction::Send(client_version, packet) => {
+                trace!("OUT ({address:?}): {packet:?}");
+                writer.send(client_version, &packet).await
+            }
+            WriterAction::SendArc(client_version, packet) => {
+                trace!("OUT ({address:?}): {packet:?}");
+                writer.send(client_version, &*packet).await
+            }
+            WriterAction::Close => break,
+        };
+        if let Err(err) = result {
+            if err.downcast_ref::<std::io::Error>()
+                .map_or(true, |e| e.kind() != ErrorKind::BrokenPipe) {
+                warn!("Error sending packet {err}");
+            }
+            break;
+        }
+        METRICS.packets_sent.inc();
+    }
+}
+
+/// Close every client connection once its queued packets have been written.
+///
+/// Writers are spawned with `AsyncRuntime::spawn_tracked`, so they can be awaited with
+/// `AsyncRuntime::wait_for_tracked`.
+pub fn close_connections(world: &mut World) {
+    let mut clients = world.query::<&NetClient>();
+    for client in clients.iter(world) {
+        client.close();
+    }
+}
+
 pub fn accept_new_clients(
     runtime: Res<AsyncRuntime>,
     mut server: ResMut<NetServer>,
     connections: Query<&NetClient>,
     mut commands: Commands,
 ) {
+    server.session_allocator.remove_expired();
     while let Ok(new_session_request) = server.new_session_requests.try_recv() {
         server.session_allocator.allocate_session(new_session_request);
     }
@@ -174,7 +304,7 @@ pub fn accept_new_clients(
         let NewSessionAttempt {
             address,
             mut reader,
-            mut writer,
+            writer,
             token,
         } = session_attempt;
         let new_session = match server.session_allocator.start_session(token, login) {
@@ -186,32 +316,12 @@ pub fn accept_new_clients(
         };
 
         let username = new_session.username;
-        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (tx, rx) = mpsc::unbounded_channel();
         info!("New game session from {} for {} (version {})", &address, &username, client_version);
 
-        runtime.spawn(async move {
-            while let Some(action) = rx.recv().await {
-                let result = match action {
-                    WriterAction::Send(client_version, packet) => {
-                        trace!("OUT ({address:?}): {packet:?}");
-                        writer.send(client_version, &packet).await
-                    }
-                    WriterAction::SendArc(client_version, packet) => {
-                        trace!("OUT ({address:?}): {packet:?}");
-                        writer.send(client_version, &*packet).await
-                    }
-                };
-                if let Err(err) = result {
-                    if err.downcast_ref::<std::io::Error>()
-                        .map_or(true, |e| e.kind() != ErrorKind::BrokenPipe) {
-                        warn!("Error sending packet {err}");
-                    }
-                    break;
-                }
-            }
-        });
+        runtime.spawn_tracked(write_packets(address, writer, rx));
 
-        let client = NetClient { address, client_version, tx };
+        let client = NetClient::new(address, client_version, tx);
         let entity = commands
             .spawn((
                 client.clone(),
@@ -227,7 +337,8 @@ pub fn accept_new_clients(
                 match reader.recv(client_version).await {
                     Ok(Some(packet)) => {
                         trace!("IN ({address:?}): {packet:?}");
-                        if let Err(err) = internal_tx.send((entity, packet)) {
+                        METRICS.packets_received.inc();
+                        if let Err(err) = internal_tx.send((entity, Instant::now(), packet)) {
                             warn!("Error forwarding packet {err}");
                             break;
                         }
@@ -247,10 +358,12 @@ pub fn accept_new_clients(
     }
 
     while let Ok(entity) = server.closed_rx.try_recv() {
-        if let Ok(connection) = connections.get(entity) {
-            info!("Connection from {} disconnected", connection.address);
-        }
+        // Kicked clients are reported again once their reader finishes.
+        let Ok(connection) = connections.get(entity) else {
+            continue;
+        };
 
+        info!("Connection from {} disconnected", connection.address);
         commands.entity(entity).despawn();
     }
 }
@@ -262,6 +375,7 @@ pub struct NewPacketEvents<'w> {
     pub select_character: EventWriter<'w, OnClientSelectCharacter>,
     pub delete_character: EventWriter<'w, OnClientDeleteCharacter>,
     pub move_request: EventWriter<'w, OnClientMove>,
+    pub emote: EventWriter<'w, OnClientEmote>,
     pub single_click: EventWriter<'w, OnClientSingleClick>,
     pub double_click: EventWriter<'w, OnClientDoubleClick>,
     pub pick_up: EventWriter<'w, OnClientPickUp>,
@@ -271,6 +385,9 @@ pub struct NewPacketEvents<'w> {
     pub profile_request: EventWriter<'w, OnClientProfileRequest>,
     pub status_request: EventWriter<'w, OnClientStatusRequest>,
     pub skills_request: EventWriter<'w, OnClientSkillsRequest>,
+    pub skill_lock: EventWriter<'w, OnClientSkillLockChange>,
+    pub stat_lock: EventWriter<'w, OnClientStatLockChange>,
+    pub use_skill: EventWriter<'w, OnClientUseSkill>,
     pub chat_message: EventWriter<'w, OnClientChatMessage>,
     pub tooltip_request: EventWriter<'w, OnClientTooltipRequest>,
     pub context_menu_request: EventWriter<'w, OnClientContextMenuRequest>,
@@ -285,42 +402,30 @@ pub fn handle_new_packets(
     mut commands: Commands,
     mut server: ResMut<NetServer>,
     lookup: Res<NetEntityLookup>,
+    features: Res<ServerFeatures>,
     gumps: ResMut<GumpLookup>,
     mut clients: Query<
-        (&NetClient, &mut View, Option<&SentCharacterList>, &mut Targeting),
+        (&mut NetClient, &mut View, Option<&SentCharacterList>, &mut Targeting),
     >,
     mut events: NewPacketEvents,
 ) {
-    while let Ok((client_entity, packet)) = server.received_packets_rx.try_recv() {
-        let Ok((client, mut view, sent_character_list, mut targeting)) = clients.get_mut(client_entity) else {
+    while let Ok((client_entity, received_at, packet)) = server.received_packets_rx.try_recv() {
+        let Ok((mut client, mut view, sent_character_list, mut targeting)) = clients.get_mut(client_entity) else {
             continue;
         };
 
         match packet {
             // Login packets
-            AnyPacket::ClientVersionRequest(_) => {
+            AnyPacket::ClientVersionRequest(request) => {
                 if sent_character_list.is_some() {
                     continue;
                 }
 
+                client.apply_reported_version(&request.version);
+
                 commands.entity(client_entity).insert(SentCharacterList);
                 client.send_packet(SupportedFeatures {
-                    feature_flags: FeatureFlags::T2A
-                        | FeatureFlags::UOR
-                        | FeatureFlags::LBR
-                        | FeatureFlags::AOS
-                        | FeatureFlags::SE
-                        | FeatureFlags::ML
-                        | FeatureFlags::NINTH_AGE
-                        | FeatureFlags::LIVE_ACCOUNT
-                        | FeatureFlags::SA
-                        | FeatureFlags::HS
-                        | FeatureFlags::GOTHIC
-                        | FeatureFlags::RUSTIC
-                        | FeatureFlags::JUNGLE
-                        | FeatureFlags::SHADOWGUARD
-                        | FeatureFlags::TOL
-                        | FeatureFlags::EJ,
+                    feature_flags: features.feature_flags,
                 });
 
                 events.character_list_request.send(OnClientCharacterListRequest {
@@ -360,6 +465,7 @@ pub fn handle_new_packets(
                     run: request.run,
                     sequence: request.sequence,
                     fast_walk: request.fast_walk,
+                    received_at,
                 });
             }
             AnyPacket::SingleClick(request) => {
@@ -377,6 +483,7 @@ pub fn handle_new_packets(
                     events.double_click.send(OnClientDoubleClick {
                         client_entity,
                         target,
+                        paperdoll: request.paperdoll,
                     });
                 } else {
                     warn!("Double click for non-existent entity {:?}", request.target_id);
@@ -455,6 +562,14 @@ pub fn handle_new_packets(
                 }
             }
 
+            AnyPacket::SkillLockRequest(request) => {
+                events.skill_lock.send(OnClientSkillLockChange {
+                    client_entity,
+                    skill_id: request.id,
+                    lock: request.lock,
+                });
+            }
+
             // Chat packets
             AnyPacket::AsciiTextMessageRequest(request) => {
                 events.chat_message.send(OnClientChatMessage {
@@ -498,6 +613,19 @@ pub fn handle_new_packets(
                             target,
                         });
                     }
+                    ExtendedCommand::Language(language) => {
+                        client.set_language(language);
+                    }
+                    ExtendedCommand::ClientType(flags) => {
+                        client.set_client_flags(flags);
+                    }
+                    ExtendedCommand::StatLock(request) => {
+                        events.stat_lock.send(OnClientStatLockChange {
+                            client_entity,
+                            stat: request.stat,
+                            lock: request.lock,
+                        });
+                    }
                     ExtendedCommand::ContextMenuResponse(response) => {
                         let target = match lookup.net_to_ecs(response.target_id) {
                             Some(x) => x,
@@ -537,6 +665,27 @@ pub fn handle_new_packets(
                 }
             }
 
+            AnyPacket::TextCommand(request) => {
+                match request.kind {
+                    TextCommandKind::Animate => match Emote::from_action(&request.command) {
+                        Some(emote) => {
+                            events.emote.send(OnClientEmote { client_entity, emote });
+                        }
+                        None => warn!("unknown emote '{}' from {client_entity}", request.command),
+                    },
+                    TextCommandKind::UseSkill => {
+                        // The command is the skill ID followed by an unused argument.
+                        match request.command.split_whitespace().next().and_then(|id| id.parse().ok()) {
+                            Some(skill_id) => {
+                                events.use_skill.send(OnClientUseSkill { client_entity, skill_id });
+                            }
+                            None => warn!("invalid skill '{}' from {client_entity}", request.command),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
             AnyPacket::WarMode(war_mode) => {
                 events.war_mode.send(OnClientWarModeChanged {
                     client_entity,
@@ -602,3 +751,131 @@ pub fn plugin(app: &mut App) {
                 .in_set(ServerSet::Receive),
         ));
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::runtime::{Builder, Handle};
+    use yewoh::protocol::{new_io, Packet, UpsertEntityWorld};
+
+    use super::*;
+
+    #[test]
+    fn test_close_flushes_pending_writes() {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let stream = TcpStream::connect(address).await.unwrap();
+            let (server_stream, _) = listener.accept().await.unwrap();
+            let (_, writer) = new_io::<true>(server_stream);
+            let (mut reader, _) = new_io::<false>(stream);
+
+            let async_runtime = AsyncRuntime::from(Handle::current());
+            let (tx, rx) = mpsc::unbounded_channel();
+            let client_version = ClientVersion::new(7, 0, 9, 0);
+            let client = NetClient::new(address, client_version, tx);
+            async_runtime.spawn_tracked(write_packets(address, writer, rx));
+
+            for _ in 0..3 {
+                client.send_packet(ClientVersionRequest::default());
+            }
+
+            let mut world = World::new();
+            world.spawn(client);
+            close_connections(&mut world);
+            async_runtime.wait_for_tracked().await;
+
+            for _ in 0..3 {
+                let packet = reader.recv(client_version).await.unwrap();
+                assert!(matches!(packet, Some(AnyPacket::ClientVersionRequest(_))));
+            }
+            assert!(reader.recv(client_version).await.unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_disconnect_despawns_client() {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let (_, new_session_requests) = mpsc::unbounded_channel();
+        let (_, new_sessions) = mpsc::unbounded_channel();
+
+        let mut app = App::new();
+        app
+            .insert_resource(AsyncRuntime::from(runtime.handle().clone()))
+            .insert_resource(NetServer::new(new_session_requests, new_sessions))
+            .add_systems(Update, accept_new_clients);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let client = NetClient::new("127.0.0.1:2593".parse().unwrap(), ClientVersion::new(7, 0, 9, 0), tx);
+        client.disconnect();
+        let client_entity = app.world_mut().spawn(client).id();
+        app.world().resource::<NetServer>().disconnect(client_entity);
+        app.update();
+        assert!(app.world().get_entity(client_entity).is_err());
+
+        // The reader reports the same client again once the connection closes.
+        app.world().resource::<NetServer>().disconnect(client_entity);
+        app.update();
+
+        assert!(matches!(rx.try_recv(), Ok(WriterAction::Send(_, AnyPacket::Logout(_)))));
+        assert!(matches!(rx.try_recv(), Ok(WriterAction::Close)));
+    }
+
+    #[test]
+    fn test_reported_version_used_for_encoding() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut client = NetClient::new("127.0.0.1:2593".parse().unwrap(), ClientVersion::new(6, 0, 0, 0), tx);
+        client.apply_reported_version("7.0.9.0");
+        assert_eq!(client.client_version(), ClientVersion::new(7, 0, 9, 0));
+
+        client.apply_reported_version("garbage");
+        assert_eq!(client.client_version(), ClientVersion::new(7, 0, 9, 0));
+
+        client.send_packet(SupportedFeatures::default());
+        let Ok(WriterAction::Send(version, AnyPacket::SupportedFeatures(packet))) = rx.try_recv() else {
+            panic!("expected supported features");
+        };
+        assert_eq!(version, ClientVersion::new(7, 0, 9, 0));
+
+        let mut encoded = Vec::new();
+        packet.encode(version, &mut encoded).unwrap();
+        assert_eq!(encoded.len(), 4);
+    }
+
+    #[test]
+    fn test_broadcast_encodes_per_client_version() {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let async_runtime = AsyncRuntime::from(Handle::current());
+
+            let mut world = World::new();
+            let mut streams = Vec::new();
+            for client_version in [ClientVersion::new(7, 0, 8, 0), ClientVersion::new(7, 0, 9, 0)] {
+                let stream = TcpStream::connect(address).await.unwrap();
+                let (server_stream, _) = listener.accept().await.unwrap();
+                let (_, writer) = new_io::<true>(server_stream);
+                let (tx, rx) = mpsc::unbounded_channel();
+                async_runtime.spawn_tracked(write_packets(address, writer, rx));
+                world.spawn(NetClient::new(address, client_version, tx));
+                streams.push(stream);
+            }
+
+            let clients = world.query::<&NetClient>().iter(&world).cloned().collect::<Vec<_>>();
+            broadcast(clients.iter(), UpsertEntityWorld::default());
+            close_connections(&mut world);
+            async_runtime.wait_for_tracked().await;
+
+            let mut lengths = Vec::new();
+            for mut stream in streams {
+                let mut data = Vec::new();
+                stream.read_to_end(&mut data).await.unwrap();
+                lengths.push(data.len());
+            }
+            assert_eq!(lengths, vec![24, 26]);
+        });
+    }
+}
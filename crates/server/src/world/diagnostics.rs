@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+
+use crate::world::ServerSet;
+
+pub const FRAME_TIME: DiagnosticPath = DiagnosticPath::const_new("yewoh/frame_time");
+
+const HISTORY_LENGTH: usize = 120;
+
+pub fn set_path(set: ServerSet) -> DiagnosticPath {
+    DiagnosticPath::new(format!("yewoh/set/{set:?}"))
+}
+
+#[derive(Default, Resource)]
+struct SpanStarts(HashMap<DiagnosticPath, Instant>);
+
+fn begin_span(path: DiagnosticPath) -> impl FnMut(ResMut<SpanStarts>) {
+    move |mut starts| {
+        starts.0.insert(path.clone(), Instant::now());
+    }
+}
+
+fn end_span(path: DiagnosticPath) -> impl FnMut(ResMut<SpanStarts>, Diagnostics) {
+    move |mut starts, mut diagnostics| {
+        if let Some(start) = starts.0.remove(&path) {
+            diagnostics.add_measurement(&path, || start.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+}
+
+fn register(app: &mut App, path: &DiagnosticPath) {
+    app.register_diagnostic(Diagnostic::new(path.clone())
+        .with_suffix("ms")
+        .with_max_history_length(HISTORY_LENGTH));
+}
+
+fn time_set(app: &mut App, schedule: impl ScheduleLabel + Clone, set: ServerSet) {
+    let path = set_path(set);
+    register(app, &path);
+    app.add_systems(schedule, (
+        begin_span(path.clone()).before(set),
+        end_span(path).after(set),
+    ));
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<SpanStarts>();
+
+    register(app, &FRAME_TIME);
+    app
+        .add_systems(First, begin_span(FRAME_TIME).before(ServerSet::Receive))
+        .add_systems(Last, end_span(FRAME_TIME).after(ServerSet::SendLast));
+
+    for set in [ServerSet::Receive, ServerSet::HandlePackets] {
+        time_set(app, First, set);
+    }
+
+    for set in [ServerSet::UpdateVisibility, ServerSet::AssignNetIds] {
+        time_set(app, PostUpdate, set);
+    }
+
+    for set in [
+        ServerSet::SendFirst,
+        ServerSet::DetectChanges,
+        ServerSet::SendEntities,
+        ServerSet::Send,
+        ServerSet::SendLast,
+    ] {
+        time_set(app, Last, set);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::diagnostic::{DiagnosticsPlugin, DiagnosticsStore};
+
+    use super::*;
+
+    #[test]
+    fn test_records_timings() {
+        let mut app = App::new();
+        app.add_plugins((DiagnosticsPlugin, plugin));
+
+        for _ in 0..5 {
+            app.update();
+        }
+
+        let store = app.world().resource::<DiagnosticsStore>();
+        let frame_time = store.get(&FRAME_TIME).unwrap();
+        assert_eq!(frame_time.history_len(), 5);
+        assert!(frame_time.average().is_some());
+
+        let send = store.get(&set_path(ServerSet::Send)).unwrap();
+        assert_eq!(send.history_len(), 5);
+    }
+}
@@ -103,6 +103,24 @@ impl Direction {
     pub fn rotate(self, n: u8) -> Direction {
         Self::from_repr((self as u8).wrapping_add(n) & 7).unwrap()
     }
+
+    /// The direction which most closely points along `delta`, or `None` if it is zero.
+    pub fn from_delta(delta: IVec2) -> Option<Direction> {
+        let abs = delta.abs();
+        let x = if abs.x * 2 < abs.y { 0 } else { delta.x.signum() };
+        let y = if abs.y * 2 < abs.x { 0 } else { delta.y.signum() };
+        match (x, y) {
+            (0, -1) => Some(Direction::North),
+            (1, -1) => Some(Direction::Right),
+            (1, 0) => Some(Direction::East),
+            (1, 1) => Some(Direction::Down),
+            (0, 1) => Some(Direction::South),
+            (-1, 1) => Some(Direction::Left),
+            (-1, 0) => Some(Direction::West),
+            (-1, -1) => Some(Direction::Up),
+            _ => None,
+        }
+    }
 }
 
 impl Distribution<Direction> for rand::distributions::Standard {
@@ -327,9 +345,26 @@ impl MapPosition {
         }
     }
 
+    /// Whether `other` is within `range` tiles, equivalent to [`Self::in_range_2d`].
     pub fn in_range(&self, other: &MapPosition, range: i32) -> bool {
+        self.in_range_2d(other, range)
+    }
+
+    /// Whether `other` is on the same map and within `range` tiles, ignoring Z.
+    pub fn in_range_2d(&self, other: &MapPosition, range: i32) -> bool {
         self.manhattan_distance(other).map_or(false, |distance| distance <= range)
     }
+
+    /// Whether `other` is on the same map and within `range`, counting Z
+    /// differences towards the distance.
+    pub fn in_range_3d(&self, other: &MapPosition, range: i32) -> bool {
+        self.map_id == other.map_id && self.position.in_range(&other.position, range)
+    }
+
+    /// The direction to face to look at `other`, or `None` if it is on the same tile.
+    pub fn direction_to(&self, other: &MapPosition) -> Option<Direction> {
+        Direction::from_delta(other.position.truncate() - self.position.truncate())
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Component, Reflect, Serialize, Deserialize)]
@@ -386,3 +421,49 @@ pub fn plugin(app: &mut App) {
         .register_type::<TooltipRequests>()
         .add_event::<OnClientTooltipRequest>();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: i32, y: i32, z: i32, map_id: u8) -> MapPosition {
+        MapPosition { position: IVec3::new(x, y, z), map_id }
+    }
+
+    #[test]
+    fn test_in_range() {
+        let origin = at(10, 10, 0, 1);
+        assert!(origin.in_range_2d(&origin, 0));
+        assert!(origin.in_range_3d(&origin, 0));
+
+        let edge = at(11, 12, 0, 1);
+        assert!(origin.in_range_2d(&edge, 3));
+        assert!(!origin.in_range_2d(&edge, 2));
+        assert!(origin.in_range_3d(&edge, 3));
+
+        let other_map = at(10, 10, 0, 2);
+        assert!(!origin.in_range_2d(&other_map, 10));
+        assert!(!origin.in_range_3d(&other_map, 10));
+
+        let above = at(10, 11, 5, 1);
+        assert!(origin.in_range_2d(&above, 1));
+        assert!(!origin.in_range_3d(&above, 1));
+        assert!(origin.in_range_3d(&above, 6));
+        assert_eq!(origin.in_range(&above, 1), origin.in_range_2d(&above, 1));
+    }
+
+    #[test]
+    fn test_direction_to() {
+        let origin = at(10, 10, 0, 1);
+        assert_eq!(origin.direction_to(&at(10, 10, 5, 1)), None);
+        assert_eq!(origin.direction_to(&at(15, 10, 0, 1)), Some(Direction::East));
+        assert_eq!(origin.direction_to(&at(15, 11, 0, 1)), Some(Direction::East));
+        assert_eq!(origin.direction_to(&at(13, 13, 0, 1)), Some(Direction::Down));
+        assert_eq!(origin.direction_to(&at(9, 4, 0, 1)), Some(Direction::North));
+        assert_eq!(origin.direction_to(&at(7, 8, 0, 1)), Some(Direction::Up));
+
+        for direction in (0..8).map(|i| Direction::from_repr(i).unwrap()) {
+            assert_eq!(Direction::from_delta(direction.as_vec2()), Some(direction));
+        }
+    }
+}
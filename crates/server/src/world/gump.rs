@@ -3,7 +3,7 @@ use bevy::ecs::reflect::ReflectMapEntities;
 use bevy::prelude::*;
 use bevy::utils::HashMap;
 use smallvec::SmallVec;
-use yewoh::protocol::{AnyPacket, GumpLayout, OpenGump};
+use yewoh::protocol::{AnyPacket, GumpLayout, GumpTextEntry, OpenGump};
 
 use crate::world::connection::NetClient;
 use crate::world::ServerSet;
@@ -143,7 +143,7 @@ pub struct OnClientCloseGump {
     pub gump: Entity,
     pub button_id: u32,
     pub on_switches: SmallVec<[u32; 16]>,
-    pub text_fields: Vec<String>,
+    pub text_fields: Vec<GumpTextEntry>,
 }
 
 pub fn update_gumps(
@@ -1,4 +1,5 @@
 use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
 use bevy::prelude::*;
 use yewoh::protocol::{PickTarget, TargetType};
@@ -14,6 +15,84 @@ pub struct OnClientMove {
     pub run: bool,
     pub sequence: u8,
     pub fast_walk: u32,
+    /// When the request packet was read from the connection.
+    pub received_at: Instant,
+}
+
+impl OnClientMove {
+    /// Whether this request only turns a character which is facing `facing`.
+    ///
+    /// Clients send a walk request in the new direction to turn in place, the
+    /// character only steps forward if it is already facing that way.
+    pub fn is_turn(&self, facing: Direction) -> bool {
+        self.direction != facing
+    }
+}
+
+pub const WALK_INTERVAL: Duration = Duration::from_millis(200);
+pub const RUN_INTERVAL: Duration = Duration::from_millis(100);
+pub const MOUNTED_WALK_INTERVAL: Duration = Duration::from_millis(100);
+pub const MOUNTED_RUN_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How many steps a client can get ahead of its movement speed, so that steps
+/// which are bunched up by network jitter are not rejected.
+pub const MOVE_BURST: u32 = 4;
+
+/// Tracks the timing of a client's movement steps, so that steps which arrive
+/// faster than the character could move can be rejected.
+#[derive(Debug, Clone, Default, Component)]
+pub struct MoveThrottle {
+    /// The earliest time the next step would be on schedule.
+    pub next_step: Option<Instant>,
+    pub mounted: bool,
+}
+
+impl MoveThrottle {
+    pub fn step_interval(&self, run: bool) -> Duration {
+        match (self.mounted, run) {
+            (false, false) => WALK_INTERVAL,
+            (false, true) => RUN_INTERVAL,
+            (true, false) => MOUNTED_WALK_INTERVAL,
+            (true, true) => MOUNTED_RUN_INTERVAL,
+        }
+    }
+
+    /// Record a step which arrived at `now`, returning false if the client is
+    /// more than [`MOVE_BURST`] steps ahead of schedule. Rejected steps are not
+    /// recorded.
+    pub fn try_step(&mut self, now: Instant, run: bool) -> bool {
+        let interval = self.step_interval(run);
+        let scheduled = self.next_step.map_or(now, |next_step| next_step.max(now));
+        if scheduled.saturating_duration_since(now) >= interval * MOVE_BURST {
+            return false;
+        }
+
+        self.next_step = Some(scheduled + interval);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emote {
+    Bow,
+    Salute,
+}
+
+impl Emote {
+    /// Parse the action name sent by the client in an animate text command.
+    pub fn from_action(action: &str) -> Option<Emote> {
+        match action.trim().to_lowercase().as_str() {
+            "bow" => Some(Emote::Bow),
+            "salute" => Some(Emote::Salute),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct OnClientEmote {
+    pub client_entity: Entity,
+    pub emote: Emote,
 }
 
 #[derive(Debug, Clone, Event)]
@@ -26,6 +105,7 @@ pub struct OnClientSingleClick {
 pub struct OnClientDoubleClick {
     pub client_entity: Entity,
     pub target: Entity,
+    pub paperdoll: bool,
 }
 
 #[derive(Debug, Clone, Event)]
@@ -186,6 +266,7 @@ pub fn update_targets(
 pub fn plugin(app: &mut App) {
     app
         .add_event::<OnClientMove>()
+        .add_event::<OnClientEmote>()
         .add_event::<OnClientSingleClick>()
         .add_event::<OnClientDoubleClick>()
         .add_event::<OnClientPickUp>()
@@ -197,3 +278,46 @@ pub fn plugin(app: &mut App) {
             update_targets,
         ).in_set(ServerSet::Send));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_emote() {
+        assert_eq!(Emote::from_action("bow"), Some(Emote::Bow));
+        assert_eq!(Emote::from_action("Salute"), Some(Emote::Salute));
+        assert_eq!(Emote::from_action("dance"), None);
+    }
+
+    #[test]
+    fn test_move_throttle() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut throttle = MoveThrottle::default();
+
+        // A few steps can arrive together.
+        for _ in 0..MOVE_BURST {
+            assert!(throttle.try_step(at(0), false));
+        }
+        assert!(!throttle.try_step(at(0), false));
+
+        // Credit is regained as time passes.
+        assert!(throttle.try_step(at(200), false));
+        assert!(!throttle.try_step(at(200), false));
+
+        // Steps on schedule are always accepted.
+        for step in 1..=20 {
+            assert!(throttle.try_step(at(1000 + step * 200), false));
+        }
+
+        throttle.mounted = true;
+        for step in 1..=20 {
+            assert!(throttle.try_step(at(5000 + step * 50), true));
+        }
+        for _ in 0..MOVE_BURST {
+            assert!(throttle.try_step(at(6000), true));
+        }
+        assert!(!throttle.try_step(at(6000), true));
+    }
+}
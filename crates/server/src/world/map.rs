@@ -1,16 +1,20 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::path::Path;
+use std::time::Duration;
 
 use bevy::prelude::*;
-use glam::IVec3;
+use glam::{IVec2, IVec3};
 use tokio::task::JoinSet;
-use yewoh::assets::map::{load_map, load_statics, map_chunk_count, MapChunk, StaticVisitor, CHUNK_SIZE};
+use yewoh::assets::map::{load_map, load_statics, map_chunk_count, MapChunk, MapTile, StaticVisitor, CHUNK_SIZE};
 use yewoh::assets::multi::MultiData;
 use yewoh::assets::tiles::{TileData, TileFlags};
 
+use crate::world::connection::Possessing;
 use crate::world::entity::{Hue, MapPosition};
 use crate::world::items::ItemGraphic;
+use crate::world::view::View;
+use crate::world::ServerSet;
 
 #[derive(Debug, Clone, Default, Reflect)]
 #[reflect(Default)]
@@ -18,6 +22,18 @@ pub struct MapInfo {
     pub size: UVec2,
     pub season: u8,
     pub is_virtual: bool,
+    pub wrap: bool,
+}
+
+impl MapInfo {
+    /// Wrap a position around the edges of the map, if the map wraps.
+    pub fn wrap_position(&self, position: IVec2) -> IVec2 {
+        if self.wrap {
+            position.rem_euclid(self.size.as_ivec2())
+        } else {
+            position
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Reflect, Resource)]
@@ -33,6 +49,14 @@ pub struct Chunk {
     pub map_chunk: MapChunk,
 }
 
+impl Chunk {
+    /// Get the land tile at a world position inside this chunk.
+    pub fn tile_at(&self, position: IVec2) -> MapTile {
+        let local = position.rem_euclid(IVec2::splat(CHUNK_SIZE as i32));
+        self.map_chunk.get(local.x as usize, local.y as usize)
+    }
+}
+
 #[derive(Debug, Clone, Default, Component, Reflect)]
 #[reflect(Component)]
 pub struct Static;
@@ -202,6 +226,11 @@ const HAS_COLLISION_FLAGS: TileFlags = TileFlags::from_bits_truncate(TileFlags::
     TileFlags::WALL.bits() |
     TileFlags::BRIDGE.bits());
 
+pub fn has_collision(tile_data: &TileData, graphic_id: u16) -> bool {
+    tile_data.items.get(graphic_id as usize)
+        .is_some_and(|info| info.flags.intersects(HAS_COLLISION_FLAGS))
+}
+
 pub fn spawn_static_entities(
     world: &mut World,
     tile_data: &TileData,
@@ -222,16 +251,133 @@ pub fn spawn_static_entities(
         )));
 
     for (entity, item) in entities.zip(statics.iter()) {
-        if let Some(info) = tile_data.items.get(item.graphic_id as usize) {
-            if info.flags.intersects(HAS_COLLISION_FLAGS) {
-                out_collision.push((entity, HasCollision));
-            }
+        if has_collision(tile_data, item.graphic_id) {
+            out_collision.push((entity, HasCollision));
         }
     }
 
     world.insert_batch(out_collision);
 }
 
+/// How long a chunk of statics stays spawned after no view covers it.
+pub const STATIC_CHUNK_UNLOAD_DELAY: Duration = Duration::from_secs(30);
+
+/// How far past a view's range static chunks are spawned.
+pub const STATIC_CHUNK_MARGIN: i32 = CHUNK_SIZE as i32;
+
+#[derive(Debug, Clone)]
+struct LoadedStaticChunk {
+    entities: Vec<Entity>,
+    last_observed: Duration,
+}
+
+/// Statics which are spawned on demand, a chunk at a time, when a view approaches them.
+///
+/// Only statics without collision should be streamed: navigation, spawners and line of sight
+/// run away from players too, so colliding statics must stay spawned.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct StaticChunks {
+    statics: HashMap<(u8, IVec2), Vec<StaticData>>,
+    loaded: HashMap<(u8, IVec2), LoadedStaticChunk>,
+}
+
+impl StaticChunks {
+    pub fn new(statics: impl IntoIterator<Item = StaticData>) -> StaticChunks {
+        let mut result = StaticChunks::default();
+        for item in statics {
+            let chunk = item.position.truncate().div_euclid(IVec2::splat(CHUNK_SIZE as i32));
+            result.statics.entry((item.map_id, chunk)).or_default().push(item);
+        }
+        result
+    }
+
+    pub fn num_chunks(&self) -> usize {
+        self.statics.len()
+    }
+
+    pub fn is_loaded(&self, map_id: u8, chunk: IVec2) -> bool {
+        self.loaded.contains_key(&(map_id, chunk))
+    }
+
+    fn observe(
+        &mut self,
+        commands: &mut Commands,
+        tile_data: &TileData,
+        map_id: u8,
+        chunk: IVec2,
+        now: Duration,
+    ) {
+        let key = (map_id, chunk);
+        if let Some(loaded) = self.loaded.get_mut(&key) {
+            loaded.last_observed = now;
+            return;
+        }
+
+        let entities = self.statics.get(&key)
+            .map(|statics| statics.iter()
+                .map(|item| {
+                    let mut entity = commands.spawn((
+                        MapPosition {
+                            map_id: item.map_id,
+                            position: item.position,
+                        },
+                        ItemGraphic(item.graphic_id),
+                        Hue(item.hue),
+                        Static,
+                    ));
+                    if has_collision(tile_data, item.graphic_id) {
+                        entity.insert(HasCollision);
+                    }
+                    entity.id()
+                })
+                .collect())
+            .unwrap_or_default();
+        self.loaded.insert(key, LoadedStaticChunk {
+            entities,
+            last_observed: now,
+        });
+    }
+}
+
+pub fn stream_static_chunks(
+    mut commands: Commands,
+    time: Res<Time>,
+    tile_data: Res<TileDataResource>,
+    mut static_chunks: ResMut<StaticChunks>,
+    views: Query<(&View, &Possessing)>,
+    positions: Query<&MapPosition>,
+) {
+    let now = time.elapsed();
+    let chunk_size = IVec2::splat(CHUNK_SIZE as i32);
+
+    for (view, possessing) in &views {
+        let Ok(position) = positions.get(possessing.entity) else {
+            continue;
+        };
+
+        let range = IVec2::splat(view.range + STATIC_CHUNK_MARGIN);
+        let center = position.position.truncate();
+        let min = (center - range).div_euclid(chunk_size);
+        let max = (center + range).div_euclid(chunk_size);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                static_chunks.observe(&mut commands, &tile_data, position.map_id, IVec2::new(x, y), now);
+            }
+        }
+    }
+
+    static_chunks.loaded.retain(|_, chunk| {
+        if now.saturating_sub(chunk.last_observed) < STATIC_CHUNK_UNLOAD_DELAY {
+            return true;
+        }
+
+        for entity in chunk.entities.drain(..) {
+            commands.entity(entity).despawn();
+        }
+        false
+    });
+}
+
 pub fn plugin(app: &mut App) {
     app
         .register_type::<MapInfo>()
@@ -241,5 +387,94 @@ pub fn plugin(app: &mut App) {
         .register_type::<HasCollision>()
         .register_type::<TileDataResource>()
         .init_resource::<MapInfos>()
-        .init_resource::<TileDataResource>();
+        .init_resource::<TileDataResource>()
+        .add_systems(PostUpdate, (
+            stream_static_chunks
+                .run_if(resource_exists::<StaticChunks>)
+                .before(ServerSet::UpdateVisibility),
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use yewoh::assets::tiles::ItemInfo;
+
+    use super::*;
+
+    #[test]
+    fn test_stream_static_chunks() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .init_resource::<TileDataResource>()
+            .insert_resource(StaticChunks::new([StaticData {
+                map_id: 1,
+                position: IVec3::new(1000, 1000, 5),
+                graphic_id: 1,
+                hue: 0,
+            }]))
+            .add_systems(Update, stream_static_chunks);
+
+        let character = app.world_mut()
+            .spawn(MapPosition { map_id: 1, position: IVec3::new(10, 10, 0) })
+            .id();
+        app.world_mut().spawn((View::default(), Possessing { entity: character }));
+
+        let mut statics = app.world_mut().query_filtered::<&MapPosition, With<Static>>();
+        app.update();
+        assert_eq!(statics.iter(app.world()).count(), 0);
+
+        app.world_mut().get_mut::<MapPosition>(character).unwrap().position = IVec3::new(1000, 1000, 0);
+        app.update();
+        let positions = statics.iter(app.world()).copied().collect::<Vec<_>>();
+        assert_eq!(positions, vec![MapPosition { map_id: 1, position: IVec3::new(1000, 1000, 5) }]);
+        assert!(app.world().resource::<StaticChunks>().is_loaded(1, IVec2::new(125, 125)));
+
+        app.world_mut().get_mut::<MapPosition>(character).unwrap().position = IVec3::new(10, 10, 0);
+        app.world_mut().resource_mut::<Time>().advance_by(STATIC_CHUNK_UNLOAD_DELAY / 2);
+        app.update();
+        assert_eq!(statics.iter(app.world()).count(), 1);
+
+        app.world_mut().resource_mut::<Time>().advance_by(STATIC_CHUNK_UNLOAD_DELAY);
+        app.update();
+        assert_eq!(statics.iter(app.world()).count(), 0);
+        assert!(!app.world().resource::<StaticChunks>().is_loaded(1, IVec2::new(125, 125)));
+    }
+
+    #[test]
+    fn test_collision_statics_stay_resident() {
+        let wall = ItemInfo {
+            name: "wall".into(),
+            flags: TileFlags::WALL | TileFlags::IMPASSABLE,
+            weight: 0,
+            quality: 0,
+            animation: 0,
+            quantity: 0,
+            value: 0,
+            height: 20,
+        };
+        let tile_data = TileData { land: Vec::new(), items: vec![wall] };
+        assert!(has_collision(&tile_data, 0));
+        assert!(!has_collision(&tile_data, 1));
+
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .insert_resource(TileDataResource { tile_data: tile_data.clone() })
+            .insert_resource(StaticChunks::default())
+            .add_systems(Update, stream_static_chunks);
+        spawn_static_entities(app.world_mut(), &tile_data, &[StaticData {
+            map_id: 1,
+            position: IVec3::new(1000, 1000, 0),
+            graphic_id: 0,
+            hue: 0,
+        }]);
+
+        app.update();
+        app.world_mut().resource_mut::<Time>().advance_by(STATIC_CHUNK_UNLOAD_DELAY * 2);
+        app.update();
+
+        let mut statics = app.world_mut().query_filtered::<(), (With<Static>, With<HasCollision>)>();
+        assert_eq!(statics.iter(app.world()).count(), 1);
+    }
 }
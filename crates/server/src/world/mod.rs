@@ -32,6 +32,8 @@ pub mod gump;
 
 pub mod sound;
 
+pub mod diagnostics;
+
 #[derive(SystemSet, Hash, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ServerSet {
     Receive,
@@ -70,6 +72,11 @@ impl Plugin for ServerPlugin {
                 gump::plugin,
                 sound::plugin,
             ))
+            .add_plugins((
+                crate::metrics::plugin,
+                crate::remote_reflect::plugin,
+                diagnostics::plugin,
+            ))
             .configure_sets(First, (
                 (
                     ServerSet::Receive,
@@ -4,7 +4,19 @@ use yewoh::assets::tiles::{TileData, TileFlags};
 
 use crate::world::entity::{Direction, MapPosition};
 use crate::world::map::Chunk;
-use crate::world::spatial::{Collider, SpatialQuery};
+use crate::world::spatial::{Collider, ItemEntry, SpatialQuery};
+
+/// The highest a character can climb in a single step.
+pub const STEP_HEIGHT: i32 = 10;
+
+/// The furthest a character can drop down in a single step.
+pub const MAX_DROP_HEIGHT: i32 = 16;
+
+/// The clearance a character needs above the surface they stand on.
+pub const PERSON_HEIGHT: i32 = 16;
+
+/// The height above a position that line of sight is measured from.
+pub const EYE_HEIGHT: i32 = 14;
 
 #[derive(Debug, Clone)]
 pub enum MoveError {
@@ -21,7 +33,8 @@ pub fn try_move_in_direction(
     ignore: Option<Entity>,
 ) -> Result<MapPosition, MoveError> {
     // Step forward and up 10 units, then drop the character down onto their destination.
-    let mut test_position = position.position + direction.as_vec2().extend(10);
+    let step = query.wrap_position(position.map_id, position.position.truncate() + direction.as_vec2());
+    let mut test_position = step.extend(position.position.z + 10);
     let mut new_z = -1;
 
     for collider in query.iter_colliders(position.map_id, test_position.truncate()) {
@@ -69,3 +82,229 @@ pub fn try_move_in_direction(
         Ok(MapPosition { map_id: position.map_id, position: test_position })
     }
 }
+
+/// Find the Z a character coming from `from_z` would stand at on a tile.
+///
+/// `land` is the terrain for the tile (if any), and `items` are the static and
+/// dynamic items which occupy it. Returns `None` if the tile cannot be stood on,
+/// or if the only surfaces are too far above or below `from_z`.
+pub fn walkable_z(
+    land: Option<MapTile>,
+    items: &[ItemEntry],
+    tile_data: &TileData,
+    from_z: i32,
+) -> Option<i32> {
+    let max_z = from_z + STEP_HEIGHT;
+    let min_z = from_z - MAX_DROP_HEIGHT;
+    let mut candidates = Vec::with_capacity(items.len() + 1);
+
+    if let Some(land) = land {
+        if !tile_data.is_land_impassable(land.tile_id) {
+            candidates.push(land.height as i32);
+        }
+    }
+
+    for item in items {
+        if tile_data.is_surface(item.graphic) {
+            candidates.push(item.z_min + tile_data.stand_height(item.graphic) as i32);
+        }
+    }
+
+    candidates.retain(|z| *z <= max_z && *z >= min_z);
+    candidates.sort_unstable_by(|a, b| b.cmp(a));
+
+    candidates.into_iter().find(|z| {
+        let top = z + PERSON_HEIGHT;
+        !items.iter().any(|item| {
+            tile_data.is_impassable(item.graphic) &&
+                !tile_data.is_surface(item.graphic) &&
+                item.z_min < top && item.z_max > *z
+        })
+    })
+}
+
+/// Find the walkable Z at a position using the spatial lookups.
+pub fn query_walkable_z(
+    query: &SpatialQuery,
+    chunk_query: &Query<(&MapPosition, &Chunk)>,
+    tile_data: &TileData,
+    map_id: u8,
+    position: IVec2,
+    from_z: i32,
+) -> Option<i32> {
+    let land = query.chunks.get_at(map_id, position)
+        .and_then(|entity| chunk_query.get(entity).ok())
+        .map(|(_, chunk)| chunk.tile_at(position));
+    let items = query.static_items.lookup.entries_at(map_id, position).iter()
+        .chain(query.dynamic_items.lookup.entries_at(map_id, position))
+        .cloned()
+        .collect::<Vec<_>>();
+    walkable_z(land, &items, tile_data, from_z)
+}
+
+/// Whether the line between `from` and `to` is clear of impassable items.
+///
+/// Only the tiles between the two ends are tested, so whatever occupies
+/// either end does not block the line.
+pub fn has_line_of_sight(
+    query: &SpatialQuery,
+    tile_data: &TileData,
+    map_id: u8,
+    from: IVec3,
+    to: IVec3,
+) -> bool {
+    let delta = (to - from).truncate().abs();
+    let steps = delta.x.max(delta.y);
+
+    (1..steps).all(|step| {
+        let t = step as f32 / steps as f32;
+        let position = from.as_vec3().lerp(to.as_vec3(), t).round().as_ivec3();
+        let eye_z = position.z + EYE_HEIGHT;
+        !query.static_items.lookup.entries_at(map_id, position.truncate()).iter()
+            .chain(query.dynamic_items.lookup.entries_at(map_id, position.truncate()))
+            .any(|item| tile_data.is_impassable(item.graphic) &&
+                item.z_min <= eye_z && item.z_max >= eye_z)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use yewoh::assets::tiles::{ItemInfo, LandInfo};
+
+    use crate::world::map::{MapInfo, MapInfos};
+    use crate::world::spatial::{ChunkLookup, SpatialCharacterLookup, SpatialDynamicItemLookup, SpatialStaticItemLookup};
+
+    use super::*;
+
+    const GRASS: u16 = 0;
+    const WALL: u16 = 0;
+    const FLOOR: u16 = 1;
+
+    fn test_tile_data() -> TileData {
+        let item = |flags, height| ItemInfo {
+            name: String::new(),
+            flags,
+            weight: 0,
+            quality: 0,
+            animation: 0,
+            quantity: 0,
+            value: 0,
+            height,
+        };
+
+        TileData {
+            land: vec![LandInfo {
+                name: "grass".into(),
+                flags: TileFlags::empty(),
+                texture_id: 0,
+            }],
+            items: vec![
+                item(TileFlags::WALL | TileFlags::IMPASSABLE, 20),
+                item(TileFlags::SURFACE, 5),
+            ],
+        }
+    }
+
+    fn item(graphic: u16, z: i32, tile_data: &TileData) -> ItemEntry {
+        ItemEntry {
+            entity: Entity::PLACEHOLDER,
+            z_min: z,
+            z_max: z + tile_data.height(graphic) as i32,
+            graphic,
+        }
+    }
+
+    #[test]
+    fn test_flat_ground() {
+        let tile_data = test_tile_data();
+        let land = MapTile { tile_id: GRASS, height: 0 };
+        assert_eq!(walkable_z(Some(land), &[], &tile_data, 0), Some(0));
+        assert_eq!(walkable_z(Some(land), &[], &tile_data, 16), Some(0));
+        assert_eq!(walkable_z(Some(land), &[], &tile_data, 20), None);
+        assert_eq!(walkable_z(None, &[], &tile_data, 0), None);
+    }
+
+    #[test]
+    fn test_step_up() {
+        let tile_data = test_tile_data();
+        let land = MapTile { tile_id: GRASS, height: 0 };
+        let items = [item(FLOOR, 0, &tile_data)];
+        assert_eq!(walkable_z(Some(land), &items, &tile_data, 0), Some(5));
+
+        let items = [item(FLOOR, 10, &tile_data)];
+        assert_eq!(walkable_z(Some(land), &items, &tile_data, 0), Some(0));
+    }
+
+    #[test]
+    fn test_blocked() {
+        let tile_data = test_tile_data();
+        let land = MapTile { tile_id: GRASS, height: 0 };
+        let items = [item(WALL, 0, &tile_data)];
+        assert_eq!(walkable_z(Some(land), &items, &tile_data, 0), None);
+    }
+
+    #[test]
+    fn test_move_wraps() {
+        let tile_data = test_tile_data();
+        let mut map_infos = MapInfos::default();
+        map_infos.maps.insert(1, MapInfo { size: UVec2::splat(16), wrap: true, ..default() });
+        map_infos.maps.insert(2, MapInfo { size: UVec2::splat(16), ..default() });
+
+        let mut static_items = SpatialStaticItemLookup::default();
+        for map_id in [1, 2] {
+            static_items.lookup.insert_map(map_id, IVec2::splat(16));
+            let entry = ItemEntry { entity: Entity::from_raw(map_id as u32), ..item(FLOOR, 0, &tile_data) };
+            static_items.lookup.insert(map_id, IVec2::new(0, 3), entry);
+        }
+
+        let mut world = World::new();
+        world.init_resource::<SpatialCharacterLookup>();
+        world.init_resource::<SpatialDynamicItemLookup>();
+        world.init_resource::<ChunkLookup>();
+        world.insert_resource(static_items);
+        world.insert_resource(map_infos);
+
+        let mut try_move = |map_id: u8| {
+            let tile_data = tile_data.clone();
+            let position = MapPosition { map_id, position: IVec3::new(15, 3, 0) };
+            world
+                .run_system_once(move |query: SpatialQuery, chunks: Query<(&MapPosition, &Chunk)>| {
+                    try_move_in_direction(&query, &chunks, &tile_data, position, Direction::East, None).ok()
+                })
+                .unwrap()
+        };
+
+        assert_eq!(try_move(1), Some(MapPosition { map_id: 1, position: IVec3::new(0, 3, 5) }));
+        assert_eq!(try_move(2), None);
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let tile_data = test_tile_data();
+        let mut static_items = SpatialStaticItemLookup::default();
+        static_items.lookup.insert_map(1, IVec2::splat(16));
+        static_items.lookup.insert(1, IVec2::new(5, 3), item(WALL, 0, &tile_data));
+
+        let mut world = World::new();
+        world.init_resource::<SpatialCharacterLookup>();
+        world.init_resource::<SpatialDynamicItemLookup>();
+        world.init_resource::<ChunkLookup>();
+        world.insert_resource(static_items);
+
+        let checks = [
+            (IVec3::new(3, 3, 0), IVec3::new(8, 3, 0), false),
+            (IVec3::new(3, 3, 0), IVec3::new(8, 4, 0), false),
+            (IVec3::new(3, 3, 0), IVec3::new(5, 3, 0), true),
+            (IVec3::new(3, 4, 0), IVec3::new(8, 4, 0), true),
+            (IVec3::new(3, 3, 20), IVec3::new(8, 3, 20), true),
+        ];
+        for (from, to, expected) in checks {
+            let tile_data = tile_data.clone();
+            let result = world
+                .run_system_once(move |query: SpatialQuery| has_line_of_sight(&query, &tile_data, 1, from, to))
+                .unwrap();
+            assert_eq!(result, expected, "{from} -> {to}");
+        }
+    }
+}
@@ -2,9 +2,11 @@ use bevy::ecs::component::{ComponentHooks, StorageType};
 use bevy::prelude::*;
 use bevy::reflect::std_traits::ReflectDefault;
 use bevy::reflect::Reflect;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
 
-use yewoh::{EntityId, MIN_ITEM_ID};
+use anyhow::bail;
+use yewoh::{EntityId, MAX_ITEM_ID, MIN_ITEM_ID};
 use crate::world::characters::CharacterBodyType;
 use crate::world::items::ItemGraphic;
 use crate::world::map::Static;
@@ -65,33 +67,107 @@ pub struct CharacterNetId;
 #[reflect(Default, Component)]
 pub struct ItemNetId;
 
+/// Which part of the ID space an entity belongs in.
+///
+/// Clients decide how to render an entity from its ID, so characters must stay below
+/// `MIN_ITEM_ID` and items at or above it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+    Character,
+    Item,
+}
+
+impl EntityKind {
+    pub fn of(id: EntityId) -> EntityKind {
+        if id.is_item() {
+            EntityKind::Item
+        } else {
+            EntityKind::Character
+        }
+    }
+}
+
+/// How long a freed ID must sit unused before it is handed out again.
+///
+/// This gives clients time to process the removal of the old entity.
+pub const NET_ID_RECYCLE_DELAY: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Resource)]
 pub struct NetIdAllocator {
     next_character: u32,
     next_item: u32,
+    now: Duration,
+    recycle_delay: Duration,
+    free_characters: VecDeque<(EntityId, Duration)>,
+    free_items: VecDeque<(EntityId, Duration)>,
 }
 
 impl Default for NetIdAllocator {
     fn default() -> Self {
-        Self {
+        Self::with_recycle_delay(NET_ID_RECYCLE_DELAY)
+    }
+}
+
+impl NetIdAllocator {
+    pub fn with_recycle_delay(recycle_delay: Duration) -> NetIdAllocator {
+        NetIdAllocator {
             next_character: 0,
             next_item: MIN_ITEM_ID,
+            now: Duration::ZERO,
+            recycle_delay,
+            free_characters: VecDeque::new(),
+            free_items: VecDeque::new(),
         }
     }
-}
 
-impl NetIdAllocator {
-    pub fn allocate_character(&mut self) -> EntityId {
-        self.next_character += 1;
-        if self.next_character >= MIN_ITEM_ID {
-            panic!("character ID overflow");
+    pub fn set_time(&mut self, now: Duration) {
+        self.now = now;
+    }
+
+    fn take_free(free: &mut VecDeque<(EntityId, Duration)>, now: Duration) -> Option<EntityId> {
+        match free.front() {
+            Some((_, available_at)) if *available_at <= now => free.pop_front().map(|(id, _)| id),
+            _ => None,
         }
-        EntityId::from_u32(self.next_character)
     }
 
-    pub fn allocate_item(&mut self) -> EntityId {
-        self.next_item += 1;
-        EntityId::from_u32(self.next_item)
+    pub fn allocate(&mut self, kind: EntityKind) -> anyhow::Result<EntityId> {
+        let (free, next, max) = match kind {
+            EntityKind::Character => (&mut self.free_characters, &mut self.next_character, MIN_ITEM_ID - 1),
+            EntityKind::Item => (&mut self.free_items, &mut self.next_item, MAX_ITEM_ID),
+        };
+
+        if let Some(id) = Self::take_free(free, self.now) {
+            return Ok(id);
+        }
+
+        if *next >= max {
+            bail!("{kind:?} IDs exhausted");
+        }
+
+        *next += 1;
+        Ok(EntityId::from_u32(*next))
+    }
+
+    pub fn allocate_character(&mut self) -> anyhow::Result<EntityId> {
+        self.allocate(EntityKind::Character)
+    }
+
+    pub fn allocate_item(&mut self) -> anyhow::Result<EntityId> {
+        self.allocate(EntityKind::Item)
+    }
+
+    /// Return an ID to the pool, to be reused after the recycle delay.
+    pub fn free(&mut self, id: EntityId) {
+        if !id.is_valid() || id.as_u32() > MAX_ITEM_ID {
+            return;
+        }
+
+        let available_at = self.now + self.recycle_delay;
+        match EntityKind::of(id) {
+            EntityKind::Character => self.free_characters.push_back((id, available_at)),
+            EntityKind::Item => self.free_items.push_back((id, available_at)),
+        }
     }
 }
 
@@ -106,6 +182,21 @@ impl NetEntityLookup {
     }
 }
 
+pub fn recycle_net_ids(
+    time: Res<Time>,
+    lookup: Res<NetEntityLookup>,
+    mut id_allocator: ResMut<NetIdAllocator>,
+    mut destroyed: EventReader<OnDestroyNetEntity>,
+) {
+    id_allocator.set_time(time.elapsed());
+    for event in destroyed.read() {
+        // Another entity may have already claimed this ID.
+        if lookup.net_to_ecs(event.id).is_none() {
+            id_allocator.free(event.id);
+        }
+    }
+}
+
 pub fn assign_net_ids(
     mut commands: Commands,
     mut id_allocator: ResMut<NetIdAllocator>,
@@ -113,19 +204,33 @@ pub fn assign_net_ids(
     new_items: Query<Entity, (Without<Static>, With<ItemGraphic>, Without<CharacterBodyType>, Without<ItemNetId>)>,
 ) {
     for entity in &new_characters {
+        let id = match id_allocator.allocate(EntityKind::Character) {
+            Ok(id) => id,
+            Err(err) => {
+                error!("failed to assign net ID to {entity}: {err}");
+                continue;
+            }
+        };
         commands.entity(entity)
             .remove::<ItemNetId>()
             .insert((
-                NetId::from(id_allocator.allocate_character()),
+                NetId::from(id),
                 CharacterNetId,
             ));
     }
 
     for entity in &new_items {
+        let id = match id_allocator.allocate(EntityKind::Item) {
+            Ok(id) => id,
+            Err(err) => {
+                error!("failed to assign net ID to {entity}: {err}");
+                continue;
+            }
+        };
         commands.entity(entity)
             .remove::<CharacterNetId>()
             .insert((
-                NetId::from(id_allocator.allocate_item()),
+                NetId::from(id),
                 ItemNetId,
             ));
     }
@@ -138,6 +243,78 @@ pub fn plugin(app: &mut App) {
         .init_resource::<NetIdAllocator>()
         .init_resource::<NetEntityLookup>()
         .add_systems(Last, (
+            recycle_net_ids,
             assign_net_ids,
-        ).in_set(ServerSet::AssignNetIds));
+        ).chain().in_set(ServerSet::AssignNetIds));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recycle_after_delay() {
+        let mut allocator = NetIdAllocator::with_recycle_delay(Duration::from_secs(10));
+        let first = allocator.allocate_character().unwrap();
+        let item = allocator.allocate_item().unwrap();
+        allocator.free(first);
+        allocator.free(item);
+
+        allocator.set_time(Duration::from_secs(5));
+        assert_ne!(allocator.allocate_character().unwrap(), first);
+        assert_ne!(allocator.allocate_item().unwrap(), item);
+
+        allocator.set_time(Duration::from_secs(10));
+        assert_eq!(allocator.allocate_character().unwrap(), first);
+        assert_eq!(allocator.allocate_item().unwrap(), item);
+        assert_ne!(allocator.allocate_character().unwrap(), first);
+    }
+
+    #[test]
+    fn test_id_ranges() {
+        let mut allocator = NetIdAllocator::default();
+        let character = allocator.allocate(EntityKind::Character).unwrap();
+        let item = allocator.allocate(EntityKind::Item).unwrap();
+        assert!(character.is_valid());
+        assert!(!character.is_item());
+        assert!(item.is_item());
+        assert_eq!(EntityKind::of(character), EntityKind::Character);
+        assert_eq!(EntityKind::of(item), EntityKind::Item);
+    }
+
+    #[test]
+    fn test_range_exhausted() {
+        let mut allocator = NetIdAllocator::with_recycle_delay(Duration::ZERO);
+        allocator.next_character = MIN_ITEM_ID - 2;
+        assert_eq!(allocator.allocate(EntityKind::Character).unwrap().as_u32(), MIN_ITEM_ID - 1);
+        assert!(allocator.allocate(EntityKind::Character).is_err());
+
+        allocator.next_item = MAX_ITEM_ID;
+        assert!(allocator.allocate(EntityKind::Item).is_err());
+
+        allocator.free(EntityId::from_u32(5));
+        assert_eq!(allocator.allocate(EntityKind::Character).unwrap().as_u32(), 5);
+    }
+
+    #[test]
+    fn test_despawn_frees_id() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .insert_resource(NetIdAllocator::with_recycle_delay(Duration::ZERO))
+            .init_resource::<NetEntityLookup>()
+            .add_event::<OnDestroyNetEntity>()
+            .add_systems(Update, (recycle_net_ids, assign_net_ids).chain());
+
+        let first = app.world_mut().spawn(CharacterBodyType(0x190)).id();
+        app.update();
+        let id = app.world().get::<NetId>(first).unwrap().id;
+
+        app.world_mut().despawn(first);
+        app.update();
+
+        let second = app.world_mut().spawn(CharacterBodyType(0x190)).id();
+        app.update();
+        assert_eq!(app.world().get::<NetId>(second).unwrap().id, id);
+    }
 }
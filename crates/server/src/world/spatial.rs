@@ -374,6 +374,7 @@ pub fn update_static_item_lookup(
         (Entity, &MapPosition, &ItemGraphic),
         (With<Static>, Without<ProcessedStatic>),
     >,
+    mut removed: RemovedComponents<Static>,
 ) {
     for (entity, position, graphic) in surfaces.iter() {
         commands.entity(entity).insert(ProcessedStatic);
@@ -389,6 +390,10 @@ pub fn update_static_item_lookup(
             graphic: **graphic,
         });
     }
+
+    for entity in removed.read() {
+        lookup.lookup.remove(entity);
+    }
 }
 
 #[derive(Debug, Clone, Default, Reflect)]
@@ -572,9 +577,17 @@ pub struct SpatialQuery<'w> {
     pub dynamic_items: Res<'w, SpatialDynamicItemLookup>,
     pub static_items: Res<'w, SpatialStaticItemLookup>,
     pub chunks: Res<'w, ChunkLookup>,
+    pub map_infos: Option<Res<'w, MapInfos>>,
 }
 
 impl SpatialQuery<'_> {
+    /// Wrap a position around the edges of its map, if the map wraps.
+    pub fn wrap_position(&self, map_id: u8, position: IVec2) -> IVec2 {
+        self.map_infos.as_ref()
+            .and_then(|map_infos| map_infos.maps.get(&map_id))
+            .map_or(position, |map_info| map_info.wrap_position(position))
+    }
+
     pub fn iter_colliders(&self, map_id: u8, position: IVec2) -> ColliderIter {
         let dynamic_items = self.dynamic_items.lookup.entries_at(map_id, position);
         let static_items = self.static_items.lookup.entries_at(map_id, position);
@@ -13,7 +13,7 @@ use crate::world::connection::{NetClient, OwningClient, Possessing};
 use crate::world::delta_grid::{delta_grid_cell, Delta, DeltaEntry, DeltaGrid};
 use crate::world::entity::{ContainedPosition, Direction, EquippedPosition, MapPosition, RootPosition};
 use crate::world::items::{Container, OnContainerOpen, ItemQuery, ItemGraphic};
-use crate::world::map::MapInfos;
+use crate::world::map::{MapInfo, MapInfos};
 use crate::world::net_id::NetId;
 use crate::world::ServerSet;
 use crate::world::spatial::SpatialQuery;
@@ -210,13 +210,18 @@ struct SeenEntity {
 #[derive(Debug, Clone, Default, Component)]
 pub struct SeenEntities {
     seen_entities: EntityHashMap<SeenEntity>,
+    /// Entities which have entered (true) or left (false) view, in order.
+    changes: Vec<(Entity, bool)>,
 }
 
 impl SeenEntities {
     pub fn insert_entity(
         &mut self, entity: Entity, parent: Option<Entity>, id: EntityId, position: IVec2,
     ) {
-        let seen = self.seen_entities.entry(entity).or_insert_with(|| default());
+        let seen = self.seen_entities.entry(entity).or_insert_with(|| {
+            self.changes.push((entity, true));
+            default()
+        });
         seen.id = id;
         seen.position = position;
         if seen.parent == parent {
@@ -238,6 +243,7 @@ impl SeenEntities {
 
     pub fn remove_entity(&mut self, entity: Entity) -> bool {
          if let Some(mut seen) = self.seen_entities.remove(&entity) {
+             self.changes.push((entity, false));
              for child in seen.children.drain() {
                  self.remove_entity(child);
              }
@@ -250,8 +256,10 @@ impl SeenEntities {
     pub fn retain(&mut self, mut f: impl FnMut(Entity, EntityId, IVec2) -> bool) {
         let mut children_to_cleanup = Vec::new();
 
+        let changes = &mut self.changes;
         self.seen_entities.retain(|entity, seen| {
             if !f(*entity, seen.id, seen.position) {
+                changes.push((*entity, false));
                 children_to_cleanup.extend(seen.children.drain());
                 false
             } else {
@@ -278,7 +286,7 @@ impl SeenEntities {
     }
 
     pub fn clear(&mut self) {
-        self.seen_entities.clear();
+        self.changes.extend(self.seen_entities.drain().map(|(entity, _)| (entity, false)));
     }
 
     pub fn open_container(&mut self, entity: Entity) {
@@ -296,6 +304,25 @@ impl SeenEntities {
     }
 }
 
+/// Sent once a client has finished its initial synchronization with the world.
+#[derive(Debug, Clone, Event)]
+pub struct OnClientSynchronized {
+    pub client_entity: Entity,
+}
+
+/// Sent when an entity enters or leaves a client's view.
+///
+/// These are sent in the order the changes happened, so an entity which enters
+/// and leaves view in the same frame is reported as entering first.
+#[derive(Debug, Clone, Event)]
+pub struct OnEntityViewChanged {
+    /// The client entity whose view changed.
+    pub observer: Entity,
+    pub entity: Entity,
+    /// Whether `observer` can now see `entity`.
+    pub visible: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Component, Reflect)]
 #[reflect(Component)]
 pub struct ExpectedCharacterState {
@@ -586,20 +613,33 @@ pub fn sync_visible_entities(
     }
 }
 
+pub fn send_change_map(client: &NetClient, map_id: u8, map: &MapInfo) {
+    client.send_packet(ExtendedCommand::ChangeMap(map_id));
+    client.send_packet(ChangeSeason { season: map.season, play_sound: true });
+}
+
 pub fn start_synchronizing(
     mut commands: Commands,
     maps: Res<MapInfos>,
     mut clients: Query<
-        (Entity, &NetClient, Option<&ViewKey>, &mut SeenEntities, Ref<Possessing>, Has<StartedEnteringWorld>),
+        (
+            Entity,
+            &NetClient,
+            Option<&ViewKey>,
+            Option<&ExpectedCharacterState>,
+            &mut SeenEntities,
+            Ref<Possessing>,
+            Has<StartedEnteringWorld>,
+        ),
         Without<Synchronizing>,
     >,
-    characters: Query<
-        (&NetId, &MapPosition, &Direction, Ref<CharacterBodyType>),
-        (With<OwningClient>, With<MapPosition>),
+    mut characters: Query<
+        (&NetId, &mut MapPosition, &Direction, Ref<CharacterBodyType>),
+        With<OwningClient>,
     >,
 ) {
-    for (entity, client, view_key, mut seen, possessing, already_in_world) in &mut clients {
-        let Ok((possessed_net, map_position, direction, body_type)) = characters.get(possessing.entity) else {
+    for (entity, client, view_key, expected, mut seen, possessing, already_in_world) in &mut clients {
+        let Ok((possessed_net, mut map_position, direction, body_type)) = characters.get_mut(possessing.entity) else {
             continue;
         };
 
@@ -609,6 +649,19 @@ pub fn start_synchronizing(
         }
 
         let Some(map) = maps.maps.get(&map_position.map_id) else {
+            // Reject moves onto unknown maps by returning to the last known position.
+            match expected.filter(|e| maps.maps.contains_key(&e.position.map_id)) {
+                Some(expected) => {
+                    warn!("{} moved to unknown map {}, returning to {:?}",
+                        possessing.entity, map_position.map_id, expected.position);
+                    *map_position = expected.position;
+                }
+                None => {
+                    if map_position.is_changed() {
+                        warn!("{} is on unknown map {}", possessing.entity, map_position.map_id);
+                    }
+                }
+            }
             continue;
         };
 
@@ -627,8 +680,7 @@ pub fn start_synchronizing(
                 map_size: map.size,
             });
         }
-        client.send_packet(ExtendedCommand::ChangeMap(map_position.map_id));
-        client.send_packet(ChangeSeason { season: map.season, play_sound: true });
+        send_change_map(client, map_position.map_id, map);
 
         commands.entity(entity)
             .remove::<Synchronized>()
@@ -643,6 +695,7 @@ pub fn start_synchronizing(
 pub fn finish_synchronizing(
     clients: Query<(Entity, &NetClient, Option<&EnteredWorld>), With<Synchronizing>>,
     mut commands: Commands,
+    mut events: EventWriter<OnClientSynchronized>,
 ) {
     for (entity, client, entered_world) in clients.iter() {
         commands.entity(entity)
@@ -652,7 +705,23 @@ pub fn finish_synchronizing(
 
         if entered_world.is_none() {
             client.send_packet(EndEnterWorld);
+            events.send(OnClientSynchronized { client_entity: entity });
+        }
+    }
+}
+
+pub fn send_view_events(
+    mut clients: Query<(Entity, &mut SeenEntities)>,
+    mut events: EventWriter<OnEntityViewChanged>,
+) {
+    for (observer, mut seen) in &mut clients {
+        if seen.changes.is_empty() {
+            continue;
         }
+
+        let seen = seen.bypass_change_detection();
+        events.send_batch(seen.changes.drain(..)
+            .map(|(entity, visible)| OnEntityViewChanged { observer, entity, visible }));
     }
 }
 
@@ -704,6 +773,8 @@ pub fn plugin(app: &mut App) {
         .register_type::<StartedEnteringWorld>()
         .register_type::<EnteredWorld>()
         .register_type::<ExpectedCharacterState>()
+        .add_event::<OnClientSynchronized>()
+        .add_event::<OnEntityViewChanged>()
         .add_systems(Last, (
             start_synchronizing,
         ).in_set(ServerSet::SendFirst))
@@ -714,7 +785,153 @@ pub fn plugin(app: &mut App) {
             ).chain(),
         ).in_set(ServerSet::SendEntities))
         .add_systems(Last, (
-            send_opened_containers,
+            (
+                send_opened_containers,
+                send_view_events,
+            ).chain(),
             finish_synchronizing,
         ).in_set(ServerSet::Send));
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    use crate::world::connection::{sent_packets, test_client, WriterAction};
+
+    use super::*;
+
+    fn sync_app() -> (App, Entity, Entity, UnboundedReceiver<WriterAction>) {
+        let mut app = App::new();
+        let mut maps = MapInfos::default();
+        maps.maps.insert(1, MapInfo { size: UVec2::new(100, 100), ..default() });
+        maps.maps.insert(2, MapInfo { size: UVec2::new(50, 50), ..default() });
+        app
+            .insert_resource(maps)
+            .add_event::<OnClientSynchronized>()
+            .add_systems(Update, (start_synchronizing, finish_synchronizing).chain());
+
+        let (client, rx) = test_client();
+        let client_entity = app.world_mut().spawn(client).id();
+        let character = app.world_mut()
+            .spawn((
+                NetId { id: EntityId::from_u32(1) },
+                CharacterBodyType(0x190),
+                MapPosition { position: IVec3::new(10, 10, 0), map_id: 1 },
+                OwningClient { client_entity },
+            ))
+            .id();
+        app.world_mut().entity_mut(client_entity).insert(Possessing { entity: character });
+        (app, client_entity, character, rx)
+    }
+
+    fn changed_maps(rx: &mut UnboundedReceiver<WriterAction>) -> Vec<u8> {
+        sent_packets::<ExtendedCommand>(rx).into_iter()
+            .filter_map(|command| match command {
+                ExtendedCommand::ChangeMap(map_id) => Some(map_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[derive(Debug, Default, Resource)]
+    struct ViewChanges(Vec<(bool, Entity)>);
+
+    fn record_view_changes(
+        mut events: EventReader<OnEntityViewChanged>,
+        mut changes: ResMut<ViewChanges>,
+    ) {
+        changes.0.extend(events.read().map(|e| (e.visible, e.entity)));
+    }
+
+    #[test]
+    fn test_view_events() {
+        let mut app = App::new();
+        app
+            .init_resource::<ViewChanges>()
+            .add_event::<OnEntityViewChanged>()
+            .add_systems(Update, (send_view_events, record_view_changes).chain());
+
+        let client_entity = app.world_mut().spawn(SeenEntities::default()).id();
+        let near = app.world_mut().spawn_empty().id();
+        let far = app.world_mut().spawn_empty().id();
+        let mut seen = app.world_mut().get_mut::<SeenEntities>(client_entity).unwrap();
+        seen.insert_entity(near, None, EntityId::from_u32(1), IVec2::new(1, 1));
+        seen.insert_entity(far, None, EntityId::from_u32(2), IVec2::new(20, 20));
+        app.update();
+        assert_eq!(app.world().resource::<ViewChanges>().0, vec![(true, near), (true, far)]);
+
+        app.world_mut().resource_mut::<ViewChanges>().0.clear();
+        let mut seen = app.world_mut().get_mut::<SeenEntities>(client_entity).unwrap();
+        seen.insert_entity(near, None, EntityId::from_u32(1), IVec2::new(2, 1));
+        seen.retain(|_, _, position| position.x < 10);
+        app.update();
+        assert_eq!(app.world().resource::<ViewChanges>().0, vec![(false, far)]);
+
+        // Changes within a frame keep their order.
+        app.world_mut().resource_mut::<ViewChanges>().0.clear();
+        let mut seen = app.world_mut().get_mut::<SeenEntities>(client_entity).unwrap();
+        seen.insert_entity(far, None, EntityId::from_u32(2), IVec2::new(3, 3));
+        seen.remove_entity(far);
+        seen.remove_entity(near);
+        seen.insert_entity(near, None, EntityId::from_u32(1), IVec2::new(2, 1));
+        app.update();
+        assert_eq!(app.world().resource::<ViewChanges>().0, vec![
+            (true, far), (false, far), (false, near), (true, near),
+        ]);
+    }
+
+    #[test]
+    fn test_change_map_resyncs_view() {
+        let (mut app, client_entity, character, mut rx) = sync_app();
+        app.update();
+        assert_eq!(app.world().get::<ViewKey>(client_entity).unwrap().map_id, 1);
+        assert!(app.world().get::<Synchronized>(client_entity).is_some());
+        assert_eq!(changed_maps(&mut rx), vec![1]);
+
+        let other = app.world_mut().spawn_empty().id();
+        app.world_mut().get_mut::<SeenEntities>(client_entity).unwrap()
+            .insert_entity(other, None, EntityId::from_u32(2), IVec2::ZERO);
+        app.world_mut().get_mut::<MapPosition>(character).unwrap().map_id = 2;
+        app.update();
+
+        assert_eq!(app.world().get::<ViewKey>(client_entity).unwrap().map_id, 2);
+        assert!(!app.world().get::<SeenEntities>(client_entity).unwrap().has_seen(other));
+        assert_eq!(changed_maps(&mut rx), vec![2]);
+    }
+
+    #[test]
+    fn test_synchronized_event_once() {
+        let (mut app, client_entity, character, _rx) = sync_app();
+        let mut cursor = app.world().resource::<Events<OnClientSynchronized>>().get_cursor();
+        let mut clients = Vec::new();
+        for map_id in [1, 1, 2] {
+            app.world_mut().get_mut::<MapPosition>(character).unwrap().map_id = map_id;
+            app.update();
+            let events = app.world().resource::<Events<OnClientSynchronized>>();
+            clients.extend(cursor.read(events).map(|e| e.client_entity));
+        }
+        assert_eq!(clients, vec![client_entity]);
+    }
+
+    #[test]
+    fn test_change_to_unknown_map() {
+        let (mut app, client_entity, character, mut rx) = sync_app();
+        app.update();
+        changed_maps(&mut rx);
+
+        let position = *app.world().get::<MapPosition>(character).unwrap();
+        app.world_mut().entity_mut(client_entity).insert(ExpectedCharacterState {
+            body_type: 0x190,
+            hue: 0,
+            flags: 0,
+            position,
+        });
+        app.world_mut().get_mut::<MapPosition>(character).unwrap().map_id = 9;
+        app.update();
+
+        assert_eq!(*app.world().get::<MapPosition>(character).unwrap(), position);
+        assert_eq!(app.world().get::<ViewKey>(client_entity).unwrap().map_id, 1);
+        assert!(changed_maps(&mut rx).is_empty());
+    }
+}